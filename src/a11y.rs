@@ -0,0 +1,146 @@
+use crate::diagnostics::Diagnostic;
+use crate::links::tag_attr;
+use crate::render::{markdown_options, strip_front_matter};
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use std::path::Path;
+
+/// Link labels that say nothing about the target when read out of context,
+/// as screen reader link lists do.
+const VAGUE_LABELS: &[&str] = &[
+    "here",
+    "click here",
+    "this",
+    "this page",
+    "this link",
+    "link",
+    "more",
+    "read more",
+    "learn more",
+    "details",
+];
+
+enum Open {
+    Image { offset: usize, text: String },
+    Link { offset: usize, text: String, has_image: bool },
+}
+
+/// Flags images without alt text, links without text or with vague labels,
+/// and tables whose header row is empty.
+pub fn check_a11y(path: &Path, source: &str) -> Vec<Diagnostic> {
+    let content = strip_front_matter(source);
+    let offset = source.len() - content.len();
+    let mut warnings = Vec::new();
+    let mut warn = |rule: &'static str, at: usize, message: String| {
+        let (line, column) = line_column(source, offset + at);
+        warnings.push(Diagnostic::warning(rule, path, message).at(line, column));
+    };
+    let mut open: Vec<Open> = Vec::new();
+    let mut table: Option<(usize, bool)> = None;
+    let mut in_head = false;
+
+    for (event, range) in Parser::new_ext(content, markdown_options(false)).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Image { .. }) => open.push(Open::Image { offset: range.start, text: String::new() }),
+            Event::Start(Tag::Link { .. }) => open.push(Open::Link {
+                offset: range.start,
+                text: String::new(),
+                has_image: false,
+            }),
+            Event::End(TagEnd::Image) => {
+                if let Some(Open::Image { offset, text }) = open.pop() {
+                    if text.trim().is_empty() {
+                        warn("image-alt", offset, "Image has no alt text".to_string());
+                    }
+                    if let Some(Open::Link { has_image, text: link_text, .. }) = open.last_mut() {
+                        *has_image = true;
+                        link_text.push_str(&text);
+                    }
+                }
+            }
+            Event::End(TagEnd::Link) => {
+                if let Some(Open::Link { offset, text, has_image }) = open.pop() {
+                    let label = text.trim().trim_end_matches(['.', ':', '!']).to_lowercase();
+                    if label.is_empty() && !has_image {
+                        warn("empty-link-text", offset, "Link has no text".to_string());
+                    } else if VAGUE_LABELS.contains(&label.as_str()) {
+                        warn(
+                            "vague-link-text",
+                            offset,
+                            format!("Link text \"{}\" does not describe the target", text.trim()),
+                        );
+                    }
+                }
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(Open::Image { text: buffer, .. } | Open::Link { text: buffer, .. }) = open.last_mut() {
+                    buffer.push_str(&text);
+                }
+                if in_head && !text.trim().is_empty() && let Some((_, labelled)) = table.as_mut() {
+                    *labelled = true;
+                }
+            }
+            Event::Html(html) | Event::InlineHtml(html) => {
+                for tag in html.split('<').skip(1) {
+                    if tag.to_ascii_lowercase().starts_with("img") && tag_attr(tag, "alt").is_none_or(|alt| alt.trim().is_empty()) {
+                        warn("image-alt", range.start, "Image has no alt text".to_string());
+                    }
+                }
+            }
+            Event::Start(Tag::Table(_)) => table = Some((range.start, false)),
+            Event::Start(Tag::TableHead) => in_head = true,
+            Event::End(TagEnd::TableHead) => in_head = false,
+            Event::End(TagEnd::Table) => {
+                if let Some((start, false)) = table.take() {
+                    warn("table-header", start, "Table has an empty header row".to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    warnings
+}
+
+/// A CSV page whose first row reads like data, so the rendered table has
+/// no header cells for screen readers to announce.
+pub fn csv_header_warning(path: &Path, has_header: bool) -> Option<Diagnostic> {
+    (!has_header).then(|| {
+        Diagnostic::warning("table-header", path, "CSV table has no header row; add column names as the first row")
+    })
+}
+
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    (before.matches('\n').count() + 1, before[line_start..].chars().count() + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_inaccessible_images_links_and_tables() {
+        let source = "---\ntitle: A\n---\n# Guide\n\n![](shot.png) ![Setup dialog](setup.png)\n\
+                      Read [here](a.md), [](b.md), [![logo](logo.png)](c.md), [Install guide](d.md).\n\n\
+                      | | |\n|---|---|\n| a | b |\n\n| Name | Age |\n|---|---|\n| Ada | 36 |\n\n\
+                      <img src=\"x.png\"> <img alt=\"Chart\" src=\"y.png\">\n";
+        let warnings = check_a11y(Path::new("guide.md"), source);
+        let found: Vec<(&str, usize, usize)> = warnings
+            .iter()
+            .map(|warning| {
+                let span = warning.span.expect("span");
+                (warning.rule, span.line, span.column)
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("image-alt", 6, 1),
+                ("vague-link-text", 7, 6),
+                ("empty-link-text", 7, 20),
+                ("table-header", 9, 1),
+                ("image-alt", 17, 1),
+            ]
+        );
+    }
+}
@@ -0,0 +1,367 @@
+use crate::render::{escape_html, DocMode};
+use crate::settings::Settings;
+use crate::site::{relative_link, GeneratedPage};
+use anyhow::{Context, Result};
+use pulldown_cmark::{html, BrokenLink, CowStr, Event, Options, Parser, Tag};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+const KIND_SECTIONS: &[(&str, &str)] = &[
+    ("module", "Modules"),
+    ("struct", "Structs"),
+    ("enum", "Enums"),
+    ("union", "Unions"),
+    ("trait", "Traits"),
+    ("function", "Functions"),
+    ("type_alias", "Type Aliases"),
+    ("constant", "Constants"),
+    ("static", "Statics"),
+    ("macro", "Macros"),
+];
+
+pub fn load_api_pages(json_path: &Path, mount: &Path, settings: &Settings) -> Result<Vec<GeneratedPage>> {
+    let raw = std::fs::read_to_string(json_path)
+        .with_context(|| format!("Failed to read rustdoc JSON {}", json_path.display()))?;
+    let krate: Value = serde_json::from_str(&raw)
+        .with_context(|| format!("Failed to parse rustdoc JSON {}", json_path.display()))?;
+    let docs = ApiDocs::new(&krate, mount, settings)
+        .with_context(|| format!("Unsupported rustdoc JSON in {}", json_path.display()))?;
+    Ok(docs.pages())
+}
+
+struct Location {
+    page: PathBuf,
+    anchor: Option<String>,
+}
+
+struct ModulePage {
+    id: String,
+    rel_path: PathBuf,
+    title: String,
+    is_crate: bool,
+}
+
+struct ApiDocs<'a> {
+    index: &'a Map<String, Value>,
+    modules: Vec<ModulePage>,
+    locations: HashMap<String, Location>,
+    settings: &'a Settings,
+}
+
+impl<'a> ApiDocs<'a> {
+    fn new(krate: &'a Value, mount: &Path, settings: &'a Settings) -> Result<Self> {
+        let index = krate
+            .get("index")
+            .and_then(Value::as_object)
+            .context("missing \"index\" table")?;
+        let root = krate
+            .get("root")
+            .and_then(id_key)
+            .context("missing \"root\" item")?;
+        let mut docs = Self {
+            index,
+            modules: Vec::new(),
+            locations: HashMap::new(),
+            settings,
+        };
+        let crate_name = docs
+            .item(&root)
+            .and_then(item_name)
+            .context("missing crate root module")?
+            .to_string();
+
+        let mut visited = HashSet::new();
+        let mut stack = vec![(root, mount.to_path_buf(), crate_name)];
+        while let Some((id, dir, path_name)) = stack.pop() {
+            if !visited.insert(id.clone()) {
+                continue;
+            }
+            let rel_path = dir.join("index.md");
+            docs.locations.insert(
+                id.clone(),
+                Location {
+                    page: rel_path.clone(),
+                    anchor: None,
+                },
+            );
+            for child in docs.module_children(&id) {
+                let Some(item) = docs.item(&child) else {
+                    continue;
+                };
+                let (Some(kind), Some(name)) = (item_kind(item), item_name(item)) else {
+                    continue;
+                };
+                if kind == "module" {
+                    stack.push((child, dir.join(name), format!("{path_name}::{name}")));
+                } else {
+                    docs.locations.insert(
+                        child,
+                        Location {
+                            page: rel_path.clone(),
+                            anchor: Some(format!("{kind}.{name}")),
+                        },
+                    );
+                }
+            }
+            docs.modules.push(ModulePage {
+                is_crate: docs.modules.is_empty(),
+                id,
+                rel_path,
+                title: path_name,
+            });
+        }
+        Ok(docs)
+    }
+
+    fn item(&self, id: &str) -> Option<&'a Value> {
+        self.index.get(id)
+    }
+
+    fn module_children(&self, id: &str) -> Vec<String> {
+        self.item(id)
+            .and_then(|item| item.get("inner"))
+            .and_then(|inner| inner.get("module"))
+            .and_then(|module| module.get("items"))
+            .and_then(Value::as_array)
+            .map(|items| items.iter().filter_map(id_key).collect())
+            .unwrap_or_default()
+    }
+
+    fn pages(&self) -> Vec<GeneratedPage> {
+        self.modules
+            .iter()
+            .map(|module| GeneratedPage {
+                rel_path: module.rel_path.clone(),
+                title: module.title.clone(),
+                html: self.module_html(module),
+                mode: DocMode::Document,
+            })
+            .collect()
+    }
+
+    fn module_html(&self, module: &ModulePage) -> String {
+        let from_dir = module.rel_path.parent().unwrap_or(Path::new(""));
+        let mut html = String::new();
+        let label = if module.is_crate { "Crate" } else { "Module" };
+        html.push_str(&format!(
+            r#"<h1><span class="api-kind">{}</span> <code>{}</code></h1>"#,
+            label,
+            escape_html(&module.title)
+        ));
+        if let Some(item) = self.item(&module.id) {
+            html.push_str(&self.docs_html(item, from_dir));
+        }
+
+        let mut children: Vec<(&str, &str, &Value, String)> = self
+            .module_children(&module.id)
+            .into_iter()
+            .filter_map(|id| {
+                let item = self.item(&id)?;
+                Some((item_kind(item)?, item_name(item)?, item, id))
+            })
+            .collect();
+        children.sort_by(|a, b| a.1.cmp(b.1));
+
+        for (kind, heading) in KIND_SECTIONS {
+            let items: Vec<_> = children.iter().filter(|child| child.0 == *kind).collect();
+            if items.is_empty() {
+                continue;
+            }
+            html.push_str(&format!(
+                r#"<h2 id="{}">{}</h2>"#,
+                heading.to_ascii_lowercase().replace(' ', "-"),
+                heading
+            ));
+            if *kind == "module" {
+                html.push_str(r#"<ul class="api-list">"#);
+                for (_, name, _, id) in items {
+                    let href = self.href(id, from_dir).unwrap_or_default();
+                    html.push_str(&format!(
+                        r#"<li><a href="{}"><code>{}</code></a></li>"#,
+                        escape_html(&href),
+                        escape_html(name)
+                    ));
+                }
+                html.push_str("</ul>");
+                continue;
+            }
+            for (kind, name, item, _) in items {
+                html.push_str(&format!(
+                    r#"<section class="api-item" id="{kind}.{name}"><h3><span class="api-kind">{}</span> <code>{}</code></h3>"#,
+                    escape_html(kind),
+                    escape_html(name),
+                    kind = escape_html(kind),
+                    name = escape_html(name),
+                ));
+                html.push_str(&self.docs_html(item, from_dir));
+                html.push_str("</section>");
+            }
+        }
+        html
+    }
+
+    fn href(&self, id: &str, from_dir: &Path) -> Option<String> {
+        let location = self.locations.get(id)?;
+        let mut href = relative_link(from_dir, &location.page.with_extension("html"), self.settings);
+        if let Some(anchor) = &location.anchor {
+            href.push('#');
+            href.push_str(anchor);
+        }
+        Some(href)
+    }
+
+    fn docs_html(&self, item: &Value, from_dir: &Path) -> String {
+        let Some(docs) = item.get("docs").and_then(Value::as_str) else {
+            return String::new();
+        };
+        let links = item.get("links").and_then(Value::as_object);
+        let resolve = |target: &str| -> Option<String> {
+            let id = links?.get(target).and_then(id_key)?;
+            self.href(&id, from_dir)
+        };
+        let mut callback = |link: BrokenLink<'_>| {
+            resolve(link.reference.as_ref())
+                .map(|href| (CowStr::from(href), CowStr::from("")))
+        };
+        let mut options = Options::empty();
+        options.insert(Options::ENABLE_TABLES);
+        options.insert(Options::ENABLE_STRIKETHROUGH);
+        let parser = Parser::new_with_broken_link_callback(docs, options, Some(&mut callback))
+            .map(|event| match event {
+                Event::Start(Tag::Link {
+                    link_type,
+                    dest_url,
+                    title,
+                    id,
+                }) => {
+                    let dest_url = resolve(dest_url.as_ref()).map(CowStr::from).unwrap_or(dest_url);
+                    Event::Start(Tag::Link {
+                        link_type,
+                        dest_url,
+                        title,
+                        id,
+                    })
+                }
+                _ => event,
+            });
+        let mut output = String::new();
+        output.push_str(r#"<div class="api-docs">"#);
+        html::push_html(&mut output, parser);
+        output.push_str("</div>");
+        output
+    }
+}
+
+fn id_key(value: &Value) -> Option<String> {
+    match value {
+        Value::String(id) => Some(id.clone()),
+        Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+fn item_name(item: &Value) -> Option<&str> {
+    item.get("name").and_then(Value::as_str)
+}
+
+fn item_kind(item: &Value) -> Option<&str> {
+    if let Some(kind) = item.get("kind").and_then(Value::as_str) {
+        return Some(kind);
+    }
+    match item.get("inner")? {
+        Value::Object(inner) => inner.keys().next().map(String::as_str),
+        Value::String(kind) => Some(kind.as_str()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"{
+  "root": 0,
+  "format_version": 39,
+  "index": {
+    "0": {"id": 0, "name": "demo", "docs": "Demo crate. See [`Widget`].", "links": {"`Widget`": 1},
+          "inner": {"module": {"is_crate": true, "items": [1, 2]}}},
+    "1": {"id": 1, "name": "Widget", "docs": "A widget.", "links": {},
+          "inner": {"struct": {}}},
+    "2": {"id": 2, "name": "parts", "docs": "Widget parts.", "links": {},
+          "inner": {"module": {"is_crate": false, "items": [3]}}},
+    "3": {"id": 3, "name": "assemble", "docs": "Builds a [`Widget`](crate::Widget).",
+          "links": {"crate::Widget": 1}, "inner": {"function": {}}}
+  }
+}"#;
+
+    fn sample_pages() -> Vec<GeneratedPage> {
+        let krate: Value = serde_json::from_str(SAMPLE).expect("sample json");
+        ApiDocs::new(&krate, Path::new("api"), &Settings::default()).expect("api docs").pages()
+    }
+
+    #[test]
+    fn generates_one_page_per_module() {
+        let pages = sample_pages();
+        let paths: Vec<&Path> = pages.iter().map(|page| page.rel_path.as_path()).collect();
+        assert!(paths.contains(&Path::new("api/index.md")));
+        assert!(paths.contains(&Path::new("api/parts/index.md")));
+        let parts = pages
+            .iter()
+            .find(|page| page.rel_path == Path::new("api/parts/index.md"))
+            .expect("parts page");
+        assert_eq!(parts.title, "demo::parts");
+        assert!(parts.html.contains(r#"id="function.assemble""#));
+    }
+
+    #[test]
+    fn resolves_intra_doc_links() {
+        let pages = sample_pages();
+        let root = pages
+            .iter()
+            .find(|page| page.rel_path == Path::new("api/index.md"))
+            .expect("root page");
+        assert!(root.html.contains(r#"href="index.html#struct.Widget""#));
+        assert!(root.html.contains(r#"href="parts/index.html""#));
+
+        let parts = pages
+            .iter()
+            .find(|page| page.rel_path == Path::new("api/parts/index.md"))
+            .expect("parts page");
+        assert!(parts.html.contains(r#"href="../index.html#struct.Widget""#));
+    }
+
+    #[test]
+    fn skips_missing_items_and_leaves_their_links_unresolved() {
+        let krate: Value = serde_json::from_str(
+            r#"{
+  "root": 0,
+  "index": {
+    "0": {"id": 0, "name": "demo", "docs": "See [`Gone`] and [`Widget`].", "links": {"`Gone`": 9, "`Widget`": 1},
+          "inner": {"module": {"is_crate": true, "items": [1, 2, 9]}}},
+    "1": {"id": 1, "name": "Widget", "docs": "A widget.", "inner": {"struct": {}}},
+    "2": {"id": 2, "docs": "No name."}
+  }
+}"#,
+        )
+        .expect("json");
+        let pages = ApiDocs::new(&krate, Path::new("api"), &Settings::default()).expect("api docs").pages();
+        assert_eq!(pages.len(), 1);
+        let html = &pages[0].html;
+        assert!(html.contains("[<code>Gone</code>]"));
+        assert!(html.contains(r##"<a href="index.html#struct.Widget"><code>Widget</code></a>"##));
+        assert_eq!(html.matches(r#"class="api-item""#).count(), 1);
+        assert!(!html.contains("No name."));
+    }
+
+    #[test]
+    fn rejects_json_without_an_index_or_root() {
+        let no_index: Value = serde_json::from_str(r#"{"root": 0}"#).expect("json");
+        let err = ApiDocs::new(&no_index, Path::new("api"), &Settings::default()).err().expect("no index");
+        assert!(err.to_string().contains("missing \"index\" table"));
+
+        let no_root: Value = serde_json::from_str(r#"{"root": 7, "index": {}}"#).expect("json");
+        let err = ApiDocs::new(&no_root, Path::new("api"), &Settings::default()).err().expect("no root");
+        assert!(err.to_string().contains("missing crate root module"));
+    }
+}
@@ -0,0 +1,149 @@
+use crate::render::front_matter_value;
+use crate::settings::Settings;
+use crate::site::{is_ignored_path, PathFilter};
+use globset::escape;
+use std::ffi::OsStr;
+use std::path::Path;
+use walkdir::WalkDir;
+
+pub fn hidden_patterns(input: &Path, audience: &str, filter: &PathFilter, settings: &Settings) -> Vec<String> {
+    let mut patterns = Vec::new();
+    let entries = WalkDir::new(input)
+        .into_iter()
+        .filter_entry(|entry| !is_ignored_path(entry.path(), input, filter))
+        .filter_map(Result::ok);
+    for entry in entries {
+        let path = entry.path();
+        if !entry.file_type().is_file() || !settings.is_markdown(path) {
+            continue;
+        }
+        let Ok(markdown) = std::fs::read_to_string(path) else {
+            continue;
+        };
+        let Some(tagged) = front_matter_value(&markdown, "audience") else {
+            continue;
+        };
+        if allows(&tagged, audience) {
+            continue;
+        }
+        let Ok(rel_path) = path.strip_prefix(input) else {
+            continue;
+        };
+        let rel_dir = rel_path.parent().unwrap_or(Path::new(""));
+        if is_landing_page(path) && !rel_dir.as_os_str().is_empty() {
+            let dir = escape(&rel_dir.to_string_lossy());
+            patterns.push(format!("{dir}/**"));
+            patterns.push(dir);
+        } else {
+            patterns.push(escape(&rel_path.to_string_lossy()));
+        }
+    }
+    patterns
+}
+
+fn allows(tagged: &str, audience: &str) -> bool {
+    tagged
+        .split(',')
+        .map(str::trim)
+        .any(|value| value.eq_ignore_ascii_case(audience))
+}
+
+fn is_landing_page(path: &Path) -> bool {
+    path.file_stem()
+        .and_then(OsStr::to_str)
+        .map(|stem| stem.eq_ignore_ascii_case("index") || stem.eq_ignore_ascii_case("readme"))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hides_directories_and_pages_for_other_audiences() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let input = dir.path();
+        std::fs::create_dir_all(input.join("internal/runbooks")).expect("internal dir");
+        std::fs::create_dir_all(input.join("guide")).expect("guide dir");
+        std::fs::write(input.join("index.md"), "# Home\n").expect("home");
+        std::fs::write(
+            input.join("internal/README.md"),
+            "---\naudience: internal\n---\n# Internal\n",
+        )
+        .expect("internal readme");
+        std::fs::write(input.join("internal/runbooks/oncall.md"), "# On call\n").expect("oncall");
+        std::fs::write(
+            input.join("guide/roadmap.md"),
+            "---\naudience: internal, partners\n---\n# Roadmap\n",
+        )
+        .expect("roadmap");
+        std::fs::write(
+            input.join("guide/intro.md"),
+            "---\naudience: public\n---\n# Intro\n",
+        )
+        .expect("intro");
+
+        let mut public = hidden_patterns(input, "public", &PathFilter::default(), &Settings::default());
+        public.sort();
+        assert_eq!(public, vec!["guide/roadmap.md", "internal", "internal/**"]);
+
+        let partners = hidden_patterns(input, "partners", &PathFilter::default(), &Settings::default());
+        assert!(partners.contains(&"guide/intro.md".to_string()));
+        assert!(!partners.contains(&"guide/roadmap.md".to_string()));
+    }
+
+    #[test]
+    fn leaves_hidden_pages_out_of_the_built_nav() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let output = tempfile::tempdir().expect("output");
+        let input = dir.path();
+        std::fs::create_dir_all(input.join("internal")).expect("internal dir");
+        std::fs::write(input.join("index.md"), "# Home\n").expect("home");
+        std::fs::write(input.join("intro.md"), "# Intro\n").expect("intro");
+        std::fs::write(
+            input.join("roadmap.md"),
+            "---\naudience: internal\n---\n# Roadmap\n",
+        )
+        .expect("roadmap");
+        std::fs::write(
+            input.join("internal/index.md"),
+            "---\naudience: internal\n---\n# Internal\n",
+        )
+        .expect("internal index");
+        std::fs::write(input.join("internal/oncall.md"), "# On call\n").expect("oncall");
+
+        let mut builder = globset::GlobSetBuilder::new();
+        for pattern in hidden_patterns(input, "public", &PathFilter::default(), &Settings::default()) {
+            builder.add(globset::Glob::new(&pattern).expect("glob"));
+        }
+        let filter = PathFilter {
+            exclude: Some(builder.build().expect("globset")),
+            ..PathFilter::default()
+        };
+        let template = crate::template::Template::built_in();
+        crate::site::build_site(
+            input,
+            output.path(),
+            &crate::site::RenderOptions {
+                live_reload: false,
+                heartbeat: false,
+                template: &template,
+                filter: &filter,
+                csv_max_rows: None,
+                generated: &crate::site::Generated::default(),
+                page_views: None,
+                pre_page_hook: None,
+                plugins: &crate::plugins::Plugins::default(),
+                settings: &Settings::default(),
+            },
+        )
+        .expect("build site");
+
+        let home = std::fs::read_to_string(output.path().join("index.html")).expect("home");
+        assert!(home.contains("intro.html"));
+        assert!(!home.contains("roadmap.html"));
+        assert!(!home.contains("internal/"));
+        assert!(!output.path().join("roadmap.html").exists());
+        assert!(!output.path().join("internal/oncall.html").exists());
+    }
+}
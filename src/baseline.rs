@@ -0,0 +1,119 @@
+use crate::diagnostics::Diagnostic;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+pub const DEFAULT_BASELINE_FILE: &str = "rendar-baseline.json";
+
+/// Known warnings, counted per file, rule, and message. Line numbers are
+/// left out so editing a page doesn't turn its old warnings into new ones.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Baseline {
+    warnings: Vec<BaselineEntry>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct BaselineEntry {
+    path: String,
+    rule: String,
+    message: String,
+    count: usize,
+}
+
+type Key = (String, String, String);
+
+impl Baseline {
+    pub fn from_diagnostics(input: &Path, diagnostics: &[Diagnostic]) -> Self {
+        let mut counts: BTreeMap<Key, usize> = BTreeMap::new();
+        for diagnostic in diagnostics {
+            *counts.entry(key(input, diagnostic)).or_default() += 1;
+        }
+        Self {
+            warnings: counts
+                .into_iter()
+                .map(|((path, rule, message), count)| BaselineEntry { path, rule, message, count })
+                .collect(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read baseline {}", path.display()))?;
+        let baseline = serde_json::from_str(&raw)
+            .with_context(|| format!("Failed to parse baseline {}", path.display()))?;
+        Ok(Some(baseline))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize baseline")?;
+        std::fs::write(path, json + "\n").with_context(|| format!("Failed to write baseline {}", path.display()))
+    }
+
+    pub fn warning_count(&self) -> usize {
+        self.warnings.iter().map(|entry| entry.count).sum()
+    }
+
+    /// The diagnostics not covered by the baseline. When a file has more
+    /// copies of a warning than recorded, the later ones count as new.
+    pub fn new_diagnostics(&self, input: &Path, diagnostics: &[Diagnostic]) -> Vec<Diagnostic> {
+        let mut remaining: BTreeMap<Key, usize> = self
+            .warnings
+            .iter()
+            .map(|entry| ((entry.path.clone(), entry.rule.clone(), entry.message.clone()), entry.count))
+            .collect();
+        diagnostics
+            .iter()
+            .filter(|diagnostic| match remaining.get_mut(&key(input, diagnostic)) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    false
+                }
+                _ => true,
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+fn key(input: &Path, diagnostic: &Diagnostic) -> Key {
+    let normalize = |path: &Path| -> PathBuf {
+        path.components()
+            .filter(|component| !matches!(component, Component::CurDir))
+            .collect()
+    };
+    let (input, path) = (normalize(input), normalize(&diagnostic.path));
+    let path = path.strip_prefix(&input).unwrap_or(&path);
+    let path = path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    (path, diagnostic.rule.to_string(), diagnostic.message.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_only_warnings_beyond_the_baseline() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let input = Path::new("./docs");
+        let todo = |line| Diagnostic::warning("todo", Path::new("./docs/guide.md"), "TODO: screenshots").at(line, 1);
+        let missing = Diagnostic::warning("missing-link", Path::new("./docs/intro.md"), "Missing link target: a.md");
+        let path = dir.path().join(DEFAULT_BASELINE_FILE);
+        Baseline::from_diagnostics(input, &[todo(3), missing.clone()]).write(&path).expect("write");
+
+        let baseline = Baseline::load(&path).expect("load").expect("baseline");
+        assert_eq!(baseline.warning_count(), 2);
+        assert!(baseline.new_diagnostics(input, &[todo(9), missing.clone()]).is_empty());
+        assert!(baseline.new_diagnostics(Path::new("docs"), &[todo(3)]).is_empty());
+        let new = baseline.new_diagnostics(input, &[todo(3), todo(7)]);
+        assert_eq!(new, [todo(7)]);
+        assert!(Baseline::load(&dir.path().join("missing.json")).expect("load").is_none());
+    }
+}
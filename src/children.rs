@@ -0,0 +1,115 @@
+use crate::render::escape_html;
+
+const DIRECTIVE: &str = "{{children";
+
+/// A subfolder or page listed by `{{children}}`. `url` is relative to the
+/// page the listing appears on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChildLink {
+    pub title: String,
+    pub url: String,
+    pub description: Option<String>,
+    pub folder: bool,
+}
+
+/// Whether rendered page HTML has a `{{children}}` directive to expand.
+pub fn has_directive(html: &str) -> bool {
+    html.contains(DIRECTIVE)
+}
+
+/// Replaces every paragraph holding only `{{children}}` or
+/// `{{children cards}}` with a list or card grid of `children`. The
+/// directive is left alone anywhere else, such as in code examples.
+pub fn expand_children(html: &str, children: &[ChildLink]) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find("<p") {
+        let (before, paragraph) = rest.split_at(start);
+        output.push_str(before);
+        let expanded = paragraph.find('>').and_then(|open_end| {
+            let (open, body) = paragraph.split_at(open_end + 1);
+            let valid_open = open == "<p>" || open.starts_with("<p ");
+            let end = body.find("</p>")?;
+            let style = body[..end].trim().strip_prefix(DIRECTIVE)?.strip_suffix("}}")?.trim();
+            let cards = match style {
+                "" => false,
+                "cards" => true,
+                _ => return None,
+            };
+            valid_open.then(|| (children_list(children, cards), open.len() + end + "</p>".len()))
+        });
+        match expanded {
+            Some((listing, consumed)) => {
+                output.push_str(&listing);
+                rest = &paragraph[consumed..];
+            }
+            None => {
+                output.push_str("<p");
+                rest = &paragraph[2..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+/// `children` as a list, or a grid of cards.
+pub fn children_list(children: &[ChildLink], cards: bool) -> String {
+    let class = if cards { "child-pages child-cards" } else { "child-pages" };
+    let mut html = format!("<ul class=\"{class}\">\n");
+    for child in children {
+        let kind = if child.folder { "child-folder" } else { "child-page" };
+        html.push_str(&format!(
+            "<li class=\"{kind}\"><a href=\"{}\">{}</a>",
+            escape_html(&child.url),
+            escape_html(&child.title)
+        ));
+        if let Some(description) = &child.description {
+            html.push_str(&format!("<p>{}</p>", escape_html(description)));
+        }
+        html.push_str("</li>\n");
+    }
+    html.push_str("</ul>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn children() -> Vec<ChildLink> {
+        vec![
+            ChildLink {
+                title: "Guides".to_string(),
+                url: "guides/index.html".to_string(),
+                description: Some("How-tos & walkthroughs".to_string()),
+                folder: true,
+            },
+            ChildLink {
+                title: "FAQ".to_string(),
+                url: "faq.html".to_string(),
+                description: None,
+                folder: false,
+            },
+        ]
+    }
+
+    #[test]
+    fn expands_directive_paragraphs() {
+        let html = "<h1>Docs</h1>\n<p>{{children}}</p>\n<p data-source-line=\"5\">{{children cards}}</p>\n";
+        let expanded = expand_children(html, &children());
+        assert!(expanded.starts_with(concat!(
+            "<h1>Docs</h1>\n<ul class=\"child-pages\">\n",
+            "<li class=\"child-folder\"><a href=\"guides/index.html\">Guides</a><p>How-tos &amp; walkthroughs</p></li>\n",
+            "<li class=\"child-page\"><a href=\"faq.html\">FAQ</a></li>\n</ul>\n"
+        )));
+        assert!(expanded.contains("<ul class=\"child-pages child-cards\">"));
+        assert!(!expanded.contains("{{children"));
+    }
+
+    #[test]
+    fn leaves_directive_in_other_text() {
+        let html = "<p>Use <code>{{children}}</code> here.</p>\n<pre><code>{{children}}\n</code></pre>\n<p>{{children grid}}</p>\n";
+        assert_eq!(expand_children(html, &children()), html);
+    }
+}
@@ -0,0 +1,59 @@
+use crate::site::is_within;
+use anyhow::{Context, Result};
+use std::path::Path;
+
+/// Written into every build output so `--clean` can tell a rendar output
+/// directory from one that holds other files.
+pub const OUTPUT_MARKER: &str = ".rendar-output";
+
+pub fn write_output_marker(output: &Path) -> Result<()> {
+    let path = output.join(OUTPUT_MARKER);
+    std::fs::write(&path, "Generated by rendar. `rendar build --clean` empties this directory.\n")
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Checks that `--clean` may replace `output` wholesale: it must not
+/// contain the input, and if it has files they must come from a rendar build.
+pub fn ensure_cleanable(input: &Path, output: &Path) -> Result<()> {
+    if !output.exists() {
+        return Ok(());
+    }
+    let canonical_input = input.canonicalize().unwrap_or_else(|_| input.to_path_buf());
+    let canonical_output = output.canonicalize().unwrap_or_else(|_| output.to_path_buf());
+    if is_within(&canonical_input, &canonical_output) {
+        return Err(anyhow::anyhow!(
+            "Refusing to clean {}: it contains the input directory",
+            output.display()
+        ));
+    }
+    let mut entries = std::fs::read_dir(output)
+        .with_context(|| format!("Failed to read output directory {}", output.display()))?;
+    if entries.next().is_some() && !output.join(OUTPUT_MARKER).exists() {
+        return Err(anyhow::anyhow!(
+            "Refusing to clean {}: it was not created by rendar (no {OUTPUT_MARKER} file). Empty it yourself or pick another --out",
+            output.display()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allows_cleaning_only_marked_output_directories() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let input = dir.path().join("docs");
+        let output = dir.path().join("site");
+        std::fs::create_dir_all(&input).expect("input");
+        assert!(ensure_cleanable(&input, &output).is_ok());
+        std::fs::create_dir_all(output.join("old")).expect("stale dir");
+        std::fs::write(output.join("old/page.html"), "stale").expect("stale page");
+
+        assert!(ensure_cleanable(&input, &output).is_err());
+        write_output_marker(&output).expect("marker");
+        assert!(ensure_cleanable(&input, &output).is_ok());
+        assert!(ensure_cleanable(&input, dir.path()).is_err());
+    }
+}
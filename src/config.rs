@@ -1,37 +1,425 @@
+use crate::diagnostics::{print_diagnostic, Diagnostic};
 use anyhow::{Context, Result};
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
 pub struct Config {
-    pub input: Option<PathBuf>,
-    pub template: Option<PathBuf>,
+    pub input: Option<InputSetting>,
+    pub template: Option<TemplateSetting>,
+    pub include: Option<Vec<String>>,
     pub exclude: Option<Vec<String>>,
+    pub include_hidden: Option<Vec<String>>,
+    pub gitignore: Option<bool>,
+    pub markdown_extensions: Option<Vec<String>>,
+    pub markdown: Option<MarkdownConfig>,
+    pub inline_assets: Option<bool>,
+    pub inline_max_bytes: Option<u64>,
+    pub file_urls: Option<bool>,
+    pub lightbox: Option<bool>,
+    pub number_headings: Option<bool>,
+    pub unknown_placeholders: Option<PlaceholderPolicy>,
+    pub build: Option<BuildConfig>,
     pub preview: Option<PreviewConfig>,
+    pub api: Option<ApiConfig>,
+    pub site: Option<SiteConfig>,
+    pub packages: Option<Vec<PackageConfig>>,
+    pub page_views: Option<PageViewsConfig>,
+    pub deploy: Option<DeployConfig>,
+    pub hooks: Option<HooksConfig>,
+    pub plugins: Option<Vec<PluginConfig>>,
+    pub freshness: Option<FreshnessConfig>,
+    pub search: Option<SearchConfig>,
+    pub thumbnails: Option<ThumbnailsConfig>,
+    pub links: Option<LinksConfig>,
+    /// `[csv."<glob>"]` tables for CSV files whose path under the input
+    /// matches the glob.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub csv: BTreeMap<String, CsvConfig>,
+    pub obsidian: Option<ObsidianConfig>,
+    pub versions: Option<Vec<VersionConfig>>,
+    /// `[profile.<name>]` tables that override the settings above.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profile: BTreeMap<String, Config>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaceholderPolicy {
+    #[default]
+    Error,
+    Warn,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct SiteConfig {
+    pub title: Option<String>,
+    pub base_url: Option<String>,
+    pub strip_number_prefixes: Option<bool>,
+    pub folder_indexes: Option<bool>,
+    pub strip_title_numbers: Option<bool>,
+    pub strip_title_heading: Option<bool>,
+    pub footer_metadata: Option<Vec<String>>,
+    pub icon: Option<PathBuf>,
+    pub language: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub strings: BTreeMap<String, String>,
+}
+
+/// `template = "theme.html"`, or a `[template]` table whose `vars` become
+/// `{{key}}` placeholders.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum TemplateSetting {
+    Path(PathBuf),
+    Table(TemplateConfig),
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateConfig {
+    pub path: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub vars: BTreeMap<String, String>,
+}
+
+/// `input = "docs"`, or a list of roots merged into one site. The first
+/// root is the site root; the others are mounted below it.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum InputSetting {
+    One(PathBuf),
+    Many(Vec<InputRoot>),
+}
+
+/// `"guides"`, or `{ path = "../shared-docs", mount = "shared" }`.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum InputRoot {
+    Path(PathBuf),
+    Mounted { path: PathBuf, mount: Option<PathBuf> },
 }
 
-#[derive(Debug, Default, Deserialize)]
+impl InputRoot {
+    pub fn path(&self) -> &Path {
+        match self {
+            InputRoot::Path(path) | InputRoot::Mounted { path, .. } => path,
+        }
+    }
+
+    fn path_mut(&mut self) -> &mut PathBuf {
+        match self {
+            InputRoot::Path(path) | InputRoot::Mounted { path, .. } => path,
+        }
+    }
+
+    /// Where the root's pages go in the site; the folder name by default.
+    pub fn mount(&self) -> PathBuf {
+        match self {
+            InputRoot::Mounted { mount: Some(mount), .. } => mount.clone(),
+            _ => self.path().file_name().map(PathBuf::from).unwrap_or_default(),
+        }
+    }
+}
+
+/// One `[[versions]]` entry: built from `input`, or from `input` as of the
+/// git revision `git`.
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct VersionConfig {
+    pub name: String,
+    pub input: Option<PathBuf>,
+    pub git: Option<String>,
+    pub latest: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct BuildConfig {
+    pub strict: Option<bool>,
+    pub pretty_urls: Option<bool>,
+    pub keep_markdown_links: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
 pub struct PreviewConfig {
     pub port: Option<u16>,
     pub open: Option<bool>,
+    pub host: Option<String>,
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct ApiConfig {
+    pub json: PathBuf,
+    pub mount: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct PageViewsConfig {
+    pub endpoint: String,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct HooksConfig {
+    pub pre_build: Option<String>,
+    pub post_build: Option<String>,
+    pub pre_page: Option<String>,
+    pub timeout: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct SearchConfig {
+    pub enabled: Option<bool>,
+    pub backend: Option<SearchBackendKind>,
+    pub url: Option<String>,
+    pub index: Option<String>,
+    pub api_key_env: Option<String>,
+    pub search_key: Option<String>,
+    pub tinysearch: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchBackendKind {
+    #[default]
+    Json,
+    Tinysearch,
+    Meilisearch,
+    Typesense,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct ThumbnailsConfig {
+    pub min_bytes: Option<u64>,
+    pub width: Option<u32>,
+    pub cache_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct LinksConfig {
+    pub state_file: Option<PathBuf>,
+}
+
+/// How to read a CSV file, overriding what is detected from its contents.
+/// Also the format of `<name>.csv.toml` files next to a CSV.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CsvConfig {
+    /// A single character, like `";"` or `"\t"`.
+    pub delimiter: Option<String>,
+    pub has_header: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct MarkdownConfig {
+    pub sanitize_html: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct ObsidianConfig {
+    pub enabled: Option<bool>,
+    pub attachments: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct FreshnessConfig {
+    pub stale_after_days: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct PluginConfig {
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct DeployConfig {
+    pub s3: Option<S3DeployConfig>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct S3DeployConfig {
+    pub bucket: Option<String>,
+    pub prefix: Option<String>,
+    pub region: Option<String>,
+    pub endpoint: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub cache_control: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct PackageConfig {
+    pub path: PathBuf,
+    pub mount: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, String>,
 }
 
 impl Config {
+    /// The site root: `input`, or the first entry of an `input` list.
+    pub fn input(&self) -> Option<PathBuf> {
+        match self.input.as_ref()? {
+            InputSetting::One(path) => Some(path.clone()),
+            InputSetting::Many(roots) => roots.first().map(|root| root.path().to_path_buf()),
+        }
+    }
+
+    pub fn template_path(&self) -> Option<PathBuf> {
+        match self.template.as_ref()? {
+            TemplateSetting::Path(path) => Some(path.clone()),
+            TemplateSetting::Table(table) => table.path.clone(),
+        }
+    }
+
+    pub fn template_vars(&self) -> BTreeMap<String, String> {
+        match self.template.as_ref() {
+            Some(TemplateSetting::Table(table)) => table.vars.clone(),
+            _ => BTreeMap::new(),
+        }
+    }
+
+    /// Replaces the site root, keeping any mounted roots.
+    pub fn set_input(&mut self, path: PathBuf) {
+        match self.input.as_mut() {
+            Some(InputSetting::Many(roots)) if !roots.is_empty() => roots[0] = InputRoot::Path(path),
+            _ => self.input = Some(InputSetting::One(path)),
+        }
+    }
+
+    /// A warning for every file or folder the config names that doesn't
+    /// exist.
+    pub fn missing_paths(&self, config_path: &Path) -> Vec<Diagnostic> {
+        let mut expected: Vec<(String, &Path, bool)> = Vec::new();
+        match self.input.as_ref() {
+            Some(InputSetting::One(path)) => expected.push(("input".to_string(), path, true)),
+            Some(InputSetting::Many(roots)) => {
+                for (idx, root) in roots.iter().enumerate() {
+                    expected.push((format!("input.{idx}"), root.path(), true));
+                }
+            }
+            None => {}
+        }
+        if let Some(TemplateSetting::Path(path) | TemplateSetting::Table(TemplateConfig { path: Some(path), .. })) =
+            self.template.as_ref()
+        {
+            expected.push(("template".to_string(), path, false));
+        }
+        if let Some(icon) = self.site.as_ref().and_then(|site| site.icon.as_deref()) {
+            expected.push(("site.icon".to_string(), icon, false));
+        }
+        if let Some(api) = self.api.as_ref() {
+            expected.push(("api.json".to_string(), &api.json, false));
+        }
+        for (idx, package) in self.packages.iter().flatten().enumerate() {
+            expected.push((format!("packages.{idx}.path"), &package.path, true));
+        }
+        for (idx, plugin) in self.plugins.iter().flatten().enumerate() {
+            expected.push((format!("plugins.{idx}.path"), &plugin.path, false));
+        }
+        for (idx, version) in self.versions.iter().flatten().enumerate() {
+            if let (Some(input), None) = (version.input.as_deref(), version.git.as_ref()) {
+                expected.push((format!("versions.{idx}.input"), input, true));
+            }
+        }
+        expected
+            .into_iter()
+            .filter(|(_, path, dir)| if *dir { !path.is_dir() } else { !path.is_file() })
+            .map(|(key, path, dir)| {
+                let kind = if dir { "folder" } else { "file" };
+                Diagnostic::warning(
+                    "missing-config-path",
+                    config_path,
+                    format!("`{key}` names a {kind} that does not exist: {}", path.display()),
+                )
+            })
+            .collect()
+    }
+
+    /// The roots after the first in an `input` list.
+    pub fn extra_inputs(&self) -> &[InputRoot] {
+        match self.input.as_ref() {
+            Some(InputSetting::Many(roots)) if !roots.is_empty() => &roots[1..],
+            _ => &[],
+        }
+    }
+
     fn resolve_paths(&mut self, base: &Path) {
-        if let Some(path) = self.input.as_mut() {
-            *path = resolve_path(base, path);
+        match self.input.as_mut() {
+            Some(InputSetting::One(path)) => *path = resolve_path(base, path),
+            Some(InputSetting::Many(roots)) => {
+                for root in roots {
+                    let path = root.path_mut();
+                    *path = resolve_path(base, path);
+                }
+            }
+            None => {}
+        }
+        match self.template.as_mut() {
+            Some(TemplateSetting::Path(path)) | Some(TemplateSetting::Table(TemplateConfig { path: Some(path), .. })) => {
+                *path = resolve_path(base, path);
+            }
+            _ => {}
+        }
+        if let Some(icon) = self.site.as_mut().and_then(|site| site.icon.as_mut()) {
+            *icon = resolve_path(base, icon);
         }
-        if let Some(path) = self.template.as_mut() {
-            *path = resolve_path(base, path);
+        if let Some(cache_dir) = self
+            .thumbnails
+            .as_mut()
+            .and_then(|thumbnails| thumbnails.cache_dir.as_mut())
+        {
+            *cache_dir = resolve_path(base, cache_dir);
+        }
+        if let Some(state_file) = self.links.as_mut().and_then(|links| links.state_file.as_mut()) {
+            *state_file = resolve_path(base, state_file);
+        }
+        if let Some(api) = self.api.as_mut() {
+            api.json = resolve_path(base, &api.json);
+        }
+        for package in self.packages.iter_mut().flatten() {
+            package.path = resolve_path(base, &package.path);
+        }
+        for plugin in self.plugins.iter_mut().flatten() {
+            plugin.path = resolve_path(base, &plugin.path);
         }
     }
 }
 
-pub fn load_config(path: Option<&Path>) -> Result<Option<Config>> {
-    let config_path = match path {
-        Some(path) => Some(path.to_path_buf()),
+pub const CONFIG_FILE: &str = "rendar.toml";
+
+/// Which config file to read and how.
+#[derive(Clone, Debug, Default)]
+pub struct ConfigSource {
+    /// `--config`; `None` reads `rendar.toml` when that exists.
+    pub path: Option<PathBuf>,
+    /// Fail on unknown config keys instead of warning about them.
+    pub strict: bool,
+    /// The `[profile.<name>]` to apply, and whether it was chosen with
+    /// `--profile`. A chosen profile must exist; the per-command default is
+    /// skipped when the file has none.
+    pub profile: Option<(String, bool)>,
+}
+
+pub fn load_config(source: &ConfigSource) -> Result<Option<Config>> {
+    let Some((config, _, unknown)) = read_config(source)? else {
+        return Ok(None);
+    };
+    if !unknown.is_empty() && source.strict {
+        let keys: Vec<String> = unknown.iter().map(ToString::to_string).collect();
+        return Err(anyhow::anyhow!("{}", keys.join("\n")));
+    }
+    unknown.iter().for_each(print_diagnostic);
+    Ok(Some(config))
+}
+
+/// The config at `path`, or in `rendar.toml` when that exists, with paths
+/// resolved against the file's folder. Also returns the file's path and
+/// warnings for unknown keys.
+pub fn read_config(source: &ConfigSource) -> Result<Option<(Config, PathBuf, Vec<Diagnostic>)>> {
+    let config_path = match &source.path {
+        Some(path) => Some(path.clone()),
         None => {
-            let candidate = PathBuf::from("rendar.toml");
+            let candidate = PathBuf::from(CONFIG_FILE);
             if candidate.exists() {
                 Some(candidate)
             } else {
@@ -46,11 +434,116 @@ pub fn load_config(path: Option<&Path>) -> Result<Option<Config>> {
 
     let raw = std::fs::read_to_string(&config_path)
         .with_context(|| format!("Failed to read config {}", config_path.display()))?;
-    let mut config: Config =
-        toml::from_str(&raw).context("Failed to parse rendar.toml")?;
+    let (mut config, unknown) = parse_config(&raw, &config_path)?;
+    if let Some((name, explicit)) = &source.profile {
+        config = select_profile(config, &raw, &config_path, name, *explicit)?;
+    }
     let base_dir = config_path.parent().unwrap_or(Path::new("."));
     config.resolve_paths(base_dir);
-    Ok(Some(config))
+    Ok(Some((config, config_path, unknown)))
+}
+
+/// JSON Schema of `rendar.toml`, for editor completion and validation.
+pub fn config_schema() -> String {
+    let schema = schemars::schema_for!(Config);
+    serde_json::to_string_pretty(&schema).unwrap_or_default()
+}
+
+/// The config and a warning for every key rendar doesn't know, which is
+/// usually a typo like `templat = ...`.
+fn parse_config(raw: &str, path: &Path) -> Result<(Config, Vec<Diagnostic>)> {
+    let mut unknown = Vec::new();
+    let config = serde_ignored::deserialize(toml::Deserializer::new(raw), |key| {
+        // `?` marks a step through an `Option`, which the file doesn't show.
+        let key = key.to_string();
+        unknown.push(key.split('.').filter(|part| *part != "?").collect::<Vec<_>>().join("."));
+    })
+    .with_context(|| format!("Failed to parse {}", path.display()))?;
+    let warnings = unknown
+        .into_iter()
+        .map(|key| {
+            let warning = Diagnostic::warning("unknown-config-key", path, format!("Unknown config key `{key}`"));
+            match key_line(raw, &key) {
+                Some(line) => warning.at(line, 1),
+                None => warning,
+            }
+        })
+        .collect();
+    Ok((config, warnings))
+}
+
+fn select_profile(config: Config, raw: &str, path: &Path, name: &str, explicit: bool) -> Result<Config> {
+    if config.profile.contains_key(name) {
+        return apply_profile(raw, path, name);
+    }
+    if !explicit {
+        return Ok(config);
+    }
+    let known: Vec<&str> = config.profile.keys().map(String::as_str).collect();
+    Err(anyhow::anyhow!(
+        "{} has no [profile.{name}]{}",
+        path.display(),
+        if known.is_empty() {
+            String::new()
+        } else {
+            format!("; profiles: {}", known.join(", "))
+        }
+    ))
+}
+
+/// The config with `[profile.<name>]` laid over the top-level settings:
+/// tables merge key by key, everything else is replaced.
+fn apply_profile(raw: &str, path: &Path, name: &str) -> Result<Config> {
+    fn merge(base: &mut toml::Table, overrides: toml::Table) {
+        for (key, value) in overrides {
+            match (base.get_mut(&key), value) {
+                (Some(toml::Value::Table(base)), toml::Value::Table(overrides)) => merge(base, overrides),
+                (_, value) => {
+                    base.insert(key, value);
+                }
+            }
+        }
+    }
+    let mut table: toml::Table = toml::from_str(raw).with_context(|| format!("Failed to parse {}", path.display()))?;
+    let overrides = match table.remove("profile") {
+        Some(toml::Value::Table(mut profiles)) => match profiles.remove(name) {
+            Some(toml::Value::Table(overrides)) => overrides,
+            _ => toml::Table::new(),
+        },
+        _ => toml::Table::new(),
+    };
+    merge(&mut table, overrides);
+    Config::deserialize(toml::Value::Table(table))
+        .with_context(|| format!("Failed to apply [profile.{name}] from {}", path.display()))
+}
+
+/// The line that sets `key`, a dotted path like `preview.prot` or
+/// `packages.0.pathh` (the first `[[packages]]` entry).
+fn key_line(raw: &str, key: &str) -> Option<usize> {
+    let parts: Vec<&str> = key.split('.').collect();
+    let (name, tables) = parts.split_last()?;
+    let header = tables
+        .iter()
+        .filter(|part| part.parse::<usize>().is_err())
+        .copied()
+        .collect::<Vec<_>>()
+        .join(".");
+    let nth = tables.iter().rev().find_map(|part| part.parse::<usize>().ok()).unwrap_or(0);
+    let mut in_table = header.is_empty();
+    let mut seen = 0;
+    for (idx, line) in raw.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            let table = line.trim_start_matches('[').split(']').next().unwrap_or_default().trim();
+            in_table = table == header && seen == nth;
+            if table == header {
+                seen += 1;
+            }
+        } else if in_table && line.split('=').next().map(|key| key.trim().trim_matches('"')) == Some(name) {
+            return Some(idx + 1);
+        }
+    }
+    None
 }
 
 fn resolve_path(base: &Path, path: &Path) -> PathBuf {
@@ -66,6 +559,13 @@ mod tests {
     use super::*;
     use tempfile::tempdir;
 
+    fn source(path: &Path) -> ConfigSource {
+        ConfigSource {
+            path: Some(path.to_path_buf()),
+            ..ConfigSource::default()
+        }
+    }
+
     #[test]
     fn loads_and_resolves_paths() {
         let dir = tempdir().expect("tempdir");
@@ -78,12 +578,21 @@ exclude = ["AGENTS.md", "CLAUDE.md"]
 [preview]
 port = 4040
 open = true
+
+[api]
+json = "target/doc/demo.json"
+
+[[packages]]
+path = "../shared/security"
+variables = { company = "ACME" }
 "#;
         std::fs::write(&config_path, content).expect("write config");
-        let config = load_config(Some(&config_path)).expect("load config");
+        let config = load_config(&source(&config_path)).expect("load config");
         let config = config.expect("config should exist");
-        assert_eq!(config.input.unwrap(), dir.path().join("docs"));
-        assert_eq!(config.template.unwrap(), dir.path().join("theme.html"));
+        assert_eq!(config.input().unwrap(), dir.path().join("docs"));
+        assert!(config.extra_inputs().is_empty());
+        assert_eq!(config.template_path().unwrap(), dir.path().join("theme.html"));
+        assert!(config.template_vars().is_empty());
         assert_eq!(
             config.exclude.unwrap(),
             vec!["AGENTS.md".to_string(), "CLAUDE.md".to_string()]
@@ -91,5 +600,109 @@ open = true
         let preview = config.preview.expect("preview config");
         assert_eq!(preview.port, Some(4040));
         assert_eq!(preview.open, Some(true));
+        let api = config.api.expect("api config");
+        assert_eq!(api.json, dir.path().join("target/doc/demo.json"));
+        assert_eq!(api.mount, None);
+        let packages = config.packages.expect("packages");
+        assert_eq!(packages[0].path, dir.path().join("../shared/security"));
+        assert_eq!(packages[0].variables.get("company").map(String::as_str), Some("ACME"));
+    }
+
+    #[test]
+    fn reports_unknown_keys_with_lines() {
+        let raw = "input = \"docs\"\ntemplat = \"theme.html\"\n\n[preview]\nport = 4000\nprot = 4000\n\n\
+                   [[packages]]\npath = \"a\"\n\n[[packages]]\npath = \"b\"\nmout = \"c\"\n";
+        let (config, unknown) = parse_config(raw, Path::new("rendar.toml")).expect("parse");
+        assert_eq!(config.input(), Some(PathBuf::from("docs")));
+        let found: Vec<String> = unknown.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            found,
+            [
+                "rendar.toml:2:1: Unknown config key `templat`",
+                "rendar.toml:6:1: Unknown config key `preview.prot`",
+                "rendar.toml:13:1: Unknown config key `packages.1.mout`",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_missing_paths() {
+        let dir = tempdir().expect("tempdir");
+        std::fs::create_dir_all(dir.path().join("docs")).expect("docs");
+        std::fs::write(dir.path().join("docs/theme.html"), "").expect("not a folder");
+        let config_path = dir.path().join("rendar.toml");
+        std::fs::write(
+            &config_path,
+            "input = [\"docs\", \"docs/theme.html\"]\ntemplate = \"docs\"\n[[packages]]\npath = \"docs\"\n",
+        )
+        .expect("write config");
+        let (mut config, _, unknown) = read_config(&source(&config_path)).expect("read").expect("config");
+        assert!(unknown.is_empty());
+        let messages: Vec<String> = config
+            .missing_paths(&config_path)
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect();
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].starts_with("`input.1` names a folder that does not exist"));
+        assert!(messages[1].starts_with("`template` names a file that does not exist"));
+
+        config.set_input(PathBuf::from("site"));
+        assert_eq!(config.input(), Some(PathBuf::from("site")));
+        assert_eq!(config.extra_inputs().len(), 1);
+        assert!(config_schema().contains("\"markdown_extensions\""));
+    }
+
+    #[test]
+    fn applies_the_selected_profile() {
+        let path = Path::new("rendar.toml");
+        let raw = "exclude = [\"drafts/**\"]\n[site]\ntitle = \"Docs\"\nbase_url = \"https://docs.example.com/\"\n\
+                   [profile.dev]\nexclude = []\n[profile.dev.site]\nbase_url = \"http://localhost:8000/\"\n";
+        let (config, unknown) = parse_config(raw, path).expect("parse");
+        assert!(unknown.is_empty());
+
+        let dev = select_profile(config, raw, path, "dev", true).expect("dev");
+        assert_eq!(dev.exclude, Some(Vec::new()));
+        let site = dev.site.expect("site");
+        assert_eq!(site.title.as_deref(), Some("Docs"));
+        assert_eq!(site.base_url.as_deref(), Some("http://localhost:8000/"));
+
+        let (config, _) = parse_config(raw, path).expect("parse");
+        let production = select_profile(config, raw, path, "production", false).expect("implicit");
+        assert_eq!(production.exclude, Some(vec!["drafts/**".to_string()]));
+        let (config, _) = parse_config(raw, path).expect("parse");
+        let Err(err) = select_profile(config, raw, path, "staging", true) else {
+            panic!("unknown profile should fail");
+        };
+        assert_eq!(err.to_string(), "rendar.toml has no [profile.staging]; profiles: dev");
+    }
+
+    #[test]
+    fn loads_input_roots_and_template_vars() {
+        let dir = tempdir().expect("tempdir");
+        let config_path = dir.path().join("rendar.toml");
+        let content = r#"
+input = ["docs", "guides", { path = "../shared-docs", mount = "shared" }]
+
+[template.vars]
+site_name = "Handbook"
+"#;
+        std::fs::write(&config_path, content).expect("write config");
+        let config = load_config(&source(&config_path)).expect("load config").expect("config");
+        assert_eq!(config.input().unwrap(), dir.path().join("docs"));
+        let extra: Vec<(PathBuf, PathBuf)> = config
+            .extra_inputs()
+            .iter()
+            .map(|root| (root.path().to_path_buf(), root.mount()))
+            .collect();
+        assert_eq!(
+            extra,
+            [
+                (dir.path().join("guides"), PathBuf::from("guides")),
+                (dir.path().join("../shared-docs"), PathBuf::from("shared")),
+            ]
+        );
+        assert_eq!(config.template_path(), None);
+        assert_eq!(config.template_vars().get("site_name").map(String::as_str), Some("Handbook"));
     }
 }
@@ -1,8 +1,120 @@
+use crate::config::CsvConfig;
+use crate::settings::Settings;
 use anyhow::{Context, Result};
-use csv::ReaderBuilder;
+use csv::{Reader, ReaderBuilder, StringRecord};
+use globset::{Glob, GlobMatcher};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Read};
 use std::path::Path;
 
-const SORT_SCRIPT: &str = r#"<script>
+/// How much of a file delimiter detection looks at.
+const SAMPLE_BYTES: u64 = 64 * 1024;
+
+/// Ends the name of the file next to a CSV that overrides how it is read,
+/// like `people.csv.toml`.
+const SIDECAR_SUFFIX: &str = ".csv.toml";
+
+/// The `[csv."<glob>"]` settings, and whether rows past the embedded ones
+/// are written to JSON files the page loads on demand. Row files are off
+/// for sites opened from disk, where pages can't fetch them.
+pub struct CsvSettings {
+    overrides: Vec<(GlobMatcher, CsvOptions)>,
+    pub row_files: bool,
+}
+
+impl Default for CsvSettings {
+    fn default() -> Self {
+        Self {
+            overrides: Vec::new(),
+            row_files: true,
+        }
+    }
+}
+
+impl CsvSettings {
+    /// Reads CSV files whose path under the input matches one of the
+    /// `[csv."<glob>"]` patterns with its settings. Where several match, the
+    /// longest pattern wins for each setting.
+    pub fn new(overrides: &BTreeMap<String, CsvConfig>) -> Result<Self> {
+        let mut compiled = Vec::new();
+        for (pattern, config) in overrides {
+            let glob = Glob::new(pattern).with_context(|| format!("Invalid csv pattern: {pattern}"))?;
+            let options = CsvOptions::from_config(config).with_context(|| format!("Invalid [csv.\"{pattern}\"]"))?;
+            compiled.push((pattern.len(), glob.compile_matcher(), options));
+        }
+        compiled.sort_by_key(|(len, ..)| *len);
+        Ok(Self {
+            overrides: compiled.into_iter().map(|(_, glob, options)| (glob, options)).collect(),
+            ..Self::default()
+        })
+    }
+}
+
+/// `<name>.csv.toml` files are read along with their CSV rather than
+/// copied as assets.
+pub fn is_csv_sidecar(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.to_ascii_lowercase().ends_with(SIDECAR_SUFFIX))
+}
+
+/// What is known about a CSV file without looking at its contents.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct CsvOptions {
+    delimiter: Option<u8>,
+    has_header: Option<bool>,
+}
+
+impl CsvOptions {
+    fn from_config(config: &CsvConfig) -> Result<Self> {
+        let delimiter = match config.delimiter.as_deref() {
+            None => None,
+            Some(delimiter) if delimiter.len() == 1 && delimiter.is_ascii() => Some(delimiter.as_bytes()[0]),
+            Some(delimiter) => {
+                return Err(anyhow::anyhow!("delimiter must be a single ASCII character, not {delimiter:?}"));
+            }
+        };
+        Ok(Self {
+            delimiter,
+            has_header: config.has_header,
+        })
+    }
+
+    fn or(self, fallback: Self) -> Self {
+        Self {
+            delimiter: self.delimiter.or(fallback.delimiter),
+            has_header: self.has_header.or(fallback.has_header),
+        }
+    }
+
+    /// The settings for the CSV at `path`, which is `rel_path` under the
+    /// input: its sidecar file first, then the `[csv]` patterns.
+    fn for_file(path: &Path, rel_path: &Path, csv: &CsvSettings) -> Result<Self> {
+        let mut options = Self::default();
+        for (glob, overrides) in &csv.overrides {
+            if glob.is_match(rel_path) {
+                options = overrides.or(options);
+            }
+        }
+        let mut sidecar = path.as_os_str().to_os_string();
+        sidecar.push(".toml");
+        let sidecar = Path::new(&sidecar);
+        if !sidecar.is_file() {
+            return Ok(options);
+        }
+        let raw = std::fs::read_to_string(sidecar).with_context(|| format!("Failed to read {}", sidecar.display()))?;
+        let config: CsvConfig =
+            toml::from_str(&raw).with_context(|| format!("Failed to parse {}", sidecar.display()))?;
+        let own = Self::from_config(&config).with_context(|| format!("Invalid {}", sidecar.display()))?;
+        Ok(own.or(options))
+    }
+}
+
+/// Sorting, filtering, column stats, and loading of row files for CSV
+/// tables.
+const TABLE_SCRIPT: &str = r#"<script>
 (function () {
   if (window.__rendarCsvSort) {
     return;
@@ -69,9 +181,288 @@ const SORT_SCRIPT: &str = r#"<script>
     });
   }
 
+  // A test for the values of a column, from what was typed into its
+  // filter: `>10`, `<=5`, `=3`, or `10..20` for numeric columns, `=text`
+  // for an exact match, and otherwise a case-insensitive substring.
+  function columnTest(query, numeric) {
+    query = query.trim();
+    if (query === "") {
+      return null;
+    }
+    if (numeric) {
+      var range = query.match(/^(\S+)\s*\.\.\s*(\S+)$/);
+      if (range && isNumeric(range[1]) && isNumeric(range[2])) {
+        var low = Number(range[1]);
+        var high = Number(range[2]);
+        return function (value) {
+          var number = isNumeric(value) ? Number(value) : NaN;
+          return number >= low && number <= high;
+        };
+      }
+      var comparison = query.match(/^(<=|>=|<|>|=)\s*(.+)$/);
+      if (comparison && isNumeric(comparison[2])) {
+        var op = comparison[1];
+        var bound = Number(comparison[2]);
+        return function (value) {
+          if (!isNumeric(value)) {
+            return false;
+          }
+          var number = Number(value);
+          if (op === "<") {
+            return number < bound;
+          }
+          if (op === "<=") {
+            return number <= bound;
+          }
+          if (op === ">") {
+            return number > bound;
+          }
+          if (op === ">=") {
+            return number >= bound;
+          }
+          return number === bound;
+        };
+      }
+    }
+    var lower = query.toLowerCase();
+    if (lower.charAt(0) === "=") {
+      var exact = lower.slice(1).trim();
+      return function (value) {
+        return value.toLowerCase() === exact;
+      };
+    }
+    return function (value) {
+      return value.toLowerCase().indexOf(lower) !== -1;
+    };
+  }
+
+  function formatNumber(number) {
+    return number.toLocaleString(undefined, { maximumFractionDigits: 2 });
+  }
+
+  function setupFilters(table) {
+    var tbody = table.tBodies[0];
+    var wrap = table.closest(".csv-table-wrap");
+    if (!tbody || !table.tHead || !wrap) {
+      return;
+    }
+    var strings = window.rendarStrings || {};
+    var headers = table.tHead.rows[0].cells;
+    var columns = headers.length;
+
+    var tools = document.createElement("div");
+    tools.className = "csv-tools";
+    var search = document.createElement("input");
+    search.type = "search";
+    search.className = "csv-filter";
+    search.placeholder = strings.csv_filter || "Filter rows";
+    search.setAttribute("aria-label", search.placeholder);
+    var statsButton = document.createElement("button");
+    statsButton.type = "button";
+    statsButton.className = "csv-stats-toggle";
+    statsButton.textContent = strings.csv_stats || "Column stats";
+    statsButton.setAttribute("aria-pressed", "false");
+    var count = document.createElement("span");
+    count.className = "csv-count";
+    count.setAttribute("aria-live", "polite");
+    tools.appendChild(search);
+    tools.appendChild(statsButton);
+    tools.appendChild(count);
+    wrap.parentNode.insertBefore(tools, wrap);
+
+    var filterRow = document.createElement("tr");
+    filterRow.className = "csv-column-filters";
+    var inputs = [];
+    for (var index = 0; index < columns; index += 1) {
+      var cell = document.createElement("td");
+      var input = document.createElement("input");
+      input.type = "search";
+      input.setAttribute(
+        "aria-label",
+        (strings.csv_filter_column || "Filter {column}").replace("{column}", headers[index].textContent)
+      );
+      cell.appendChild(input);
+      filterRow.appendChild(cell);
+      inputs.push(input);
+    }
+    table.tHead.appendChild(filterRow);
+
+    var foot = table.createTFoot();
+    foot.hidden = true;
+    var statsRow = foot.insertRow();
+    statsRow.className = "csv-stats";
+
+    var numericColumns = null;
+    function columnTypes() {
+      if (!numericColumns) {
+        var rows = Array.prototype.slice.call(tbody.rows);
+        numericColumns = inputs.map(function (_, column) {
+          var values = rows.map(function (row) {
+            return getCellValue(row, column);
+          }).filter(function (value) {
+            return value !== "";
+          });
+          return values.length > 0 && values.every(isNumeric);
+        });
+      }
+      return numericColumns;
+    }
+
+    function updateStats(rows) {
+      while (statsRow.cells.length) {
+        statsRow.deleteCell(0);
+      }
+      columnTypes().forEach(function (numeric, column) {
+        var values = rows.map(function (row) {
+          return getCellValue(row, column);
+        }).filter(function (value) {
+          return value !== "";
+        });
+        var lines = [[strings.csv_count || "count", String(values.length)]];
+        if (numeric && values.length > 0) {
+          var numbers = values.map(Number);
+          var min = numbers.reduce(function (a, b) { return Math.min(a, b); });
+          var max = numbers.reduce(function (a, b) { return Math.max(a, b); });
+          var sum = numbers.reduce(function (a, b) { return a + b; }, 0);
+          lines.push([strings.csv_min || "min", formatNumber(min)]);
+          lines.push([strings.csv_max || "max", formatNumber(max)]);
+          lines.push([strings.csv_mean || "mean", formatNumber(sum / numbers.length)]);
+        }
+        var cell = statsRow.insertCell();
+        lines.forEach(function (line) {
+          var entry = document.createElement("div");
+          entry.textContent = line[0] + ": " + line[1];
+          cell.appendChild(entry);
+        });
+      });
+    }
+
+    function apply() {
+      var rows = Array.prototype.slice.call(tbody.rows);
+      var types = columnTypes();
+      var tests = inputs.map(function (input, column) {
+        return columnTest(input.value, types[column]);
+      });
+      var query = search.value.trim().toLowerCase();
+      var visible = rows.filter(function (row) {
+        var show = tests.every(function (test, column) {
+          return !test || test(getCellValue(row, column));
+        });
+        if (show && query) {
+          var text = Array.prototype.map.call(row.cells, function (cell) {
+            return cell.textContent;
+          }).join("\t").toLowerCase();
+          show = text.indexOf(query) !== -1;
+        }
+        row.hidden = !show;
+        return show;
+      });
+      count.textContent = visible.length === rows.length
+        ? ""
+        : (strings.csv_matching || "{count} of {total} rows")
+            .replace("{count}", String(visible.length))
+            .replace("{total}", String(rows.length));
+      if (!foot.hidden) {
+        updateStats(visible);
+      }
+    }
+
+    search.addEventListener("input", apply);
+    inputs.forEach(function (input) {
+      input.addEventListener("input", apply);
+    });
+    statsButton.addEventListener("click", function () {
+      foot.hidden = !foot.hidden;
+      statsButton.setAttribute("aria-pressed", String(!foot.hidden));
+      apply();
+    });
+    table.addEventListener("csv-rows-added", function () {
+      numericColumns = null;
+      apply();
+    });
+  }
+
+  function renderCell(value) {
+    var cell = document.createElement("td");
+    var trimmed = value.trim();
+    if (/^https?:\/\//i.test(trimmed)) {
+      var link = document.createElement("a");
+      link.href = trimmed;
+      link.target = "_blank";
+      link.rel = "noopener noreferrer";
+      link.textContent = trimmed;
+      cell.appendChild(link);
+    } else {
+      cell.textContent = value;
+    }
+    return cell;
+  }
+
+  function setupLoading(preview) {
+    var button = preview.querySelector(".csv-load-more");
+    var tbody = preview.querySelector("table.csv-table tbody");
+    var shown = preview.querySelector(".csv-shown");
+    if (!button || !tbody) {
+      return;
+    }
+    var columns = preview.querySelectorAll("table.csv-table thead th").length;
+    var loading = false;
+    function load() {
+      var next = preview.getAttribute("data-rows-next");
+      if (loading || !next) {
+        return;
+      }
+      loading = true;
+      button.disabled = true;
+      fetch(new URL(next, document.baseURI))
+        .then(function (response) {
+          if (!response.ok) {
+            throw new Error(response.statusText);
+          }
+          return response.json();
+        })
+        .then(function (page) {
+          var rows = document.createDocumentFragment();
+          page.rows.forEach(function (values) {
+            var row = document.createElement("tr");
+            for (var index = 0; index < columns; index += 1) {
+              row.appendChild(renderCell(values[index] || ""));
+            }
+            rows.appendChild(row);
+          });
+          tbody.appendChild(rows);
+          tbody.parentNode.dispatchEvent(new CustomEvent("csv-rows-added"));
+          if (shown) {
+            shown.textContent = String(tbody.rows.length);
+          }
+          if (page.next) {
+            preview.setAttribute("data-rows-next", page.next);
+          } else {
+            preview.removeAttribute("data-rows-next");
+            button.remove();
+          }
+        })
+        .catch(function () {})
+        .then(function () {
+          loading = false;
+          button.disabled = false;
+        });
+    }
+    button.addEventListener("click", load);
+    if (window.IntersectionObserver) {
+      new IntersectionObserver(function (entries) {
+        if (entries.some(function (entry) { return entry.isIntersecting; })) {
+          load();
+        }
+      }, { root: preview.querySelector(".csv-table-wrap") }).observe(button);
+    }
+  }
+
   function init() {
     var tables = document.querySelectorAll("table.csv-table");
     Array.prototype.forEach.call(tables, setupTable);
+    Array.prototype.forEach.call(tables, setupFilters);
+    Array.prototype.forEach.call(document.querySelectorAll(".csv-preview[data-rows-next]"), setupLoading);
   }
 
   if (document.readyState === "loading") {
@@ -123,55 +514,83 @@ pub fn csv_extra_head() -> &'static str {
     CSV_PAGE_HEAD
 }
 
-pub fn render_csv_file(path: &Path, max_rows: Option<usize>) -> Result<String> {
-    let contents = std::fs::read_to_string(path)
-        .with_context(|| format!("Failed to read CSV file {}", path.display()))?;
-    let delimiter = detect_delimiter(&contents);
-
-    let mut reader = ReaderBuilder::new()
-        .delimiter(delimiter)
-        .has_headers(false)
-        .from_reader(contents.as_bytes());
+/// Renders the CSV at `path` as a sortable table, streaming records from
+/// disk. Only the first `max_rows` rows are embedded. With `row_files`, the
+/// output path of the page, the rest are written next to it as JSON files
+/// of `max_rows` rows each (`data.rows-1.json`, ...), which the page loads
+/// as the reader scrolls; otherwise the table is cut off with a notice.
+/// `rel_path` is the file's path under the input, for `[csv]` patterns.
+pub fn render_csv_file(
+    path: &Path,
+    rel_path: &Path,
+    max_rows: Option<usize>,
+    row_files: Option<&Path>,
+    settings: &Settings,
+) -> Result<String> {
+    let locale = &settings.locale;
+    let options = CsvOptions::for_file(path, rel_path, &settings.csv)?;
+    let mut records = csv_reader(path, options)?.into_records();
+    let mut read_row = || -> Result<Option<Vec<String>>> {
+        records
+            .next()
+            .transpose()
+            .context("Failed to parse CSV record")
+            .map(|record| record.as_ref().map(record_cells))
+    };
 
-    let read_cap = max_rows.map(|limit| limit.saturating_add(2));
     let mut rows: Vec<Vec<String>> = Vec::new();
-    let mut truncated = false;
-
-    for result in reader.records() {
-        let record = result.context("Failed to parse CSV record")?;
-        if let Some(limit) = read_cap {
-            if rows.len() >= limit {
-                truncated = true;
-                break;
-            }
+    while rows.len() < 2 {
+        match read_row()? {
+            Some(row) => rows.push(row),
+            None => break,
         }
-        rows.push(record.iter().map(|cell| cell.to_string()).collect());
     }
-
     if rows.is_empty() {
         return Ok(r#"<div class="csv-preview"><div class="csv-empty">Empty CSV.</div></div>"#
             .to_string());
     }
 
-    let header_row = if rows.len() >= 2 && is_header_row(&rows[0], &rows[1]) {
-        Some(rows[0].clone())
+    let header_row = if options
+        .has_header
+        .unwrap_or_else(|| rows.len() >= 2 && is_header_row(&rows[0], &rows[1]))
+    {
+        Some(rows.remove(0))
     } else {
         None
     };
-
-    let data_start = if header_row.is_some() { 1 } else { 0 };
-    let mut data_rows: Vec<Vec<String>> = rows.into_iter().skip(data_start).collect();
-
-    let mut data_truncated = false;
-    if let Some(limit) = max_rows {
-        if data_rows.len() > limit {
-            data_rows.truncate(limit);
-            data_truncated = true;
+    let mut data_rows = rows;
+    while max_rows.is_none_or(|limit| data_rows.len() < limit) {
+        match read_row()? {
+            Some(row) => data_rows.push(row),
+            None => break,
         }
     }
-    if truncated {
-        data_truncated = true;
+    // Rows read to look for a header but past the cap go on to the row
+    // files, or count as cut off.
+    let mut overflow = match max_rows {
+        Some(limit) if data_rows.len() > limit => data_rows.split_off(limit),
+        _ => Vec::new(),
     }
+    .into_iter();
+    let mut next_row = || -> Result<Option<Vec<String>>> {
+        match overflow.next() {
+            Some(row) => Ok(Some(row)),
+            None => read_row(),
+        }
+    };
+
+    let rest = match (max_rows, row_files.filter(|_| settings.csv.row_files)) {
+        (Some(limit), Some(page)) => {
+            let (written, files) = write_row_files(page, limit, &mut next_row)?;
+            remove_row_files(page, files + 1)?;
+            (written > 0).then(|| RestOfRows::Files {
+                total: data_rows.len() + written,
+                first: row_file_name(page, 1),
+            })
+        }
+        (Some(_), None) => next_row()?.map(|_| RestOfRows::Cut),
+        (None, _) => None,
+    };
 
     let mut max_cols = header_row.as_ref().map(|row| row.len()).unwrap_or(0);
     for row in &data_rows {
@@ -183,17 +602,36 @@ pub fn render_csv_file(path: &Path, max_rows: Option<usize>) -> Result<String> {
 
     let header = header_row.unwrap_or_else(|| {
         (1..=max_cols)
-            .map(|idx| format!("Column {}", idx))
+            .map(|idx| locale.text_with("csv_column", &[("index", &idx.to_string())]))
             .collect()
     });
 
     let mut html = String::new();
-    html.push_str(r#"<div class="csv-preview">"#);
-    if data_truncated {
-        html.push_str(&format!(
-            r#"<div class="csv-notice">Showing first {} rows.</div>"#,
-            data_rows.len()
-        ));
+    match &rest {
+        Some(RestOfRows::Files { total, first }) => {
+            html.push_str(&format!(
+                r#"<div class="csv-preview" data-rows-next="{}">"#,
+                html_escape(first)
+            ));
+            html.push_str(&format!(
+                r#"<div class="csv-notice">{}</div>"#,
+                locale.text_with(
+                    "csv_partial",
+                    &[
+                        ("shown", &format!(r#"<span class="csv-shown">{}</span>"#, data_rows.len())),
+                        ("total", &total.to_string()),
+                    ]
+                )
+            ));
+        }
+        Some(RestOfRows::Cut) => {
+            html.push_str(r#"<div class="csv-preview">"#);
+            html.push_str(&format!(
+                r#"<div class="csv-notice">{}</div>"#,
+                locale.text_with("csv_truncated", &[("rows", &data_rows.len().to_string())])
+            ));
+        }
+        None => html.push_str(r#"<div class="csv-preview">"#),
     }
     html.push_str(r#"<div class="csv-table-wrap">"#);
     html.push_str(r#"<table class="csv-table">"#);
@@ -215,13 +653,127 @@ pub fn render_csv_file(path: &Path, max_rows: Option<usize>) -> Result<String> {
         }
         html.push_str("</tr>");
     }
-    html.push_str("</tbody></table></div>");
+    html.push_str("</tbody></table>");
+    if let Some(RestOfRows::Files { .. }) = rest {
+        html.push_str(&format!(
+            r#"<button type="button" class="csv-load-more">{}</button>"#,
+            html_escape(locale.text("csv_load_more"))
+        ));
+    }
+    html.push_str("</div>");
     html.push_str("</div>");
-    html.push_str(SORT_SCRIPT);
+    html.push_str(TABLE_SCRIPT);
 
     Ok(html)
 }
 
+/// Rows a CSV page doesn't embed.
+enum RestOfRows {
+    /// Written to row files, the first named `first`.
+    Files { total: usize, first: String },
+    /// Left out.
+    Cut,
+}
+
+#[derive(Serialize)]
+struct RowFile<'a> {
+    rows: &'a [Vec<String>],
+    /// The next file, relative to the page.
+    next: Option<String>,
+}
+
+/// Writes the rows left in `next_row` to row files of `per_file` rows next
+/// to `page`, and returns how many rows and files there were.
+fn write_row_files(
+    page: &Path,
+    per_file: usize,
+    next_row: &mut impl FnMut() -> Result<Option<Vec<String>>>,
+) -> Result<(usize, usize)> {
+    let mut written = 0;
+    let mut chunk: Vec<Vec<String>> = Vec::with_capacity(per_file);
+    let mut index = 1;
+    while let Some(row) = next_row()? {
+        if chunk.len() == per_file {
+            write_row_file(page, index, &chunk, Some(row_file_name(page, index + 1)))?;
+            written += chunk.len();
+            chunk.clear();
+            index += 1;
+        }
+        chunk.push(row);
+    }
+    if chunk.is_empty() {
+        return Ok((written, 0));
+    }
+    write_row_file(page, index, &chunk, None)?;
+    Ok((written + chunk.len(), index))
+}
+
+fn write_row_file(page: &Path, index: usize, rows: &[Vec<String>], next: Option<String>) -> Result<()> {
+    let path = page.with_file_name(row_file_name(page, index));
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create output directory {}", parent.display()))?;
+    }
+    let file = File::create(&path).with_context(|| format!("Failed to write CSV rows {}", path.display()))?;
+    serde_json::to_writer(BufWriter::new(file), &RowFile { rows, next })
+        .with_context(|| format!("Failed to write CSV rows {}", path.display()))
+}
+
+/// `data.rows-2.json` for the second row file of `data.html`.
+fn row_file_name(page: &Path, index: usize) -> String {
+    let stem = page.file_stem().and_then(|stem| stem.to_str()).unwrap_or("rows");
+    format!("{stem}.rows-{index}.json")
+}
+
+/// Removes row files from index `from` on, left by an earlier build of a
+/// longer file.
+fn remove_row_files(page: &Path, from: usize) -> Result<()> {
+    for index in from.. {
+        let stale = page.with_file_name(row_file_name(page, index));
+        if !stale.is_file() {
+            return Ok(());
+        }
+        std::fs::remove_file(&stale)
+            .with_context(|| format!("Failed to remove stale output {}", stale.display()))?;
+    }
+    Ok(())
+}
+
+/// A reader over the records of the CSV at `path`, with its delimiter
+/// taken from `options` or detected from the start of the file.
+fn csv_reader(path: &Path, options: CsvOptions) -> Result<Reader<File>> {
+    csv_reader_with(path, options, ReaderBuilder::new().has_headers(false))
+}
+
+fn csv_reader_with(path: &Path, options: CsvOptions, builder: &mut ReaderBuilder) -> Result<Reader<File>> {
+    let delimiter = match options.delimiter {
+        Some(delimiter) => delimiter,
+        None => sniff_delimiter(path)?,
+    };
+    builder
+        .delimiter(delimiter)
+        .from_path(path)
+        .with_context(|| format!("Failed to read CSV file {}", path.display()))
+}
+
+fn sniff_delimiter(path: &Path) -> Result<u8> {
+    let mut sample = Vec::new();
+    File::open(path)
+        .and_then(|file| file.take(SAMPLE_BYTES).read_to_end(&mut sample))
+        .with_context(|| format!("Failed to read CSV file {}", path.display()))?;
+    // A line cut off at the end of the sample would skew the counts.
+    if sample.len() as u64 == SAMPLE_BYTES
+        && let Some(end) = sample.iter().rposition(|&byte| byte == b'\n')
+    {
+        sample.truncate(end + 1);
+    }
+    Ok(detect_delimiter(&String::from_utf8_lossy(&sample)))
+}
+
+fn record_cells(record: &StringRecord) -> Vec<String> {
+    record.iter().map(|cell| cell.to_string()).collect()
+}
+
 fn detect_delimiter(sample: &str) -> u8 {
     let candidates = [b',', b';', b'\t', b'|'];
     let mut best = b',';
@@ -276,6 +828,48 @@ fn delimiter_score(bytes: &[u8], delimiter: u8) -> i32 {
     (mean * 100.0) as i32 - ((max - min) as i32 * 10) - (zero_lines as i32 * 25)
 }
 
+/// Whether the rendered table of the CSV at `path` gets a header row. Files
+/// with fewer than two rows count as having one.
+pub fn csv_has_header(path: &Path, rel_path: &Path, settings: &Settings) -> Result<bool> {
+    let options = CsvOptions::for_file(path, rel_path, &settings.csv)?;
+    if let Some(has_header) = options.has_header {
+        return Ok(has_header);
+    }
+    let mut reader = csv_reader(path, options)?;
+    let mut rows = Vec::new();
+    for result in reader.records().take(2) {
+        let record = result.context("Failed to parse CSV record")?;
+        rows.push(record_cells(&record));
+    }
+    Ok(rows.len() < 2 || is_header_row(&rows[0], &rows[1]))
+}
+
+/// The rows of the CSV at `path` as maps from column name to cell, taking
+/// the first row as the header. Of the file's settings, only the delimiter
+/// applies.
+pub fn csv_records(path: &Path, rel_path: &Path, settings: &Settings) -> Result<Vec<BTreeMap<String, String>>> {
+    let options = CsvOptions::for_file(path, rel_path, &settings.csv)?;
+    let mut reader = csv_reader_with(path, options, ReaderBuilder::new().flexible(true))?;
+    let header: Vec<String> = reader
+        .headers()
+        .with_context(|| format!("Failed to read the header row of {}", path.display()))?
+        .iter()
+        .map(|name| name.trim().to_string())
+        .collect();
+    let mut records = Vec::new();
+    for result in reader.records() {
+        let record = result.with_context(|| format!("Failed to parse CSV record in {}", path.display()))?;
+        records.push(
+            header
+                .iter()
+                .cloned()
+                .zip(record.iter().map(str::to_string))
+                .collect(),
+        );
+    }
+    Ok(records)
+}
+
 fn is_header_row(first: &[String], second: &[String]) -> bool {
     if first.is_empty() || second.is_empty() {
         return false;
@@ -292,7 +886,7 @@ fn is_header_row(first: &[String], second: &[String]) -> bool {
         .filter(|cell| !cell.trim().is_empty() && !is_numeric(cell))
         .count();
 
-    let strong_header = first_text >= (cols + 1) / 2 && first_numeric < second_numeric;
+    let strong_header = first_text >= cols.div_ceil(2) && first_numeric < second_numeric;
     let text_heavier = first_text > second_text && first_numeric <= second_numeric;
     strong_header || text_heavier
 }
@@ -332,6 +926,7 @@ fn html_escape(input: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::i18n::Locale;
 
     #[test]
     fn detects_comma_delimiter() {
@@ -351,4 +946,145 @@ mod tests {
         let second = vec!["Ada".to_string(), "36".to_string()];
         assert!(is_header_row(&first, &second));
     }
+
+    #[test]
+    fn writes_rows_past_the_cap_to_row_files() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let csv = dir.path().join("data.csv");
+        let page = dir.path().join("out/data.html");
+        std::fs::write(&csv, "Name,Age\nAda,36\nBob,41\nCy,29\nDee,52\nEd,33\n").expect("csv");
+        std::fs::create_dir_all(dir.path().join("out")).expect("out dir");
+        std::fs::write(dir.path().join("out/data.rows-3.json"), "{}").expect("stale");
+
+        let html = render_csv_file(&csv, Path::new("data.csv"), Some(2), Some(&page), &Settings::default()).expect("render");
+        assert!(html.contains(r#"<div class="csv-preview" data-rows-next="data.rows-1.json">"#));
+        assert!(html.contains(r#"Showing <span class="csv-shown">2</span> of 5 rows."#));
+        assert!(html.contains("<td>Bob</td>") && !html.contains("<td>Cy</td>"));
+        assert!(html.contains(r#"<button type="button" class="csv-load-more">"#));
+        let rows = |index: usize| {
+            std::fs::read_to_string(dir.path().join(format!("out/data.rows-{index}.json"))).expect("row file")
+        };
+        assert_eq!(rows(1), r#"{"rows":[["Cy","29"],["Dee","52"]],"next":"data.rows-2.json"}"#);
+        assert_eq!(rows(2), r#"{"rows":[["Ed","33"]],"next":null}"#);
+        assert!(!dir.path().join("out/data.rows-3.json").exists());
+
+        let cut = render_csv_file(&csv, Path::new("data.csv"), Some(2), None, &Settings::default()).expect("render");
+        assert!(cut.contains("Showing first 2 rows.") && !cut.contains("<button"));
+        let all = render_csv_file(&csv, Path::new("data.csv"), None, Some(&page), &Settings::default()).expect("render");
+        assert!(all.contains("<td>Ed</td>") && !all.contains("csv-notice"));
+    }
+
+    #[test]
+    fn renders_the_markup_the_table_script_expects() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let csv = dir.path().join("sales.csv");
+        std::fs::write(&csv, "Region,Units\nNorth,12\nSouth,7.5\n").expect("csv");
+        let html = render_csv_file(&csv, Path::new("sales.csv"), None, None, &Settings::default()).expect("render");
+        assert!(html.starts_with(concat!(
+            r#"<div class="csv-preview"><div class="csv-table-wrap"><table class="csv-table">"#,
+            r#"<thead><tr><th scope="col">Region</th><th scope="col">Units</th></tr></thead>"#,
+            "<tbody><tr><td>North</td><td>12</td></tr><tr><td>South</td><td>7.5</td></tr></tbody></table>",
+        )));
+        // The filter row, stats row, and tools are added by the script.
+        let markup = html.strip_suffix(TABLE_SCRIPT).expect("table script");
+        assert!(!markup.contains("csv-tools") && !markup.contains("csv-stats"));
+        for selector in ["table.csv-table thead th", "table.csv-table tbody", ".csv-table-wrap", ".csv-preview[data-rows-next]", ".csv-shown", ".csv-load-more"] {
+            assert!(TABLE_SCRIPT.contains(&format!("\"{selector}\"")), "script no longer uses {selector}");
+        }
+    }
+
+    #[test]
+    fn table_script_strings_are_passed_to_pages() {
+        let mut rest = TABLE_SCRIPT;
+        let mut keys = Vec::new();
+        while let Some(start) = rest.find("strings.") {
+            let after = &rest[start + "strings.".len()..];
+            let key_len = after.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(after.len());
+            let key = &after[..key_len];
+            let fallback = after[key_len..]
+                .strip_prefix(" || \"")
+                .and_then(|tail| tail.split_once('"'))
+                .map(|(fallback, _)| fallback);
+            assert!(crate::i18n::SCRIPT_KEYS.contains(&key), "{key} is not in SCRIPT_KEYS");
+            assert_eq!(fallback, Some(Locale::default().text(key)), "English fallback for {key}");
+            keys.push(key);
+            rest = &after[key_len..];
+        }
+        assert!(keys.contains(&"csv_mean") && keys.contains(&"csv_filter_column"));
+    }
+
+    #[test]
+    fn keeps_rows_read_for_header_detection_past_the_cap() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let csv = dir.path().join("bare.csv");
+        let page = dir.path().join("bare.html");
+        std::fs::write(&csv, "1,2\n3,4\n5,6\n").expect("csv");
+
+        let html = render_csv_file(&csv, Path::new("bare.csv"), Some(1), Some(&page), &Settings::default()).expect("render");
+        assert!(html.contains(r#"Showing <span class="csv-shown">1</span> of 3 rows."#));
+        assert!(html.contains("<td>1</td>") && !html.contains("<td>3</td>"));
+        let rows = |index: usize| std::fs::read_to_string(dir.path().join(format!("bare.rows-{index}.json"))).expect("row file");
+        assert_eq!(rows(1), r#"{"rows":[["3","4"]],"next":"bare.rows-2.json"}"#);
+        assert_eq!(rows(2), r#"{"rows":[["5","6"]],"next":null}"#);
+
+        std::fs::write(&csv, "1,2\n3,4\n").expect("csv");
+        let cut = render_csv_file(&csv, Path::new("bare.csv"), Some(1), None, &Settings::default()).expect("render");
+        assert!(cut.contains("Showing first 1 rows.") && !cut.contains("<td>3</td>"));
+    }
+
+    #[test]
+    fn reports_whether_csv_files_have_a_header() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let named = dir.path().join("named.csv");
+        let bare = dir.path().join("bare.csv");
+        std::fs::write(&named, "Name,Age\nAda,36\n").expect("named");
+        std::fs::write(&bare, "1,2\n3,4\n").expect("bare");
+        assert!(csv_has_header(&named, Path::new("named.csv"), &Settings::default()).expect("named header"));
+        assert!(!csv_has_header(&bare, Path::new("bare.csv"), &Settings::default()).expect("bare header"));
+    }
+
+    #[test]
+    fn applies_pattern_and_sidecar_overrides() {
+        let overrides = BTreeMap::from([
+            (
+                "exports/*.csv".to_string(),
+                CsvConfig {
+                    delimiter: Some(";".to_string()),
+                    has_header: Some(true),
+                },
+            ),
+            (
+                "exports/raw-*.csv".to_string(),
+                CsvConfig {
+                    delimiter: None,
+                    has_header: Some(false),
+                },
+            ),
+        ]);
+        let settings = Settings {
+            csv: CsvSettings::new(&overrides).expect("overrides"),
+            ..Settings::default()
+        };
+        let dir = tempfile::tempdir().expect("tempdir");
+        let raw = dir.path().join("raw-1.csv");
+        std::fs::write(&raw, "Name;City\nAda;Paris, France\n").expect("raw");
+
+        let html = render_csv_file(&raw, Path::new("exports/raw-1.csv"), None, None, &settings).expect("render");
+        assert!(html.contains("<td>Name</td>") && html.contains("<td>Paris, France</td>"));
+        assert!(!csv_has_header(&raw, Path::new("exports/raw-1.csv"), &settings).expect("header"));
+        assert!(csv_has_header(&raw, Path::new("exports/totals.csv"), &settings).expect("header"));
+
+        std::fs::write(dir.path().join("raw-1.csv.toml"), "delimiter = \"\\t\"\nhas_header = true\n").expect("sidecar");
+        assert_eq!(
+            CsvOptions::for_file(&raw, Path::new("exports/raw-1.csv"), &settings.csv).expect("options"),
+            CsvOptions {
+                delimiter: Some(b'\t'),
+                has_header: Some(true),
+            }
+        );
+        std::fs::write(dir.path().join("raw-1.csv.toml"), "delimiter = \"::\"\n").expect("sidecar");
+        assert!(CsvOptions::for_file(&raw, Path::new("raw-1.csv"), &settings.csv).is_err());
+        assert!(is_csv_sidecar(Path::new("exports/raw-1.CSV.toml")));
+        assert!(!is_csv_sidecar(Path::new("_generate.toml")));
+    }
 }
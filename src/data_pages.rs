@@ -0,0 +1,210 @@
+use crate::csv_preview::csv_records;
+use crate::diagnostics::print_diagnostic;
+use crate::packages::substitute_variables;
+use crate::render::{page_title, render_markdown_source, slugify};
+use crate::settings::Settings;
+use crate::site::{collect_index_dirs, walk_entries, Generated, GeneratedPage, PathFilter};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsStr;
+use std::path::{Component, Path, PathBuf};
+
+/// Turns a CSV or JSON file into one page per record, in the folder the
+/// file sits in.
+pub const GENERATE_FILE: &str = "_generate.toml";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GenerateSpec {
+    /// CSV with a header row, or a JSON array of objects.
+    data: PathBuf,
+    /// Markdown with `{{field}}` placeholders.
+    template: PathBuf,
+    /// Page file name, e.g. `{{name}}.md`. Field values are slugified.
+    path: String,
+    title: Option<String>,
+}
+
+type Record = BTreeMap<String, String>;
+
+/// `_generate.toml` files and the templates they name are read by rendar
+/// rather than built as pages or copied as assets.
+pub fn is_generator_file(path: &Path) -> bool {
+    if path.file_name().and_then(OsStr::to_str) == Some(GENERATE_FILE) {
+        return true;
+    }
+    let Some(dir) = path.parent() else {
+        return false;
+    };
+    let spec_path = dir.join(GENERATE_FILE);
+    spec_path.is_file() && load_spec(&spec_path).is_ok_and(|spec| dir.join(spec.template) == path)
+}
+
+pub fn load_data_pages(input: &Path, filter: &PathFilter, settings: &Settings) -> Result<Generated> {
+    let mut generated = Generated::default();
+    let mut index_dirs = None;
+    for entry in walk_entries(input, filter) {
+        if !entry.file_type().is_file() || entry.file_name() != GENERATE_FILE {
+            continue;
+        }
+        let index_dirs = index_dirs.get_or_insert_with(|| collect_index_dirs(input, filter, settings));
+        generated.pages.extend(generate_pages(input, entry.path(), index_dirs, settings)?);
+    }
+    Ok(generated)
+}
+
+fn load_spec(path: &Path) -> Result<GenerateSpec> {
+    let raw = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    toml::from_str(&raw).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+fn generate_pages(
+    input: &Path,
+    spec_path: &Path,
+    index_dirs: &std::collections::HashSet<PathBuf>,
+    settings: &Settings,
+) -> Result<Vec<GeneratedPage>> {
+    let spec = load_spec(spec_path)?;
+    let dir = spec_path.parent().unwrap_or(input);
+    let rel_dir = dir.strip_prefix(input).unwrap_or(Path::new(""));
+    let template_path = dir.join(&spec.template);
+    let template = std::fs::read_to_string(&template_path)
+        .with_context(|| format!("Failed to read page template {}", template_path.display()))?;
+    let records = load_records(&dir.join(&spec.data), &rel_dir.join(&spec.data), settings)?;
+
+    let mut pages = Vec::new();
+    let mut seen: HashMap<PathBuf, usize> = HashMap::new();
+    for (idx, record) in records.iter().enumerate() {
+        let slugs: Record = record
+            .iter()
+            .map(|(key, value)| (key.clone(), slugify(value)))
+            .collect();
+        let name = substitute_variables(&spec.path, &slugs);
+        let mut rel_path = rel_dir.join(&name);
+        if !settings.is_markdown(&rel_path) {
+            rel_path = rel_dir.join(format!("{name}.md"));
+        }
+        if name.contains("{{")
+            || rel_path.file_stem().is_none_or(|stem| stem.is_empty())
+            || Path::new(&name)
+                .components()
+                .any(|component| !matches!(component, Component::Normal(_)))
+        {
+            return Err(anyhow::anyhow!(
+                "{}: record {} gives the invalid page path {name:?}",
+                spec_path.display(),
+                idx + 1
+            ));
+        }
+        if let Some(first) = seen.insert(rel_path.clone(), idx) {
+            return Err(anyhow::anyhow!(
+                "{}: records {} and {} both generate {}",
+                spec_path.display(),
+                first + 1,
+                idx + 1,
+                rel_path.display()
+            ));
+        }
+        let source = substitute_variables(&template, record);
+        let rendered = render_markdown_source(&source, &input.join(&rel_path), input, index_dirs, settings);
+        rendered.diagnostics.iter().for_each(print_diagnostic);
+        let title = spec
+            .title
+            .as_deref()
+            .map(|title| substitute_variables(title, record))
+            .or_else(|| page_title(&source))
+            .unwrap_or_else(|| name.trim_end_matches(".md").replace(['-', '_'], " "));
+        pages.push(GeneratedPage {
+            rel_path,
+            title,
+            html: rendered.html,
+            mode: rendered.mode,
+        });
+    }
+    Ok(pages)
+}
+
+fn load_records(path: &Path, rel_path: &Path, settings: &Settings) -> Result<Vec<Record>> {
+    if path.extension().and_then(OsStr::to_str) != Some("json") {
+        return csv_records(path, rel_path, settings);
+    }
+    let raw = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let items: Vec<serde_json::Map<String, serde_json::Value>> = serde_json::from_str(&raw)
+        .with_context(|| format!("{} must hold a JSON array of objects", path.display()))?;
+    Ok(items
+        .into_iter()
+        .map(|item| {
+            item.into_iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        serde_json::Value::String(text) => text,
+                        serde_json::Value::Null => String::new(),
+                        other => other.to_string(),
+                    };
+                    (key, value)
+                })
+                .collect()
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_a_page_per_record() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let input = dir.path();
+        std::fs::create_dir_all(input.join("team")).expect("team dir");
+        std::fs::write(
+            input.join("team/_generate.toml"),
+            "data = \"people.csv\"\ntemplate = \"_person.md\"\npath = \"{{name}}.md\"\n",
+        )
+        .expect("spec");
+        std::fs::write(input.join("team/people.csv"), "name,role\nAda Lovelace,Analyst\nAlan Turing,Cryptanalyst\n")
+            .expect("data");
+        std::fs::write(input.join("team/_person.md"), "# {{name}}\n\nWorks as **{{role}}**.\n").expect("template");
+
+        let generated = load_data_pages(input, &PathFilter::default(), &Settings::default()).expect("pages");
+        let pages: Vec<(PathBuf, &str)> = generated
+            .pages
+            .iter()
+            .map(|page| (page.rel_path.clone(), page.title.as_str()))
+            .collect();
+        assert_eq!(
+            pages,
+            [
+                (PathBuf::from("team/ada-lovelace.md"), "Ada Lovelace"),
+                (PathBuf::from("team/alan-turing.md"), "Alan Turing"),
+            ]
+        );
+        assert!(generated.pages[1].html.contains("<strong>Cryptanalyst</strong>"));
+        assert!(is_generator_file(&input.join("team/_person.md")));
+        assert!(!is_generator_file(&input.join("team/people.csv")));
+    }
+
+    #[test]
+    fn reads_json_records_and_rejects_duplicate_paths() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let input = dir.path();
+        std::fs::write(
+            input.join(GENERATE_FILE),
+            "data = \"parts.json\"\ntemplate = \"_part.md\"\npath = \"{{sku}}\"\ntitle = \"{{sku}}: {{name}}\"\n",
+        )
+        .expect("spec");
+        std::fs::write(input.join("parts.json"), r#"[{"sku": 10, "name": "Fan"}, {"sku": 11, "name": "PSU"}]"#)
+            .expect("data");
+        std::fs::write(input.join("_part.md"), "Part {{name}}\n").expect("template");
+        let generated = load_data_pages(input, &PathFilter::default(), &Settings::default()).expect("pages");
+        assert_eq!(generated.pages[0].rel_path, PathBuf::from("10.md"));
+        assert_eq!(generated.pages[1].title, "11: PSU");
+
+        std::fs::write(input.join("parts.json"), r#"[{"sku": "A"}, {"sku": "a"}]"#).expect("data");
+        let Err(err) = load_data_pages(input, &PathFilter::default(), &Settings::default()) else {
+            panic!("duplicate paths should fail");
+        };
+        assert!(err.to_string().contains("records 1 and 2 both generate a.md"));
+    }
+}
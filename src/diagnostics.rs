@@ -0,0 +1,140 @@
+use serde::Serialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+}
+
+/// 1-based position in the source file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A problem found while rendering or checking a source file. `rule` is a
+/// stable kebab-case id, like `missing-link`, that tools can filter on.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub path: PathBuf,
+    pub span: Option<Span>,
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn warning(rule: &'static str, path: &Path, message: impl Into<String>) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            span: None,
+            rule,
+            severity: Severity::Warning,
+            message: message.into(),
+        }
+    }
+
+    pub fn at(mut self, line: usize, column: usize) -> Self {
+        self.span = Some(Span { line, column });
+        self
+    }
+}
+
+/// `path:line:column: message`, the format editors and terminals link.
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some(span) = self.span {
+            write!(f, ":{}:{}", span.line, span.column)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+pub fn print_diagnostic(diagnostic: &Diagnostic) {
+    match diagnostic.severity {
+        Severity::Warning => eprintln!("Warning: {diagnostic}"),
+    }
+}
+
+pub fn to_json(diagnostics: &[Diagnostic]) -> serde_json::Value {
+    serde_json::json!({ "diagnostics": diagnostics })
+}
+
+/// A SARIF 2.1.0 log, the format code scanning dashboards import.
+pub fn to_sarif(diagnostics: &[Diagnostic]) -> serde_json::Value {
+    let mut rules: Vec<&str> = diagnostics.iter().map(|diagnostic| diagnostic.rule).collect();
+    rules.sort_unstable();
+    rules.dedup();
+    let results: Vec<serde_json::Value> = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let mut location = serde_json::json!({
+                "artifactLocation": { "uri": sarif_uri(&diagnostic.path) },
+            });
+            if let Some(span) = diagnostic.span {
+                location["region"] = serde_json::json!({
+                    "startLine": span.line,
+                    "startColumn": span.column,
+                });
+            }
+            serde_json::json!({
+                "ruleId": diagnostic.rule,
+                "level": diagnostic.severity,
+                "message": { "text": diagnostic.message },
+                "locations": [{ "physicalLocation": location }],
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "version": "2.1.0",
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "rendar",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules.iter().map(|rule| serde_json::json!({ "id": rule })).collect::<Vec<_>>(),
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+fn sarif_uri(path: &Path) -> String {
+    let path = path.strip_prefix("./").unwrap_or(path);
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_diagnostics_for_terminals_and_tools() {
+        let missing = Diagnostic::warning("missing-link", Path::new("./docs/guide.md"), "Missing link target: setup.md").at(3, 7);
+        let duplicate = Diagnostic::warning("near-duplicate", Path::new("docs/a.md"), "Near-duplicate of docs/b.md");
+        assert_eq!(missing.to_string(), "./docs/guide.md:3:7: Missing link target: setup.md");
+        assert_eq!(duplicate.to_string(), "docs/a.md: Near-duplicate of docs/b.md");
+
+        let diagnostics = vec![missing, duplicate];
+        let json = to_json(&diagnostics);
+        assert_eq!(json["diagnostics"][0]["rule"], "missing-link");
+        assert_eq!(json["diagnostics"][0]["span"]["line"], 3);
+        assert_eq!(json["diagnostics"][0]["severity"], "warning");
+
+        let sarif = to_sarif(&diagnostics);
+        let results = &sarif["runs"][0]["results"];
+        assert_eq!(results[0]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"], "docs/guide.md");
+        assert_eq!(results[0]["locations"][0]["physicalLocation"]["region"]["startColumn"], 7);
+        assert!(results[1]["locations"][0]["physicalLocation"]["region"].is_null());
+        assert_eq!(sarif["runs"][0]["tool"]["driver"]["rules"][1]["id"], "near-duplicate");
+    }
+}
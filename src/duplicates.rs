@@ -0,0 +1,109 @@
+use crate::diagnostics::Diagnostic;
+use crate::hygiene::{closes_fence, fence_marker};
+use crate::render::strip_front_matter;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+const SHINGLE_WORDS: usize = 5;
+
+/// Hashes of every run of `SHINGLE_WORDS` consecutive words in the page,
+/// ignoring case, punctuation, front matter, and fenced code.
+fn shingles(markdown: &str) -> HashSet<u64> {
+    let mut words = Vec::new();
+    let mut fence: Option<(char, usize)> = None;
+    for line in strip_front_matter(markdown).lines() {
+        match fence {
+            Some((marker, len)) => {
+                if closes_fence(line, marker, len) {
+                    fence = None;
+                }
+                continue;
+            }
+            None => {
+                if let Some(open) = fence_marker(line) {
+                    fence = Some(open);
+                    continue;
+                }
+            }
+        }
+        words.extend(
+            line.split(|ch: char| !ch.is_alphanumeric())
+                .filter(|word| !word.is_empty())
+                .map(str::to_lowercase),
+        );
+    }
+    words
+        .windows(SHINGLE_WORDS)
+        .map(|window| {
+            let mut hasher = DefaultHasher::new();
+            window.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect()
+}
+
+fn similarity(a: &HashSet<u64>, b: &HashSet<u64>) -> f64 {
+    let shared = a.intersection(b).count();
+    let total = a.len() + b.len() - shared;
+    if total == 0 {
+        0.0
+    } else {
+        shared as f64 / total as f64
+    }
+}
+
+/// Reports each pair of pages whose shingle sets overlap (Jaccard
+/// similarity) at least `threshold`.
+pub fn duplicate_warnings(pages: &[(PathBuf, String)], threshold: f64) -> Vec<Diagnostic> {
+    let sets: Vec<(&Path, HashSet<u64>)> = pages
+        .iter()
+        .map(|(path, markdown)| (path.as_path(), shingles(markdown)))
+        .filter(|(_, set)| !set.is_empty())
+        .collect();
+    let mut warnings = Vec::new();
+    for (idx, (path, set)) in sets.iter().enumerate() {
+        for (other, other_set) in &sets[idx + 1..] {
+            let score = similarity(set, other_set);
+            if score >= threshold {
+                warnings.push(Diagnostic::warning(
+                    "near-duplicate",
+                    path,
+                    format!("Near-duplicate of {} ({:.0}% similar)", other.display(), score * 100.0),
+                ));
+            }
+        }
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_divergent_copies_above_threshold() {
+        let guide = "# Install\n\nDownload the installer from the releases page and run it with administrator rights. \
+                     Then open a terminal and check that the version command prints the expected release number.\n";
+        let copy = format!("---\nowner: ops\n---\n{guide}\nAsk in the help channel if it fails.\n");
+        let other = "# Usage\n\nRun the build command in the project folder to render every page into the output directory.\n";
+        let pages = vec![
+            (PathBuf::from("guide/install.md"), guide.to_string()),
+            (PathBuf::from("old/install.md"), copy),
+            (PathBuf::from("guide/usage.md"), other.to_string()),
+        ];
+        assert_eq!(
+            duplicate_warnings(&pages, 0.6).iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec!["guide/install.md: Near-duplicate of old/install.md (76% similar)"]
+        );
+        assert!(duplicate_warnings(&pages, 0.9).is_empty());
+    }
+
+    #[test]
+    fn ignores_code_blocks_and_punctuation() {
+        let a = shingles("One two, three. Four five six!\n```\nlots of code words here too\n```\n");
+        let b = shingles("one two three four five six\n");
+        assert_eq!(similarity(&a, &b), 1.0);
+    }
+}
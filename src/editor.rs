@@ -0,0 +1,72 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// Opens `rel_path` under `input` at `line` in `$EDITOR`, or VS Code when
+/// it is unset. Paths that leave the input are refused.
+pub fn open_in_editor(input: &Path, rel_path: &str, line: usize) -> Result<()> {
+    let path = input
+        .join(rel_path)
+        .canonicalize()
+        .with_context(|| format!("No source file {rel_path}"))?;
+    let root = input.canonicalize().unwrap_or_else(|_| input.to_path_buf());
+    if !path.starts_with(&root) || !path.is_file() {
+        return Err(anyhow::anyhow!("{rel_path} is not a file in the input directory"));
+    }
+    let command = editor_command(std::env::var("EDITOR").ok().as_deref(), &path, line);
+    let (program, args) = command.split_first().context("EDITOR is empty")?;
+    let mut child = std::process::Command::new(program)
+        .args(args)
+        .spawn()
+        .with_context(|| format!("Failed to start {program}"))?;
+    // Reap the editor when it exits, without holding up the request.
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// `editor` split into words, followed by the location in the form the
+/// editor understands: `--goto file:line` for VS Code and its forks,
+/// `+line file` (vi, Emacs, nano, and most others) otherwise.
+fn editor_command(editor: Option<&str>, path: &Path, line: usize) -> Vec<String> {
+    let mut command: Vec<String> = editor
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or("code")
+        .split_whitespace()
+        .map(str::to_string)
+        .collect();
+    let program = PathBuf::from(&command[0]);
+    let name = program.file_stem().and_then(|name| name.to_str()).unwrap_or_default();
+    if matches!(name, "code" | "code-insiders" | "codium" | "cursor") {
+        command.push("--goto".to_string());
+        command.push(format!("{}:{line}", path.display()));
+    } else {
+        command.push(format!("+{line}"));
+        command.push(path.display().to_string());
+    }
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_editor_specific_goto_arguments() {
+        let path = Path::new("/docs/guide.md");
+        assert_eq!(editor_command(None, path, 12), ["code", "--goto", "/docs/guide.md:12"]);
+        assert_eq!(
+            editor_command(Some("/usr/bin/codium --wait"), path, 3),
+            ["/usr/bin/codium", "--wait", "--goto", "/docs/guide.md:3"]
+        );
+        assert_eq!(editor_command(Some("vim"), path, 7), ["vim", "+7", "/docs/guide.md"]);
+    }
+
+    #[test]
+    fn refuses_paths_outside_the_input() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let input = dir.path().join("docs");
+        std::fs::create_dir_all(&input).expect("input");
+        std::fs::write(dir.path().join("secret.txt"), "secret").expect("outside file");
+        assert!(open_in_editor(&input, "../secret.txt", 1).is_err());
+        assert!(open_in_editor(&input, "missing.md", 1).is_err());
+    }
+}
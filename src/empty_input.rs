@@ -0,0 +1,190 @@
+use crate::diagnostics::Diagnostic;
+use crate::sidebar::is_sidebar;
+use crate::settings::Settings;
+use crate::site::{is_ignored_path, walk_entries, PathFilter};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Folder names that usually hold documentation, listed first among the
+/// suggestions.
+const DOCS_NAMES: &[&str] = &["docs", "doc", "documentation", "wiki", "book", "guide", "content", "notes"];
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target", "vendor"];
+/// How deep to look below each candidate folder for Markdown files.
+const SUGGEST_DEPTH: usize = 4;
+const MAX_SUGGESTIONS: usize = 3;
+const RULE: &str = "empty-input";
+
+/// Explains why `input` has no Markdown pages, or `None` when it has some.
+pub fn empty_input_report(input: &Path, filter: &PathFilter, settings: &Settings) -> Option<Diagnostic> {
+    if !input.is_dir() {
+        let mut report = "Input directory does not exist.".to_string();
+        push_suggestions(&mut report, input, settings);
+        return Some(Diagnostic::warning(RULE, input, report));
+    }
+    let mut files = 0;
+    let mut dirs = 0;
+    for entry in walk_entries(input, filter) {
+        if entry.file_type().is_dir() {
+            dirs += 1;
+        } else if entry.file_type().is_file() {
+            if settings.is_markdown(entry.path()) && !is_sidebar(entry.path()) {
+                return None;
+            }
+            files += 1;
+        }
+    }
+    let excluded = WalkDir::new(input)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry.file_type().is_file()
+                && settings.is_markdown(entry.path())
+                && is_ignored_path(entry.path(), input, filter)
+        })
+        .count();
+
+    let mut report = format!(
+        "No Markdown files found. Scanned {files} {} in {dirs} {}",
+        if files == 1 { "file" } else { "files" },
+        if dirs == 1 { "folder" } else { "folders" },
+    );
+    if excluded > 0 {
+        report.push_str(&format!(
+            "; {excluded} Markdown {} skipped as hidden or excluded",
+            if excluded == 1 { "file was" } else { "files were" }
+        ));
+    }
+    report.push('.');
+    push_suggestions(&mut report, input, settings);
+    Some(Diagnostic::warning(RULE, input, report))
+}
+
+fn push_suggestions(report: &mut String, input: &Path, settings: &Settings) {
+    let suggestions = suggest_roots(input, settings);
+    if !suggestions.is_empty() {
+        report.push_str(" Folders with Markdown nearby:");
+        for (dir, count) in suggestions {
+            report.push_str(&format!(
+                "\n  {} ({count} Markdown {})",
+                dir.display(),
+                if count == 1 { "file" } else { "files" }
+            ));
+        }
+    }
+    report.push_str("\nPass --input <dir> or set `input` in rendar.toml to pick the docs folder.");
+}
+
+/// Subfolders and sibling folders of `input` that contain Markdown files,
+/// docs-like names first, then by the number of files.
+fn suggest_roots(input: &Path, settings: &Settings) -> Vec<(PathBuf, usize)> {
+    let absolute = std::path::absolute(input).unwrap_or_else(|_| input.to_path_buf());
+    let mut candidates = subdirs(&absolute);
+    if let Some(parent) = absolute.parent() {
+        candidates.extend(subdirs(parent).into_iter().filter(|dir| *dir != absolute));
+    }
+    let mut found: Vec<(PathBuf, usize)> = candidates
+        .into_iter()
+        .filter_map(|dir| {
+            let count = count_markdown(&dir, settings);
+            (count > 0).then_some((dir, count))
+        })
+        .collect();
+    found.sort_by_key(|(dir, count)| (!is_docs_name(dir), std::cmp::Reverse(*count), dir.clone()));
+    found.truncate(MAX_SUGGESTIONS);
+    let base = std::env::current_dir().unwrap_or_default();
+    found
+        .into_iter()
+        .map(|(dir, count)| (relative_to(&dir, &base), count))
+        .collect()
+}
+
+fn subdirs(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir() && !is_skipped(path.file_name()))
+        .collect()
+}
+
+fn count_markdown(dir: &Path, settings: &Settings) -> usize {
+    WalkDir::new(dir)
+        .max_depth(SUGGEST_DEPTH)
+        .into_iter()
+        .filter_entry(|entry| entry.path() == dir || !is_skipped(entry.path().file_name()))
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file() && settings.is_markdown(entry.path()))
+        .count()
+}
+
+fn is_skipped(name: Option<&OsStr>) -> bool {
+    name.and_then(OsStr::to_str)
+        .is_some_and(|name| name.starts_with('.') || SKIPPED_DIRS.contains(&name))
+}
+
+fn is_docs_name(dir: &Path) -> bool {
+    dir.file_name()
+        .and_then(OsStr::to_str)
+        .is_some_and(|name| DOCS_NAMES.contains(&name.to_ascii_lowercase().as_str()))
+}
+
+/// `path` relative to `base` when it is inside it or a sibling of it, so the
+/// suggestion can be pasted into `--input`.
+fn relative_to(path: &Path, base: &Path) -> PathBuf {
+    if let Ok(rel) = path.strip_prefix(base) {
+        return rel.to_path_buf();
+    }
+    match (base.parent(), path.parent()) {
+        (Some(base_parent), Some(parent)) if base_parent == parent => {
+            Path::new("..").join(path.file_name().unwrap_or_default())
+        }
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Preview page shown while the input has no pages, instead of a bare 404.
+pub fn placeholder_html(report: &str) -> String {
+    format!(
+        "<h1>No pages to preview yet</h1>\n<pre>{}</pre>\n<p>This page reloads as soon as a Markdown file appears.</p>\n",
+        crate::render::escape_html(report)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_empty_input_and_suggests_docs_folders() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let input = dir.path().join("project");
+        std::fs::create_dir_all(input.join("src")).expect("src");
+        std::fs::write(input.join("src/main.rs"), "fn main() {}").expect("source");
+        std::fs::create_dir_all(input.join("docs/guide")).expect("docs");
+        std::fs::write(input.join("docs/guide/intro.md"), "# Intro").expect("intro");
+        std::fs::create_dir_all(input.join("archive")).expect("archive");
+        std::fs::write(input.join("archive/a.md"), "# A").expect("a");
+        std::fs::write(input.join("archive/b.md"), "# B").expect("b");
+
+        assert!(empty_input_report(&input, &PathFilter::default(), &Settings::default()).is_none());
+
+        let src = input.join("src");
+        let report = empty_input_report(&src, &PathFilter::default(), &Settings::default()).expect("report").to_string();
+        assert!(report.starts_with(&format!("{}: No Markdown files found. Scanned 1 file in 1 folder.", src.display())));
+        let docs = report.find("docs (1 Markdown file)").expect("docs suggestion");
+        let archive = report.find("archive (2 Markdown files)").expect("archive suggestion");
+        assert!(docs < archive);
+    }
+
+    #[test]
+    fn reports_missing_input() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let report = empty_input_report(&dir.path().join("dcos"), &PathFilter::default(), &Settings::default()).expect("report");
+        assert_eq!(report.rule, "empty-input");
+        assert!(report.message.contains("does not exist"));
+        assert!(report.message.contains("--input"));
+    }
+}
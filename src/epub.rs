@@ -0,0 +1,580 @@
+use crate::links::{resolve_local, rewrite_attr};
+use crate::site::ExportedPage;
+use anyhow::{Context, Result};
+use html5ever::buffer_queue::BufferQueue;
+use html5ever::tendril::StrTendril;
+use html5ever::tokenizer::states::RawKind;
+use html5ever::tokenizer::{Tag, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeSet, HashMap};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+const CONTAINER_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+const EPUB_STYLE: &str = r#"body {
+  font-family: serif;
+  line-height: 1.5;
+}
+
+pre, code {
+  font-family: monospace;
+  font-size: 0.9em;
+}
+
+pre {
+  white-space: pre-wrap;
+}
+
+table {
+  border-collapse: collapse;
+}
+
+th, td {
+  border: 1px solid #999999;
+  padding: 4px 8px;
+}
+
+img {
+  max-width: 100%;
+}
+"#;
+
+pub struct EpubMetadata<'a> {
+    pub title: &'a str,
+    pub language: &'a str,
+}
+
+pub fn write_epub(
+    out_file: &Path,
+    input: &Path,
+    pages: &[ExportedPage],
+    metadata: &EpubMetadata<'_>,
+) -> Result<()> {
+    let chapters = chapter_names(pages);
+    let mut images = BTreeSet::new();
+    let mut documents = Vec::new();
+    for (page, chapter) in pages.iter().zip(chapter_files(pages.len())) {
+        let body = rewrite_page_html(page, input, &chapters, &mut images);
+        documents.push((chapter, xhtml_document(&page.title, &to_xhtml(&body))));
+    }
+
+    let file = std::fs::File::create(out_file)
+        .with_context(|| format!("Failed to create {}", out_file.display()))?;
+    let mut zip = ZipWriter::new(file);
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    zip.start_file("mimetype", stored)?;
+    zip.write_all(b"application/epub+zip")?;
+    zip.start_file("META-INF/container.xml", deflated)?;
+    zip.write_all(CONTAINER_XML.as_bytes())?;
+    zip.start_file("OEBPS/style.css", deflated)?;
+    zip.write_all(EPUB_STYLE.as_bytes())?;
+    zip.start_file("OEBPS/nav.xhtml", deflated)?;
+    zip.write_all(nav_document(metadata.title, pages).as_bytes())?;
+    zip.start_file("OEBPS/content.opf", deflated)?;
+    zip.write_all(package_document(metadata, pages.len(), &images).as_bytes())?;
+    for (chapter, document) in &documents {
+        zip.start_file(format!("OEBPS/{chapter}"), deflated)?;
+        zip.write_all(document.as_bytes())?;
+    }
+    for image in &images {
+        let source = input.join(image);
+        let bytes = std::fs::read(&source)
+            .with_context(|| format!("Failed to read image {}", source.display()))?;
+        zip.start_file(format!("OEBPS/{}", url_path(image)), stored)?;
+        zip.write_all(&bytes)?;
+    }
+    zip.finish()
+        .with_context(|| format!("Failed to write {}", out_file.display()))?;
+    Ok(())
+}
+
+fn chapter_files(count: usize) -> impl Iterator<Item = String> {
+    (1..=count).map(|idx| format!("text/page-{idx:03}.xhtml"))
+}
+
+fn chapter_names(pages: &[ExportedPage]) -> HashMap<PathBuf, String> {
+    let mut chapters = HashMap::new();
+    for (page, chapter) in pages.iter().zip(chapter_files(pages.len())) {
+        let file = chapter.trim_start_matches("text/").to_string();
+        chapters.insert(page.output_rel.clone(), file.clone());
+        let dir = page.output_rel.parent().unwrap_or(Path::new(""));
+        if is_landing(&page.rel_path) {
+            chapters.entry(dir.join("index.html")).or_insert(file);
+        }
+    }
+    chapters
+}
+
+fn is_landing(rel_path: &Path) -> bool {
+    rel_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .map(|stem| stem.eq_ignore_ascii_case("readme") || stem.eq_ignore_ascii_case("index"))
+        .unwrap_or(false)
+}
+
+fn rewrite_page_html(
+    page: &ExportedPage,
+    input: &Path,
+    chapters: &HashMap<PathBuf, String>,
+    images: &mut BTreeSet<PathBuf>,
+) -> String {
+    let source_dir = page.rel_path.parent().unwrap_or(Path::new(""));
+    let output_dir = page.output_rel.parent().unwrap_or(Path::new(""));
+    let html = rewrite_attr(&page.html, "src", |value| {
+        let rel = resolve_local(value, source_dir)?;
+        if !input.join(&rel).is_file() {
+            return None;
+        }
+        let href = format!("../{}", url_path(&rel));
+        images.insert(rel);
+        Some(escape_xml(&href))
+    });
+    rewrite_attr(&html, "href", |value| {
+        let (path, fragment) = match value.split_once('#') {
+            Some((path, fragment)) => (path, Some(fragment)),
+            None => (value, None),
+        };
+        let rel = resolve_local(path, output_dir)?;
+        let chapter = chapters.get(&rel)?;
+        Some(escape_xml(&match fragment {
+            Some(fragment) => format!("{chapter}#{fragment}"),
+            None => chapter.clone(),
+        }))
+    })
+}
+
+/// Elements that never have content, written self-closed.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr",
+];
+
+/// Re-serializes page HTML as well-formed XHTML for a chapter: void
+/// elements are self-closed, text and attributes are escaped for XML,
+/// unclosed elements are closed, and stray end tags are dropped. Scripts
+/// and comments are left out, since reading systems don't run the theme's
+/// scripts.
+fn to_xhtml(html: &str) -> String {
+    let input = BufferQueue::default();
+    input.push_back(StrTendril::from(html));
+    let tokenizer = Tokenizer::new(XhtmlWriter::default(), Default::default());
+    let _ = tokenizer.feed(&input);
+    tokenizer.end();
+    let writer = &tokenizer.sink;
+    let mut xhtml = writer.output.take();
+    for name in writer.open.borrow().iter().rev() {
+        xhtml.push_str(&format!("</{name}>"));
+    }
+    xhtml
+}
+
+#[derive(Default)]
+struct XhtmlWriter {
+    output: RefCell<String>,
+    open: RefCell<Vec<String>>,
+    in_script: Cell<bool>,
+}
+
+impl XhtmlWriter {
+    fn start_tag(&self, tag: &Tag) -> TokenSinkResult<()> {
+        let name = tag.name.to_string();
+        if name == "script" {
+            self.in_script.set(true);
+            return TokenSinkResult::RawData(RawKind::ScriptData);
+        }
+        let mut output = self.output.borrow_mut();
+        output.push('<');
+        output.push_str(&name);
+        let namespace = match name.as_str() {
+            "svg" => Some("http://www.w3.org/2000/svg"),
+            "math" => Some("http://www.w3.org/1998/Math/MathML"),
+            _ => None,
+        };
+        if let Some(namespace) = namespace
+            && !tag.attrs.iter().any(|attr| &*attr.name.local == "xmlns")
+        {
+            output.push_str(&format!(" xmlns=\"{namespace}\""));
+        }
+        for attr in &tag.attrs {
+            let attr_name = &*attr.name.local;
+            // Prefixes other than the ones the chapter declares would make
+            // the document invalid XML.
+            let prefixed = attr_name.split_once(':').is_some_and(|(prefix, _)| !matches!(prefix, "xml" | "epub"));
+            if prefixed || !attr_name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':' | '.')) {
+                continue;
+            }
+            output.push_str(&format!(" {attr_name}=\"{}\"", escape_xml(&attr.value)));
+        }
+        if VOID_ELEMENTS.contains(&name.as_str()) || tag.self_closing {
+            output.push_str("/>");
+            return TokenSinkResult::Continue;
+        }
+        output.push('>');
+        self.open.borrow_mut().push(name.clone());
+        match name.as_str() {
+            "style" => TokenSinkResult::RawData(RawKind::Rawtext),
+            "textarea" | "title" => TokenSinkResult::RawData(RawKind::Rcdata),
+            _ => TokenSinkResult::Continue,
+        }
+    }
+
+    fn end_tag(&self, tag: &Tag) {
+        if &*tag.name == "script" {
+            self.in_script.set(false);
+            return;
+        }
+        let mut open = self.open.borrow_mut();
+        let Some(depth) = open.iter().rposition(|name| name == &*tag.name) else {
+            return;
+        };
+        let mut output = self.output.borrow_mut();
+        for name in open.drain(depth..).rev() {
+            output.push_str(&format!("</{name}>"));
+        }
+    }
+}
+
+impl TokenSink for XhtmlWriter {
+    type Handle = ();
+
+    fn process_token(&self, token: Token, _line_number: u64) -> TokenSinkResult<()> {
+        match token {
+            Token::TagToken(tag) if tag.kind == TagKind::StartTag => return self.start_tag(&tag),
+            Token::TagToken(tag) => self.end_tag(&tag),
+            Token::CharacterTokens(text) if !self.in_script.get() => {
+                self.output.borrow_mut().push_str(&escape_xml(&text));
+            }
+            _ => {}
+        }
+        TokenSinkResult::Continue
+    }
+}
+
+fn xhtml_document(title: &str, body: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+<head>
+  <title>{}</title>
+  <link rel="stylesheet" type="text/css" href="../style.css"/>
+</head>
+<body>
+{}
+</body>
+</html>
+"#,
+        escape_xml(title),
+        body
+    )
+}
+
+fn nav_document(title: &str, pages: &[ExportedPage]) -> String {
+    let mut items = String::new();
+    for (page, chapter) in pages.iter().zip(chapter_files(pages.len())) {
+        items.push_str(&format!(
+            "      <li><a href=\"{}\">{}</a></li>\n",
+            chapter,
+            escape_xml(&page.title)
+        ));
+    }
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+<head>
+  <title>{title}</title>
+</head>
+<body>
+  <nav epub:type="toc" id="toc">
+    <h1>{title}</h1>
+    <ol>
+{items}    </ol>
+  </nav>
+</body>
+</html>
+"#,
+        title = escape_xml(title),
+        items = items
+    )
+}
+
+fn package_document(metadata: &EpubMetadata<'_>, chapters: usize, images: &BTreeSet<PathBuf>) -> String {
+    let mut manifest = String::new();
+    let mut spine = String::new();
+    manifest.push_str(
+        "    <item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n",
+    );
+    manifest.push_str("    <item id=\"style\" href=\"style.css\" media-type=\"text/css\"/>\n");
+    for (idx, chapter) in chapter_files(chapters).enumerate() {
+        manifest.push_str(&format!(
+            "    <item id=\"page-{}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>\n",
+            idx + 1,
+            chapter
+        ));
+        spine.push_str(&format!("    <itemref idref=\"page-{}\"/>\n", idx + 1));
+    }
+    for (idx, image) in images.iter().enumerate() {
+        manifest.push_str(&format!(
+            "    <item id=\"image-{}\" href=\"{}\" media-type=\"{}\"/>\n",
+            idx + 1,
+            escape_xml(&url_path(image)),
+            image_media_type(image)
+        ));
+    }
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="book-id">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="book-id">urn:rendar:{identifier}</dc:identifier>
+    <dc:title>{title}</dc:title>
+    <dc:language>{language}</dc:language>
+    <meta property="dcterms:modified">{modified}</meta>
+  </metadata>
+  <manifest>
+{manifest}  </manifest>
+  <spine>
+{spine}  </spine>
+</package>
+"#,
+        identifier = escape_xml(&identifier(metadata.title)),
+        title = escape_xml(metadata.title),
+        language = escape_xml(metadata.language),
+        modified = utc_timestamp(),
+        manifest = manifest,
+        spine = spine
+    )
+}
+
+fn identifier(title: &str) -> String {
+    let mut slug = String::new();
+    for ch in title.chars() {
+        if ch.is_ascii_alphanumeric() {
+            slug.push(ch.to_ascii_lowercase());
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_matches('-');
+    if slug.is_empty() {
+        "site".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+fn image_media_type(path: &Path) -> &'static str {
+    match path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+        .as_deref()
+    {
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("svg") => "image/svg+xml",
+        Some("webp") => "image/webp",
+        _ => "application/octet-stream",
+    }
+}
+
+fn url_path(path: &Path) -> String {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn utc_timestamp() -> String {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    format_utc(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+    )
+}
+
+/// Seconds since the Unix epoch as `YYYY-MM-DDTHH:MM:SSZ`.
+pub fn format_utc(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
+}
+
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+fn escape_xml(input: &str) -> String {
+    input
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn page(rel: &str, output: &str, title: &str, html: &str) -> ExportedPage {
+        ExportedPage {
+            rel_path: PathBuf::from(rel),
+            output_rel: PathBuf::from(output),
+            title: title.to_string(),
+            html: html.to_string(),
+        }
+    }
+
+    #[test]
+    fn converts_days_to_civil_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19_723), (2024, 1, 1));
+    }
+
+    #[test]
+    fn writes_epub_with_toc_and_images() {
+        let input = tempfile::tempdir().expect("input");
+        let out = tempfile::tempdir().expect("out");
+        std::fs::create_dir_all(input.path().join("guide/img")).expect("img dir");
+        std::fs::write(input.path().join("guide/img/shot.png"), b"png").expect("image");
+
+        let pages = vec![
+            page("README.md", "README.html", "Home", r#"<p><a href="guide/setup.html#run">Setup</a></p>"#),
+            page(
+                "guide/setup.md",
+                "guide/setup.html",
+                "Setup",
+                r#"<p><img src="img/shot.png" alt="shot" /><a href="../index.html">Home</a></p>"#,
+            ),
+        ];
+        let epub_path = out.path().join("site.epub");
+        write_epub(
+            &epub_path,
+            input.path(),
+            &pages,
+            &EpubMetadata {
+                title: "Handbook",
+                language: "en",
+            },
+        )
+        .expect("write epub");
+
+        let file = std::fs::File::open(&epub_path).expect("open epub");
+        let mut archive = zip::ZipArchive::new(file).expect("zip");
+        assert_eq!(archive.by_index(0).expect("first").name(), "mimetype");
+
+        let mut read = |name: &str| {
+            let mut contents = String::new();
+            archive
+                .by_name(name)
+                .expect(name)
+                .read_to_string(&mut contents)
+                .expect("read entry");
+            contents
+        };
+        let nav = read("OEBPS/nav.xhtml");
+        assert!(nav.contains(r#"<a href="text/page-001.xhtml">Home</a>"#));
+        assert!(nav.contains(r#"<a href="text/page-002.xhtml">Setup</a>"#));
+        let opf = read("OEBPS/content.opf");
+        assert!(opf.contains("<dc:title>Handbook</dc:title>"));
+        assert!(opf.contains(r#"href="guide/img/shot.png" media-type="image/png""#));
+        let home = read("OEBPS/text/page-001.xhtml");
+        assert!(home.contains(r#"href="page-002.xhtml#run""#));
+        let setup = read("OEBPS/text/page-002.xhtml");
+        assert!(setup.contains(r#"src="../guide/img/shot.png""#));
+        assert!(setup.contains(r#"href="page-001.xhtml""#));
+        assert_eq!(read("OEBPS/guide/img/shot.png"), "png");
+    }
+
+    #[test]
+    fn writes_chapters_as_well_formed_xhtml() {
+        let input = tempfile::tempdir().expect("input");
+        let out = tempfile::tempdir().expect("out");
+        let pages = vec![
+            page(
+                "notes.md",
+                "notes.html",
+                "Notes & more",
+                "<p>Text<br>more&nbsp;here <img src=\"missing.png\" alt=\"a < b\"></p>\n<div><p>Unclosed</div></span>\n<!-- note -->",
+            ),
+            page(
+                "data.csv",
+                "data.html",
+                "Data",
+                "<table><tr><td>1 < 2 && 3</td></tr></table><script>if (a < b && c) { document.write('<p>'); }</script>",
+            ),
+        ];
+        let epub_path = out.path().join("site.epub");
+        write_epub(
+            &epub_path,
+            input.path(),
+            &pages,
+            &EpubMetadata {
+                title: "Handbook",
+                language: "en",
+            },
+        )
+        .expect("write epub");
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&epub_path).expect("open epub")).expect("zip");
+        let names: Vec<String> = archive
+            .file_names()
+            .filter(|name| name.ends_with(".xhtml"))
+            .map(str::to_string)
+            .collect();
+        assert_eq!(names.len(), 3);
+        let mut chapters = Vec::new();
+        for name in names {
+            let mut contents = String::new();
+            archive.by_name(&name).expect(&name).read_to_string(&mut contents).expect("read");
+            let options = roxmltree::ParsingOptions {
+                allow_dtd: true,
+                ..Default::default()
+            };
+            if let Err(err) = roxmltree::Document::parse_with_options(&contents, options) {
+                panic!("{name} is not well-formed XML: {err}\n{contents}");
+            }
+            chapters.push(contents);
+        }
+        let chapters = chapters.concat();
+        assert!(chapters.contains("Text<br/>more\u{a0}here <img src=\"missing.png\" alt=\"a &lt; b\"/>"));
+        assert!(chapters.contains("<div><p>Unclosed</p></div>"));
+        assert!(chapters.contains("<td>1 &lt; 2 &amp;&amp; 3</td>"));
+        assert!(!chapters.contains("<script") && !chapters.contains("document.write"));
+        assert!(!chapters.contains("note"));
+    }
+}
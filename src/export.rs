@@ -0,0 +1,247 @@
+use crate::epub::{write_epub, EpubMetadata};
+use crate::site::{self, RenderOptions};
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+const COMBINED_HTML: &str = "__rendar_export.html";
+const COMBINED_PDF: &str = "site.pdf";
+const EPUB_FILE: &str = "site.epub";
+
+const CHROME_CANDIDATES: &[&str] = &[
+    "chromium",
+    "chromium-browser",
+    "google-chrome",
+    "google-chrome-stable",
+    "chrome",
+    "msedge",
+];
+
+#[cfg(target_os = "macos")]
+const CHROME_APP_PATHS: &[&str] = &[
+    "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+    "/Applications/Chromium.app/Contents/MacOS/Chromium",
+    "/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+];
+
+#[cfg(not(target_os = "macos"))]
+const CHROME_APP_PATHS: &[&str] = &[];
+
+pub struct PdfOptions<'a> {
+    pub chrome: Option<&'a Path>,
+    pub combined: bool,
+    pub title: &'a str,
+}
+
+pub fn export_pdf(
+    input: &Path,
+    out: &Path,
+    options: &RenderOptions<'_>,
+    pdf: &PdfOptions<'_>,
+) -> Result<usize> {
+    let chrome = find_chrome(pdf.chrome)?;
+    let temp_dir = tempfile::tempdir().context("Failed to create export directory")?;
+    let site_dir = temp_dir.path();
+    site::build_site(input, site_dir, options)?;
+    std::fs::create_dir_all(out)
+        .with_context(|| format!("Failed to create output directory {}", out.display()))?;
+
+    if pdf.combined {
+        let html = site::build_combined_html(input, pdf.title, options)?;
+        let html_path = site_dir.join(COMBINED_HTML);
+        std::fs::write(&html_path, html)
+            .with_context(|| format!("Failed to write {}", html_path.display()))?;
+        print_to_pdf(&chrome, &html_path, &out.join(COMBINED_PDF))?;
+        return Ok(1);
+    }
+
+    let pages = site::pages_in_nav_order(input, options);
+    for page in &pages {
+        let pdf_path = out.join(page.output_rel.with_extension("pdf"));
+        if let Some(parent) = pdf_path.parent() {
+            std::fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create output directory {}", parent.display())
+            })?;
+        }
+        print_to_pdf(&chrome, &site_dir.join(&page.output_rel), &pdf_path)?;
+    }
+    Ok(pages.len())
+}
+
+pub fn export_epub(
+    input: &Path,
+    out: &Path,
+    options: &RenderOptions<'_>,
+    title: &str,
+) -> Result<PathBuf> {
+    let pages = site::render_pages_in_nav_order(input, options)?;
+    std::fs::create_dir_all(out)
+        .with_context(|| format!("Failed to create output directory {}", out.display()))?;
+    let epub_path = out.join(EPUB_FILE);
+    write_epub(
+        &epub_path,
+        input,
+        &pages,
+        &EpubMetadata {
+            title,
+            language: "en",
+        },
+    )?;
+    Ok(epub_path)
+}
+
+pub fn find_chrome(explicit: Option<&Path>) -> Result<PathBuf> {
+    if let Some(path) = explicit {
+        return chrome_at(path, "--chrome");
+    }
+    if let Some(path) = std::env::var_os("RENDAR_CHROME") {
+        return chrome_at(Path::new(&path), "RENDAR_CHROME");
+    }
+    for candidate in CHROME_APP_PATHS {
+        let path = Path::new(candidate);
+        if path.exists() {
+            return Ok(path.to_path_buf());
+        }
+    }
+    CHROME_CANDIDATES
+        .iter()
+        .find_map(|name| find_in_path(name))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "No Chromium-based browser found; install Chromium or pass --chrome <path>"
+            )
+        })
+}
+
+/// The browser named by `source`, a path or a program on `PATH`.
+fn chrome_at(path: &Path, source: &str) -> Result<PathBuf> {
+    if path.is_file() {
+        return Ok(path.to_path_buf());
+    }
+    find_in_path(&path.to_string_lossy()).ok_or_else(|| {
+        anyhow::anyhow!("Chromium executable {} from {source} not found", path.display())
+    })
+}
+
+fn find_in_path(name: &str) -> Option<PathBuf> {
+    let paths = std::env::var_os("PATH")?;
+    for dir in std::env::split_paths(&paths) {
+        let candidate = dir.join(name);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        let candidate = dir.join(format!("{name}.exe"));
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+    }
+    None
+}
+
+fn print_to_pdf(chrome: &Path, html: &Path, pdf: &Path) -> Result<()> {
+    let status = Command::new(chrome)
+        .arg("--headless")
+        .arg("--disable-gpu")
+        .arg("--no-pdf-header-footer")
+        .arg("--virtual-time-budget=10000")
+        .arg(format!("--print-to-pdf={}", pdf.display()))
+        .arg(file_url(html))
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .with_context(|| format!("Failed to run {}", chrome.display()))?;
+    if !status.success() {
+        return Err(anyhow::anyhow!(
+            "Chromium failed to print {} ({status})",
+            html.display()
+        ));
+    }
+    Ok(())
+}
+
+fn file_url(path: &Path) -> String {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let path = path.to_string_lossy().replace('\\', "/");
+    let path = path.trim_start_matches("//?/");
+    if path.starts_with('/') {
+        format!("file://{path}")
+    } else {
+        format!("file:///{path}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::Settings;
+    use crate::template::Template;
+    use std::io::Read;
+
+    #[test]
+    fn exports_api_module_pages_to_epub() {
+        let input = tempfile::tempdir().expect("input");
+        let out = tempfile::tempdir().expect("out");
+        std::fs::write(input.path().join("README.md"), "# Handbook\n").expect("readme");
+        let json = input.path().join("demo.json");
+        std::fs::write(
+            &json,
+            r#"{"root": 0, "index": {
+  "0": {"id": 0, "name": "demo", "docs": "Demo crate.", "inner": {"module": {"is_crate": true, "items": [1]}}},
+  "1": {"id": 1, "name": "parts", "docs": "Widget parts.", "inner": {"module": {"is_crate": false, "items": []}}}
+}}"#,
+        )
+        .expect("rustdoc json");
+        let generated = site::Generated {
+            pages: crate::api_docs::load_api_pages(&json, Path::new("api"), &Settings::default()).expect("api pages"),
+            assets: Vec::new(),
+        };
+        let template = Template::built_in();
+        let options = RenderOptions {
+            live_reload: false,
+            heartbeat: false,
+            template: &template,
+            filter: &site::PathFilter::default(),
+            csv_max_rows: None,
+            generated: &generated,
+            page_views: None,
+            pre_page_hook: None,
+            plugins: &crate::plugins::Plugins::default(),
+            settings: &Settings::default(),
+        };
+
+        let epub_path = export_epub(input.path(), out.path(), &options, "Handbook").expect("export epub");
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&epub_path).expect("open epub")).expect("zip");
+        let mut nav = String::new();
+        archive.by_name("OEBPS/nav.xhtml").expect("nav").read_to_string(&mut nav).expect("read nav");
+        assert!(nav.contains(">Handbook</a>"));
+        assert!(nav.contains(">demo</a>"));
+        assert!(nav.contains(">demo::parts</a>"));
+        let mut chapter = String::new();
+        archive
+            .by_name("OEBPS/text/page-003.xhtml")
+            .expect("parts chapter")
+            .read_to_string(&mut chapter)
+            .expect("read chapter");
+        assert!(chapter.contains("Widget parts."));
+    }
+
+    #[test]
+    fn errors_when_explicit_chrome_is_missing() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let missing = dir.path().join("no-such-chrome");
+        let result = find_chrome(Some(&missing));
+        assert!(result.is_err());
+        let err = chrome_at(&missing, "RENDAR_CHROME").expect_err("missing chrome");
+        assert!(err.to_string().contains("from RENDAR_CHROME not found"));
+    }
+
+    #[test]
+    fn builds_file_urls() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let page = dir.path().join("page.html");
+        std::fs::write(&page, "<html></html>").expect("page");
+        let url = file_url(&page);
+        assert!(url.starts_with("file:///"));
+        assert!(url.ends_with("/page.html"));
+    }
+}
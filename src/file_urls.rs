@@ -0,0 +1,93 @@
+use crate::links::{resolve_local, rewrite_attr};
+use crate::site::relative_path;
+use anyhow::{Context, Result};
+use std::ffi::OsStr;
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// Rewrites every page in `output` so it can be browsed from `file://`:
+/// root-relative URLs become page-relative, and links to folders point at
+/// their `index.html`.
+pub fn relativize_site(output: &Path) -> Result<usize> {
+    let mut pages = 0;
+    for entry in WalkDir::new(output).into_iter().filter_map(Result::ok) {
+        let path = entry.path();
+        if !entry.file_type().is_file() || path.extension().and_then(OsStr::to_str) != Some("html") {
+            continue;
+        }
+        let html = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let page_dir = path
+            .parent()
+            .and_then(|parent| parent.strip_prefix(output).ok())
+            .unwrap_or(Path::new(""));
+        let relativized = relativize_page(&html, page_dir, output);
+        if relativized != html {
+            std::fs::write(path, relativized)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        pages += 1;
+    }
+    Ok(pages)
+}
+
+fn relativize_page(html: &str, page_dir: &Path, output: &Path) -> String {
+    let html = rewrite_attr(html, "href", |value| relativize_url(value, page_dir, output));
+    rewrite_attr(&html, "src", |value| relativize_url(value, page_dir, output))
+}
+
+fn relativize_url(value: &str, page_dir: &Path, output: &Path) -> Option<String> {
+    if value.starts_with("//") || value.starts_with('#') || value.contains(':') {
+        return None;
+    }
+    let split = value.find(['?', '#']).unwrap_or(value.len());
+    let (path, suffix) = value.split_at(split);
+    if path.is_empty() {
+        return None;
+    }
+    let mut target = resolve_local(path, page_dir)?;
+    let is_dir = path.ends_with('/') || output.join(&target).is_dir();
+    if is_dir {
+        if !output.join(&target).join("index.html").is_file() {
+            return None;
+        }
+        target.push("index.html");
+    } else if !path.starts_with('/') {
+        return None;
+    }
+    Some(format!("{}{suffix}", relative_path(page_dir, &target)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewrites_root_relative_and_folder_links() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let output = dir.path();
+        std::fs::create_dir_all(output.join("guide/setup")).expect("guide dir");
+        std::fs::write(output.join("index.html"), "").expect("home");
+        std::fs::write(output.join("guide/setup/index.html"), "").expect("setup");
+        std::fs::write(
+            output.join("guide/intro.html"),
+            concat!(
+                r#"<a href="/index.html#top">home</a><a href="setup/">setup</a>"#,
+                r#"<a href="setup?tab=1">tab</a><a href="../">up</a><img src="/logo.png">"#,
+                r##"<a href="usage.html">usage</a><a href="https://example.com/">ext</a><a href="#x">x</a>"##,
+            ),
+        )
+        .expect("intro");
+
+        assert_eq!(relativize_site(output).expect("relativize"), 3);
+        let html = std::fs::read_to_string(output.join("guide/intro.html")).expect("intro");
+        assert_eq!(
+            html,
+            concat!(
+                r#"<a href="../index.html#top">home</a><a href="setup/index.html">setup</a>"#,
+                r#"<a href="setup/index.html?tab=1">tab</a><a href="../index.html">up</a><img src="../logo.png">"#,
+                r##"<a href="usage.html">usage</a><a href="https://example.com/">ext</a><a href="#x">x</a>"##,
+            )
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use crate::i18n::Locale;
+use crate::settings::Settings;
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Freshness {
+    pub stale_after_days: Option<u64>,
+}
+
+/// Returns the "Last updated" note for a page, or `None` when freshness
+/// notes are not enabled or the date is unknown.
+pub fn freshness_html(path: &Path, settings: &Settings) -> Option<String> {
+    let freshness = settings.freshness.as_ref()?;
+    let updated = last_updated(path)?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    Some(format_freshness(updated, now, freshness.stale_after_days, &settings.locale))
+}
+
+/// Last commit time from git, falling back to the file's modification time
+/// for untracked files or when git is unavailable.
+fn last_updated(path: &Path) -> Option<u64> {
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let git = Command::new("git")
+        .args(["log", "-1", "--format=%ct", "--"])
+        .arg(path.file_name()?)
+        .current_dir(dir)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .and_then(|stdout| stdout.trim().parse().ok());
+    git.or_else(|| {
+        std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()?
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|age| age.as_secs())
+    })
+}
+
+fn format_freshness(updated: u64, now: u64, stale_after_days: Option<u64>, locale: &Locale) -> String {
+    let age_days = now.saturating_sub(updated) / DAY_SECS;
+    let badge = match stale_after_days {
+        Some(days) if age_days > days => {
+            format!(" <span class=\"stale-badge\">{}</span>", locale.text("possibly_outdated"))
+        }
+        _ => String::new(),
+    };
+    format!(
+        "<p class=\"page-updated\">{} <time datetime=\"{}\">{}</time>{badge}</p>\n",
+        locale.text("last_updated"),
+        format_date(updated),
+        format_age(age_days, locale)
+    )
+}
+
+fn format_age(days: u64, locale: &Locale) -> String {
+    let (count, one, many) = match days {
+        0 => return locale.text("today").to_string(),
+        1..=29 => (days, "day_ago", "days_ago"),
+        30..=364 => (days / 30, "month_ago", "months_ago"),
+        _ => (days / 365, "year_ago", "years_ago"),
+    };
+    locale.text_with(if count == 1 { one } else { many }, &[("count", &count.to_string())])
+}
+
+pub fn format_date(secs: u64) -> String {
+    // Days since the epoch to a civil date (Howard Hinnant's algorithm).
+    let days = (secs / DAY_SECS) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_age_and_date() {
+        let locale = Locale::default();
+        assert_eq!(format_age(0, &locale), "today");
+        assert_eq!(format_age(1, &locale), "1 day ago");
+        assert_eq!(format_age(75, &locale), "2 months ago");
+        assert_eq!(format_age(800, &locale), "2 years ago");
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(1_709_251_200), "2024-03-01");
+    }
+
+    #[test]
+    fn adds_stale_badge_past_threshold() {
+        let updated = 1_709_251_200;
+        let now = updated + 200 * DAY_SECS;
+        assert_eq!(
+            format_freshness(updated, now, Some(180), &Locale::default()),
+            "<p class=\"page-updated\">Last updated <time datetime=\"2024-03-01\">6 months ago</time> <span class=\"stale-badge\">Possibly outdated</span></p>\n"
+        );
+        assert!(!format_freshness(updated, now, Some(365), &Locale::default()).contains("stale-badge"));
+    }
+}
@@ -0,0 +1,71 @@
+use crate::process_pool::ProcessPool;
+use anyhow::Result;
+use std::path::Path;
+use std::process::Command;
+
+pub const DEFAULT_TIMEOUT_SECS: u64 = 300;
+
+#[derive(Clone, Copy)]
+pub struct PageHook<'a> {
+    pub command: &'a str,
+    pub pool: &'a ProcessPool,
+}
+
+pub fn run_hook(
+    pool: &ProcessPool,
+    name: &str,
+    command: &str,
+    env: &[(&str, &Path)],
+    cache_key: Option<&str>,
+) -> Result<()> {
+    let mut process = shell_command(command);
+    for (key, value) in env {
+        process.env(key, value);
+    }
+    pool.run(&format!("{name} hook `{command}`"), process, cache_key)
+}
+
+#[cfg(windows)]
+fn shell_command(command: &str) -> Command {
+    let mut process = Command::new("cmd");
+    process.arg("/C").arg(command);
+    process
+}
+
+#[cfg(not(windows))]
+fn shell_command(command: &str) -> Command {
+    let mut process = Command::new("sh");
+    process.arg("-c").arg(command);
+    process
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn pool() -> ProcessPool {
+        ProcessPool::new(1, Duration::from_secs(10))
+    }
+
+    #[test]
+    fn passes_paths_in_environment() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let marker = dir.path().join("marker.txt");
+        run_hook(
+            &pool(),
+            "pre_build",
+            "printf '%s' \"$RENDAR_INPUT\" > \"$RENDAR_OUTPUT/marker.txt\"",
+            &[("RENDAR_INPUT", Path::new("docs")), ("RENDAR_OUTPUT", dir.path())],
+            None,
+        )
+        .expect("hook");
+        assert_eq!(std::fs::read_to_string(marker).expect("marker"), "docs");
+    }
+
+    #[test]
+    fn fails_on_nonzero_exit() {
+        let result = run_hook(&pool(), "post_build", "exit 3", &[], None);
+        assert!(result.is_err());
+    }
+}
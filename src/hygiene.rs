@@ -0,0 +1,151 @@
+use crate::diagnostics::Diagnostic;
+use std::path::Path;
+
+const INVISIBLE_CHARS: &[(char, &str)] = &[
+    ('\u{200B}', "zero-width space"),
+    ('\u{200C}', "zero-width non-joiner"),
+    ('\u{200E}', "left-to-right mark"),
+    ('\u{200F}', "right-to-left mark"),
+    ('\u{2060}', "word joiner"),
+    ('\u{FEFF}', "byte order mark"),
+];
+
+struct Fence {
+    marker: char,
+    len: usize,
+    start_line: usize,
+    ending: Option<&'static str>,
+    reported: bool,
+}
+
+pub fn check_hygiene(path: &Path, source: &str) -> Vec<Diagnostic> {
+    let mut warnings = Vec::new();
+    let mut warn = |rule: &'static str, line: usize, column: usize, message: String| {
+        warnings.push(Diagnostic::warning(rule, path, message).at(line, column));
+    };
+    let mut fence: Option<Fence> = None;
+
+    for (idx, raw_line) in source.split_inclusive('\n').enumerate() {
+        let line_no = idx + 1;
+        let (line, ending) = if let Some(line) = raw_line.strip_suffix("\r\n") {
+            (line, Some("CRLF"))
+        } else if let Some(line) = raw_line.strip_suffix('\n') {
+            (line, Some("LF"))
+        } else {
+            (raw_line, None)
+        };
+
+        for (column, ch) in line.chars().enumerate() {
+            if ch == '\u{FEFF}' && idx == 0 && column == 0 {
+                continue;
+            }
+            if let Some((_, name)) = INVISIBLE_CHARS.iter().find(|(invisible, _)| *invisible == ch) {
+                warn("invisible-character", line_no, column + 1, format!("invisible character U+{:04X} ({name})", ch as u32));
+            } else if ch == '\u{00A0}' && fence.is_some() {
+                warn("code-nbsp", line_no, column + 1, "non-breaking space in code block".to_string());
+            }
+        }
+
+        match fence.as_mut() {
+            None => {
+                if let Some((marker, len)) = fence_marker(line) {
+                    fence = Some(Fence {
+                        marker,
+                        len,
+                        start_line: line_no,
+                        ending: None,
+                        reported: false,
+                    });
+                }
+            }
+            Some(open) => {
+                if closes_fence(line, open.marker, open.len) {
+                    fence = None;
+                    continue;
+                }
+                if let Some(ending) = ending {
+                    match open.ending {
+                        None => open.ending = Some(ending),
+                        Some(first) if first != ending && !open.reported => {
+                            open.reported = true;
+                            warn(
+                                "mixed-line-endings",
+                                line_no,
+                                line.chars().count() + 1,
+                                format!(
+                                    "{ending} line ending in code block opened at line {} that uses {first}",
+                                    open.start_line
+                                ),
+                            );
+                        }
+                        _ => {}
+                    }
+                }
+                let trimmed = line.trim_end_matches([' ', '\t']);
+                if trimmed.len() != line.len() && trimmed.ends_with('\\') {
+                    warn(
+                        "continuation-whitespace",
+                        line_no,
+                        trimmed.chars().count() + 1,
+                        "trailing whitespace after line continuation in code block".to_string(),
+                    );
+                }
+            }
+        }
+    }
+    warnings
+}
+
+pub fn fence_marker(line: &str) -> Option<(char, usize)> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 {
+        return None;
+    }
+    let rest = &line[indent..];
+    let marker = rest.chars().next().filter(|ch| *ch == '`' || *ch == '~')?;
+    let len = rest.chars().take_while(|ch| *ch == marker).count();
+    if len < 3 || (marker == '`' && rest[len..].contains('`')) {
+        return None;
+    }
+    Some((marker, len))
+}
+
+pub fn closes_fence(line: &str, marker: char, len: usize) -> bool {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return false;
+    }
+    let count = trimmed.chars().take_while(|ch| *ch == marker).count();
+    count >= len && trimmed[count..].trim().is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_mixed_line_endings_in_code_blocks() {
+        let source = "Intro\r\n```sh\nls -la\r\necho ok\n```\nmixed\r\noutside\n";
+        let warnings = check_hygiene(Path::new("doc.md"), source);
+        assert_eq!(warnings[0].rule, "mixed-line-endings");
+        assert_eq!(
+            warnings.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec!["doc.md:4:8: LF line ending in code block opened at line 2 that uses CRLF"]
+        );
+    }
+
+    #[test]
+    fn flags_invisible_characters_with_locations() {
+        let source = "\u{FEFF}# Title\nrun\u{200B}me\n```\ncurl -X\u{00A0}GET \\  \n```\nmid\u{FEFF}\n";
+        let warnings = check_hygiene(Path::new("doc.md"), source);
+        assert_eq!(
+            warnings.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                "doc.md:2:4: invisible character U+200B (zero-width space)",
+                "doc.md:4:8: non-breaking space in code block",
+                "doc.md:4:14: trailing whitespace after line continuation in code block",
+                "doc.md:6:4: invisible character U+FEFF (byte order mark)",
+            ]
+        );
+    }
+}
@@ -0,0 +1,313 @@
+use anyhow::Result;
+use std::collections::{BTreeMap, HashMap};
+
+/// Built-in UI strings. `{name}` marks a value filled in at render time.
+const EN: &[(&str, &str)] = &[
+    ("nav_pages", "Pages"),
+    ("nav_folders", "Folders"),
+    ("nav_filter", "Filter pages"),
+    ("home", "Home"),
+    ("search", "Search"),
+    ("csv_truncated", "Showing first {rows} rows."),
+    ("csv_column", "Column {index}"),
+    ("csv_partial", "Showing {shown} of {total} rows."),
+    ("csv_load_more", "Load more rows"),
+    ("csv_filter", "Filter rows"),
+    ("csv_filter_column", "Filter {column}"),
+    ("csv_matching", "{count} of {total} rows"),
+    ("csv_stats", "Column stats"),
+    ("csv_count", "count"),
+    ("csv_min", "min"),
+    ("csv_max", "max"),
+    ("csv_mean", "mean"),
+    ("last_updated", "Last updated"),
+    ("possibly_outdated", "Possibly outdated"),
+    ("today", "today"),
+    ("day_ago", "{count} day ago"),
+    ("days_ago", "{count} days ago"),
+    ("month_ago", "{count} month ago"),
+    ("months_ago", "{count} months ago"),
+    ("year_ago", "{count} year ago"),
+    ("years_ago", "{count} years ago"),
+    ("copy_link", "Copy link to this section"),
+    ("copied", "Copied"),
+    ("copy_failed", "Copy failed"),
+    ("previous_image", "Previous image"),
+    ("next_image", "Next image"),
+    ("footnote_backref", "Back to reference {number}"),
+    ("keyboard_shortcuts", "Keyboard shortcuts"),
+    ("previous_page", "Previous page"),
+    ("next_page", "Next page"),
+    ("show_shortcuts", "Show keyboard shortcuts"),
+    ("generated_at", "Generated {time}"),
+    ("built_with", "Built with rendar {version}"),
+    ("source_commit", "Commit {commit}"),
+];
+
+const DE: &[(&str, &str)] = &[
+    ("nav_pages", "Seiten"),
+    ("nav_folders", "Ordner"),
+    ("nav_filter", "Seiten filtern"),
+    ("home", "Start"),
+    ("search", "Suchen"),
+    ("csv_truncated", "Die ersten {rows} Zeilen werden angezeigt."),
+    ("csv_column", "Spalte {index}"),
+    ("csv_partial", "{shown} von {total} Zeilen werden angezeigt."),
+    ("csv_load_more", "Weitere Zeilen laden"),
+    ("csv_filter", "Zeilen filtern"),
+    ("csv_filter_column", "{column} filtern"),
+    ("csv_matching", "{count} von {total} Zeilen"),
+    ("csv_stats", "Spaltenstatistik"),
+    ("csv_count", "Anzahl"),
+    ("csv_min", "Min."),
+    ("csv_max", "Max."),
+    ("csv_mean", "Mittel"),
+    ("last_updated", "Zuletzt aktualisiert"),
+    ("possibly_outdated", "Möglicherweise veraltet"),
+    ("today", "heute"),
+    ("day_ago", "vor {count} Tag"),
+    ("days_ago", "vor {count} Tagen"),
+    ("month_ago", "vor {count} Monat"),
+    ("months_ago", "vor {count} Monaten"),
+    ("year_ago", "vor {count} Jahr"),
+    ("years_ago", "vor {count} Jahren"),
+    ("copy_link", "Link zu diesem Abschnitt kopieren"),
+    ("copied", "Kopiert"),
+    ("copy_failed", "Kopieren fehlgeschlagen"),
+    ("previous_image", "Vorheriges Bild"),
+    ("next_image", "Nächstes Bild"),
+    ("footnote_backref", "Zurück zum Verweis {number}"),
+    ("keyboard_shortcuts", "Tastenkürzel"),
+    ("previous_page", "Vorherige Seite"),
+    ("next_page", "Nächste Seite"),
+    ("show_shortcuts", "Tastenkürzel anzeigen"),
+    ("generated_at", "Erstellt {time}"),
+    ("built_with", "Erstellt mit rendar {version}"),
+    ("source_commit", "Commit {commit}"),
+];
+
+const FR: &[(&str, &str)] = &[
+    ("nav_pages", "Pages"),
+    ("nav_folders", "Dossiers"),
+    ("nav_filter", "Filtrer les pages"),
+    ("home", "Accueil"),
+    ("search", "Rechercher"),
+    ("csv_truncated", "Affichage des {rows} premières lignes."),
+    ("csv_column", "Colonne {index}"),
+    ("csv_partial", "{shown} lignes affichées sur {total}."),
+    ("csv_load_more", "Charger plus de lignes"),
+    ("csv_filter", "Filtrer les lignes"),
+    ("csv_filter_column", "Filtrer {column}"),
+    ("csv_matching", "{count} lignes sur {total}"),
+    ("csv_stats", "Statistiques des colonnes"),
+    ("csv_count", "nombre"),
+    ("csv_min", "min"),
+    ("csv_max", "max"),
+    ("csv_mean", "moyenne"),
+    ("last_updated", "Mis à jour"),
+    ("possibly_outdated", "Peut-être obsolète"),
+    ("today", "aujourd’hui"),
+    ("day_ago", "il y a {count} jour"),
+    ("days_ago", "il y a {count} jours"),
+    ("month_ago", "il y a {count} mois"),
+    ("months_ago", "il y a {count} mois"),
+    ("year_ago", "il y a {count} an"),
+    ("years_ago", "il y a {count} ans"),
+    ("copy_link", "Copier le lien vers cette section"),
+    ("copied", "Copié"),
+    ("copy_failed", "Échec de la copie"),
+    ("previous_image", "Image précédente"),
+    ("next_image", "Image suivante"),
+    ("footnote_backref", "Retour à l’appel {number}"),
+    ("keyboard_shortcuts", "Raccourcis clavier"),
+    ("previous_page", "Page précédente"),
+    ("next_page", "Page suivante"),
+    ("show_shortcuts", "Afficher les raccourcis clavier"),
+    ("generated_at", "Généré le {time}"),
+    ("built_with", "Généré avec rendar {version}"),
+    ("source_commit", "Commit {commit}"),
+];
+
+const ES: &[(&str, &str)] = &[
+    ("nav_pages", "Páginas"),
+    ("nav_folders", "Carpetas"),
+    ("nav_filter", "Filtrar páginas"),
+    ("home", "Inicio"),
+    ("search", "Buscar"),
+    ("csv_truncated", "Se muestran las primeras {rows} filas."),
+    ("csv_column", "Columna {index}"),
+    ("csv_partial", "Mostrando {shown} de {total} filas."),
+    ("csv_load_more", "Cargar más filas"),
+    ("csv_filter", "Filtrar filas"),
+    ("csv_filter_column", "Filtrar {column}"),
+    ("csv_matching", "{count} de {total} filas"),
+    ("csv_stats", "Estadísticas de columnas"),
+    ("csv_count", "cantidad"),
+    ("csv_min", "mín."),
+    ("csv_max", "máx."),
+    ("csv_mean", "media"),
+    ("last_updated", "Última actualización"),
+    ("possibly_outdated", "Posiblemente desactualizado"),
+    ("today", "hoy"),
+    ("day_ago", "hace {count} día"),
+    ("days_ago", "hace {count} días"),
+    ("month_ago", "hace {count} mes"),
+    ("months_ago", "hace {count} meses"),
+    ("year_ago", "hace {count} año"),
+    ("years_ago", "hace {count} años"),
+    ("copy_link", "Copiar enlace a esta sección"),
+    ("copied", "Copiado"),
+    ("copy_failed", "No se pudo copiar"),
+    ("previous_image", "Imagen anterior"),
+    ("next_image", "Imagen siguiente"),
+    ("footnote_backref", "Volver a la referencia {number}"),
+    ("keyboard_shortcuts", "Atajos de teclado"),
+    ("previous_page", "Página anterior"),
+    ("next_page", "Página siguiente"),
+    ("show_shortcuts", "Mostrar atajos de teclado"),
+    ("generated_at", "Generado el {time}"),
+    ("built_with", "Generado con rendar {version}"),
+    ("source_commit", "Commit {commit}"),
+];
+
+const LOCALES: &[(&str, &[(&str, &str)])] = &[("en", EN), ("de", DE), ("fr", FR), ("es", ES)];
+
+/// Strings used by the page scripts, passed to them as `window.rendarStrings`.
+pub const SCRIPT_KEYS: &[&str] = &[
+    "nav_filter",
+    "search",
+    "copy_link",
+    "copied",
+    "copy_failed",
+    "previous_image",
+    "next_image",
+    "keyboard_shortcuts",
+    "previous_page",
+    "next_page",
+    "show_shortcuts",
+    "csv_filter",
+    "csv_filter_column",
+    "csv_matching",
+    "csv_stats",
+    "csv_count",
+    "csv_min",
+    "csv_max",
+    "csv_mean",
+];
+
+/// The UI strings of `[site] language` with the `[site.strings]`
+/// overrides applied.
+pub struct Locale {
+    language: String,
+    strings: HashMap<&'static str, String>,
+    customized: bool,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self {
+            language: "en".to_string(),
+            strings: HashMap::new(),
+            customized: false,
+        }
+    }
+}
+
+impl Locale {
+    /// Selects the locale table for `language` and applies `overrides`.
+    /// Languages without a table use English strings, which the overrides
+    /// can then replace.
+    pub fn new(language: &str, overrides: &BTreeMap<String, String>) -> Result<Self> {
+        let base = language.split(['-', '_']).next().unwrap_or(language).to_ascii_lowercase();
+        let table = match LOCALES.iter().find(|(code, _)| *code == base) {
+            Some((_, table)) => table,
+            None => {
+                eprintln!(
+                    "Warning: no built-in strings for language {language}; using English (available: {})",
+                    LOCALES.iter().map(|(code, _)| *code).collect::<Vec<_>>().join(", ")
+                );
+                EN
+            }
+        };
+        let mut strings: HashMap<&'static str, String> =
+            table.iter().map(|(key, value)| (*key, value.to_string())).collect();
+        for (key, value) in overrides {
+            let Some((key, _)) = EN.iter().find(|(known, _)| known == key) else {
+                return Err(anyhow::anyhow!(
+                    "Unknown UI string {key} in [site.strings]. Known strings: {}",
+                    EN.iter().map(|(key, _)| *key).collect::<Vec<_>>().join(", ")
+                ));
+            };
+            strings.insert(key, value.clone());
+        }
+        Ok(Self {
+            language: language.to_string(),
+            customized: base != "en" || !overrides.is_empty(),
+            strings,
+        })
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// The UI string for `key`.
+    pub fn text(&self, key: &str) -> &str {
+        if let Some(value) = self.strings.get(key) {
+            return value;
+        }
+        EN.iter()
+            .find(|(known, _)| *known == key)
+            .map_or("", |(_, value)| value)
+    }
+
+    /// The UI string for `key` with its `{name}` values filled in.
+    pub fn text_with(&self, key: &str, values: &[(&str, &str)]) -> String {
+        values
+            .iter()
+            .fold(self.text(key).to_string(), |text, (name, value)| {
+                text.replace(&format!("{{{name}}}"), value)
+            })
+    }
+
+    /// Script defining the page scripts' strings, or `None` for the English
+    /// defaults they already contain.
+    pub fn strings_script(&self) -> Option<String> {
+        if !self.customized {
+            return None;
+        }
+        let strings: BTreeMap<&str, &str> = SCRIPT_KEYS
+            .iter()
+            .filter_map(|key| Some((*key, self.strings.get(key)?.as_str())))
+            .collect();
+        let json = serde_json::to_string(&strings).ok()?.replace("</", "<\\/");
+        Some(format!("<script>window.rendarStrings = {json};</script>"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_locale_defines_every_string() {
+        for (code, table) in LOCALES {
+            for (key, _) in EN {
+                assert!(table.iter().any(|(known, _)| known == key), "{code} is missing {key}");
+            }
+            assert_eq!(table.len(), EN.len(), "{code} has extra strings");
+        }
+    }
+
+    #[test]
+    fn applies_overrides_and_rejects_unknown_keys() {
+        let overrides = BTreeMap::from([("nav_pages".to_string(), "Artikel".to_string())]);
+        let locale = Locale::new("de-AT", &overrides).expect("locale");
+        assert_eq!(locale.strings["nav_pages"], "Artikel");
+        assert_eq!(locale.strings["nav_folders"], "Ordner");
+        assert_eq!(locale.language, "de-AT");
+
+        let typo = BTreeMap::from([("nav_page".to_string(), "Docs".to_string())]);
+        assert!(Locale::new("en", &typo).is_err());
+    }
+}
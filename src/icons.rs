@@ -0,0 +1,111 @@
+use crate::settings::Settings;
+use crate::site::relative_link;
+use anyhow::{Context, Result};
+use image::imageops::FilterType;
+use image::DynamicImage;
+use std::path::Path;
+
+const PNG_ICONS: &[(&str, u32)] = &[
+    ("favicon-16x16.png", 16),
+    ("favicon-32x32.png", 32),
+    ("apple-touch-icon.png", 180),
+    ("icon-192.png", 192),
+    ("icon-512.png", 512),
+];
+const ICO_SIZES: &[u32] = &[16, 32, 48];
+
+/// Writes `favicon.ico` and the PNG icon set generated from `[site] icon`
+/// into `output`. Does nothing when no icon is configured.
+pub fn generate_icons(output: &Path, settings: &Settings) -> Result<usize> {
+    let Some(source) = &settings.site_icon else {
+        return Ok(0);
+    };
+    let image = image::open(source)
+        .with_context(|| format!("Failed to read site icon {}", source.display()))?;
+    let square = square_image(image);
+    for (name, size) in PNG_ICONS {
+        let path = output.join(name);
+        square
+            .resize_exact(*size, *size, FilterType::Lanczos3)
+            .save(&path)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    write_ico(&square, &output.join("favicon.ico"))?;
+    Ok(PNG_ICONS.len() + 1)
+}
+
+/// `<link>` tags for the generated icons, relative to the page at `page_rel`.
+pub fn icon_links(page_rel: &Path, settings: &Settings) -> Option<String> {
+    settings.site_icon.as_ref()?;
+    let page_dir = page_rel.parent().unwrap_or(Path::new(""));
+    let href = |name: &str| relative_link(page_dir, Path::new(name), settings);
+    Some(format!(
+        concat!(
+            "<link rel=\"icon\" href=\"{}\" sizes=\"48x48\">",
+            "<link rel=\"icon\" type=\"image/png\" sizes=\"32x32\" href=\"{}\">",
+            "<link rel=\"icon\" type=\"image/png\" sizes=\"16x16\" href=\"{}\">",
+            "<link rel=\"apple-touch-icon\" href=\"{}\">",
+        ),
+        href("favicon.ico"),
+        href("favicon-32x32.png"),
+        href("favicon-16x16.png"),
+        href("apple-touch-icon.png"),
+    ))
+}
+
+/// Centers non-square images on a transparent square canvas.
+fn square_image(image: DynamicImage) -> DynamicImage {
+    let (width, height) = (image.width(), image.height());
+    if width == height {
+        return image;
+    }
+    let side = width.max(height);
+    let mut canvas = image::RgbaImage::new(side, side);
+    image::imageops::overlay(
+        &mut canvas,
+        &image.to_rgba8(),
+        i64::from((side - width) / 2),
+        i64::from((side - height) / 2),
+    );
+    DynamicImage::ImageRgba8(canvas)
+}
+
+fn write_ico(image: &DynamicImage, path: &Path) -> Result<()> {
+    let frames = ICO_SIZES
+        .iter()
+        .map(|size| {
+            let icon = image.resize_exact(*size, *size, FilterType::Lanczos3).to_rgba8();
+            image::codecs::ico::IcoFrame::as_png(icon.as_raw(), *size, *size, image::ExtendedColorType::Rgba8)
+        })
+        .collect::<image::ImageResult<Vec<_>>>()
+        .with_context(|| format!("Failed to encode {}", path.display()))?;
+    let file = std::fs::File::create(path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    image::codecs::ico::IcoEncoder::new(file)
+        .encode_images(&frames)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn squares_wide_images_on_transparent_canvas() {
+        let wide = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(40, 20, image::Rgba([255, 0, 0, 255])));
+        let square = square_image(wide).to_rgba8();
+        assert_eq!(square.dimensions(), (40, 40));
+        assert_eq!(square.get_pixel(0, 0)[3], 0);
+        assert_eq!(square.get_pixel(20, 20), &image::Rgba([255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn writes_ico_with_png_frames() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let image = DynamicImage::ImageRgba8(image::RgbaImage::new(64, 64));
+        let path = dir.path().join("favicon.ico");
+        write_ico(&image, &path).expect("ico");
+        let bytes = std::fs::read(&path).expect("read ico");
+        assert_eq!(&bytes[..6], &[0, 0, 1, 0, 3, 0]);
+    }
+}
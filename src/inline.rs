@@ -0,0 +1,184 @@
+use crate::deploy::content_type;
+use crate::links::{resolve_local, rewrite_attr, tag_attr};
+use anyhow::{Context, Result};
+use std::ffi::OsStr;
+use std::path::Path;
+use walkdir::WalkDir;
+
+pub const DEFAULT_MAX_INLINE_BYTES: u64 = 64 * 1024;
+
+pub fn inline_site(output: &Path, max_bytes: u64) -> Result<usize> {
+    let mut pages = 0;
+    for entry in WalkDir::new(output).into_iter().filter_map(Result::ok) {
+        let path = entry.path();
+        if !entry.file_type().is_file() || path.extension().and_then(OsStr::to_str) != Some("html") {
+            continue;
+        }
+        let html = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let page_dir = path
+            .parent()
+            .and_then(|parent| parent.strip_prefix(output).ok())
+            .unwrap_or(Path::new(""));
+        let inlined = inline_page(&html, page_dir, output, max_bytes);
+        if inlined != html {
+            std::fs::write(path, inlined)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        pages += 1;
+    }
+    Ok(pages)
+}
+
+fn inline_page(html: &str, page_dir: &Path, output: &Path, max_bytes: u64) -> String {
+    let html = replace_tags(html, "<link", None, |tag| {
+        if tag_attr(tag, "rel")? != "stylesheet" {
+            return None;
+        }
+        let css_path = resolve_file(tag_attr(tag, "href")?, page_dir)?;
+        let css = std::fs::read_to_string(output.join(&css_path)).ok()?;
+        let css_dir = css_path.parent().unwrap_or(Path::new(""));
+        Some(format!("<style>{}</style>", inline_css_urls(&css, css_dir, output, max_bytes)))
+    });
+    let html = replace_tags(&html, "<script", Some("</script>"), |tag| {
+        let js = std::fs::read_to_string(output.join(resolve_file(tag_attr(tag, "src")?, page_dir)?)).ok()?;
+        Some(format!("<script>{}</script>", js.replace("</script", "<\\/script")))
+    });
+    rewrite_attr(&html, "src", |value| data_uri(value, page_dir, output, max_bytes, false))
+}
+
+fn inline_css_urls(css: &str, css_dir: &Path, output: &Path, max_bytes: u64) -> String {
+    let mut inlined = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("url(") {
+        let value_start = start + "url(".len();
+        let Some(len) = rest[value_start..].find(')') else {
+            break;
+        };
+        inlined.push_str(&rest[..value_start]);
+        let value = &rest[value_start..value_start + len];
+        let unquoted = value.trim().trim_matches(['"', '\'']);
+        match data_uri(unquoted, css_dir, output, max_bytes, true) {
+            Some(uri) => inlined.push_str(&format!("\"{uri}\"")),
+            None => inlined.push_str(value),
+        }
+        rest = &rest[value_start + len..];
+    }
+    inlined.push_str(rest);
+    inlined
+}
+
+fn data_uri(value: &str, base_dir: &Path, output: &Path, max_bytes: u64, fonts: bool) -> Option<String> {
+    let path = output.join(resolve_file(value, base_dir)?);
+    let mime = content_type(&path);
+    if !(mime.starts_with("image/") || (fonts && mime.starts_with("font/"))) {
+        return None;
+    }
+    if std::fs::metadata(&path).ok()?.len() > max_bytes {
+        return None;
+    }
+    let bytes = std::fs::read(&path).ok()?;
+    Some(format!("data:{mime};base64,{}", base64_encode(&bytes)))
+}
+
+fn resolve_file(value: &str, base_dir: &Path) -> Option<std::path::PathBuf> {
+    if value.starts_with("//") || value.starts_with('#') {
+        return None;
+    }
+    let path = value.split(['?', '#']).next().unwrap_or(value);
+    resolve_local(path, base_dir)
+}
+
+fn replace_tags(
+    html: &str,
+    open: &str,
+    close: Option<&str>,
+    mut replace: impl FnMut(&str) -> Option<String>,
+) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find(open) {
+        let Some(tag_len) = rest[start..].find('>') else {
+            break;
+        };
+        let tag_end = start + tag_len + 1;
+        let end = match close {
+            Some(close) => match rest[tag_end..].find(close) {
+                Some(offset) => tag_end + offset + close.len(),
+                None => break,
+            },
+            None => tag_end,
+        };
+        output.push_str(&rest[..start]);
+        match replace(&rest[start..tag_end]) {
+            Some(replacement) => output.push_str(&replacement),
+            None => output.push_str(&rest[start..end]),
+        }
+        rest = &rest[end..];
+    }
+    output.push_str(rest);
+    output
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let value = (u32::from(chunk[0]) << 16)
+            | (u32::from(chunk.get(1).copied().unwrap_or(0)) << 8)
+            | u32::from(chunk.get(2).copied().unwrap_or(0));
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[(value >> (18 - 6 * index) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_base64_with_padding() {
+        assert_eq!(base64_encode(b"Man"), "TWFu");
+        assert_eq!(base64_encode(b"Ma"), "TWE=");
+        assert_eq!(base64_encode(b"M"), "TQ==");
+    }
+
+    #[test]
+    fn inlines_local_styles_scripts_and_small_images() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let output = dir.path();
+        std::fs::create_dir_all(output.join("guide")).expect("guide dir");
+        std::fs::write(output.join("style.css"), "body { background: url('dot.png'); }").expect("css");
+        std::fs::write(output.join("app.js"), "console.log('</script>');").expect("js");
+        std::fs::write(output.join("dot.png"), b"Man").expect("dot");
+        std::fs::write(output.join("big.png"), vec![0u8; 64]).expect("big");
+        std::fs::write(
+            output.join("guide/page.html"),
+            concat!(
+                r#"<link rel="stylesheet" href="../style.css">"#,
+                r#"<link rel="stylesheet" href="https://cdn.example.com/katex.css">"#,
+                r#"<script defer src="../app.js"></script>"#,
+                r#"<img src="../dot.png"><img src="../big.png">"#,
+            ),
+        )
+        .expect("page");
+
+        assert_eq!(inline_site(output, 16).expect("inline"), 1);
+        let html = std::fs::read_to_string(output.join("guide/page.html")).expect("page");
+        assert_eq!(
+            html,
+            concat!(
+                r#"<style>body { background: url("data:image/png;base64,TWFu"); }</style>"#,
+                r#"<link rel="stylesheet" href="https://cdn.example.com/katex.css">"#,
+                r#"<script>console.log('<\/script>');</script>"#,
+                r#"<img src="data:image/png;base64,TWFu"><img src="../big.png">"#,
+            )
+        );
+    }
+}
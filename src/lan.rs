@@ -0,0 +1,60 @@
+use qrcode::render::unicode::Dense1x2;
+use qrcode::QrCode;
+use std::net::IpAddr;
+
+/// IPv4 addresses of this machine that other devices on the network can
+/// reach, with their interface names. Loopback and link-local addresses
+/// are left out.
+pub fn lan_addresses() -> Vec<(String, IpAddr)> {
+    let mut addresses: Vec<(String, IpAddr)> = local_ip_address::list_afinet_netifas()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(_, ip)| match ip {
+            IpAddr::V4(ip) => !ip.is_loopback() && !ip.is_link_local() && !ip.is_unspecified(),
+            IpAddr::V6(_) => false,
+        })
+        .collect();
+    addresses.sort();
+    addresses.dedup();
+    addresses
+}
+
+/// Whether `host` listens on every interface, so LAN URLs apply.
+pub fn is_wildcard(host: &str) -> bool {
+    matches!(host.trim_start_matches('[').trim_end_matches(']'), "0.0.0.0" | "::")
+}
+
+/// `url` as a QR code drawn with half-block characters, two modules per
+/// line, with the quiet zone scanners need. Colors are inverted so the
+/// code reads dark-on-light in a terminal with a dark background.
+pub fn qr_code(url: &str) -> Option<String> {
+    let code = QrCode::new(url.as_bytes()).ok()?;
+    Some(
+        code.render::<Dense1x2>()
+            .dark_color(Dense1x2::Light)
+            .light_color(Dense1x2::Dark)
+            .quiet_zone(true)
+            .build(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_wildcard_hosts() {
+        assert!(is_wildcard("0.0.0.0"));
+        assert!(is_wildcard("[::]"));
+        assert!(!is_wildcard("127.0.0.1"));
+        assert!(!is_wildcard("192.168.1.20"));
+    }
+
+    #[test]
+    fn draws_qr_codes_as_text() {
+        let code = qr_code("http://192.168.1.20:3000/").expect("qr code");
+        let lines: Vec<&str> = code.lines().collect();
+        assert!(lines.len() > 10);
+        assert!(lines.iter().all(|line| line.chars().count() == lines[0].chars().count()));
+    }
+}
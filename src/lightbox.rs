@@ -0,0 +1,132 @@
+use crate::settings::Settings;
+
+/// Lightbox CSS and script for a page, or `None` when it is turned off.
+/// A `lightbox` front matter value overrides the site-wide setting.
+pub fn lightbox_head(page: Option<bool>, settings: &Settings) -> Option<&'static str> {
+    lightbox_enabled(settings.lightbox, page).then_some(LIGHTBOX_HEAD)
+}
+
+fn lightbox_enabled(site: Option<bool>, page: Option<bool>) -> bool {
+    page.or(site).unwrap_or(false)
+}
+
+const LIGHTBOX_HEAD: &str = r#"<style>
+.rendar-lightbox { position: fixed; inset: 0; z-index: 9000; display: flex; align-items: center; justify-content: center; background: rgba(0, 0, 0, 0.88); cursor: zoom-out; }
+.rendar-lightbox img { max-width: 94vw; max-height: 88vh; box-shadow: 0 8px 32px rgba(0, 0, 0, 0.5); background: #fff; }
+.rendar-lightbox figcaption { position: absolute; bottom: 16px; left: 0; right: 0; text-align: center; color: #eee; font-size: 14px; }
+.rendar-lightbox button { position: absolute; top: 50%; transform: translateY(-50%); border: none; background: rgba(255, 255, 255, 0.15); color: #fff; font-size: 32px; width: 48px; height: 64px; cursor: pointer; }
+.rendar-lightbox .lightbox-prev { left: 16px; }
+.rendar-lightbox .lightbox-next { right: 16px; }
+img.lightbox-image { cursor: zoom-in; }
+</style>
+<script>
+document.addEventListener("DOMContentLoaded", () => {
+  const images = Array.from(new Set(document.querySelectorAll("main img, .content img, article img")))
+    .filter((img) => {
+      const link = img.closest("a");
+      return !link || link.classList.contains("thumbnail");
+    });
+  if (!images.length) {
+    return;
+  }
+  const fullSource = (img) => {
+    const link = img.closest("a.thumbnail");
+    return link ? link.href : img.currentSrc || img.src;
+  };
+  let overlay = null;
+  let index = 0;
+  function show(next) {
+    index = (next + images.length) % images.length;
+    const img = images[index];
+    overlay.querySelector("img").src = fullSource(img);
+    overlay.querySelector("img").alt = img.alt;
+    const caption = img.title || img.alt;
+    const counter = images.length > 1 ? ` (${index + 1} / ${images.length})` : "";
+    overlay.querySelector("figcaption").textContent = caption ? `${caption}${counter}` : counter.trim();
+  }
+  function close() {
+    overlay.remove();
+    overlay = null;
+    (images[index].closest("a.thumbnail") || images[index]).focus({ preventScroll: true });
+  }
+  function open(start) {
+    overlay = document.createElement("figure");
+    overlay.className = "rendar-lightbox";
+    overlay.setAttribute("role", "dialog");
+    overlay.setAttribute("aria-modal", "true");
+    overlay.innerHTML = '<img alt=""><figcaption></figcaption>';
+    if (images.length > 1) {
+      for (const [name, step, label] of [["prev", -1, "‹"], ["next", 1, "›"]]) {
+        const button = document.createElement("button");
+        button.type = "button";
+        button.className = `lightbox-${name}`;
+        button.textContent = label;
+        const strings = window.rendarStrings || {};
+        button.setAttribute(
+          "aria-label",
+          name === "prev" ? strings.previous_image || "Previous image" : strings.next_image || "Next image"
+        );
+        button.addEventListener("click", (event) => {
+          event.stopPropagation();
+          show(index + step);
+        });
+        overlay.appendChild(button);
+      }
+    }
+    overlay.addEventListener("click", close);
+    document.body.appendChild(overlay);
+    show(start);
+  }
+  images.forEach((img, position) => {
+    img.classList.add("lightbox-image");
+    const target = img.closest("a.thumbnail") || img;
+    if (target === img) {
+      // Thumbnail links are focusable already and open on Enter.
+      img.tabIndex = 0;
+      img.addEventListener("keydown", (event) => {
+        if (event.key === "Enter" && !overlay) {
+          event.preventDefault();
+          open(position);
+        }
+      });
+    }
+    target.addEventListener("click", (event) => {
+      event.preventDefault();
+      open(position);
+    });
+  });
+  document.addEventListener("keydown", (event) => {
+    if (!overlay) {
+      return;
+    }
+    if (event.key === "Escape") {
+      close();
+    } else if (event.key === "ArrowRight") {
+      show(index + 1);
+    } else if (event.key === "ArrowLeft") {
+      show(index - 1);
+    } else if (event.key === "Home") {
+      show(0);
+    } else if (event.key === "End") {
+      show(images.length - 1);
+    } else {
+      return;
+    }
+    event.preventDefault();
+  });
+});
+</script>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn front_matter_overrides_site_setting() {
+        assert!(!lightbox_enabled(None, None));
+        assert!(lightbox_enabled(Some(true), None));
+        assert!(!lightbox_enabled(Some(true), Some(false)));
+        assert!(lightbox_enabled(None, Some(true)));
+    }
+}
@@ -0,0 +1,227 @@
+use crate::links::resolve_local;
+use crate::render::{markdown_options, strip_front_matter};
+use crate::sidebar::is_sidebar;
+use crate::settings::Settings;
+use crate::site::{is_ignored_path, relative_path, walk_entries, PathFilter};
+use anyhow::{Context, Result};
+use pulldown_cmark::{Event, Parser, Tag};
+use std::ffi::OsStr;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// A link destination in a source file that `check --fix` rewrites.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkFix {
+    pub line: usize,
+    pub range: Range<usize>,
+    pub old: String,
+    pub new: String,
+}
+
+pub struct FileFixes {
+    pub path: PathBuf,
+    pub source: String,
+    pub fixes: Vec<LinkFix>,
+}
+
+impl FileFixes {
+    pub fn fixed_source(&self) -> String {
+        let mut fixed = self.source.clone();
+        for fix in self.fixes.iter().rev() {
+            fixed.replace_range(fix.range.clone(), &fix.new);
+        }
+        fixed
+    }
+}
+
+/// Broken local links whose target can be found unambiguously: a different
+/// letter case, a path written from the wrong base (`/page.md` meaning
+/// `./page.md`, or a root-relative path without the leading `/`), or a
+/// Markdown page that moved but still has a unique file name.
+pub fn find_link_fixes(input: &Path, filter: &PathFilter, settings: &Settings) -> Result<Vec<FileFixes>> {
+    let pages: Vec<PathBuf> = walk_entries(input, filter)
+        .filter(|entry| entry.file_type().is_file() && settings.is_markdown(entry.path()) && !is_sidebar(entry.path()))
+        .filter_map(|entry| entry.path().strip_prefix(input).ok().map(Path::to_path_buf))
+        .collect();
+    let mut files = Vec::new();
+    for rel_path in &pages {
+        let path = input.join(rel_path);
+        let source = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read markdown file {}", path.display()))?;
+        let rel_dir = rel_path.parent().unwrap_or(Path::new(""));
+        let fixes = source_fixes(&source, |dest| fix_target(dest, rel_dir, input, filter, &pages, settings));
+        if !fixes.is_empty() {
+            files.push(FileFixes { path, source, fixes });
+        }
+    }
+    Ok(files)
+}
+
+fn source_fixes(source: &str, mut fix: impl FnMut(&str) -> Option<String>) -> Vec<LinkFix> {
+    let content = strip_front_matter(source);
+    let offset = source.len() - content.len();
+    let mut fixes = Vec::new();
+    for (event, range) in Parser::new_ext(content, markdown_options(false)).into_offset_iter() {
+        let (Event::Start(Tag::Link { dest_url, .. }) | Event::Start(Tag::Image { dest_url, .. })) = event else {
+            continue;
+        };
+        let Some(new) = fix(&dest_url) else {
+            continue;
+        };
+        // Only inline links spell the destination out next to the text;
+        // reference definitions and escaped destinations are left alone.
+        let Some(start) = content[range.clone()].rfind(&format!("]({dest_url}")) else {
+            continue;
+        };
+        let start = offset + range.start + start + 2;
+        fixes.push(LinkFix {
+            line: source[..start].matches('\n').count() + 1,
+            range: start..start + dest_url.len(),
+            old: dest_url.to_string(),
+            new,
+        });
+    }
+    fixes
+}
+
+fn fix_target(
+    dest: &str,
+    rel_dir: &Path,
+    input: &Path,
+    filter: &PathFilter,
+    pages: &[PathBuf],
+    settings: &Settings,
+) -> Option<String> {
+    let (base, suffix) = dest.split_at(dest.find(['#', '?']).unwrap_or(dest.len()));
+    if base.is_empty() || base.contains(':') {
+        return None;
+    }
+    let target = resolve_local(base, rel_dir)?;
+    if input.join(&target).exists() {
+        return None;
+    }
+    let exists = |rel: &Path| {
+        let path = input.join(rel);
+        path.exists() && !is_ignored_path(&path, input, filter)
+    };
+    let rooted = base.starts_with('/');
+    let found = if let Some(found) = match_case(input, &target).filter(|found| exists(found)) {
+        if rooted {
+            return Some(format!("/{}{suffix}", slash_path(&found)));
+        }
+        found
+    } else if let Some(found) = resolve_local(base.trim_start_matches('/'), if rooted { rel_dir } else { Path::new("") })
+        .filter(|found| exists(found))
+    {
+        found
+    } else if settings.is_markdown(Path::new(base)) {
+        let name = target.file_name()?.to_string_lossy().to_lowercase();
+        let mut matches = pages.iter().filter(|page| {
+            page.file_name()
+                .and_then(OsStr::to_str)
+                .is_some_and(|page_name| page_name.to_lowercase() == name)
+        });
+        match (matches.next(), matches.next()) {
+            (Some(found), None) => found.clone(),
+            _ => return None,
+        }
+    } else {
+        return None;
+    };
+    Some(format!("{}{suffix}", relative_path(rel_dir, &found)))
+}
+
+/// `rel` with each component replaced by the single entry that matches it
+/// ignoring case.
+fn match_case(input: &Path, rel: &Path) -> Option<PathBuf> {
+    let mut found = PathBuf::new();
+    for component in rel.components() {
+        let name = component.as_os_str().to_string_lossy().to_lowercase();
+        let mut matches = std::fs::read_dir(input.join(&found))
+            .ok()?
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_name().to_string_lossy().to_lowercase() == name);
+        let (Some(entry), None) = (matches.next(), matches.next()) else {
+            return None;
+        };
+        found.push(entry.file_name());
+    }
+    Some(found)
+}
+
+fn slash_path(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// A unified diff of the changed lines, for review before writing.
+pub fn diff(files: &[FileFixes]) -> String {
+    let mut out = String::new();
+    for file in files {
+        let path = file.path.display();
+        out.push_str(&format!("--- {path}\n+++ {path}\n"));
+        let fixed = file.fixed_source();
+        let old_lines: Vec<&str> = file.source.lines().collect();
+        let new_lines: Vec<&str> = fixed.lines().collect();
+        let mut lines: Vec<usize> = file.fixes.iter().map(|fix| fix.line).collect();
+        lines.dedup();
+        for line in lines {
+            out.push_str(&format!(
+                "@@ -{line} +{line} @@\n-{}\n+{}\n",
+                old_lines[line - 1],
+                new_lines[line - 1]
+            ));
+        }
+    }
+    out
+}
+
+pub fn write_fixes(files: &[FileFixes]) -> Result<()> {
+    for file in files {
+        std::fs::write(&file.path, file.fixed_source())
+            .with_context(|| format!("Failed to write {}", file.path.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixes_case_base_and_moved_pages() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let input = dir.path();
+        std::fs::create_dir_all(input.join("guide/setup")).expect("dirs");
+        std::fs::write(input.join("guide/Install.md"), "# Install").expect("install");
+        std::fs::write(input.join("guide/setup/proxy.md"), "# Proxy").expect("proxy");
+        std::fs::write(input.join("faq.md"), "# FAQ").expect("faq");
+        std::fs::write(
+            input.join("guide/index.md"),
+            "---\ntitle: Guide\n---\n# Guide\n\n[install](install.md#linux) and [faq](/faq.md)\n\
+             [proxy](proxy.md), [here](/Install.md), [root](guide/setup/proxy.md)\n\
+             [gone](missing.md) [web](https://example.com/x.md)\n",
+        )
+        .expect("index");
+
+        let files = find_link_fixes(input, &PathFilter::default(), &Settings::default()).expect("fixes");
+        assert_eq!(files.len(), 1);
+        let fixes: Vec<(&str, &str)> = files[0].fixes.iter().map(|fix| (fix.old.as_str(), fix.new.as_str())).collect();
+        assert_eq!(
+            fixes,
+            [
+                ("install.md#linux", "Install.md#linux"),
+                ("proxy.md", "setup/proxy.md"),
+                ("/Install.md", "Install.md"),
+                ("guide/setup/proxy.md", "setup/proxy.md"),
+            ]
+        );
+        assert_eq!(files[0].fixes[0].line, 6);
+        assert!(diff(&files).contains("@@ -6 +6 @@\n-[install](install.md#linux) and [faq](/faq.md)\n+[install](Install.md#linux)"));
+
+        write_fixes(&files).expect("write");
+        assert!(find_link_fixes(input, &PathFilter::default(), &Settings::default()).expect("refix").is_empty());
+    }
+}
@@ -0,0 +1,268 @@
+use crate::diagnostics::Diagnostic;
+use crate::freshness::format_date;
+use crate::render::{markdown_options, strip_front_matter};
+use crate::sidebar::is_sidebar;
+use crate::settings::Settings;
+use crate::site::{walk_entries, PathFilter};
+use anyhow::{Context, Result};
+use pulldown_cmark::{Event, Parser, Tag};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_STATE_FILE: &str = "rendar-links.json";
+/// Consecutive failed checks before a link counts as broken, so a single
+/// timeout in CI is not reported.
+const BROKEN_AFTER: u32 = 2;
+/// Results kept per link to detect flapping.
+const HISTORY_LEN: usize = 8;
+/// Status changes within the kept history that make a link flapping.
+const FLAPPING_CHANGES: usize = 3;
+const CHECK_THREADS: usize = 8;
+
+/// Check results per URL, stored in a JSON file meant to be committed so
+/// each run can compare against the previous ones.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct LinkState {
+    pub links: BTreeMap<String, LinkRecord>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct LinkRecord {
+    /// HTTP status code of the last check, or why it failed.
+    pub status: String,
+    pub last_checked: u64,
+    pub last_ok: Option<u64>,
+    pub failures: u32,
+    /// Recent results, oldest first: `+` for OK and `-` for a failure.
+    pub history: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Transition {
+    NewlyBroken,
+    Flapping,
+    Recovered,
+}
+
+pub type CheckResult = std::result::Result<u16, String>;
+
+impl LinkRecord {
+    fn is_flapping(&self) -> bool {
+        let changes = self
+            .history
+            .as_bytes()
+            .windows(2)
+            .filter(|pair| pair[0] != pair[1])
+            .count();
+        changes >= FLAPPING_CHANGES
+    }
+}
+
+impl LinkState {
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read link state {}", path.display()))?;
+        serde_json::from_str(&text)
+            .with_context(|| format!("Failed to parse link state {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize link state")?;
+        std::fs::write(path, format!("{json}\n"))
+            .with_context(|| format!("Failed to write link state {}", path.display()))
+    }
+
+    /// Stores a check result and returns the transition it causes, if any.
+    pub fn record(&mut self, url: &str, result: &CheckResult, now: u64) -> Option<Transition> {
+        let record = self.links.entry(url.to_string()).or_default();
+        let was_broken = record.failures >= BROKEN_AFTER;
+        let was_flapping = record.is_flapping();
+        let ok = result.is_ok();
+        record.status = match result {
+            Ok(status) => status.to_string(),
+            Err(reason) => reason.clone(),
+        };
+        record.last_checked = now;
+        record.history.push(if ok { '+' } else { '-' });
+        if record.history.len() > HISTORY_LEN {
+            record.history.remove(0);
+        }
+        if ok {
+            record.last_ok = Some(now);
+            record.failures = 0;
+        } else {
+            record.failures += 1;
+        }
+        if record.is_flapping() && !was_flapping {
+            Some(Transition::Flapping)
+        } else if record.failures == BROKEN_AFTER {
+            Some(Transition::NewlyBroken)
+        } else if ok && was_broken {
+            Some(Transition::Recovered)
+        } else {
+            None
+        }
+    }
+
+    /// Drops links that no page uses anymore.
+    pub fn retain_urls(&mut self, urls: &BTreeMap<String, PathBuf>) {
+        self.links.retain(|url, _| urls.contains_key(url));
+    }
+}
+
+/// External `http(s)` link and image targets, mapped to the first page that
+/// uses each.
+pub fn collect_external_links(
+    input: &Path,
+    filter: &PathFilter,
+    settings: &Settings,
+) -> Result<BTreeMap<String, PathBuf>> {
+    let mut links = BTreeMap::new();
+    for entry in walk_entries(input, filter) {
+        let path = entry.path();
+        if !entry.file_type().is_file() || !settings.is_markdown(path) || is_sidebar(path) {
+            continue;
+        }
+        let markdown = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read markdown file {}", path.display()))?;
+        for url in external_links(&markdown) {
+            links.entry(url).or_insert_with(|| path.to_path_buf());
+        }
+    }
+    Ok(links)
+}
+
+fn external_links(markdown: &str) -> Vec<String> {
+    Parser::new_ext(strip_front_matter(markdown), markdown_options(false))
+        .filter_map(|event| match event {
+            Event::Start(Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. })
+                if dest_url.starts_with("http://") || dest_url.starts_with("https://") =>
+            {
+                Some(dest_url.split('#').next().unwrap_or_default().to_string())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Requests each URL (HEAD, falling back to GET for servers that reject
+/// HEAD) on a few threads at a time.
+pub fn check_urls(urls: &[&String]) -> Vec<CheckResult> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(15))
+        .user_agent(concat!("rendar/", env!("CARGO_PKG_VERSION")))
+        .build();
+    let mut results = Vec::with_capacity(urls.len());
+    for chunk in urls.chunks(CHECK_THREADS) {
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = chunk
+                .iter()
+                .map(|url| scope.spawn(|| check_url(&agent, url)))
+                .collect();
+            results.extend(handles.into_iter().map(|handle| {
+                handle.join().unwrap_or_else(|_| Err("check panicked".to_string()))
+            }));
+        });
+    }
+    results
+}
+
+fn check_url(agent: &ureq::Agent, url: &str) -> CheckResult {
+    let response = match agent.head(url).call() {
+        Err(ureq::Error::Status(405 | 403 | 501, _)) => agent.get(url).call(),
+        response => response,
+    };
+    match response {
+        Ok(response) => Ok(response.status()),
+        Err(ureq::Error::Status(code, _)) => Err(format!("HTTP {code}")),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Checks every external link, updates the state file, and returns warnings
+/// for links that just broke or started flapping.
+pub fn check_link_rot(input: &Path, filter: &PathFilter, state_file: &Path, settings: &Settings) -> Result<Vec<Diagnostic>> {
+    let links = collect_external_links(input, filter, settings)?;
+    let mut state = LinkState::load(state_file)?;
+    state.retain_urls(&links);
+    let urls: Vec<&String> = links.keys().collect();
+    let results = check_urls(&urls);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let mut warnings = Vec::new();
+    for (url, result) in urls.iter().zip(&results) {
+        let page = &links[*url];
+        let reason = result.as_ref().err().map_or("", String::as_str);
+        match state.record(url, result, now) {
+            Some(Transition::NewlyBroken) => {
+                let last_ok = state.links[*url]
+                    .last_ok
+                    .map(|time| format!("; last OK {}", format_date(time)))
+                    .unwrap_or_default();
+                warnings.push(Diagnostic::warning(
+                    "broken-external-link",
+                    page,
+                    format!("External link {url} is broken ({reason}{last_ok})"),
+                ));
+            }
+            Some(Transition::Flapping) => warnings.push(Diagnostic::warning(
+                "flapping-external-link",
+                page,
+                format!("External link {url} is flapping (recent checks: {})", state.links[*url].history),
+            )),
+            Some(Transition::Recovered) => {
+                eprintln!("External link {url} works again");
+            }
+            None => {}
+        }
+    }
+    state.save(state_file)?;
+    Ok(warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_transitions_instead_of_every_failure() {
+        let mut state = LinkState::default();
+        let url = "https://example.com/guide";
+        let fail: CheckResult = Err("HTTP 404".to_string());
+        assert_eq!(state.record(url, &Ok(200), 1), None);
+        assert_eq!(state.record(url, &fail, 2), None);
+        assert_eq!(state.record(url, &fail, 3), Some(Transition::NewlyBroken));
+        assert_eq!(state.record(url, &fail, 4), None);
+        assert_eq!(state.record(url, &Ok(200), 5), Some(Transition::Recovered));
+        let record = &state.links[url];
+        assert_eq!(record.history, "+---+");
+        assert_eq!(record.last_ok, Some(5));
+    }
+
+    #[test]
+    fn detects_flapping_links() {
+        let mut state = LinkState::default();
+        let url = "https://flaky.example.com";
+        let fail: CheckResult = Err("timed out".to_string());
+        assert_eq!(state.record(url, &Ok(200), 1), None);
+        assert_eq!(state.record(url, &fail, 2), None);
+        assert_eq!(state.record(url, &Ok(200), 3), None);
+        assert_eq!(state.record(url, &fail, 4), Some(Transition::Flapping));
+        assert_eq!(state.record(url, &Ok(200), 5), None);
+    }
+
+    #[test]
+    fn extracts_external_link_and_image_targets() {
+        let markdown = "[Docs](https://docs.rs/rendar#usage) [local](guide.md) ![Logo](http://example.com/logo.png)\n";
+        assert_eq!(
+            external_links(markdown),
+            vec!["https://docs.rs/rendar", "http://example.com/logo.png"]
+        );
+    }
+}
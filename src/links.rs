@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+pub fn rewrite_attr(html: &str, attr: &str, mut rewrite: impl FnMut(&str) -> Option<String>) -> String {
+    let needle = format!(" {attr}=\"");
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find(&needle) {
+        let value_start = start + needle.len();
+        let Some(len) = rest[value_start..].find('"') else {
+            break;
+        };
+        output.push_str(&rest[..value_start]);
+        let value = &rest[value_start..value_start + len];
+        match rewrite(value) {
+            Some(replacement) => output.push_str(&replacement),
+            None => output.push_str(value),
+        }
+        rest = &rest[value_start + len..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Value of a double-quoted attribute in a single HTML tag.
+pub fn tag_attr<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let needle = format!(" {name}=\"");
+    let start = tag.find(&needle)? + needle.len();
+    let len = tag[start..].find('"')?;
+    Some(&tag[start..start + len])
+}
+
+pub fn resolve_local(value: &str, base_dir: &Path) -> Option<PathBuf> {
+    if value.is_empty() || value.contains("://") || value.starts_with("data:") {
+        return None;
+    }
+    let joined = match value.strip_prefix('/') {
+        Some(rooted) => PathBuf::from(rooted),
+        None => base_dir.join(value),
+    };
+    let mut parts = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::Normal(part) => parts.push(part),
+            Component::ParentDir if !parts.pop() => return None,
+            _ => {}
+        }
+    }
+    Some(parts)
+}
+
+/// `src`, `srcset`, and `poster` URLs of a page in `page_dir`, made
+/// relative to the site root, for pages merged into one document there.
+pub fn rebase_resources(html: &str, page_dir: &Path) -> String {
+    let html = rewrite_attr(html, "src", |value| rebase_url(value, page_dir));
+    let html = rewrite_attr(&html, "poster", |value| rebase_url(value, page_dir));
+    rewrite_attr(&html, "srcset", |value| {
+        let candidates: Vec<String> = value
+            .split(',')
+            .map(|candidate| {
+                let candidate = candidate.trim();
+                let (url, descriptor) = candidate.split_once(char::is_whitespace).unwrap_or((candidate, ""));
+                let url = rebase_url(url, page_dir).unwrap_or_else(|| url.to_string());
+                match descriptor.trim() {
+                    "" => url,
+                    descriptor => format!("{url} {descriptor}"),
+                }
+            })
+            .collect();
+        Some(candidates.join(", "))
+    })
+}
+
+/// A relative URL from a page in `page_dir` as a URL from the site root,
+/// keeping its query and fragment. `None` for URLs with a scheme and for
+/// fragment-only ones.
+fn rebase_url(value: &str, page_dir: &Path) -> Option<String> {
+    let scheme = value
+        .split_once(':')
+        .is_some_and(|(scheme, _)| !scheme.is_empty() && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.')));
+    if scheme || value.starts_with('#') || value.starts_with("//") {
+        return None;
+    }
+    let (path, suffix) = value.split_at(value.find(['?', '#']).unwrap_or(value.len()));
+    let rel = resolve_local(path, page_dir)?;
+    let parts: Vec<String> = rel.components().map(|part| part.as_os_str().to_string_lossy().into_owned()).collect();
+    Some(format!("{}{suffix}", parts.join("/")))
+}
+
+/// Prefixes the ids of a page in `page_dir` with `namespace`, for pages
+/// merged into one document. Links to merged pages become fragments in
+/// it; other local links are made relative to the site root.
+pub fn namespace_anchors(
+    html: &str,
+    namespace: &str,
+    page_dir: &Path,
+    namespaces: &HashMap<PathBuf, String>,
+) -> String {
+    let html = rewrite_attr(html, "id", |id| Some(format!("{namespace}-{id}")));
+    rewrite_attr(&html, "href", |value| {
+        let (path, fragment) = match value.split_once('#') {
+            Some((path, fragment)) => (path, Some(fragment)),
+            None => (value, None),
+        };
+        let target = if path.is_empty() {
+            namespace
+        } else {
+            match resolve_local(path, page_dir).and_then(|rel| namespaces.get(&rel)) {
+                Some(target) => target.as_str(),
+                None => return rebase_url(value, page_dir),
+            }
+        };
+        Some(match fragment {
+            Some(fragment) => format!("#{target}-{fragment}"),
+            None => format!("#{target}"),
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebases_resources_onto_the_site_root() {
+        let html = concat!(
+            r#"<img src="img/shot.png" srcset="img/shot.png 1x, ../shared/shot@2x.png 2x" alt="">"#,
+            r#"<video poster="poster.jpg?v=2" src="https://cdn.example.com/clip.mp4"></video>"#,
+            r#"<img src="data:image/png;base64,AAAA">"#,
+        );
+        assert_eq!(
+            rebase_resources(html, Path::new("guide/setup")),
+            concat!(
+                r#"<img src="guide/setup/img/shot.png" srcset="guide/setup/img/shot.png 1x, guide/shared/shot@2x.png 2x" alt="">"#,
+                r#"<video poster="guide/setup/poster.jpg?v=2" src="https://cdn.example.com/clip.mp4"></video>"#,
+                r#"<img src="data:image/png;base64,AAAA">"#,
+            )
+        );
+    }
+
+    #[test]
+    fn namespaces_ids_and_links_between_pages() {
+        let mut namespaces = HashMap::new();
+        namespaces.insert(PathBuf::from("index.html"), "page-1".to_string());
+        namespaces.insert(PathBuf::from("guide/intro.html"), "page-2".to_string());
+        let html = concat!(
+            r##"<h2 id="setup">Setup</h2><p>See<sup class="footnote-reference"><a href="#1">1</a></sup> "##,
+            r##"<a href="intro.html#setup">intro</a>, <a href="../index.html">home</a>, "##,
+            r##"<a href="files/spec.pdf#page=2">spec</a>, <a href="mailto:team@example.com">mail</a>, "##,
+            r##"<a href="https://example.com/#x">ext</a></p><div class="footnote-definition" id="1"></div>"##,
+        );
+        let namespaced = namespace_anchors(html, "page-2", Path::new("guide"), &namespaces);
+        assert!(namespaced.contains(r#"<h2 id="page-2-setup">"#));
+        assert!(namespaced.contains(r##"<a href="#page-2-1">1</a>"##));
+        assert!(namespaced.contains(r##"<a href="#page-2-setup">intro</a>"##));
+        assert!(namespaced.contains(r##"<a href="#page-1">home</a>"##));
+        assert!(namespaced.contains(r##"<a href="guide/files/spec.pdf#page=2">spec</a>"##));
+        assert!(namespaced.contains(r##"<a href="mailto:team@example.com">mail</a>"##));
+        assert!(namespaced.contains(r##"<a href="https://example.com/#x">ext</a>"##));
+        assert!(namespaced.contains(r#"class="footnote-definition" id="page-2-1""#));
+    }
+}
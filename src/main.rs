@@ -1,22 +1,76 @@
 use anyhow::{Context, Result};
 use axum::extract::State;
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
 use globset::{Glob, GlobSet, GlobSetBuilder};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
-use std::time::Duration;
-
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use settings::Settings;
+
+mod a11y;
+mod api_docs;
+mod audience;
+mod baseline;
+mod children;
+mod clean;
 mod config;
 mod csv_preview;
+mod data_pages;
+mod deploy;
+mod diagnostics;
+mod duplicates;
+mod editor;
+mod empty_input;
+mod epub;
+mod export;
+mod file_urls;
+mod freshness;
+mod hooks;
+mod hygiene;
+mod i18n;
+mod icons;
+mod inline;
+mod lan;
+mod lightbox;
+mod link_fix;
+mod link_rot;
+mod links;
+mod manifest;
+mod nav;
+mod obsidian;
+mod packages;
+mod page_views;
+mod plugins;
+mod preview_auth;
+mod process_pool;
+mod provenance;
+mod redirects;
 mod render;
-mod slides;
+mod sanitize;
+mod search;
+mod serve;
+mod settings;
+mod sidebar;
 mod site;
+mod sitemap;
+mod slides;
+mod staging;
+mod stats;
 mod template;
+mod thumbnails;
+mod todos;
+mod versions;
 
 #[derive(Parser)]
 #[command(name = "rendar", version, about = "Render a Markdown tree into a static HTML site")]
 struct Cli {
+    /// Fail on unknown keys in the config file instead of warning.
+    #[arg(long, global = true)]
+    strict_config: bool,
+    /// Apply `[profile.<NAME>]` from the config (default: `dev` for preview, `production` otherwise, if defined).
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
     #[command(subcommand)]
     command: Command,
 }
@@ -40,9 +94,24 @@ enum Command {
         /// Glob patterns to exclude from rendering (relative to input).
         #[arg(long, value_name = "PATTERN", action = clap::ArgAction::Append)]
         exclude: Vec<String>,
+        /// Only include pages whose `audience` front matter allows this audience.
+        #[arg(long, value_name = "NAME")]
+        audience: Option<String>,
         /// Maximum CSV rows to render (0 = unlimited).
         #[arg(long, value_name = "ROWS", default_value_t = 1000)]
         csv_max_rows: usize,
+        /// Extra files to write into the output directory.
+        #[arg(long, value_name = "FILE", action = clap::ArgAction::Append)]
+        emit: Vec<Emit>,
+        /// Show the slowest pages after the build summary.
+        #[arg(long)]
+        timings: bool,
+        /// Empty the output directory first so removed or renamed pages don't linger.
+        #[arg(long)]
+        clean: bool,
+        /// Fail without touching the output directory if the build reports any warning.
+        #[arg(long)]
+        strict: bool,
     },
     /// Check for broken links and other warnings without writing output.
     Check {
@@ -55,6 +124,104 @@ enum Command {
         /// Glob patterns to exclude from rendering (relative to input).
         #[arg(long, value_name = "PATTERN", action = clap::ArgAction::Append)]
         exclude: Vec<String>,
+        /// Only include pages whose `audience` front matter allows this audience.
+        #[arg(long, value_name = "NAME")]
+        audience: Option<String>,
+        /// Also flag mixed line endings in code blocks and invisible Unicode characters.
+        #[arg(long)]
+        hygiene: bool,
+        /// Also report `TODO:`/`FIXME:` markers and `<!-- todo -->` comments.
+        #[arg(long)]
+        todos: bool,
+        /// Also flag missing alt text, empty or vague link text, and tables without a header row.
+        #[arg(long)]
+        a11y: bool,
+        /// Also report pages whose text overlaps at least this much (0-1, default 0.8).
+        #[arg(long, value_name = "THRESHOLD", num_args = 0..=1, default_missing_value = "0.8")]
+        duplicates: Option<f64>,
+        /// Also check external links and report ones that newly broke or started flapping.
+        #[arg(long)]
+        external_links: bool,
+        /// Print the findings to stdout as JSON or SARIF, besides the warnings on stderr.
+        #[arg(long, value_enum, default_value = "text")]
+        format: CheckFormat,
+        /// Rewrite broken links that have one obvious target (wrong case, wrong base folder, moved page), after showing a diff.
+        #[arg(long, conflicts_with = "format")]
+        fix: bool,
+        /// Apply `--fix` changes without asking.
+        #[arg(long, requires = "fix")]
+        yes: bool,
+        /// Exit successfully as long as there are at most this many (new) warnings.
+        #[arg(long, value_name = "N")]
+        max_warnings: Option<usize>,
+        /// Baseline of known warnings to leave out of the report (default: rendar-baseline.json).
+        #[arg(long, value_name = "FILE")]
+        baseline: Option<PathBuf>,
+        /// Record the current warnings in the baseline file and exit.
+        #[arg(long)]
+        write_baseline: bool,
+    },
+    /// Print page, word, image, and broken link totals per directory.
+    Stats {
+        /// Input directory to scan (defaults to current directory).
+        #[arg(short, long)]
+        input: Option<PathBuf>,
+        /// Optional config file path (e.g., rendar.toml).
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+        /// Glob patterns to exclude from rendering (relative to input).
+        #[arg(long, value_name = "PATTERN", action = clap::ArgAction::Append)]
+        exclude: Vec<String>,
+        /// Only include pages whose `audience` front matter allows this audience.
+        #[arg(long, value_name = "NAME")]
+        audience: Option<String>,
+    },
+    /// Export the rendered site to printable and e-reader formats.
+    #[command(group(
+        clap::ArgGroup::new("format")
+            .required(true)
+            .multiple(true)
+            .args(["pdf", "epub"])
+    ))]
+    Export {
+        /// Output directory for exported files.
+        #[arg(short, long)]
+        out: PathBuf,
+        /// Input directory to render (defaults to current directory).
+        #[arg(short, long)]
+        input: Option<PathBuf>,
+        /// Optional config file path (e.g., rendar.toml).
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+        /// Optional template file path.
+        #[arg(long)]
+        template: Option<PathBuf>,
+        /// Glob patterns to exclude from rendering (relative to input).
+        #[arg(long, value_name = "PATTERN", action = clap::ArgAction::Append)]
+        exclude: Vec<String>,
+        /// Only include pages whose `audience` front matter allows this audience.
+        #[arg(long, value_name = "NAME")]
+        audience: Option<String>,
+        /// Maximum CSV rows to render (0 = unlimited).
+        #[arg(long, value_name = "ROWS", default_value_t = 1000)]
+        csv_max_rows: usize,
+        /// Export PDFs using headless Chromium.
+        #[arg(long)]
+        pdf: bool,
+        /// Write one combined PDF in navigation order instead of one per page.
+        #[arg(long, requires = "pdf")]
+        combined: bool,
+        /// Path to the Chromium/Chrome executable (defaults to auto-detect).
+        #[arg(long, value_name = "PATH", requires = "pdf")]
+        chrome: Option<PathBuf>,
+        /// Export a single EPUB with all pages in navigation order.
+        #[arg(long)]
+        epub: bool,
+    },
+    /// Upload the build output to a hosting target.
+    Deploy {
+        #[command(subcommand)]
+        target: DeployTarget,
     },
     /// Start a local preview server with live reload.
     Preview {
@@ -88,6 +255,9 @@ enum Command {
         /// Port for the preview server.
         #[arg(long)]
         port: Option<u16>,
+        /// Address to bind the preview server to (e.g. 0.0.0.0 for LAN access).
+        #[arg(long, value_name = "ADDRESS")]
+        host: Option<String>,
         /// Glob patterns to exclude from rendering (relative to input).
         #[arg(long, value_name = "PATTERN", action = clap::ArgAction::Append)]
         exclude: Vec<String>,
@@ -95,10 +265,121 @@ enum Command {
         #[arg(long, value_name = "ROWS", default_value_t = 1000)]
         csv_max_rows: usize,
     },
+    /// Serve an already-built output directory over HTTP.
+    Serve {
+        /// Output directory produced by `build`.
+        #[arg(short, long)]
+        out: PathBuf,
+        /// Port to listen on.
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+        /// Address to bind to (e.g. 0.0.0.0 to accept outside connections).
+        #[arg(long, value_name = "ADDRESS", default_value = "127.0.0.1")]
+        host: String,
+        /// Answer unknown paths with index.html instead of a 404, for single-page apps.
+        #[arg(long)]
+        spa: bool,
+    },
+    /// Validate rendar.toml or print its JSON Schema.
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Print a shell completion script for rendar's commands and flags.
+    Completions {
+        /// Shell to generate the script for.
+        shell: clap_complete::Shell,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Report unknown keys, missing paths, and template errors, then print the effective config.
+    Check {
+        /// Input directory (overrides the config).
+        #[arg(short, long)]
+        input: Option<PathBuf>,
+        /// Optional config file path (e.g., rendar.toml).
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+        /// Optional template file path.
+        #[arg(long)]
+        template: Option<PathBuf>,
+    },
+    /// Print a JSON Schema of rendar.toml for editor completion.
+    Schema,
+}
+
+#[derive(Subcommand)]
+enum DeployTarget {
+    /// Sync the output directory to an S3-compatible bucket (AWS S3, Cloudflare R2, MinIO).
+    S3 {
+        /// Build output directory to upload.
+        #[arg(short, long)]
+        out: PathBuf,
+        /// Optional config file path (e.g., rendar.toml).
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+        /// Bucket name.
+        #[arg(long)]
+        bucket: Option<String>,
+        /// Key prefix inside the bucket (defaults to the bucket root).
+        #[arg(long)]
+        prefix: Option<String>,
+        /// Bucket region (defaults to AWS_REGION or us-east-1).
+        #[arg(long)]
+        region: Option<String>,
+        /// Endpoint URL for non-AWS services such as R2 or MinIO.
+        #[arg(long, value_name = "URL")]
+        endpoint: Option<String>,
+        /// Print the planned uploads and deletions without changing the bucket.
+        #[arg(long)]
+        dry_run: bool,
+        /// Delete objects under the prefix that are not in the output
+        /// directory. Needs a directory written by `rendar build`.
+        #[arg(long)]
+        delete: bool,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum CheckFormat {
+    /// Warnings on stderr only.
+    Text,
+    /// `{"diagnostics": [...]}` with path, span, rule, severity, and message.
+    Json,
+    /// SARIF 2.1.0, for code scanning dashboards.
+    Sarif,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum Emit {
+    /// JSON list of every rendered page path.
+    #[value(name = "page-list.json")]
+    PageList,
+    /// JSON description of the navigation tree.
+    #[value(name = "nav.json")]
+    Nav,
+    /// Report page listing TODO and FIXME markers.
+    #[value(name = "todos.html")]
+    Todos,
+    /// JSON list of every output file with its SHA-256 and source file.
+    #[value(name = "manifest.json")]
+    Manifest,
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    let profile = match cli.profile {
+        Some(profile) => (profile, true),
+        None if matches!(cli.command, Command::Preview { .. }) => ("dev".to_string(), false),
+        None => ("production".to_string(), false),
+    };
+    let source = |path| config::ConfigSource {
+        path,
+        strict: cli.strict_config,
+        profile: Some(profile.clone()),
+    };
     match cli.command {
         Command::Build {
             out,
@@ -106,13 +387,105 @@ fn main() -> Result<()> {
             config,
             template,
             exclude,
+            audience,
             csv_max_rows,
-        } => run_build(out, input, config, template, exclude, csv_max_rows),
+            emit,
+            timings,
+            clean,
+            strict,
+        } => run_build(
+            out,
+            input,
+            source(config),
+            template,
+            exclude,
+            audience,
+            csv_max_rows,
+            emit,
+            timings,
+            clean,
+            strict,
+        ),
         Command::Check {
             input,
             config,
             exclude,
-        } => run_check(input, config, exclude),
+            audience,
+            hygiene,
+            todos,
+            a11y,
+            duplicates,
+            external_links,
+            format,
+            fix,
+            yes,
+            max_warnings,
+            baseline,
+            write_baseline,
+        } => run_check(
+            input,
+            source(config),
+            exclude,
+            audience,
+            hygiene,
+            todos,
+            a11y,
+            duplicates,
+            external_links,
+            format,
+            fix.then_some(yes),
+            CheckLimits {
+                max_warnings,
+                baseline,
+                write_baseline,
+            },
+        ),
+        Command::Stats {
+            input,
+            config,
+            exclude,
+            audience,
+        } => run_stats(input, source(config), exclude, audience),
+        Command::Export {
+            out,
+            input,
+            config,
+            template,
+            exclude,
+            audience,
+            csv_max_rows,
+            pdf,
+            combined,
+            chrome,
+            epub,
+        } => run_export(
+            out,
+            input,
+            source(config),
+            template,
+            exclude,
+            audience,
+            csv_max_rows,
+            ExportFormats {
+                pdf,
+                combined,
+                chrome,
+                epub,
+            },
+        ),
+        Command::Deploy {
+            target:
+                DeployTarget::S3 {
+                    out,
+                    config,
+                    bucket,
+                    prefix,
+                    region,
+                    endpoint,
+                    dry_run,
+                    delete,
+                },
+        } => run_deploy_s3(out, source(config), bucket, prefix, region, endpoint, dry_run, delete),
         Command::Preview {
             input,
             config,
@@ -124,11 +497,12 @@ fn main() -> Result<()> {
             daemon_child,
             auto_exit,
             port,
+            host,
             exclude,
             csv_max_rows,
         } => run_preview(
             input,
-            config,
+            source(config),
             template,
             start_on,
             open,
@@ -137,54 +511,501 @@ fn main() -> Result<()> {
             daemon_child,
             auto_exit,
             port,
+            host,
             exclude,
             csv_max_rows,
         ),
+        Command::Serve { out, port, host, spa } => run_serve(out, port, host, spa),
+        Command::Config {
+            action: ConfigAction::Check { input, config, template },
+        } => run_config_check(input, source(config), template),
+        Command::Config {
+            action: ConfigAction::Schema,
+        } => {
+            println!("{}", config::config_schema());
+            Ok(())
+        }
+        Command::Completions { shell } => {
+            clap_complete::generate(shell, &mut Cli::command(), "rendar", &mut std::io::stdout());
+            Ok(())
+        }
     }
 }
 
+const SLOWEST_PAGES: usize = 10;
+
+#[allow(clippy::too_many_arguments)]
 fn run_build(
     out: PathBuf,
     input: Option<PathBuf>,
-    config: Option<PathBuf>,
+    config: config::ConfigSource,
     template: Option<PathBuf>,
     exclude: Vec<String>,
+    audience: Option<String>,
     csv_max_rows: usize,
+    emit: Vec<Emit>,
+    timings: bool,
+    clean: bool,
+    strict: bool,
 ) -> Result<()> {
-    let config = config::load_config(config.as_deref())?;
+    let started = Instant::now();
+    let (config, mut settings) = load_config(&config)?;
+    let strict = strict
+        || config
+            .as_ref()
+            .and_then(|cfg| cfg.build.as_ref())
+            .and_then(|build| build.strict)
+            .unwrap_or(false);
     let input = resolve_input(input, config.as_ref());
+    settings.provenance = provenance::Provenance::collect(&input);
+    if clean {
+        clean::ensure_cleanable(&input, &out)?;
+    }
+    let hooks = config.as_ref().and_then(|cfg| cfg.hooks.as_ref());
+    let hook_env = [("RENDAR_INPUT", input.as_path()), ("RENDAR_OUTPUT", out.as_path())];
+    let hook_pool = hook_pool(hook_timeout(hooks));
+    if let Some(command) = hooks.and_then(|hooks| hooks.pre_build.as_deref()) {
+        hooks::run_hook(&hook_pool, "pre_build", command, &hook_env, None)?;
+    }
     let template = resolve_template(template, config.as_ref());
-    let template = load_template(template)?;
-    let excludes = resolve_excludes(exclude, config.as_ref())?;
-    site::build_site(
-        &input,
-        &out,
-        &site::RenderOptions {
-            live_reload: false,
-            heartbeat: false,
-            template: &template,
-            exclude: excludes.as_ref(),
-            csv_max_rows: normalize_csv_max_rows(csv_max_rows),
-        },
-    )?;
-    println!("Rendered site to {}", out.display());
+    let template = load_template(template, config.as_ref())?;
+    let filter = resolve_audience_filter(exclude.clone(), config.as_ref(), &input, audience.as_deref(), &settings)?;
+    let mut generated = load_generated(config.as_ref(), &input, &filter, &mut settings)?;
+    if emit.contains(&Emit::Todos) {
+        generated.pages.push(todos::todo_report_page(&input, &filter, &settings)?);
+    }
+    let plugins = load_plugins(config.as_ref())?;
+    let file_urls = config.as_ref().and_then(|cfg| cfg.file_urls).unwrap_or(false);
+    settings.csv.row_files = !file_urls;
+    let page_views = config
+        .as_ref()
+        .and_then(|cfg| cfg.page_views.as_ref())
+        .map(|page_views| page_views.endpoint.as_str());
+    if file_urls && page_views.is_some() {
+        eprintln!("Skipping the page view counter: it needs a server and file_urls is set");
+    }
+    let options = site::RenderOptions {
+        live_reload: false,
+        heartbeat: false,
+        template: &template,
+        filter: &filter,
+        csv_max_rows: normalize_csv_max_rows(csv_max_rows),
+        generated: &generated,
+        page_views: page_views.filter(|_| !file_urls),
+        pre_page_hook: hooks
+            .and_then(|hooks| hooks.pre_page.as_deref())
+            .map(|command| hooks::PageHook {
+                command,
+                pool: &hook_pool,
+            }),
+        plugins: &plugins,
+        settings: &settings,
+    };
+    let mut written = Vec::new();
+    let summary = staging::build_staged(&input, &out, !clean, |staging| {
+        let summary = match config.as_ref().and_then(|cfg| cfg.versions.as_deref()) {
+            Some(versions) if !versions.is_empty() => build_versions(
+                versions,
+                &input,
+                staging,
+                &options,
+                &exclude,
+                config.as_ref(),
+                audience.as_deref(),
+            )?,
+            _ => site::build_site(&input, staging, &options)?,
+        };
+        let warnings = summary.warnings() + template.diagnostics().len();
+        if strict && warnings > 0 {
+            return Err(anyhow::anyhow!(
+                "Build failed with {warnings} warning(s) in strict mode; {} was left unchanged",
+                out.display()
+            ));
+        }
+        clean::write_output_marker(staging)?;
+        finish_output(staging, config.as_ref())?;
+        if emit.contains(&Emit::PageList) {
+            let path = page_views::write_page_list(staging, &site::page_paths(&input, &options))?;
+            written.push(path.strip_prefix(staging).unwrap_or(&path).to_path_buf());
+        }
+        if emit.contains(&Emit::Nav) {
+            let title = resolve_site_title(config.as_ref(), &input, &filter, &settings);
+            let nav = site::nav_tree(&input, &title, &options);
+            let path = staging.join("nav.json");
+            let json = serde_json::to_string_pretty(&nav).context("Failed to serialize nav.json")?;
+            std::fs::write(&path, json)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            written.push(PathBuf::from("nav.json"));
+        }
+        if emit.contains(&Emit::Manifest) {
+            manifest::write_manifest(staging, &site::output_sources(&input, &options)?)?;
+            written.push(PathBuf::from(manifest::MANIFEST_FILE));
+        }
+        Ok(summary)
+    })?;
+    search::publish_search(&summary.search_sections, &settings)?;
+    println!(
+        "Rendered {} page(s) and copied {} asset(s) to {} in {:.2}s ({} warning(s))",
+        summary.pages,
+        summary.assets,
+        out.display(),
+        started.elapsed().as_secs_f64(),
+        summary.warnings()
+    );
+    if timings {
+        println!("Slowest pages:");
+        for (page, elapsed) in summary.slowest_pages(SLOWEST_PAGES) {
+            println!(
+                "  {:>8.1} ms  {}",
+                elapsed.as_secs_f64() * 1000.0,
+                page.display()
+            );
+        }
+    }
+    for path in written {
+        println!("Wrote {}", out.join(path).display());
+    }
+    if let Some(command) = hooks.and_then(|hooks| hooks.post_build.as_deref()) {
+        hooks::run_hook(&hook_pool, "post_build", command, &hook_env, None)?;
+    }
+    Ok(())
+}
+
+/// Builds each `[[versions]]` entry into `<out>/<name>/`, copies the latest
+/// one to `<out>/latest/`, and adds the version dropdown to every page.
+fn build_versions(
+    versions: &[config::VersionConfig],
+    input: &Path,
+    out: &Path,
+    options: &site::RenderOptions<'_>,
+    exclude: &[String],
+    config: Option<&config::Config>,
+    audience: Option<&str>,
+) -> Result<site::BuildSummary> {
+    let versions = versions::prepare_versions(versions, input)?;
+    let mut summary = site::BuildSummary::default();
+    for version in &versions {
+        let filter = resolve_audience_filter(exclude.to_vec(), config, &version.input, audience, options.settings)?;
+        let version_options = site::RenderOptions {
+            filter: &filter,
+            ..options.clone()
+        };
+        let built = site::build_site(&version.input, &out.join(&version.name), &version_options)?;
+        summary.pages += built.pages;
+        summary.assets += built.assets;
+        summary.diagnostics.extend(built.diagnostics);
+        summary.page_timings.extend(
+            built
+                .page_timings
+                .into_iter()
+                .map(|(page, elapsed)| (Path::new(&version.name).join(page), elapsed)),
+        );
+        if version.latest {
+            summary.search_sections = built.search_sections;
+            staging::copy_dir(&out.join(&version.name), &out.join(versions::LATEST))?;
+        }
+    }
+    for dir in versions.iter().map(|version| version.name.as_str()).chain([versions::LATEST]) {
+        versions::add_version_selector(out, dir, &versions, options.settings)?;
+    }
+    versions::write_root_redirect(out)?;
+    Ok(summary)
+}
+
+/// Output-wide passes that run after the pages are written.
+fn finish_output(out: &Path, config: Option<&config::Config>) -> Result<()> {
+    let file_urls = config.and_then(|cfg| cfg.file_urls).unwrap_or(false);
+    if let Some(settings) = config.and_then(|cfg| cfg.thumbnails.as_ref()) {
+        thumbnails::generate_thumbnails(
+            out,
+            &thumbnails::ThumbnailSettings {
+                min_bytes: settings.min_bytes.unwrap_or(thumbnails::DEFAULT_MIN_BYTES),
+                width: settings.width.unwrap_or(thumbnails::DEFAULT_WIDTH),
+                cache_dir: settings
+                    .cache_dir
+                    .clone()
+                    .unwrap_or_else(|| PathBuf::from(thumbnails::DEFAULT_CACHE_DIR)),
+            },
+        )?;
+    }
+    if file_urls {
+        file_urls::relativize_site(out)?;
+    }
+    if config.and_then(|cfg| cfg.inline_assets).unwrap_or(false) {
+        let max_bytes = config
+            .and_then(|cfg| cfg.inline_max_bytes)
+            .unwrap_or(inline::DEFAULT_MAX_INLINE_BYTES);
+        inline::inline_site(out, max_bytes)?;
+    }
     Ok(())
 }
 
-fn run_check(input: Option<PathBuf>, config: Option<PathBuf>, exclude: Vec<String>) -> Result<()> {
-    let config = config::load_config(config.as_deref())?;
+#[allow(clippy::too_many_arguments)]
+fn run_check(
+    input: Option<PathBuf>,
+    config: config::ConfigSource,
+    exclude: Vec<String>,
+    audience: Option<String>,
+    hygiene: bool,
+    todos: bool,
+    a11y: bool,
+    duplicates: Option<f64>,
+    external_links: bool,
+    format: CheckFormat,
+    fix: Option<bool>,
+    limits: CheckLimits,
+) -> Result<()> {
+    if let Some(threshold) = duplicates
+        && !(0.0..=1.0).contains(&threshold)
+    {
+        return Err(anyhow::anyhow!("--duplicates threshold must be between 0 and 1"));
+    }
+    let (config, mut settings) = load_config(&config)?;
     let input = resolve_input(input, config.as_ref());
-    let excludes = resolve_excludes(exclude, config.as_ref())?;
-    let warnings = site::check_site(&input, excludes.as_ref())?;
-    if warnings > 0 {
+    let filter = resolve_audience_filter(exclude, config.as_ref(), &input, audience.as_deref(), &settings)?;
+    if let Some(yes) = fix {
+        fix_links(&input, &filter, yes, &settings)?;
+    }
+    let template = template::Template::built_in();
+    let generated = load_generated(config.as_ref(), &input, &filter, &mut settings)?;
+    let plugins = load_plugins(config.as_ref())?;
+    let options = site::RenderOptions {
+        live_reload: false,
+        heartbeat: false,
+        template: &template,
+        filter: &filter,
+        csv_max_rows: None,
+        generated: &generated,
+        page_views: None,
+        pre_page_hook: None,
+        plugins: &plugins,
+        settings: &settings,
+    };
+    let mut diagnostics = site::check_site(&input, &options, hygiene, todos, a11y, duplicates)?;
+    if external_links {
+        let state_file = config
+            .as_ref()
+            .and_then(|cfg| cfg.links.as_ref())
+            .and_then(|links| links.state_file.clone())
+            .unwrap_or_else(|| PathBuf::from(link_rot::DEFAULT_STATE_FILE));
+        diagnostics.extend(link_rot::check_link_rot(&input, &filter, &state_file, &settings)?);
+    }
+    let baseline_path = limits
+        .baseline
+        .unwrap_or_else(|| PathBuf::from(baseline::DEFAULT_BASELINE_FILE));
+    if limits.write_baseline {
+        let baseline = baseline::Baseline::from_diagnostics(&input, &diagnostics);
+        baseline.write(&baseline_path)?;
+        eprintln!("Recorded {} warning(s) in {}", baseline.warning_count(), baseline_path.display());
+        return Ok(());
+    }
+    if let Some(baseline) = baseline::Baseline::load(&baseline_path)? {
+        let known = diagnostics.len();
+        diagnostics = baseline.new_diagnostics(&input, &diagnostics);
+        if known > diagnostics.len() {
+            eprintln!(
+                "{} known warning(s) in {} not shown",
+                known - diagnostics.len(),
+                baseline_path.display()
+            );
+        }
+    }
+    diagnostics.iter().for_each(diagnostics::print_diagnostic);
+    let report = match format {
+        CheckFormat::Text => None,
+        CheckFormat::Json => Some(diagnostics::to_json(&diagnostics)),
+        CheckFormat::Sarif => Some(diagnostics::to_sarif(&diagnostics)),
+    };
+    if let Some(report) = report {
+        println!("{}", serde_json::to_string_pretty(&report).context("Failed to serialize diagnostics")?);
+    }
+    let allowed = limits.max_warnings.unwrap_or(0);
+    if diagnostics.len() > allowed {
+        if limits.max_warnings.is_some() {
+            eprintln!("{} warning(s), more than the {allowed} allowed by --max-warnings", diagnostics.len());
+        }
         std::process::exit(1);
     }
     Ok(())
 }
 
+struct CheckLimits {
+    max_warnings: Option<usize>,
+    baseline: Option<PathBuf>,
+    write_baseline: bool,
+}
+
+/// Shows the `check --fix` diff and writes it when `yes` is set or the
+/// user confirms. The check that follows sees the fixed sources.
+fn fix_links(input: &Path, filter: &site::PathFilter, yes: bool, settings: &Settings) -> Result<()> {
+    let files = link_fix::find_link_fixes(input, filter, settings)?;
+    let count: usize = files.iter().map(|file| file.fixes.len()).sum();
+    if count == 0 {
+        eprintln!("No links to fix.");
+        return Ok(());
+    }
+    print!("{}", link_fix::diff(&files));
+    let apply = yes || {
+        use std::io::IsTerminal;
+        if std::io::stdin().is_terminal() {
+            eprint!("Apply {count} link fix(es) in {} file(s)? [y/N] ", files.len());
+            let mut answer = String::new();
+            std::io::stdin().read_line(&mut answer).context("Failed to read answer")?;
+            matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+        } else {
+            eprintln!("Not applying fixes without a terminal; pass --yes to write them.");
+            false
+        }
+    };
+    if apply {
+        link_fix::write_fixes(&files)?;
+        eprintln!("Fixed {count} link(s) in {} file(s).", files.len());
+    }
+    Ok(())
+}
+
+fn run_stats(
+    input: Option<PathBuf>,
+    config: config::ConfigSource,
+    exclude: Vec<String>,
+    audience: Option<String>,
+) -> Result<()> {
+    let (config, settings) = load_config(&config)?;
+    let input = resolve_input(input, config.as_ref());
+    let filter = resolve_audience_filter(exclude, config.as_ref(), &input, audience.as_deref(), &settings)?;
+    let dirs = stats::collect_stats(&input, &filter, &settings)?;
+    print!("{}", stats::format_stats(&dirs));
+    Ok(())
+}
+
+struct ExportFormats {
+    pdf: bool,
+    combined: bool,
+    chrome: Option<PathBuf>,
+    epub: bool,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_export(
+    out: PathBuf,
+    input: Option<PathBuf>,
+    config: config::ConfigSource,
+    template: Option<PathBuf>,
+    exclude: Vec<String>,
+    audience: Option<String>,
+    csv_max_rows: usize,
+    formats: ExportFormats,
+) -> Result<()> {
+    let (config, mut settings) = load_config(&config)?;
+    let input = resolve_input(input, config.as_ref());
+    settings.provenance = provenance::Provenance::collect(&input);
+    let template = resolve_template(template, config.as_ref());
+    let template = load_template(template, config.as_ref())?;
+    let filter = resolve_audience_filter(exclude, config.as_ref(), &input, audience.as_deref(), &settings)?;
+    let generated = load_generated(config.as_ref(), &input, &filter, &mut settings)?;
+    let plugins = load_plugins(config.as_ref())?;
+    let title = resolve_site_title(config.as_ref(), &input, &filter, &settings);
+    let options = site::RenderOptions {
+        live_reload: false,
+        heartbeat: false,
+        template: &template,
+        filter: &filter,
+        csv_max_rows: normalize_csv_max_rows(csv_max_rows),
+        generated: &generated,
+        page_views: None,
+        pre_page_hook: None,
+        plugins: &plugins,
+        settings: &settings,
+    };
+    if formats.pdf {
+        let count = export::export_pdf(
+            &input,
+            &out,
+            &options,
+            &export::PdfOptions {
+                chrome: formats.chrome.as_deref(),
+                combined: formats.combined,
+                title: &title,
+            },
+        )?;
+        println!("Exported {count} PDF file(s) to {}", out.display());
+    }
+    if formats.epub {
+        let path = export::export_epub(&input, &out, &options, &title)?;
+        println!("Exported EPUB to {}", path.display());
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_deploy_s3(
+    out: PathBuf,
+    config: config::ConfigSource,
+    bucket: Option<String>,
+    prefix: Option<String>,
+    region: Option<String>,
+    endpoint: Option<String>,
+    dry_run: bool,
+    delete: bool,
+) -> Result<()> {
+    let config = config::load_config(&config)?;
+    let s3 = config
+        .as_ref()
+        .and_then(|cfg| cfg.deploy.as_ref())
+        .and_then(|deploy| deploy.s3.as_ref());
+    let bucket = bucket
+        .or_else(|| s3.and_then(|s3| s3.bucket.clone()))
+        .context("No bucket configured; pass --bucket or set bucket under [deploy.s3]")?;
+    let region = region
+        .or_else(|| s3.and_then(|s3| s3.region.clone()))
+        .or_else(|| std::env::var("AWS_REGION").ok())
+        .or_else(|| std::env::var("AWS_DEFAULT_REGION").ok())
+        .unwrap_or_else(|| "us-east-1".to_string());
+    let endpoint = endpoint
+        .or_else(|| s3.and_then(|s3| s3.endpoint.clone()))
+        .or_else(|| std::env::var("AWS_ENDPOINT_URL_S3").ok())
+        .or_else(|| std::env::var("AWS_ENDPOINT_URL").ok())
+        .unwrap_or_else(|| format!("https://s3.{region}.amazonaws.com"));
+    let target = deploy::S3Target {
+        bucket,
+        prefix: prefix
+            .or_else(|| s3.and_then(|s3| s3.prefix.clone()))
+            .unwrap_or_default(),
+        region,
+        endpoint,
+    };
+    let credentials = deploy::Credentials::from_env()?;
+    let cache_control = s3.map(|s3| s3.cache_control.clone()).unwrap_or_default();
+    let summary = deploy::deploy_s3(
+        &out,
+        &target,
+        &credentials,
+        &deploy::DeployOptions {
+            dry_run,
+            delete,
+            cache_control: &cache_control,
+        },
+    )?;
+    let (verb, deleted) = if dry_run { ("Would upload", "would delete") } else { ("Uploaded", "deleted") };
+    let stale = if delete {
+        format!("{deleted} {} stale object(s)", summary.deleted)
+    } else {
+        format!("kept {} stale object(s) (pass --delete to remove them)", summary.stale)
+    };
+    println!(
+        "{verb} {} file(s), {stale}, {} unchanged (s3://{}/{})",
+        summary.uploaded,
+        summary.unchanged,
+        target.bucket,
+        target.prefix.trim_matches('/')
+    );
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
 fn run_preview(
     input: Option<PathBuf>,
-    config: Option<PathBuf>,
+    config: config::ConfigSource,
     template: Option<PathBuf>,
     start_on: Option<PathBuf>,
     open: bool,
@@ -193,6 +1014,7 @@ fn run_preview(
     daemon_child: bool,
     auto_exit: Option<u64>,
     port: Option<u16>,
+    host: Option<String>,
     exclude: Vec<String>,
     csv_max_rows: usize,
 ) -> Result<()> {
@@ -204,16 +1026,19 @@ fn run_preview(
     if daemon {
         return spawn_preview_daemon();
     }
-    let config = config::load_config(config.as_deref())?;
-    let input_override = input.or_else(|| config.as_ref().and_then(|cfg| cfg.input.clone()));
-    let preview_paths = resolve_preview_paths(input_override, start_on)?;
+    let preview_settings = PreviewSettings {
+        config: config.clone(),
+        template,
+        exclude,
+    };
+    let (config, settings) = load_config(&config)?;
+    let input_override = input.or_else(|| config.as_ref().and_then(|cfg| cfg.input()));
+    let preview_paths = resolve_preview_paths(input_override, start_on, &settings)?;
     let input = preview_paths.input_root;
     let start_page = preview_paths.start_page;
-    let template = resolve_template(template, config.as_ref());
-    let template = load_template(template)?;
-    let excludes = resolve_excludes(exclude, config.as_ref())?;
+    let preview_site = preview_settings.site(&input, config.as_ref(), settings)?;
     if let Some(start_page) = start_page.as_ref()
-        && site::is_ignored_path(start_page, &input, excludes.as_ref())
+        && site::is_ignored_path(start_page, &input, &preview_site.filter)
     {
         return Err(anyhow::anyhow!(
             "Start page {} is ignored",
@@ -224,23 +1049,31 @@ fn run_preview(
     let output = temp_dir.path().to_path_buf();
     let auto_exit_duration = auto_exit.map(Duration::from_secs);
     let auto_exit_enabled = auto_exit_duration.is_some();
-    site::build_site(
-        &input,
-        &output,
-        &site::RenderOptions {
-            live_reload: true,
-            heartbeat: auto_exit_enabled,
-            template: &template,
-            exclude: excludes.as_ref(),
-            csv_max_rows: normalize_csv_max_rows(csv_max_rows),
-        },
-    )?;
+    // One pool for the session, so its cache of finished hook runs spans
+    // rebuilds.
+    let pool = hook_pool(preview_site.hook_timeout);
+    let options = preview_site.options(auto_exit_enabled, normalize_csv_max_rows(csv_max_rows), &pool);
+    let started = Instant::now();
+    let pipeline = site::SitePipeline::new(&input, &options)?;
+    let summary = pipeline.build(&output)?;
+    let status = Arc::new(Mutex::new(PreviewStatus {
+        error: None,
+        diagnostics: summary.diagnostics.clone(),
+        built_at: now_millis(),
+        build_time: started.elapsed(),
+    }));
+    let snapshot = pipeline.snapshot(summary);
+
+    let start_rel = start_page.as_ref().and_then(|start_page| {
+        let index_dirs = site::collect_index_dirs(&input, options.filter, options.settings);
+        site::output_rel_path(start_page, &input, &index_dirs, options.settings)
+    });
 
     let version = Arc::new(AtomicU64::new(1));
     let watcher_version = Arc::clone(&version);
+    let watcher_status = Arc::clone(&status);
     let input_clone = input.clone();
     let output_clone = output.clone();
-    let watcher_excludes = excludes.clone();
     let watcher_heartbeat = auto_exit_enabled;
 
     std::thread::spawn(move || {
@@ -248,8 +1081,11 @@ fn run_preview(
             &input_clone,
             &output_clone,
             watcher_version,
-            template,
-            watcher_excludes,
+            watcher_status,
+            snapshot,
+            preview_settings,
+            preview_site,
+            pool,
             watcher_heartbeat,
             normalize_csv_max_rows(csv_max_rows),
         ) {
@@ -258,22 +1094,42 @@ fn run_preview(
     });
 
     let preferred_port = resolve_preview_port(port, config.as_ref());
-    let (listener, port) = bind_preview_listener(preferred_port)?;
-    let address = format!("127.0.0.1:{}", port);
-    let start_url = if let Some(start_page) = start_page.as_ref() {
-        let index_dirs = site::collect_index_dirs(&input, excludes.as_ref());
-        match site::output_rel_path(start_page, &input, &index_dirs) {
-            Some(rel) => format!("http://{address}/{}", path_to_url(&rel)),
-            None => format!("http://{address}/"),
-        }
-    } else {
-        format!("http://{address}/")
-    };
+    let host = resolve_preview_host(host, config.as_ref());
+    let (listener, port) = bind_preview_listener(&host, preferred_port)?;
+    let address = format!("{}:{}", url_host(&host), port);
+    let loopback = listener.local_addr().is_ok_and(|address| address.ip().is_loopback());
+    let password = config
+        .as_ref()
+        .and_then(|cfg| cfg.preview.as_ref())
+        .and_then(|preview| preview.password.clone());
+    let auth = preview_auth::PreviewAuth::for_preview(password, loopback)?;
+    let query = auth.as_ref().map(preview_auth::PreviewAuth::url_query).unwrap_or_default();
+    let start_path = start_rel.map(|rel| path_to_url(&rel)).unwrap_or_default();
+    let start_url = format!("http://{address}/{start_path}{query}");
     if daemon_child {
         println!("URL={start_url}");
         println!("PID={}", std::process::id());
     } else {
         println!("Preview server running at {start_url}");
+        if matches!(auth, Some(preview_auth::PreviewAuth::Password(_))) {
+            println!("Log in with any user name and the password from [preview] password");
+        }
+        let lan_urls: Vec<String> = if lan::is_wildcard(&host) {
+            lan::lan_addresses()
+                .into_iter()
+                .map(|(_, ip)| format!("http://{ip}:{port}/{start_path}{query}"))
+                .collect()
+        } else if loopback {
+            Vec::new()
+        } else {
+            vec![start_url.clone()]
+        };
+        for url in lan_urls {
+            println!("On your network: {url}");
+            if let Some(code) = lan::qr_code(&url) {
+                println!("{code}");
+            }
+        }
     }
     let open = resolve_preview_open(open, no_open, daemon_child, config.as_ref());
     if open {
@@ -284,66 +1140,487 @@ fn run_preview(
     rt.block_on(async move {
         let listener = tokio::net::TcpListener::from_std(listener)
             .context("Failed to use preview listener")?;
-        serve_preview(output, version, listener, auto_exit_duration).await
+        serve_preview(output, input, version, status, listener, auto_exit_duration, auth).await
     })
 }
 
+#[allow(clippy::too_many_arguments)]
 fn watch_and_rebuild(
     input: &std::path::Path,
     output: &std::path::Path,
     version: Arc<AtomicU64>,
-    template: template::Template,
-    excludes: Option<GlobSet>,
+    status: Arc<Mutex<PreviewStatus>>,
+    mut snapshot: site::SiteSnapshot,
+    preview_settings: PreviewSettings,
+    mut preview_site: PreviewSite,
+    mut pool: process_pool::ProcessPool,
     heartbeat: bool,
     csv_max_rows: Option<usize>,
 ) -> Result<()> {
     use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
     use std::sync::mpsc::channel;
-    use std::time::Instant;
 
     let (tx, rx) = channel();
     let mut watcher = RecommendedWatcher::new(tx, Config::default())
         .context("Failed to initialize file watcher")?;
-    watcher
-        .watch(input, RecursiveMode::Recursive)
-        .context("Failed to watch input directory")?;
+    // The input and the extra roots of `input = [...]`, with the mount
+    // point and filter of each.
+    let watch_roots = |preview_site: &PreviewSite| -> Vec<(PathBuf, PathBuf, site::PathFilter)> {
+        std::iter::once((input, Path::new(""), &preview_site.filter))
+            .chain(
+                preview_site
+                    .settings
+                    .mounted_roots
+                    .iter()
+                    .map(|mounted| (mounted.root.as_path(), mounted.mount.as_path(), &mounted.filter)),
+            )
+            .map(|(path, mount, filter)| {
+                let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+                (path, mount.to_path_buf(), filter.clone())
+            })
+            .collect()
+    };
+    let mut roots = watch_roots(&preview_site);
+    for (path, _, _) in &roots {
+        watcher
+            .watch(path, RecursiveMode::Recursive)
+            .with_context(|| format!("Failed to watch input directory {}", path.display()))?;
+    }
+
+    let mut settings_files = preview_settings.files(preview_site.template_path.as_deref());
+    // Files outside the inputs need their folder watched; editors often save
+    // by replacing the file, which a watch on the file itself would miss.
+    let watch_settings =
+        |watcher: &mut RecommendedWatcher, files: &[PathBuf], roots: &[(PathBuf, PathBuf, site::PathFilter)]| {
+            for dir in files.iter().filter_map(|file| file.parent()) {
+                if !roots.iter().any(|(root, _, _)| dir.starts_with(root)) && dir.is_dir() {
+                    watcher
+                        .watch(dir, RecursiveMode::NonRecursive)
+                        .with_context(|| format!("Failed to watch {}", dir.display()))?;
+                }
+            }
+            Ok::<_, anyhow::Error>(())
+        };
+    watch_settings(&mut watcher, &settings_files, &roots)?;
+    let mut last_failed = false;
 
     loop {
-        let _ = rx.recv().context("File watcher channel closed")?;
+        let event = rx.recv().context("File watcher channel closed")?;
+        let mut reload = touches_settings(&event, &settings_files);
+        let mut changed = match changed_paths(event, &roots) {
+            Some(paths) if paths.is_empty() && !reload => continue,
+            paths => paths,
+        };
         let start = Instant::now();
-        while rx.recv_timeout(Duration::from_millis(200)).is_ok() {
+        while let Ok(event) = rx.recv_timeout(Duration::from_millis(200)) {
+            reload |= touches_settings(&event, &settings_files);
+            match (changed.as_mut(), changed_paths(event, &roots)) {
+                (Some(changed), Some(paths)) => changed.extend(paths),
+                _ => changed = None,
+            }
             if start.elapsed() > Duration::from_secs(2) {
                 break;
             }
         }
-        if let Err(err) = site::build_site(
-            input,
-            output,
-            &site::RenderOptions {
-                live_reload: true,
-                heartbeat,
-                template: &template,
-                exclude: excludes.as_ref(),
-                csv_max_rows,
-            },
-        ) {
-            eprintln!("Failed to rebuild preview: {err}");
+        // After a failure, pages the failed build skipped may still be stale.
+        if last_failed || reload {
+            changed = None;
+        }
+        let started = Instant::now();
+        let result = if reload {
+            preview_settings.load(input).map(|loaded| {
+                if loaded.hook_timeout != preview_site.hook_timeout {
+                    pool = hook_pool(loaded.hook_timeout);
+                }
+                preview_site = loaded;
+            })
         } else {
-            version.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        };
+        if reload && result.is_ok() {
+            // Roots added to or dropped from `input = [...]`.
+            let new_roots = watch_roots(&preview_site);
+            for (path, _, _) in &roots {
+                if !new_roots.iter().any(|(new, _, _)| new == path) {
+                    let _ = watcher.unwatch(path);
+                }
+            }
+            for (path, _, _) in &new_roots {
+                if !roots.iter().any(|(old, _, _)| old == path) {
+                    watcher
+                        .watch(path, RecursiveMode::Recursive)
+                        .with_context(|| format!("Failed to watch input directory {}", path.display()))?;
+                }
+            }
+            roots = new_roots;
+            settings_files = preview_settings.files(preview_site.template_path.as_deref());
+            watch_settings(&mut watcher, &settings_files, &roots)?;
+        }
+        let options = preview_site.options(heartbeat, csv_max_rows, &pool);
+        let result =
+            result.and_then(|()| rebuild_preview_changes(input, output, &options, changed, &mut snapshot));
+        last_failed = result.is_err();
+        if let Ok(mut current) = status.lock() {
+            match result {
+                Ok(diagnostics) => {
+                    current.error = None;
+                    current.diagnostics = diagnostics;
+                    current.built_at = now_millis();
+                    current.build_time = started.elapsed();
+                }
+                Err(err) => {
+                    let error = format!("{err:#}");
+                    eprintln!("Failed to rebuild preview: {error}");
+                    current.error = Some(error);
+                }
+            }
+        }
+        version.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Relevant paths from an event, relative to the site (under the mount
+/// point for files in extra roots); `None` when the event can't be
+/// attributed to files and needs a full rebuild.
+fn changed_paths(
+    event: notify::Result<notify::Event>,
+    roots: &[(PathBuf, PathBuf, site::PathFilter)],
+) -> Option<Vec<PathBuf>> {
+    match event {
+        Ok(event) => Some(
+            event
+                .paths
+                .iter()
+                .filter_map(|path| {
+                    roots.iter().find_map(|(root, mount, filter)| {
+                        let rel = path.strip_prefix(root).ok()?;
+                        (!site::is_ignored_path(path, root, filter)).then(|| mount.join(rel))
+                    })
+                })
+                .collect(),
+        ),
+        Err(_) => None,
+    }
+}
+
+fn touches_settings(event: &notify::Result<notify::Event>, settings_files: &[PathBuf]) -> bool {
+    event
+        .as_ref()
+        .is_ok_and(|event| event.paths.iter().any(|path| settings_files.contains(&settings_file(path))))
+}
+
+/// The config and template a preview was started with, reloaded when
+/// either file changes.
+struct PreviewSettings {
+    config: config::ConfigSource,
+    template: Option<PathBuf>,
+    exclude: Vec<String>,
+}
+
+impl PreviewSettings {
+    /// The config file (or the `rendar.toml` that would be picked up once
+    /// created) and the template, as absolute paths.
+    fn files(&self, template: Option<&Path>) -> Vec<PathBuf> {
+        let config = self.config.path.as_deref().unwrap_or(Path::new(config::CONFIG_FILE));
+        std::iter::once(config).chain(template).map(settings_file).collect()
+    }
+
+    fn load(&self, input: &Path) -> Result<PreviewSite> {
+        let (config, settings) = load_config(&self.config)?;
+        self.site(input, config.as_ref(), settings)
+    }
+
+    /// Everything the preview of `input` reads from `config`.
+    fn site(&self, input: &Path, config: Option<&config::Config>, mut settings: Settings) -> Result<PreviewSite> {
+        let template_path = resolve_template(self.template.clone(), config);
+        let template = load_template(template_path.clone(), config)?;
+        let filter = resolve_filter(self.exclude.clone(), config, input)?;
+        let generated = load_generated(config, input, &filter, &mut settings)?;
+        let plugins = load_plugins(config)?;
+        let hooks = config.and_then(|cfg| cfg.hooks.as_ref());
+        settings.source_lines = true;
+        settings.provenance = provenance::Provenance::collect(input);
+        Ok(PreviewSite {
+            template_path,
+            template,
+            filter,
+            generated,
+            plugins,
+            pre_page_hook: hooks.and_then(|hooks| hooks.pre_page.clone()),
+            hook_timeout: hook_timeout(hooks),
+            settings,
+        })
+    }
+}
+
+/// A loaded preview configuration, replaced as a whole when the config or
+/// template changes so no part of it goes stale.
+struct PreviewSite {
+    template_path: Option<PathBuf>,
+    template: template::Template,
+    filter: site::PathFilter,
+    generated: site::Generated,
+    plugins: plugins::Plugins,
+    pre_page_hook: Option<String>,
+    hook_timeout: Duration,
+    settings: Settings,
+}
+
+impl PreviewSite {
+    fn options<'a>(
+        &'a self,
+        heartbeat: bool,
+        csv_max_rows: Option<usize>,
+        pool: &'a process_pool::ProcessPool,
+    ) -> site::RenderOptions<'a> {
+        site::RenderOptions {
+            live_reload: true,
+            heartbeat,
+            template: &self.template,
+            filter: &self.filter,
+            csv_max_rows,
+            generated: &self.generated,
+            page_views: None,
+            pre_page_hook: self
+                .pre_page_hook
+                .as_deref()
+                .map(|command| hooks::PageHook { command, pool }),
+            plugins: &self.plugins,
+            settings: &self.settings,
+        }
+    }
+}
+
+/// `path` made absolute through its canonical folder, so it compares equal
+/// to watcher event paths even when the file itself was just replaced.
+fn settings_file(path: &Path) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    match (absolute.parent().and_then(|dir| dir.canonicalize().ok()), absolute.file_name()) {
+        (Some(dir), Some(name)) => dir.join(name),
+        _ => absolute,
+    }
+}
+
+/// Re-renders only the pages affected by `changed`, falling back to a full
+/// rebuild when the change is structural or unknown (`None`). Returns what
+/// the rebuilt pages reported.
+fn rebuild_preview_changes(
+    input: &std::path::Path,
+    output: &std::path::Path,
+    options: &site::RenderOptions<'_>,
+    changed: Option<Vec<PathBuf>>,
+    snapshot: &mut site::SiteSnapshot,
+) -> Result<Vec<diagnostics::Diagnostic>> {
+    if let Some(changed) = changed {
+        let pipeline = site::SitePipeline::new(input, options)?;
+        if pipeline.rebuild_changed(output, &changed, snapshot)?.is_some() {
+            return Ok(pipeline.diagnostics());
+        }
+    }
+    let (rebuilt, diagnostics) = rebuild_preview(input, output, options)?;
+    *snapshot = rebuilt;
+    Ok(diagnostics)
+}
+
+/// Full rebuild through a staging directory, so a failed rebuild keeps
+/// serving the last good site. Staging starts empty, which drops the
+/// outputs of deleted and renamed sources.
+fn rebuild_preview(
+    input: &std::path::Path,
+    output: &std::path::Path,
+    options: &site::RenderOptions<'_>,
+) -> Result<(site::SiteSnapshot, Vec<diagnostics::Diagnostic>)> {
+    let pipeline = site::SitePipeline::new(input, options)?;
+    let summary = staging::build_staged(input, output, false, |staging| pipeline.build(staging))?;
+    let diagnostics = summary.diagnostics.clone();
+    Ok((pipeline.snapshot(summary), diagnostics))
+}
+
+fn run_serve(out: PathBuf, port: u16, host: String, spa: bool) -> Result<()> {
+    if !out.is_dir() {
+        return Err(anyhow::anyhow!(
+            "{} is not a directory; run `rendar build --out {}` first",
+            out.display(),
+            out.display()
+        ));
+    }
+    let bind_host = host.trim_start_matches('[').trim_end_matches(']');
+    let listener = std::net::TcpListener::bind((bind_host, port))
+        .with_context(|| format!("Failed to bind server on {host}:{port}"))?;
+    listener
+        .set_nonblocking(true)
+        .context("Failed to set server listener to non-blocking")?;
+    let port = listener.local_addr().context("Failed to read server address")?.port();
+    println!("Serving {} at http://{}:{port}/ (Ctrl-C to stop)", out.display(), url_host(&host));
+
+    let rt = tokio::runtime::Runtime::new().context("Failed to start async runtime")?;
+    rt.block_on(async move {
+        let listener = tokio::net::TcpListener::from_std(listener).context("Failed to use server listener")?;
+        serve::serve(out, listener, spa).await
+    })
+}
+
+/// Checks the config the way other commands would load it and prints the
+/// settings they would use: CLI flags, then the file, then the environment
+/// and built-in defaults.
+fn run_config_check(
+    input: Option<PathBuf>,
+    config: config::ConfigSource,
+    template: Option<PathBuf>,
+) -> Result<()> {
+    let (mut cfg, mut diagnostics) = match config::read_config(&config)? {
+        Some((cfg, path, unknown)) => {
+            let mut diagnostics = unknown;
+            diagnostics.extend(cfg.missing_paths(&path));
+            eprintln!("Checked {}", path.display());
+            (cfg, diagnostics)
         }
+        None => {
+            eprintln!("No {} found; showing the defaults", config::CONFIG_FILE);
+            (config::Config::default(), Vec::new())
+        }
+    };
+    if let Some(path) = template.as_ref().filter(|path| !path.is_file()) {
+        diagnostics.push(diagnostics::Diagnostic::warning(
+            "missing-config-path",
+            path,
+            "`--template` names a file that does not exist",
+        ));
     }
+    let template = resolve_template(template, Some(&cfg));
+    // The template prints its own warnings as it loads; a missing one is
+    // already reported.
+    let template_warnings = match template.as_ref() {
+        Some(path) if !path.is_file() => 0,
+        _ => load_template(template.clone(), Some(&cfg))?.diagnostics().len(),
+    };
+
+    cfg.set_input(resolve_input(input, Some(&cfg)));
+    if let Some(template) = template {
+        cfg.template = Some(match cfg.template.take() {
+            Some(config::TemplateSetting::Table(table)) => config::TemplateSetting::Table(config::TemplateConfig {
+                path: Some(template),
+                ..table
+            }),
+            _ => config::TemplateSetting::Path(template),
+        });
+    }
+    let preview = config::PreviewConfig {
+        port: Some(resolve_preview_port(None, Some(&cfg))),
+        open: Some(resolve_preview_open(false, false, false, Some(&cfg))),
+        host: Some(resolve_preview_host(None, Some(&cfg))),
+        password: cfg.preview.as_ref().and_then(|preview| preview.password.clone()),
+    };
+    cfg.preview = Some(preview);
+    cfg.unknown_placeholders.get_or_insert_default();
+    cfg.markdown_extensions
+        .get_or_insert_with(|| vec!["md".to_string(), "markdown".to_string()]);
+    cfg.gitignore.get_or_insert(false);
+    cfg.file_urls.get_or_insert(false);
+    cfg.inline_assets.get_or_insert(false);
+    let build = cfg.build.get_or_insert_default();
+    build.strict.get_or_insert(false);
+    build.pretty_urls.get_or_insert(false);
+    build.keep_markdown_links.get_or_insert(false);
+    if let Some(s3) = cfg.deploy.as_mut().and_then(|deploy| deploy.s3.as_mut())
+        && s3.region.is_none()
+    {
+        s3.region = std::env::var("AWS_REGION")
+            .or_else(|_| std::env::var("AWS_DEFAULT_REGION"))
+            .ok();
+    }
+    print!("{}", toml::to_string_pretty(&cfg).context("Failed to print the effective config")?);
+
+    diagnostics.iter().for_each(diagnostics::print_diagnostic);
+    match diagnostics.len() + template_warnings {
+        0 => Ok(()),
+        problems => Err(anyhow::anyhow!("Config check found {problems} problem(s)")),
+    }
+}
+
+fn load_config(source: &config::ConfigSource) -> Result<(Option<config::Config>, Settings)> {
+    let config = config::load_config(source)?;
+    let mut settings = Settings::from_config(config.as_ref())?;
+    for mounted in &mut settings.mounted_roots {
+        mounted.filter = resolve_filter(Vec::new(), config.as_ref(), &mounted.root)?;
+    }
+    Ok((config, settings))
 }
 
-fn load_template(path: Option<PathBuf>) -> Result<template::Template> {
+fn load_template(
+    path: Option<PathBuf>,
+    config: Option<&config::Config>,
+) -> Result<template::Template> {
+    let policy = config
+        .and_then(|cfg| cfg.unknown_placeholders)
+        .unwrap_or_default();
     match path {
-        Some(path) => template::Template::from_path(&path),
+        Some(path) => template::Template::from_path(
+            &path,
+            policy,
+            &config.map(config::Config::template_vars).unwrap_or_default(),
+        ),
         None => Ok(template::Template::built_in()),
     }
 }
 
+/// Pages and assets from data files, API docs, and packages. Records the
+/// generated page paths in `settings` for link checks.
+fn load_generated(
+    config: Option<&config::Config>,
+    input: &Path,
+    filter: &site::PathFilter,
+    settings: &mut Settings,
+) -> Result<site::Generated> {
+    let mut generated = data_pages::load_data_pages(input, filter, settings)?;
+    if let Some(api) = config.and_then(|cfg| cfg.api.as_ref()) {
+        let mount = api.mount.clone().unwrap_or_else(|| PathBuf::from("api"));
+        generated.pages.extend(api_docs::load_api_pages(&api.json, &mount, settings)?);
+    }
+    if let Some(imports) = config.and_then(|cfg| cfg.packages.as_deref()) {
+        generated.extend(packages::load_packages(imports, settings)?);
+    }
+    settings.generated_pages = generated.pages.iter().map(|page| page.rel_path.clone()).collect();
+    Ok(generated)
+}
+
+fn hook_timeout(hooks: Option<&config::HooksConfig>) -> Duration {
+    Duration::from_secs(
+        hooks
+            .and_then(|hooks| hooks.timeout)
+            .unwrap_or(hooks::DEFAULT_TIMEOUT_SECS),
+    )
+}
+
+fn hook_pool(timeout: Duration) -> process_pool::ProcessPool {
+    process_pool::ProcessPool::new(std::thread::available_parallelism().map_or(1, usize::from), timeout)
+}
+
+fn load_plugins(config: Option<&config::Config>) -> Result<plugins::Plugins> {
+    let paths: Vec<PathBuf> = config
+        .and_then(|cfg| cfg.plugins.as_deref())
+        .unwrap_or_default()
+        .iter()
+        .map(|plugin| plugin.path.clone())
+        .collect();
+    plugins::Plugins::load(&paths)
+}
+
+fn resolve_site_title(
+    config: Option<&config::Config>,
+    input: &Path,
+    filter: &site::PathFilter,
+    settings: &Settings,
+) -> String {
+    config
+        .and_then(|cfg| cfg.site.as_ref())
+        .and_then(|site| site.title.clone())
+        .unwrap_or_else(|| site::default_site_title(input, filter, settings))
+}
+
 fn resolve_input(input: Option<PathBuf>, config: Option<&config::Config>) -> PathBuf {
     input
-        .or_else(|| config.and_then(|cfg| cfg.input.clone()))
+        .or_else(|| config.and_then(|cfg| cfg.input()))
         .unwrap_or_else(|| PathBuf::from("."))
 }
 
@@ -351,7 +1628,7 @@ fn resolve_template(
     template: Option<PathBuf>,
     config: Option<&config::Config>,
 ) -> Option<PathBuf> {
-    template.or_else(|| config.and_then(|cfg| cfg.template.clone()))
+    template.or_else(|| config.and_then(config::Config::template_path))
 }
 
 fn resolve_preview_port(port: Option<u16>, config: Option<&config::Config>) -> u16 {
@@ -363,12 +1640,33 @@ fn resolve_preview_port(port: Option<u16>, config: Option<&config::Config>) -> u
     .unwrap_or(3000)
 }
 
-fn bind_preview_listener(preferred_port: u16) -> Result<(std::net::TcpListener, u16)> {
-    let address = ("127.0.0.1", preferred_port);
+fn resolve_preview_host(host: Option<String>, config: Option<&config::Config>) -> String {
+    host.or_else(|| {
+        config
+            .and_then(|cfg| cfg.preview.as_ref())
+            .and_then(|preview| preview.host.clone())
+    })
+    .unwrap_or_else(|| "127.0.0.1".to_string())
+}
+
+/// Host for preview URLs: wildcard addresses are opened via loopback, and
+/// IPv6 literals are bracketed.
+fn url_host(host: &str) -> String {
+    match host {
+        "0.0.0.0" => "127.0.0.1".to_string(),
+        "::" | "[::]" => "[::1]".to_string(),
+        host if host.contains(':') && !host.starts_with('[') => format!("[{host}]"),
+        host => host.to_string(),
+    }
+}
+
+fn bind_preview_listener(host: &str, preferred_port: u16) -> Result<(std::net::TcpListener, u16)> {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let address = (host, preferred_port);
     let listener = match std::net::TcpListener::bind(address) {
         Ok(listener) => listener,
         Err(_err) if preferred_port == 3000 => {
-            let fallback = std::net::TcpListener::bind(("127.0.0.1", 0)).with_context(|| {
+            let fallback = std::net::TcpListener::bind((host, 0)).with_context(|| {
                 format!(
                     "Failed to bind preview server on {} and auto-select fallback port",
                     preferred_port
@@ -379,7 +1677,8 @@ fn bind_preview_listener(preferred_port: u16) -> Result<(std::net::TcpListener,
         }
         Err(err) => {
             return Err(anyhow::anyhow!(
-                "Failed to bind preview server on {}: {}",
+                "Failed to bind preview server on {}:{}: {}",
+                host,
                 preferred_port,
                 err
             ));
@@ -414,10 +1713,40 @@ fn resolve_preview_open(
     }
 }
 
-fn resolve_excludes(
+fn resolve_filter(
+    cli: Vec<String>,
+    config: Option<&config::Config>,
+    input: &Path,
+) -> Result<site::PathFilter> {
+    let include = config.and_then(|cfg| cfg.include.as_deref()).unwrap_or_default();
+    let mut filter = site::PathFilter {
+        include: site::IncludeFilter::new(include)?,
+        exclude: build_globs(exclude_patterns(cli, config), "exclude")?,
+        include_hidden: build_globs(include_hidden_patterns(config), "include_hidden")?,
+        ignore_files: Vec::new(),
+    };
+    let gitignore = config.and_then(|cfg| cfg.gitignore).unwrap_or(false);
+    filter.ignore_files = site::load_ignore_files(input, &filter, gitignore)?;
+    Ok(filter)
+}
+
+fn resolve_audience_filter(
     cli: Vec<String>,
     config: Option<&config::Config>,
-) -> Result<Option<GlobSet>> {
+    input: &Path,
+    audience: Option<&str>,
+    settings: &Settings,
+) -> Result<site::PathFilter> {
+    let mut filter = resolve_filter(cli.clone(), config, input)?;
+    if let Some(audience) = audience {
+        let mut patterns = exclude_patterns(cli, config);
+        patterns.extend(audience::hidden_patterns(input, audience, &filter, settings));
+        filter.exclude = build_globs(patterns, "exclude")?;
+    }
+    Ok(filter)
+}
+
+fn exclude_patterns(cli: Vec<String>, config: Option<&config::Config>) -> Vec<String> {
     let patterns = if !cli.is_empty() {
         cli
     } else {
@@ -425,7 +1754,30 @@ fn resolve_excludes(
             .and_then(|cfg| cfg.exclude.clone())
             .unwrap_or_default()
     };
+    // `dir/**` also matches `dir` itself, so the walk skips the folder
+    // instead of visiting every file below it.
+    patterns
+        .into_iter()
+        .flat_map(|pattern| {
+            let folder = pattern.strip_suffix("/**").map(str::to_string);
+            std::iter::once(pattern).chain(folder)
+        })
+        .collect()
+}
 
+fn include_hidden_patterns(config: Option<&config::Config>) -> Vec<String> {
+    config
+        .and_then(|cfg| cfg.include_hidden.as_ref())
+        .map(|patterns| {
+            patterns
+                .iter()
+                .map(|pattern| pattern.trim_end_matches('/').to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn build_globs(patterns: Vec<String>, kind: &str) -> Result<Option<GlobSet>> {
     if patterns.is_empty() {
         return Ok(None);
     }
@@ -433,7 +1785,7 @@ fn resolve_excludes(
     let mut builder = GlobSetBuilder::new();
     for pattern in patterns {
         let glob = Glob::new(&pattern)
-            .with_context(|| format!("Invalid exclude pattern: {}", pattern))?;
+            .with_context(|| format!("Invalid {kind} pattern: {}", pattern))?;
         builder.add(glob);
     }
     Ok(Some(builder.build()?))
@@ -447,19 +1799,21 @@ struct PreviewPaths {
 fn resolve_preview_paths(
     input_override: Option<PathBuf>,
     start_on: Option<PathBuf>,
+    settings: &Settings,
 ) -> Result<PreviewPaths> {
     let cwd = std::env::current_dir().context("Failed to read current directory")?;
-    resolve_preview_paths_with_cwd(&cwd, input_override, start_on)
+    resolve_preview_paths_with_cwd(&cwd, input_override, start_on, settings)
 }
 
 fn resolve_preview_paths_with_cwd(
     cwd: &Path,
     input_override: Option<PathBuf>,
     start_on: Option<PathBuf>,
+    settings: &Settings,
 ) -> Result<PreviewPaths> {
     let start_on = start_on.map(|path| resolve_path_from_cwd(cwd, path));
     let start_page = match start_on {
-        Some(path) => Some(resolve_start_page(&path)?),
+        Some(path) => Some(resolve_start_page(&path, settings)?),
         None => None,
     };
 
@@ -475,14 +1829,14 @@ fn resolve_preview_paths_with_cwd(
         cwd.to_path_buf()
     };
 
-    if let Some(start_page) = start_page.as_ref() {
-        if !is_within(start_page, &input_root) {
-            return Err(anyhow::anyhow!(
-                "Start page {} is not under input root {}",
-                start_page.display(),
-                input_root.display()
-            ));
-        }
+    if let Some(start_page) = start_page.as_ref()
+        && !is_within(start_page, &input_root)
+    {
+        return Err(anyhow::anyhow!(
+            "Start page {} is not under input root {}",
+            start_page.display(),
+            input_root.display()
+        ));
     }
 
     Ok(PreviewPaths {
@@ -491,7 +1845,7 @@ fn resolve_preview_paths_with_cwd(
     })
 }
 
-fn resolve_start_page(start_on: &Path) -> Result<PathBuf> {
+fn resolve_start_page(start_on: &Path, settings: &Settings) -> Result<PathBuf> {
     if start_on.is_dir() {
         find_landing_page(start_on).ok_or_else(|| {
             anyhow::anyhow!(
@@ -506,7 +1860,7 @@ fn resolve_start_page(start_on: &Path) -> Result<PathBuf> {
                 start_on.display()
             ));
         }
-        if !is_markdown_file(start_on) {
+        if !is_markdown_file(start_on, settings) {
             return Err(anyhow::anyhow!(
                 "Start page {} is not a Markdown or CSV file",
                 start_on.display()
@@ -569,14 +1923,10 @@ fn resolve_path_from_cwd(cwd: &Path, path: PathBuf) -> PathBuf {
     }
 }
 
-fn is_markdown_file(path: &Path) -> bool {
-    matches!(
-        path.extension()
-            .and_then(|ext| ext.to_str())
-            .map(|ext| ext.to_ascii_lowercase())
-            .as_deref(),
-        Some("md") | Some("markdown") | Some("csv")
-    )
+fn is_markdown_file(path: &Path, settings: &Settings) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| settings.is_markdown_extension(ext) || ext.eq_ignore_ascii_case("csv"))
 }
 
 fn normalize_csv_max_rows(value: usize) -> Option<usize> {
@@ -606,9 +1956,12 @@ fn path_to_url(path: &Path) -> String {
 
 async fn serve_preview(
     output: PathBuf,
+    input: PathBuf,
     version: Arc<AtomicU64>,
+    status: Arc<Mutex<PreviewStatus>>,
     listener: tokio::net::TcpListener,
     auto_exit: Option<Duration>,
+    auth: Option<preview_auth::PreviewAuth>,
 ) -> Result<()> {
     use axum::{routing::get, routing::post, Router};
     use tower_http::services::ServeDir;
@@ -616,18 +1969,39 @@ async fn serve_preview(
     let auto_exit_state = auto_exit.as_ref().map(|_| AutoExitState {
         last_seen: Arc::new(AtomicU64::new(now_millis())),
     });
+    // Only the machine running the preview may open files in its editor.
+    let local = listener.local_addr().is_ok_and(|address| address.ip().is_loopback());
     let state = Arc::new(PreviewState {
+        input,
         version,
+        status,
         auto_exit: auto_exit_state.clone(),
     });
     let app = Router::new()
         .route("/__rendar_version", get(version_handler))
+        .route("/__rendar_error", get(error_handler))
+        .route("/__rendar_status", get(status_handler))
+        .route(
+            "/__rendar_edit",
+            if local { post(edit_handler) } else { post(|| async { axum::http::StatusCode::FORBIDDEN }) },
+        )
         .route(
             "/__rendar_heartbeat",
             post(heartbeat_handler).get(heartbeat_handler),
         )
-        .nest_service("/", ServeDir::new(output).append_index_html_on_directories(true))
+        .nest_service("/", ServeDir::new(&output).append_index_html_on_directories(true))
         .with_state(state);
+    let app = app.layer(axum::middleware::from_fn_with_state(
+        Arc::new(serve::CachePolicy::preview(output)),
+        serve::cache_headers,
+    ));
+    let app = match auth {
+        Some(auth) => app.layer(axum::middleware::from_fn_with_state(
+            Arc::new(auth),
+            preview_auth::require_auth,
+        )),
+        None => app,
+    };
 
     if let Some(timeout) = auto_exit {
         let shutdown = Arc::new(tokio::sync::Notify::new());
@@ -659,10 +2033,44 @@ async fn serve_preview(
 
 #[derive(Clone)]
 struct PreviewState {
+    input: PathBuf,
     version: Arc<AtomicU64>,
+    status: Arc<Mutex<PreviewStatus>>,
     auto_exit: Option<AutoExitState>,
 }
 
+/// Outcome of the last preview rebuild: the error if it failed, and the
+/// diagnostics of the pages it rendered otherwise. `built_at` and
+/// `build_time` describe the last rebuild that succeeded.
+struct PreviewStatus {
+    error: Option<String>,
+    diagnostics: Vec<diagnostics::Diagnostic>,
+    built_at: u64,
+    build_time: Duration,
+}
+
+impl PreviewStatus {
+    /// What the status bar on preview pages shows, with diagnostic paths
+    /// relative to `input` to match each page's `rendar-source`.
+    fn to_json(&self, input: &Path) -> serde_json::Value {
+        let diagnostics: Vec<_> = self
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostics::Diagnostic {
+                path: match diagnostic.path.strip_prefix(input) {
+                    Ok(rel_path) => PathBuf::from(path_to_url(rel_path)),
+                    Err(_) => diagnostic.path.clone(),
+                },
+                ..diagnostic.clone()
+            })
+            .collect();
+        let mut json = diagnostics::to_json(&diagnostics);
+        json["built_at"] = self.built_at.into();
+        json["duration_ms"] = (self.build_time.as_millis() as u64).into();
+        json
+    }
+}
+
 #[derive(Clone)]
 struct AutoExitState {
     last_seen: Arc<AtomicU64>,
@@ -672,6 +2080,43 @@ async fn version_handler(State(state): State<Arc<PreviewState>>) -> String {
     state.version.load(Ordering::SeqCst).to_string()
 }
 
+async fn error_handler(State(state): State<Arc<PreviewState>>) -> String {
+    state
+        .status
+        .lock()
+        .ok()
+        .and_then(|status| status.error.clone())
+        .unwrap_or_default()
+}
+
+async fn status_handler(State(state): State<Arc<PreviewState>>) -> axum::Json<serde_json::Value> {
+    let status = state
+        .status
+        .lock()
+        .map(|status| status.to_json(&state.input))
+        .unwrap_or_else(|_| diagnostics::to_json(&[]));
+    axum::Json(status)
+}
+
+#[derive(serde::Deserialize)]
+struct EditRequest {
+    path: String,
+    line: Option<usize>,
+}
+
+async fn edit_handler(
+    State(state): State<Arc<PreviewState>>,
+    axum::extract::Query(request): axum::extract::Query<EditRequest>,
+) -> axum::http::StatusCode {
+    match editor::open_in_editor(&state.input, &request.path, request.line.unwrap_or(1)) {
+        Ok(()) => axum::http::StatusCode::NO_CONTENT,
+        Err(err) => {
+            eprintln!("Failed to open {} in the editor: {err:#}", request.path);
+            axum::http::StatusCode::NOT_FOUND
+        }
+    }
+}
+
 async fn heartbeat_handler(State(state): State<Arc<PreviewState>>) -> axum::http::StatusCode {
     if let Some(auto_exit) = state.auto_exit.as_ref() {
         auto_exit
@@ -747,7 +2192,7 @@ fn spawn_preview_daemon() -> Result<()> {
     let (tx, rx) = mpsc::channel();
     std::thread::spawn(move || {
         let reader = BufReader::new(stdout);
-        for line in reader.lines().flatten() {
+        for line in reader.lines().map_while(Result::ok) {
             if line.starts_with("URL=") || line.starts_with("PID=") {
                 let _ = tx.send(line);
             }
@@ -789,13 +2234,20 @@ mod tests {
     use super::*;
     use crate::config::{Config, PreviewConfig};
 
+    #[test]
+    fn generates_completions_for_subcommands_and_flags() {
+        let mut script = Vec::new();
+        clap_complete::generate(clap_complete::Shell::Bash, &mut Cli::command(), "rendar", &mut script);
+        let script = String::from_utf8(script).expect("utf-8");
+        assert!(script.contains("rendar__subcmd__completions"));
+        assert!(script.contains("--strict-config"));
+    }
+
     #[test]
     fn resolves_input_with_cli_override() {
         let config = Config {
-            input: Some(PathBuf::from("config-input")),
-            template: None,
-            exclude: None,
-            preview: None,
+            input: Some(config::InputSetting::One(PathBuf::from("config-input"))),
+            ..Default::default()
         };
         let resolved = resolve_input(Some(PathBuf::from("cli-input")), Some(&config));
         assert_eq!(resolved, PathBuf::from("cli-input"));
@@ -804,10 +2256,8 @@ mod tests {
     #[test]
     fn resolves_template_with_config_fallback() {
         let config = Config {
-            input: None,
-            template: Some(PathBuf::from("config-template.html")),
-            exclude: None,
-            preview: None,
+            template: Some(config::TemplateSetting::Path(PathBuf::from("config-template.html"))),
+            ..Default::default()
         };
         let resolved = resolve_template(None, Some(&config));
         assert_eq!(resolved, Some(PathBuf::from("config-template.html")));
@@ -816,13 +2266,13 @@ mod tests {
     #[test]
     fn resolves_preview_port_with_cli_override() {
         let config = Config {
-            input: None,
-            template: None,
-            exclude: None,
             preview: Some(PreviewConfig {
                 port: Some(4000),
                 open: None,
+                host: None,
+                password: None,
             }),
+            ..Default::default()
         };
         let resolved = resolve_preview_port(Some(5000), Some(&config));
         assert_eq!(resolved, 5000);
@@ -831,26 +2281,159 @@ mod tests {
     #[test]
     fn resolves_preview_open_with_config_fallback() {
         let config = Config {
-            input: None,
-            template: None,
-            exclude: None,
             preview: Some(PreviewConfig {
                 port: None,
                 open: Some(true),
+                host: None,
+                password: None,
             }),
+            ..Default::default()
         };
         let resolved = resolve_preview_open(false, false, false, Some(&config));
         assert!(resolved);
     }
 
     #[test]
-    fn resolves_preview_port_default_when_unset() {
+    fn resolves_preview_host_from_config_and_formats_urls() {
         let config = Config {
-            input: None,
+            preview: Some(PreviewConfig {
+                host: Some("0.0.0.0".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(resolve_preview_host(None, Some(&config)), "0.0.0.0");
+        assert_eq!(resolve_preview_host(Some("::".to_string()), Some(&config)), "::");
+        assert_eq!(resolve_preview_host(None, None), "127.0.0.1");
+        assert_eq!(url_host("0.0.0.0"), "127.0.0.1");
+        assert_eq!(url_host("::"), "[::1]");
+        assert_eq!(url_host("192.168.1.20"), "192.168.1.20");
+    }
+
+    #[test]
+    fn failed_preview_rebuild_keeps_last_good_output() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let input = dir.path().join("docs");
+        let output = dir.path().join("preview");
+        std::fs::create_dir_all(&input).expect("input dir");
+        std::fs::write(input.join("index.md"), "# Home").expect("index");
+        let template = template::Template::built_in();
+        let options = site::RenderOptions {
+            live_reload: true,
+            heartbeat: false,
+            template: &template,
+            filter: &site::PathFilter::default(),
+            csv_max_rows: None,
+            generated: &site::Generated::default(),
+            page_views: None,
+            pre_page_hook: None,
+            plugins: &plugins::Plugins::default(),
+            settings: &Settings::default(),
+        };
+        site::build_site(&input, &output, &options).expect("initial build");
+
+        std::fs::write(input.join("broken.md"), [0xff, 0xfe]).expect("broken page");
+        assert!(rebuild_preview(&input, &output, &options).is_err());
+        assert!(output.join("index.html").exists());
+        assert!(!dir.path().join(".preview.staging").exists());
+
+        std::fs::remove_file(input.join("broken.md")).expect("fix page");
+        std::fs::write(input.join("index.md"), "# Welcome").expect("index");
+        std::fs::write(input.join("links.md"), "[Gone](gone.md)").expect("page with broken link");
+        let (_, diagnostics) = rebuild_preview(&input, &output, &options).expect("rebuild");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].rule, "missing-link");
+        let html = std::fs::read_to_string(output.join("index.html")).expect("html");
+        assert!(html.contains("Welcome"));
+    }
+
+    #[test]
+    fn preview_reload_applies_config_changes() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let input = dir.path().join("docs");
+        let output = dir.path().join("preview");
+        let config_path = dir.path().join("rendar.toml");
+        std::fs::create_dir_all(&input).expect("input dir");
+        std::fs::write(input.join("index.md"), "# Home").expect("index");
+        std::fs::write(input.join("01-intro.md"), "# Intro").expect("intro");
+        std::fs::write(&config_path, "").expect("config");
+        let preview_settings = PreviewSettings {
+            config: config::ConfigSource {
+                path: Some(config_path.clone()),
+                ..config::ConfigSource::default()
+            },
             template: None,
-            exclude: None,
-            preview: None,
+            exclude: Vec::new(),
         };
+        let pool = hook_pool(hook_timeout(None));
+
+        let preview_site = preview_settings.load(&input).expect("load");
+        rebuild_preview(&input, &output, &preview_site.options(false, None, &pool)).expect("build");
+        assert!(output.join("intro.html").exists());
+
+        std::fs::write(&config_path, "[site]\nstrip_number_prefixes = false\n").expect("config edit");
+        let preview_site = preview_settings.load(&input).expect("reload");
+        rebuild_preview(&input, &output, &preview_site.options(false, None, &pool)).expect("rebuild");
+        assert!(output.join("01-intro.html").exists());
+        assert!(!output.join("intro.html").exists());
+    }
+
+    #[test]
+    fn preview_status_lists_warnings_by_source_page() {
+        let input = PathBuf::from("/docs");
+        let status = PreviewStatus {
+            error: None,
+            diagnostics: vec![diagnostics::Diagnostic::warning(
+                "missing-link",
+                &input.join("guide").join("setup.md"),
+                "Missing link target gone.md",
+            )
+            .at(3, 1)],
+            built_at: 1_700_000_000_000,
+            build_time: Duration::from_millis(120),
+        };
+        let json = status.to_json(&input);
+        assert_eq!(json["built_at"], 1_700_000_000_000u64);
+        assert_eq!(json["duration_ms"], 120);
+        assert_eq!(json["diagnostics"][0]["path"], "guide/setup.md");
+        assert_eq!(json["diagnostics"][0]["span"]["line"], 3);
+    }
+
+    #[test]
+    fn strict_build_fails_on_warnings_and_keeps_output() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let input = dir.path().join("docs");
+        let output = dir.path().join("site");
+        std::fs::create_dir_all(&input).expect("input dir");
+        std::fs::write(input.join("index.md"), "# Home").expect("index");
+        let build = |strict| {
+            run_build(
+                output.clone(),
+                Some(input.clone()),
+                config::ConfigSource::default(),
+                None,
+                Vec::new(),
+                None,
+                0,
+                Vec::new(),
+                false,
+                false,
+                strict,
+            )
+        };
+        build(true).expect("clean strict build");
+
+        std::fs::write(input.join("index.md"), "# Welcome\n\n[Gone](gone.md)").expect("broken link");
+        let err = build(true).expect_err("strict build with a warning");
+        assert!(err.to_string().contains("1 warning(s)"));
+        let html = std::fs::read_to_string(output.join("index.html")).expect("html");
+        assert!(!html.contains("Welcome"));
+        build(false).expect("lenient build");
+    }
+
+    #[test]
+    fn resolves_preview_port_default_when_unset() {
+        let config = Config::default();
         let resolved = resolve_preview_port(None, Some(&config));
         assert_eq!(resolved, 3000);
     }
@@ -859,7 +2442,7 @@ mod tests {
     fn resolves_start_page_from_directory() {
         let dir = tempfile::tempdir().expect("tempdir");
         std::fs::write(dir.path().join("README.md"), "# Readme").expect("readme");
-        let resolved = resolve_start_page(dir.path()).expect("resolve start");
+        let resolved = resolve_start_page(dir.path(), &Settings::default()).expect("resolve start");
         assert!(resolved.ends_with("README.md"));
     }
 
@@ -875,7 +2458,7 @@ mod tests {
         let cwd = root_path.to_path_buf();
         let input_override = Some(root_path.to_path_buf());
         let start_on = Some(page.clone());
-        let paths = resolve_preview_paths_with_cwd(&cwd, input_override, start_on)
+        let paths = resolve_preview_paths_with_cwd(&cwd, input_override, start_on, &Settings::default())
             .expect("preview paths");
         assert_eq!(paths.input_root, root_path);
         assert_eq!(paths.start_page.unwrap(), page);
@@ -910,7 +2493,7 @@ mod tests {
         let cwd = root.path().to_path_buf();
         let input_override = Some(input_root);
         let start_on = Some(page);
-        let result = resolve_preview_paths_with_cwd(&cwd, input_override, start_on);
+        let result = resolve_preview_paths_with_cwd(&cwd, input_override, start_on, &Settings::default());
         assert!(result.is_err());
     }
 }
@@ -0,0 +1,104 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+#[derive(Debug, Serialize)]
+struct ManifestEntry {
+    path: String,
+    sha256: String,
+    size: u64,
+    /// Left out for files the build creates itself, like the search index.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+}
+
+/// Lists every file under `out` with its SHA-256 and, when it was copied
+/// or rendered from one, its source file. `sources` maps output paths to
+/// source paths. Sources under the current directory are listed relative
+/// to it.
+pub fn write_manifest(out: &Path, sources: &HashMap<PathBuf, PathBuf>) -> Result<PathBuf> {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let mut files = Vec::new();
+    for entry in WalkDir::new(out).sort_by_file_name().into_iter() {
+        let entry = entry.with_context(|| format!("Failed to list {}", out.display()))?;
+        let rel_path = entry.path().strip_prefix(out).unwrap_or(entry.path());
+        if !entry.file_type().is_file()
+            || rel_path == Path::new(MANIFEST_FILE)
+            || rel_path == Path::new(crate::clean::OUTPUT_MARKER)
+        {
+            continue;
+        }
+        let bytes = std::fs::read(entry.path())
+            .with_context(|| format!("Failed to read {}", entry.path().display()))?;
+        files.push(ManifestEntry {
+            path: slash_path(rel_path),
+            sha256: Sha256::digest(&bytes)
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect(),
+            size: bytes.len() as u64,
+            source: sources
+                .get(rel_path)
+                .map(|source| source_path(source.strip_prefix(&cwd).unwrap_or(source))),
+        });
+    }
+    let json = serde_json::to_string_pretty(&serde_json::json!({ "files": files }))
+        .context("Failed to serialize manifest")?;
+    let path = out.join(MANIFEST_FILE);
+    std::fs::write(&path, json + "\n")
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+fn source_path(path: &Path) -> String {
+    if path.is_absolute() {
+        path.to_string_lossy().into_owned()
+    } else {
+        slash_path(path)
+    }
+}
+
+fn slash_path(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_files_with_hashes_and_sources() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let out = dir.path();
+        std::fs::create_dir_all(out.join("guide")).expect("guide dir");
+        std::fs::write(out.join("guide/setup.html"), "<p>Setup</p>").expect("page");
+        std::fs::write(out.join("search-index.json"), "[]").expect("index");
+        std::fs::write(out.join(MANIFEST_FILE), "stale").expect("old manifest");
+        let sources = HashMap::from([(
+            PathBuf::from("guide/setup.html"),
+            PathBuf::from("docs/guide/setup.md"),
+        )]);
+
+        let path = write_manifest(out, &sources).expect("manifest");
+        let manifest: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).expect("read")).expect("json");
+        let files = manifest["files"].as_array().expect("files");
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0]["path"], "guide/setup.html");
+        assert_eq!(files[0]["source"], "docs/guide/setup.md");
+        assert_eq!(files[0]["size"], 12);
+        assert_eq!(
+            files[1]["sha256"],
+            "4f53cda18c2baa0c0354bb5f9a3ecbe5ed12ab4d8e11ba873c2f11161202b945"
+        );
+        assert!(files[1].get("source").is_none());
+    }
+}
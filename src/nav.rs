@@ -0,0 +1,173 @@
+use crate::i18n::Locale;
+use crate::render::escape_html;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// A link in the sidebar or breadcrumbs. `url` is relative to the page the
+/// link appears on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NavLink {
+    pub title: String,
+    pub url: String,
+}
+
+/// Navigation for a single page: sibling pages, subfolders with a landing
+/// page, and the landing pages above it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PageNav {
+    pub title: String,
+    pub pages: Vec<NavLink>,
+    pub folders: Vec<NavLink>,
+    pub breadcrumbs: Vec<NavLink>,
+}
+
+/// The whole site as nested sections, as written to `nav.json`. URLs are
+/// relative to the output root.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NavTree {
+    pub title: String,
+    pub root: NavSection,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NavSection {
+    pub title: String,
+    pub path: String,
+    pub url: Option<String>,
+    pub pages: Vec<NavPage>,
+    pub sections: Vec<NavSection>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NavPage {
+    pub title: String,
+    pub url: String,
+    pub source: String,
+    pub landing: bool,
+}
+
+/// A page as templates see it through `{{site}}` and `{{page}}`. URLs are
+/// relative to the output root.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PageData {
+    pub title: String,
+    pub url: String,
+    pub source: String,
+    pub section: String,
+    pub landing: bool,
+    pub front_matter: BTreeMap<String, String>,
+}
+
+pub fn nav_html(nav: &PageNav, locale: &Locale) -> String {
+    let mut html = String::new();
+    for (key, class, links) in [
+        ("nav_pages", "nav-pages", &nav.pages),
+        ("nav_folders", "nav-folders", &nav.folders),
+    ] {
+        if links.is_empty() {
+            continue;
+        }
+        html.push_str(r#"<div class="nav-section">"#);
+        html.push_str(&format!(r#"<div class="nav-title">{}</div>"#, escape_html(locale.text(key))));
+        html.push_str(&format!(r#"<ul class="nav-list {class}">"#));
+        for link in links {
+            html.push_str(&format!(
+                r#"<li><a href="{}">{}</a></li>"#,
+                link.url,
+                escape_html(&link.title)
+            ));
+        }
+        html.push_str("</ul></div>");
+    }
+    html
+}
+
+pub fn breadcrumbs_html(nav: &PageNav) -> String {
+    let mut crumbs: Vec<String> = nav
+        .breadcrumbs
+        .iter()
+        .map(|link| format!(r#"<a href="{}">{}</a>"#, link.url, escape_html(&link.title)))
+        .collect();
+    crumbs.push(format!(r#"<span>{}</span>"#, escape_html(&nav.title)));
+    crumbs.join(r#"<span class="sep">/</span>"#)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::Settings;
+    use crate::site::{page_nav, PathFilter};
+    use std::path::{Path, PathBuf};
+
+    /// Renders the nav and breadcrumbs of `page` in a tree made of `files`
+    /// and compares them with `tests/fixtures/nav/<name>.html`. Set
+    /// `UPDATE_GOLDEN=1` to rewrite the fixture.
+    fn assert_golden(name: &str, files: &[&str], page: &str) {
+        let dir = tempfile::tempdir().expect("tempdir");
+        for file in files {
+            let path = dir.path().join(file);
+            if file.ends_with('/') {
+                std::fs::create_dir_all(&path).expect("dir");
+                continue;
+            }
+            std::fs::create_dir_all(path.parent().expect("parent")).expect("parent dir");
+            let title = file.trim_end_matches(".md");
+            std::fs::write(&path, format!("# {title}\n")).expect("page");
+        }
+        let settings = Settings::default();
+        let nav = page_nav(dir.path(), &PathFilter::default(), Path::new(page), &settings).expect("page nav");
+        let actual = format!("{}\n{}\n", nav_html(&nav, &settings.locale), breadcrumbs_html(&nav));
+        let golden = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/nav")
+            .join(format!("{name}.html"));
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::create_dir_all(golden.parent().expect("fixtures dir")).expect("fixtures");
+            std::fs::write(&golden, &actual).expect("write golden");
+        }
+        let expected = std::fs::read_to_string(&golden)
+            .unwrap_or_else(|_| panic!("missing {}; run with UPDATE_GOLDEN=1", golden.display()));
+        assert_eq!(actual, expected, "{name} differs from {}", golden.display());
+    }
+
+    #[test]
+    fn renders_nav_for_tricky_trees() {
+        assert_golden(
+            "empty-dirs",
+            &["README.md", "guide.md", "assets/", "drafts/notes/", "api/index.md"],
+            "guide.md",
+        );
+        assert_golden(
+            "deep-nesting",
+            &[
+                "index.md",
+                "a/README.md",
+                "a/b/page.md",
+                "a/b/c/index.md",
+                "a/b/c/d/leaf.md",
+                "a/b/c/d/e/README.md",
+            ],
+            "a/b/c/d/leaf.md",
+        );
+        assert_golden("only-index", &["index.md"], "index.md");
+        assert_golden("only-readme", &["docs/README.md"], "docs/README.md");
+        assert_golden(
+            "unicode-names",
+            &["README.md", "über-uns/README.md", "über-uns/café.md", "über-uns/日本語.md", "ñandú/index.md"],
+            "über-uns/café.md",
+        );
+    }
+
+    #[test]
+    fn serializes_page_nav() {
+        let nav = PageNav {
+            title: "Setup".to_string(),
+            pages: vec![NavLink { title: "Intro".to_string(), url: "intro.html".to_string() }],
+            folders: Vec::new(),
+            breadcrumbs: vec![NavLink { title: "Home".to_string(), url: "../index.html".to_string() }],
+        };
+        let json = serde_json::to_value(&nav).expect("json");
+        assert_eq!(json["pages"][0]["url"], "intro.html");
+        assert_eq!(json["breadcrumbs"][0]["title"], "Home");
+        assert_eq!(breadcrumbs_html(&nav), r#"<a href="../index.html">Home</a><span class="sep">/</span><span>Setup</span>"#);
+    }
+}
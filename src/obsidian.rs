@@ -0,0 +1,531 @@
+use crate::render::{github_slug, strip_front_matter};
+use crate::settings::Settings;
+use crate::site::{relative_path, walk_entries, PathFilter};
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "svg", "webp", "avif", "bmp"];
+/// How deep `![[Note]]` embeds may nest before the rest become links.
+const MAX_EMBED_DEPTH: usize = 4;
+
+/// `[obsidian]` settings. `attachments` is the vault folder that
+/// `![[image.png]]` embeds prefer when a name is ambiguous.
+#[derive(Default)]
+pub struct ObsidianSettings {
+    pub attachments: Option<PathBuf>,
+    /// The files of the vault last scanned, shared by the pages of a build.
+    vault: Mutex<Option<Arc<Vault>>>,
+}
+
+impl ObsidianSettings {
+    pub fn new(attachments: Option<PathBuf>) -> Self {
+        Self {
+            attachments,
+            vault: Mutex::default(),
+        }
+    }
+}
+
+/// Lists the files wikilinks can point at, for the pages about to be
+/// rendered. Called for every build so preview sees added and renamed
+/// files.
+pub fn index_vault(input: &Path, filter: &PathFilter, settings: &Settings) {
+    if let Some(obsidian) = &settings.obsidian {
+        let vault = Vault::scan(input, filter, obsidian.attachments.as_deref());
+        *obsidian.vault.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(Arc::new(vault));
+    }
+}
+
+fn vault(input_root: &Path, obsidian: &ObsidianSettings) -> Arc<Vault> {
+    let mut cached = obsidian.vault.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    match cached.as_ref() {
+        Some(vault) if vault.root == input_root => vault.clone(),
+        _ => {
+            let vault = Arc::new(Vault::scan(input_root, &PathFilter::default(), obsidian.attachments.as_deref()));
+            *cached = Some(vault.clone());
+            vault
+        }
+    }
+}
+
+/// `markdown` with Obsidian syntax turned into what the renderer reads:
+/// `[[Note]]` and `[[Note#Heading|label]]` into Markdown links,
+/// `![[image.png]]` into images, `![[Note]]` on a line of its own into the
+/// note's content, and `> [!tip] Title` callouts into `:::` containers.
+pub fn convert<'a>(markdown: &'a str, source_path: &Path, input_root: &Path, settings: &Settings) -> Cow<'a, str> {
+    let Some(obsidian) = &settings.obsidian else {
+        return Cow::Borrowed(markdown);
+    };
+    if !(markdown.contains("[[") || markdown.contains("[!")) {
+        return Cow::Borrowed(markdown);
+    }
+    let vault = vault(input_root, obsidian);
+    let from_dir = source_path
+        .parent()
+        .and_then(|dir| dir.strip_prefix(input_root).ok())
+        .unwrap_or(Path::new(""));
+    let rel_source = source_path.strip_prefix(input_root).unwrap_or(source_path);
+    Cow::Owned(convert_in(markdown, from_dir, &vault, &mut vec![rel_source.to_path_buf()], settings))
+}
+
+/// Converts `markdown` for a page in `from_dir`. `embedding` lists the
+/// notes being embedded into each other, to stop cycles.
+fn convert_in(
+    markdown: &str,
+    from_dir: &Path,
+    vault: &Vault,
+    embedding: &mut Vec<PathBuf>,
+    settings: &Settings,
+) -> String {
+    let markdown = convert_callouts(markdown);
+    let mut output = String::with_capacity(markdown.len());
+    let mut fence = None;
+    for line in markdown.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\r', '\n']);
+        if in_fence(&mut fence, content) || !content.contains("[[") {
+            output.push_str(line);
+            continue;
+        }
+        if let Some(embedded) = note_embed(content.trim(), from_dir, vault, embedding, settings) {
+            output.push_str(&embedded);
+            continue;
+        }
+        output.push_str(&convert_line(content, from_dir, vault, settings));
+        output.push_str(&line[content.len()..]);
+    }
+    output
+}
+
+/// Updates the fenced code state for `line` and tells whether the line is
+/// part of a fence, markers included.
+fn in_fence(fence: &mut Option<(char, usize)>, line: &str) -> bool {
+    let trimmed = line.trim_start();
+    let indent = line.len() - trimmed.len();
+    let fence_char = trimmed.chars().next().filter(|ch| matches!(ch, '`' | '~'));
+    let fence_len = fence_char.map_or(0, |ch| trimmed.chars().take_while(|c| *c == ch).count());
+    match (*fence, fence_char) {
+        (None, Some(ch)) if indent < 4 && fence_len >= 3 => {
+            *fence = Some((ch, fence_len));
+            true
+        }
+        (Some((open, len)), Some(ch)) if ch == open && fence_len >= len && trimmed[fence_len..].trim().is_empty() => {
+            *fence = None;
+            true
+        }
+        (open, _) => open.is_some(),
+    }
+}
+
+/// Turns `> [!type] Title` blockquotes into `::: type Title` containers.
+/// Foldable callouts (`[!type]-` or `[!type]+`) become `::: details`. The
+/// closing `:::` takes the place of the blank line after the quote, so
+/// later lines keep their numbers.
+fn convert_callouts(markdown: &str) -> Cow<'_, str> {
+    if !markdown.contains("[!") {
+        return Cow::Borrowed(markdown);
+    }
+    let mut output = String::with_capacity(markdown.len() + 16);
+    let mut fence = None;
+    let mut lines = markdown.split_inclusive('\n').peekable();
+    while let Some(line) = lines.next() {
+        let content = line.trim_end_matches(['\r', '\n']);
+        let header = (!in_fence(&mut fence, content)).then(|| callout_header(content)).flatten();
+        let Some(header) = header else {
+            output.push_str(line);
+            continue;
+        };
+        output.push_str(&header);
+        output.push('\n');
+        while let Some(body) = lines.peek().and_then(|line| quoted(line)) {
+            output.push_str(body);
+            lines.next();
+        }
+        match lines.peek() {
+            Some(next) if next.trim().is_empty() => {
+                output.push_str(":::");
+                output.push_str(&next[next.trim_end_matches(['\r', '\n']).len()..]);
+                lines.next();
+            }
+            _ => {
+                if !output.ends_with('\n') {
+                    output.push('\n');
+                }
+                output.push_str(":::\n");
+            }
+        }
+    }
+    Cow::Owned(output)
+}
+
+/// The container marker for a callout's first line, such as
+/// `::: warning Mind the gap` for `> [!attention] Mind the gap`.
+fn callout_header(line: &str) -> Option<String> {
+    let rest = quoted(line)?.trim_start().strip_prefix("[!")?;
+    let (kind, rest) = rest.split_once(']')?;
+    let (foldable, title) = match rest.strip_prefix(['-', '+']) {
+        Some(title) => (true, title),
+        None => (false, rest),
+    };
+    let kind = kind.trim().to_lowercase();
+    if kind.is_empty() || !kind.chars().all(|ch| ch.is_alphanumeric() || matches!(ch, '-' | '_')) {
+        return None;
+    }
+    let title = match title.trim() {
+        "" => {
+            let mut chars = kind.chars();
+            chars.next().map(|first| first.to_uppercase().chain(chars).collect()).unwrap_or_default()
+        }
+        title => title.to_string(),
+    };
+    let class = match kind.as_str() {
+        _ if foldable => "details",
+        "hint" => "tip",
+        "attention" => "warning",
+        "error" => "danger",
+        "summary" | "tldr" => "abstract",
+        "check" | "done" => "success",
+        "help" | "faq" => "question",
+        "fail" | "missing" => "failure",
+        "cite" => "quote",
+        other => other,
+    };
+    Some(format!("::: {class} {title}"))
+}
+
+/// What follows the `>` of a quoted line, minus one space.
+fn quoted(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let rest = trimmed.strip_prefix('>')?;
+    Some(rest.strip_prefix(' ').unwrap_or(rest))
+}
+
+/// `line` with wikilinks and embeds outside code spans converted.
+fn convert_line(line: &str, from_dir: &Path, vault: &Vault, settings: &Settings) -> String {
+    let mut output = String::with_capacity(line.len() + 16);
+    let mut rest = line;
+    while !rest.is_empty() {
+        let next_code = rest.find('`');
+        let next_link = rest.find("[[");
+        match (next_code, next_link) {
+            (Some(code), link) if link.is_none_or(|link| code < link) => {
+                let ticks = rest[code..].len() - rest[code..].trim_start_matches('`').len();
+                let after = code + ticks;
+                let close = rest[after..]
+                    .match_indices(&"`".repeat(ticks))
+                    .map(|(index, _)| after + index)
+                    .find(|&index| !rest[index + ticks..].starts_with('`'));
+                let end = close.map_or(rest.len(), |close| close + ticks);
+                output.push_str(&rest[..end]);
+                rest = &rest[end..];
+            }
+            (_, Some(link)) => {
+                let Some(len) = rest[link + 2..].find("]]") else {
+                    output.push_str(rest);
+                    break;
+                };
+                let embed = link > 0 && rest[..link].ends_with('!');
+                let start = if embed { link - 1 } else { link };
+                output.push_str(&rest[..start]);
+                let inner = &rest[link + 2..link + 2 + len];
+                output.push_str(&wikilink(inner, embed, from_dir, vault, settings));
+                rest = &rest[link + 2 + len + 2..];
+            }
+            _ => {
+                output.push_str(rest);
+                break;
+            }
+        }
+    }
+    output
+}
+
+struct Target<'a> {
+    name: &'a str,
+    fragment: Option<&'a str>,
+    label: Option<&'a str>,
+}
+
+fn parse_target(inner: &str) -> Target<'_> {
+    let (target, label) = match inner.split_once('|') {
+        // A `|` inside a table row is written `\|`.
+        Some((target, label)) => (target.trim_end_matches('\\'), Some(label.trim())),
+        None => (inner, None),
+    };
+    let (name, fragment) = match target.split_once('#') {
+        Some((name, fragment)) => (name.trim(), Some(fragment.trim())),
+        None => (target.trim(), None),
+    };
+    Target {
+        name,
+        fragment,
+        label: label.filter(|label| !label.is_empty()),
+    }
+}
+
+/// The Markdown for `[[inner]]`, or `![[inner]]` when `embed`.
+fn wikilink(inner: &str, embed: bool, from_dir: &Path, vault: &Vault, settings: &Settings) -> String {
+    let target = parse_target(inner);
+    let extension = attachment_extension(target.name, settings);
+    let resolved = (!target.name.is_empty())
+        .then(|| vault.resolve(target.name, from_dir, settings))
+        .flatten();
+    let mut url = match resolved {
+        Some(path) => relative_path(from_dir, path),
+        None if target.name.is_empty() || extension.is_some() || settings.is_markdown(Path::new(target.name)) => {
+            target.name.to_string()
+        }
+        None => format!("{}.md", target.name),
+    };
+    // `#^block` references name a paragraph, which has no anchor here.
+    if let Some(fragment) = target.fragment.filter(|fragment| !fragment.starts_with('^') && !fragment.is_empty()) {
+        url.push('#');
+        url.push_str(&github_slug(fragment));
+    }
+    let url = if url.contains([' ', '(', ')', '<']) { format!("<{}>", url.replace('>', "%3E")) } else { url };
+    let stem = if extension.is_some() || settings.is_markdown(Path::new(target.name)) {
+        Path::new(target.name)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or(target.name)
+    } else {
+        target.name.rsplit('/').next().unwrap_or(target.name)
+    };
+    if embed && extension.as_deref().is_some_and(|extension| IMAGE_EXTENSIONS.contains(&extension)) {
+        // `![[chart.png|300]]` sets a width, which Markdown images can't.
+        let alt = target.label.filter(|label| !is_size(label)).unwrap_or(stem);
+        return format!("![{}]({url})", escape_label(alt));
+    }
+    let label = match (target.label, target.fragment) {
+        (Some(label), _) => label.to_string(),
+        (None, Some(fragment)) if target.name.is_empty() => fragment.trim_start_matches('^').to_string(),
+        (None, Some(fragment)) if !fragment.starts_with('^') => format!("{stem} > {fragment}"),
+        _ => stem.to_string(),
+    };
+    format!("[{}]({url})", escape_label(&label))
+}
+
+/// The lowercase extension of a name that points at an attachment rather
+/// than a note. Note names may contain dots, as in `v1.2 notes`.
+fn attachment_extension(name: &str, settings: &Settings) -> Option<String> {
+    let extension = Path::new(name).extension()?.to_str()?;
+    (extension.len() <= 5
+        && extension.chars().all(|ch| ch.is_ascii_alphanumeric())
+        && !settings.is_markdown(Path::new(name)))
+    .then(|| extension.to_ascii_lowercase())
+}
+
+fn is_size(label: &str) -> bool {
+    let mut parts = label.splitn(2, 'x');
+    parts.all(|part| !part.is_empty() && part.chars().all(|ch| ch.is_ascii_digit()))
+}
+
+fn escape_label(label: &str) -> String {
+    label.replace('[', "\\[").replace(']', "\\]")
+}
+
+/// The content of the note (or one of its sections) that `line`, a lone
+/// `![[Note]]` or `![[Note#Heading]]`, embeds, wrapped in `::: embed`.
+fn note_embed(
+    line: &str,
+    from_dir: &Path,
+    vault: &Vault,
+    embedding: &mut Vec<PathBuf>,
+    settings: &Settings,
+) -> Option<String> {
+    let inner = line.strip_prefix("![[")?.strip_suffix("]]")?;
+    if inner.contains("]]") || embedding.len() >= MAX_EMBED_DEPTH {
+        return None;
+    }
+    let target = parse_target(inner);
+    if attachment_extension(target.name, settings).is_some() {
+        return None;
+    }
+    let path = vault.resolve(target.name, from_dir, settings)?;
+    if embedding.contains(path) {
+        return None;
+    }
+    let markdown = std::fs::read_to_string(vault.root.join(path)).ok()?;
+    let body = strip_front_matter(&markdown);
+    let body = match target.fragment {
+        Some(heading) if !heading.starts_with('^') => section(body, heading)?,
+        _ => body,
+    };
+    embedding.push(path.clone());
+    let converted = convert_in(body, from_dir, vault, embedding, settings);
+    embedding.pop();
+    let mut html = String::from("::: embed\n");
+    html.push_str(&converted);
+    if !converted.ends_with('\n') {
+        html.push('\n');
+    }
+    html.push_str(":::\n");
+    Some(html)
+}
+
+/// The part of `markdown` from the heading `heading` up to the next one of
+/// the same or a higher level.
+fn section<'a>(markdown: &'a str, heading: &str) -> Option<&'a str> {
+    let wanted = github_slug(heading);
+    let mut fence = None;
+    let mut start: Option<(usize, usize)> = None;
+    let mut offset = 0;
+    for line in markdown.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\r', '\n']);
+        if !in_fence(&mut fence, content) {
+            let level = content.chars().take_while(|ch| *ch == '#').count();
+            let text = content[level..].strip_prefix(' ');
+            if let Some(text) = text.filter(|_| (1..=6).contains(&level)) {
+                match start {
+                    Some((begin, open_level)) if level <= open_level => return Some(&markdown[begin..offset]),
+                    None if github_slug(text.trim_end_matches('#').trim()) == wanted => start = Some((offset, level)),
+                    _ => {}
+                }
+            }
+        }
+        offset += line.len();
+    }
+    start.map(|(begin, _)| &markdown[begin..])
+}
+
+/// The files of the vault that wikilinks can name.
+struct Vault {
+    root: PathBuf,
+    /// Paths relative to the root, with their lowercase `/`-separated
+    /// spelling for matching.
+    files: Vec<(PathBuf, String)>,
+    attachments: Option<PathBuf>,
+}
+
+impl Vault {
+    fn scan(root: &Path, filter: &PathFilter, attachments: Option<&Path>) -> Self {
+        let files = walk_entries(root, filter)
+            .filter(|entry| entry.file_type().is_file())
+            .filter_map(|entry| {
+                let rel = entry.path().strip_prefix(root).ok()?.to_path_buf();
+                let key = rel.to_string_lossy().replace('\\', "/").to_lowercase();
+                Some((rel, key))
+            })
+            .collect();
+        let attachments = attachments.map(Path::to_path_buf).or_else(|| attachment_folder(root));
+        Self {
+            root: root.to_path_buf(),
+            files,
+            attachments,
+        }
+    }
+
+    /// The file `name` refers to, as Obsidian finds it: by path from the
+    /// vault root, or by file name anywhere, preferring the linking
+    /// page's folder, then the attachments folder, then the shortest path.
+    /// Notes may leave out their extension.
+    fn resolve(&self, name: &str, from_dir: &Path, settings: &Settings) -> Option<&PathBuf> {
+        let wanted = name.trim_start_matches('/').replace('\\', "/").to_lowercase();
+        let bare_note = attachment_extension(name, settings).is_none() && !settings.is_markdown(Path::new(name));
+        let matches = |key: &str| key == wanted || key.ends_with(&format!("/{wanted}"));
+        self.files
+            .iter()
+            .filter(|(rel, key)| {
+                if bare_note {
+                    settings.is_markdown(rel) && key.rsplit_once('.').is_some_and(|(stem, _)| matches(stem))
+                } else {
+                    matches(key)
+                }
+            })
+            .min_by_key(|(rel, _)| {
+                let parent = rel.parent().unwrap_or(Path::new(""));
+                let place = if parent == from_dir {
+                    0
+                } else if self.attachments.as_deref().is_some_and(|dir| rel.starts_with(dir)) {
+                    1
+                } else {
+                    2
+                };
+                (place, rel.components().count(), rel.clone())
+            })
+            .map(|(rel, _)| rel)
+    }
+}
+
+/// `attachmentFolderPath` from the vault's `.obsidian/app.json`, unless it
+/// is relative to each note.
+fn attachment_folder(root: &Path) -> Option<PathBuf> {
+    let json = std::fs::read_to_string(root.join(".obsidian/app.json")).ok()?;
+    let settings: serde_json::Value = serde_json::from_str(&json).ok()?;
+    let folder = settings.get("attachmentFolderPath")?.as_str()?.trim_start_matches('/');
+    (!folder.is_empty() && !folder.starts_with("./")).then(|| PathBuf::from(folder))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vault() -> (tempfile::TempDir, Vault) {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let root = dir.path();
+        for (path, contents) in [
+            ("Home.md", "# Home\n"),
+            ("notes/Project Plan.md", "# Plan\n\nIntro\n\n## Goals\n\nShip it.\n\n## Risks\n\nNone.\n"),
+            ("notes/Loop.md", "![[Loop]]\n"),
+            ("Attachments/chart.png", "png"),
+            ("notes/chart.png", "png"),
+            ("Attachments/spec.pdf", "pdf"),
+            (".obsidian/app.json", r#"{"attachmentFolderPath": "Attachments"}"#),
+        ] {
+            std::fs::create_dir_all(root.join(path).parent().expect("parent")).expect("dirs");
+            std::fs::write(root.join(path), contents).expect("write");
+        }
+        let vault = Vault::scan(root, &PathFilter::default(), None);
+        (dir, vault)
+    }
+
+    #[test]
+    fn converts_wikilinks_to_markdown_links() {
+        let settings = Settings::default();
+        let (_dir, vault) = vault();
+        let from = Path::new("");
+        assert_eq!(
+            convert_line("See [[Project Plan]] and [[Project Plan#Goals|the goals]].", from, &vault, &settings),
+            "See [Project Plan](<notes/Project Plan.md>) and [the goals](<notes/Project Plan.md#goals>)."
+        );
+        assert_eq!(convert_line("[[Home#Setting Up]]", from, &vault, &settings), "[Home > Setting Up](Home.md#setting-up)");
+        assert_eq!(convert_line("[[#Local]] [[Missing]]", from, &vault, &settings), "[Local](#local) [Missing](Missing.md)");
+        assert_eq!(convert_line("`[[Home]]` stays", from, &vault, &settings), "`[[Home]]` stays");
+        assert_eq!(
+            convert_line("| [[Home\\|start]] |", from, &vault, &settings),
+            "| [start](Home.md) |"
+        );
+    }
+
+    #[test]
+    fn embeds_attachments_by_name() {
+        let settings = Settings::default();
+        let (_dir, vault) = vault();
+        assert_eq!(convert_line("![[chart.png|300]]", Path::new(""), &vault, &settings), "![chart](Attachments/chart.png)");
+        assert_eq!(convert_line("![[chart.png]]", Path::new("notes"), &vault, &settings), "![chart](chart.png)");
+        assert_eq!(convert_line("![[spec.pdf]]", Path::new("notes"), &vault, &settings), "[spec](../Attachments/spec.pdf)");
+    }
+
+    #[test]
+    fn embeds_notes_and_sections() {
+        let settings = Settings::default();
+        let (_dir, vault) = vault();
+        let embedded = convert_in("Before\n![[Project Plan#Goals]]\nAfter\n", Path::new(""), &vault, &mut Vec::new(), &settings);
+        assert_eq!(embedded, "Before\n::: embed\n## Goals\n\nShip it.\n\n:::\nAfter\n");
+        let looped = convert_in("![[Loop]]\n", Path::new("notes"), &vault, &mut vec![PathBuf::from("notes/Loop.md")], &settings);
+        assert_eq!(looped, "[Loop](Loop.md)\n");
+    }
+
+    #[test]
+    fn converts_callouts_to_containers() {
+        let markdown = "> [!tip] Try this\n> Some *advice*.\n\nAfter\n> [!faq]- Why?\n> Because.\n```\n> [!note]\n```\n";
+        assert_eq!(
+            convert_callouts(markdown),
+            "::: tip Try this\nSome *advice*.\n:::\nAfter\n::: details Why?\nBecause.\n:::\n```\n> [!note]\n```\n"
+        );
+        assert_eq!(convert_callouts("> [!WARNING]\n> Careful"), "::: warning Warning\nCareful\n:::\n");
+    }
+}
@@ -0,0 +1,249 @@
+use crate::config::PackageConfig;
+use crate::diagnostics::print_diagnostic;
+use crate::render::{page_title, render_markdown_source};
+use crate::settings::Settings;
+use crate::site::{collect_index_dirs, Generated, GeneratedAsset, GeneratedPage, PathFilter};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+
+const MANIFEST_FILE: &str = "rendar-package.toml";
+
+#[derive(Debug, Default, Deserialize)]
+struct PackageManifest {
+    name: Option<String>,
+    mount: Option<PathBuf>,
+    #[serde(default)]
+    variables: BTreeMap<String, String>,
+}
+
+pub fn load_packages(imports: &[PackageConfig], settings: &Settings) -> Result<Generated> {
+    let mut generated = Generated::default();
+    for import in imports {
+        generated.extend(load_package(import, settings)?);
+    }
+    Ok(generated)
+}
+
+fn load_package(import: &PackageConfig, settings: &Settings) -> Result<Generated> {
+    let root = import.path.as_path();
+    let manifest_path = root.join(MANIFEST_FILE);
+    let raw = std::fs::read_to_string(&manifest_path).with_context(|| {
+        format!("Failed to read package manifest {}", manifest_path.display())
+    })?;
+    let manifest: PackageManifest = toml::from_str(&raw).with_context(|| {
+        format!("Failed to parse package manifest {}", manifest_path.display())
+    })?;
+
+    let mount = import
+        .mount
+        .clone()
+        .or_else(|| manifest.mount.clone())
+        .or_else(|| manifest.name.clone().map(PathBuf::from))
+        .or_else(|| root.file_name().map(PathBuf::from))
+        .unwrap_or_default();
+    if mount
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(anyhow::anyhow!(
+            "Package mount {} must be a relative path inside the site",
+            mount.display()
+        ));
+    }
+
+    let mut variables = manifest.variables;
+    variables.extend(import.variables.clone());
+
+    let index_dirs = collect_index_dirs(root, &PathFilter::default(), settings);
+    let mut generated = Generated::default();
+    let entries = WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || !is_hidden(entry.file_name()))
+        .filter_map(Result::ok);
+    for entry in entries {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let path = entry.path();
+        let rel = path.strip_prefix(root).with_context(|| {
+            format!("Failed to compute relative path for {}", path.display())
+        })?;
+        if rel == Path::new(MANIFEST_FILE) {
+            continue;
+        }
+        let rel_path = mount.join(rel);
+        if !settings.is_markdown(path) {
+            generated.assets.push(GeneratedAsset {
+                rel_path,
+                source: path.to_path_buf(),
+            });
+            continue;
+        }
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read markdown file {}", path.display()))?;
+        let source = substitute_variables(&raw, &variables);
+        let rendered = render_markdown_source(&source, path, root, &index_dirs, settings);
+        rendered.diagnostics.iter().for_each(print_diagnostic);
+        let title = page_title(&source).unwrap_or_else(|| {
+            path.file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or("Document")
+                .replace(['-', '_'], " ")
+        });
+        generated.pages.push(GeneratedPage {
+            rel_path,
+            title,
+            html: rendered.html,
+            mode: rendered.mode,
+        });
+    }
+    Ok(generated)
+}
+
+fn is_hidden(name: &std::ffi::OsStr) -> bool {
+    name.to_str().map(|name| name.starts_with('.')).unwrap_or(false)
+}
+
+pub fn substitute_variables(source: &str, variables: &BTreeMap<String, String>) -> String {
+    if variables.is_empty() {
+        return source.to_string();
+    }
+    let mut output = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(start) = rest.find("{{") {
+        let after_open = &rest[start + 2..];
+        let Some(end) = after_open.find("}}") else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+        match variables.get(after_open[..end].trim()) {
+            Some(value) => output.push_str(value),
+            None => output.push_str(&rest[start..start + 2 + end + 2]),
+        }
+        rest = &after_open[end + 2..];
+    }
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substitutes_known_variables_only() {
+        let mut variables = BTreeMap::new();
+        variables.insert("company".to_string(), "ACME".to_string());
+        let output = substitute_variables("{{company}} and {{ company }} keep {{other}}", &variables);
+        assert_eq!(output, "ACME and ACME keep {{other}}");
+    }
+
+    #[test]
+    fn loads_package_pages_with_overridden_variables() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let package = dir.path().join("security");
+        std::fs::create_dir_all(package.join("img")).expect("package dir");
+        std::fs::write(
+            package.join(MANIFEST_FILE),
+            "name = \"security\"\nmount = \"policies\"\n[variables]\ncompany = \"Default Corp\"\nteam = \"Ops\"\n",
+        )
+        .expect("manifest");
+        std::fs::write(
+            package.join("index.md"),
+            "# {{company}} Security\n\nOwned by {{team}}.\n",
+        )
+        .expect("index");
+        std::fs::write(package.join("img/lock.png"), "png").expect("asset");
+
+        let mut variables = BTreeMap::new();
+        variables.insert("company".to_string(), "ACME".to_string());
+        let generated = load_packages(&[PackageConfig {
+            path: package.clone(),
+            mount: None,
+            variables,
+        }], &Settings::default())
+        .expect("load packages");
+
+        assert_eq!(generated.pages.len(), 1);
+        let page = &generated.pages[0];
+        assert_eq!(page.rel_path, PathBuf::from("policies/index.md"));
+        assert_eq!(page.title, "ACME Security");
+        assert!(page.html.contains("Owned by Ops."));
+        assert_eq!(generated.assets.len(), 1);
+        assert_eq!(generated.assets[0].rel_path, PathBuf::from("policies/img/lock.png"));
+    }
+
+    #[test]
+    fn rejects_mounts_outside_the_site() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(dir.path().join(MANIFEST_FILE), "mount = \"../escape\"\n").expect("manifest");
+        let result = load_packages(&[PackageConfig {
+            path: dir.path().to_path_buf(),
+            mount: None,
+            variables: BTreeMap::new(),
+        }], &Settings::default());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn keeps_unknown_variables_in_package_pages() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(dir.path().join(MANIFEST_FILE), "mount = \"shared\"\n[variables]\nteam = \"Ops\"\n")
+            .expect("manifest");
+        std::fs::write(dir.path().join("guide.md"), "# Guide\n\n{{team}} owns {{ product }} and {{}}.\n")
+            .expect("guide");
+        let generated = load_packages(&[PackageConfig {
+            path: dir.path().to_path_buf(),
+            mount: None,
+            variables: BTreeMap::new(),
+        }], &Settings::default())
+        .expect("load packages");
+        assert!(generated.pages[0].html.contains("Ops owns {{ product }} and {{}}."));
+    }
+
+    #[test]
+    fn source_pages_win_over_package_pages_at_the_same_path() {
+        let site = tempfile::tempdir().expect("site");
+        let package = tempfile::tempdir().expect("package");
+        let output = tempfile::tempdir().expect("output");
+        std::fs::create_dir_all(site.path().join("policies")).expect("policies dir");
+        std::fs::write(site.path().join("policies/index.md"), "# Local Policies\n").expect("local index");
+        std::fs::write(package.path().join(MANIFEST_FILE), "mount = \"policies\"\n").expect("manifest");
+        std::fs::write(package.path().join("index.md"), "# Shared Policies\n").expect("package index");
+        std::fs::write(package.path().join("travel.md"), "# Travel\n").expect("package page");
+        let generated = load_packages(&[PackageConfig {
+            path: package.path().to_path_buf(),
+            mount: None,
+            variables: BTreeMap::new(),
+        }], &Settings::default())
+        .expect("load packages");
+
+        let template = crate::template::Template::built_in();
+        crate::site::build_site(
+            site.path(),
+            output.path(),
+            &crate::site::RenderOptions {
+                live_reload: false,
+                heartbeat: false,
+                template: &template,
+                filter: &PathFilter::default(),
+                csv_max_rows: None,
+                generated: &generated,
+                page_views: None,
+                pre_page_hook: None,
+                plugins: &crate::plugins::Plugins::default(),
+                settings: &Settings::default(),
+            },
+        )
+        .expect("build site");
+
+        let index = std::fs::read_to_string(output.path().join("policies/index.html")).expect("index");
+        assert!(index.contains("Local Policies"));
+        assert!(!index.contains("Shared Policies"));
+        let travel = std::fs::read_to_string(output.path().join("policies/travel.html")).expect("travel");
+        assert!(travel.contains("Local Policies</a>"));
+    }
+}
@@ -0,0 +1,85 @@
+use anyhow::{Context, Result};
+use std::path::{Component, Path, PathBuf};
+
+pub const PAGE_LIST_FILE: &str = "page-list.json";
+
+pub fn page_view_script(endpoint: &str, page: &Path) -> String {
+    let endpoint = js_string(endpoint);
+    let body = js_string(&serde_json::json!({ "path": url_path(page) }).to_string());
+    format!(
+        r#"<script>
+(function () {{
+  var endpoint = {endpoint};
+  var body = {body};
+  if (navigator.sendBeacon && navigator.sendBeacon(endpoint, body)) {{
+    return;
+  }}
+  if (window.fetch) {{
+    fetch(endpoint, {{ method: "POST", body: body, keepalive: true, mode: "no-cors" }}).catch(function () {{}});
+  }}
+}})();
+</script>"#
+    )
+}
+
+pub fn write_page_list(out: &Path, pages: &[PathBuf]) -> Result<PathBuf> {
+    let pages: Vec<String> = pages.iter().map(|page| url_path(page)).collect();
+    let json = serde_json::to_string_pretty(&serde_json::json!({ "pages": pages }))
+        .context("Failed to serialize page list")?;
+    let path = out.join(PAGE_LIST_FILE);
+    std::fs::write(&path, json).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+fn url_path(path: &Path) -> String {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn js_string(value: &str) -> String {
+    serde_json::Value::from(value).to_string().replace("</", "<\\/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn script_posts_site_relative_path() {
+        let script = page_view_script("https://count.example.com/hit", Path::new("guide/intro.html"));
+        assert!(script.contains(r#"var endpoint = "https://count.example.com/hit";"#));
+        assert!(script.contains(r#"var body = "{\"path\":\"guide/intro.html\"}";"#));
+    }
+
+    #[test]
+    fn writes_page_list_json() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let pages = vec![PathBuf::from("index.html"), PathBuf::from("guide/intro.html")];
+        let path = write_page_list(dir.path(), &pages).expect("page list");
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).expect("read")).expect("json");
+        assert_eq!(json["pages"], serde_json::json!(["index.html", "guide/intro.html"]));
+    }
+
+    #[test]
+    fn script_cannot_close_its_own_tag() {
+        let script = page_view_script("https://count.example.com/</script><b>", Path::new("a.html"));
+        assert_eq!(script.matches("</script>").count(), 1);
+        assert!(script.contains(r#""https://count.example.com/<\/script><b>""#));
+    }
+
+    #[test]
+    fn page_list_drops_dot_components() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let pages = vec![PathBuf::from("./guide/./intro.html")];
+        let path = write_page_list(dir.path(), &pages).expect("page list");
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).expect("read")).expect("json");
+        assert_eq!(json["pages"], serde_json::json!(["guide/intro.html"]));
+    }
+}
@@ -0,0 +1,314 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+#[derive(Default)]
+pub struct Plugins {
+    plugins: Vec<Plugin>,
+}
+
+impl Plugins {
+    pub fn load(paths: &[PathBuf]) -> Result<Self> {
+        let plugins = paths
+            .iter()
+            .map(|path| Plugin::load(path))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { plugins })
+    }
+
+    pub fn transform_markdown(&self, page: &Path, markdown: String) -> Result<String> {
+        let mut markdown = markdown;
+        for plugin in &self.plugins {
+            markdown = plugin.transform(MARKDOWN_EXPORT, page, markdown)?;
+        }
+        Ok(markdown)
+    }
+
+    pub fn transform_html(&self, page: &Path, html: String) -> Result<String> {
+        let mut html = html;
+        for plugin in &self.plugins {
+            html = plugin.transform(HTML_EXPORT, page, html)?;
+        }
+        Ok(html)
+    }
+}
+
+const MARKDOWN_EXPORT: &str = "rendar_transform_markdown";
+const HTML_EXPORT: &str = "rendar_transform_html";
+
+/// Limits for one call into a plugin.
+#[cfg(feature = "plugins")]
+#[derive(Clone, Copy)]
+struct Limits {
+    /// Roughly the number of WebAssembly instructions a call may run, so a
+    /// plugin stuck in a loop fails its page instead of hanging the build.
+    fuel: u64,
+    memory_bytes: usize,
+}
+
+#[cfg(feature = "plugins")]
+const LIMITS: Limits = Limits {
+    fuel: 2_000_000_000,
+    memory_bytes: 256 * 1024 * 1024,
+};
+
+/// A compiled plugin. Every call runs in a fresh instance, so memory
+/// handed out by `rendar_alloc` never needs to be freed and nothing
+/// carries over from one page to the next.
+#[cfg(feature = "plugins")]
+struct Plugin {
+    path: PathBuf,
+    engine: wasmtime::Engine,
+    module: wasmtime::Module,
+    limits: Limits,
+}
+
+#[cfg(feature = "plugins")]
+struct Runtime {
+    store: wasmtime::Store<wasmtime::StoreLimits>,
+    instance: wasmtime::Instance,
+    memory: wasmtime::Memory,
+    alloc: wasmtime::TypedFunc<i32, i32>,
+}
+
+#[cfg(feature = "plugins")]
+impl Plugin {
+    fn load(path: &Path) -> Result<Self> {
+        Self::load_with(path, LIMITS)
+    }
+
+    fn load_with(path: &Path, limits: Limits) -> Result<Self> {
+        use anyhow::Context;
+        use wasmtime::{Config, Engine, Module};
+
+        let engine = Engine::new(Config::new().consume_fuel(true)).map_err(anyhow::Error::from)?;
+        let module = Module::from_file(&engine, path)
+            .map_err(anyhow::Error::from)
+            .with_context(|| format!("Failed to load plugin {}", path.display()))?;
+        let plugin = Self {
+            path: path.to_path_buf(),
+            engine,
+            module,
+            limits,
+        };
+        // Reports missing exports and imports once, at startup.
+        plugin.instantiate()?;
+        Ok(plugin)
+    }
+
+    fn instantiate(&self) -> Result<Runtime> {
+        use anyhow::Context;
+        use wasmtime::{Instance, Store, StoreLimitsBuilder};
+
+        let path = &self.path;
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(self.limits.memory_bytes)
+            .instances(1)
+            .build();
+        let mut store = Store::new(&self.engine, limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(self.limits.fuel).map_err(anyhow::Error::from)?;
+        let instance = Instance::new(&mut store, &self.module, &[])
+            .map_err(anyhow::Error::from)
+            .with_context(|| {
+                format!(
+                    "Failed to instantiate plugin {} (plugins must not import anything)",
+                    path.display()
+                )
+            })?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .with_context(|| format!("Plugin {} does not export `memory`", path.display()))?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&mut store, "rendar_alloc")
+            .map_err(anyhow::Error::from)
+            .with_context(|| format!("Plugin {} does not export `rendar_alloc`", path.display()))?;
+        Ok(Runtime {
+            store,
+            instance,
+            memory,
+            alloc,
+        })
+    }
+
+    fn transform(&self, export: &str, page: &Path, input: String) -> Result<String> {
+        use anyhow::Context;
+
+        if self.module.get_export(export).is_none() {
+            return Ok(input);
+        }
+        let Runtime {
+            mut store,
+            instance,
+            memory,
+            alloc,
+        } = self.instantiate()?;
+        let context = || format!("Plugin {} failed in {export} for {}", self.path.display(), page.display());
+        let func = instance
+            .get_typed_func::<(i32, i32, i32, i32), i64>(&mut store, export)
+            .map_err(anyhow::Error::from)
+            .with_context(context)?;
+        let page_name = page.to_string_lossy().replace('\\', "/");
+        let (page_ptr, page_len) = write_bytes(&mut store, &memory, &alloc, page_name.as_bytes()).with_context(context)?;
+        let (input_ptr, input_len) = write_bytes(&mut store, &memory, &alloc, input.as_bytes()).with_context(context)?;
+        let result = func
+            .call(&mut store, (page_ptr, page_len, input_ptr, input_len))
+            .map_err(anyhow::Error::from)
+            .with_context(context)?;
+        if result < 0 {
+            return Ok(input);
+        }
+        let ptr = (result >> 32) as usize;
+        let len = (result & 0xFFFF_FFFF) as usize;
+        let mut output = vec![0u8; len];
+        memory.read(&store, ptr, &mut output).with_context(context)?;
+        String::from_utf8(output).with_context(|| format!("{} returned invalid UTF-8", context()))
+    }
+}
+
+#[cfg(feature = "plugins")]
+fn write_bytes(
+    store: &mut wasmtime::Store<wasmtime::StoreLimits>,
+    memory: &wasmtime::Memory,
+    alloc: &wasmtime::TypedFunc<i32, i32>,
+    bytes: &[u8],
+) -> Result<(i32, i32)> {
+    let len = i32::try_from(bytes.len())?;
+    let ptr = alloc.call(&mut *store, len)?;
+    memory.write(&mut *store, ptr as u32 as usize, bytes)?;
+    Ok((ptr, len))
+}
+
+#[cfg(not(feature = "plugins"))]
+enum Plugin {}
+
+#[cfg(not(feature = "plugins"))]
+impl Plugin {
+    fn load(path: &Path) -> Result<Self> {
+        Err(anyhow::anyhow!(
+            "Cannot load plugin {}: rendar was built without the `plugins` feature",
+            path.display()
+        ))
+    }
+
+    fn transform(&self, _export: &str, _page: &Path, _input: String) -> Result<String> {
+        match *self {}
+    }
+}
+
+#[cfg(all(test, feature = "plugins"))]
+mod tests {
+    use super::*;
+
+    const PLUGIN: &str = r#"(module
+  (memory (export "memory") 1)
+  (global $next (mut i32) (i32.const 1024))
+  (func (export "rendar_alloc") (param $size i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $next))
+    (global.set $next (i32.add (global.get $next) (local.get $size)))
+    (local.get $ptr))
+  (func (export "rendar_transform_markdown") (param i32 i32 i32 i32) (result i64)
+    (i64.const -1))
+  (func (export "rendar_transform_html") (param $page i32) (param $page_len i32) (param i32 i32) (result i64)
+    (i64.or
+      (i64.shl (i64.extend_i32_u (local.get $page)) (i64.const 32))
+      (i64.extend_i32_u (local.get $page_len)))))"#;
+
+    fn load_plugin() -> Plugins {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("echo.wat");
+        std::fs::write(&path, PLUGIN).expect("plugin");
+        Plugins::load(&[path]).expect("load plugin")
+    }
+
+    #[test]
+    fn keeps_input_when_plugin_declines() {
+        let plugins = load_plugin();
+        let markdown = plugins
+            .transform_markdown(Path::new("guide/intro.md"), "# Intro".to_string())
+            .expect("transform");
+        assert_eq!(markdown, "# Intro");
+    }
+
+    /// Bump allocator that grows memory as needed and never frees, and an
+    /// HTML transform that returns the page name.
+    const GROWING_PLUGIN: &str = r#"(module
+  (memory (export "memory") 1)
+  (global $next (mut i32) (i32.const 1024))
+  (func (export "rendar_alloc") (param $size i32) (result i32)
+    (local $ptr i32)
+    (local $end i32)
+    (local.set $ptr (global.get $next))
+    (local.set $end (i32.add (local.get $ptr) (local.get $size)))
+    (if (i32.gt_u (local.get $end) (i32.mul (memory.size) (i32.const 65536)))
+      (then
+        (if (i32.lt_s
+              (memory.grow (i32.add (i32.div_u (i32.sub (local.get $end) (i32.mul (memory.size) (i32.const 65536))) (i32.const 65536)) (i32.const 1)))
+              (i32.const 0))
+          (then unreachable))))
+    (global.set $next (local.get $end))
+    (local.get $ptr))
+  (func (export "rendar_transform_html") (param $page i32) (param $page_len i32) (param i32 i32) (result i64)
+    (i64.or
+      (i64.shl (i64.extend_i32_u (local.get $page)) (i64.const 32))
+      (i64.extend_i32_u (local.get $page_len)))))"#;
+
+    const LOOPING_PLUGIN: &str = r#"(module
+  (memory (export "memory") 1)
+  (func (export "rendar_alloc") (param i32) (result i32)
+    (i32.const 1024))
+  (func (export "rendar_transform_markdown") (param i32 i32 i32 i32) (result i64)
+    (loop $forever (br $forever))
+    (i64.const -1)))"#;
+
+    fn load_limited(source: &str, limits: Limits) -> Plugin {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("plugin.wat");
+        std::fs::write(&path, source).expect("plugin");
+        Plugin::load_with(&path, limits).expect("load plugin")
+    }
+
+    #[test]
+    fn stops_plugins_that_run_out_of_fuel() {
+        let plugin = load_limited(
+            LOOPING_PLUGIN,
+            Limits {
+                fuel: 1_000_000,
+                ..LIMITS
+            },
+        );
+        let err = plugin
+            .transform(MARKDOWN_EXPORT, Path::new("intro.md"), "# Intro".to_string())
+            .expect_err("out of fuel");
+        assert!(format!("{err:#}").contains("fuel"), "{err:#}");
+    }
+
+    #[test]
+    fn gives_every_call_fresh_memory() {
+        let plugin = load_limited(
+            GROWING_PLUGIN,
+            Limits {
+                memory_bytes: 4 * 1024 * 1024,
+                ..LIMITS
+            },
+        );
+        let page = "x".repeat(1024 * 1024);
+        for _ in 0..8 {
+            let html = plugin
+                .transform(HTML_EXPORT, Path::new("big.md"), page.clone())
+                .expect("transform");
+            assert_eq!(html, "big.md");
+        }
+        let too_big = "x".repeat(8 * 1024 * 1024);
+        assert!(plugin.transform(HTML_EXPORT, Path::new("huge.md"), too_big).is_err());
+    }
+
+    #[test]
+    fn reads_transformed_output_from_plugin_memory() {
+        let plugins = load_plugin();
+        let html = plugins
+            .transform_html(Path::new("guide/intro.md"), "<h1>Intro</h1>".to_string())
+            .expect("transform");
+        assert_eq!(html, "guide/intro.md");
+    }
+}
@@ -0,0 +1,142 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::process::Command;
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Runs external processes with a bounded number of concurrent workers and a
+/// per-process timeout. Successful runs with a cache key are remembered, so
+/// the same work is not repeated while the pool is alive.
+pub struct ProcessPool {
+    workers: usize,
+    timeout: Duration,
+    free_slots: Mutex<usize>,
+    slot_freed: Condvar,
+    completed: Mutex<HashSet<String>>,
+}
+
+struct Slot<'a>(&'a ProcessPool);
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut free) = self.0.free_slots.lock() {
+            *free += 1;
+        }
+        self.0.slot_freed.notify_one();
+    }
+}
+
+impl ProcessPool {
+    pub fn new(workers: usize, timeout: Duration) -> Self {
+        Self {
+            workers: workers.max(1),
+            timeout,
+            free_slots: Mutex::new(workers.max(1)),
+            slot_freed: Condvar::new(),
+            completed: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// How many processes may run at once.
+    pub fn workers(&self) -> usize {
+        self.workers
+    }
+
+    pub fn run(&self, label: &str, mut command: Command, cache_key: Option<&str>) -> Result<()> {
+        if let Some(key) = cache_key
+            && self.completed.lock().is_ok_and(|completed| completed.contains(key))
+        {
+            return Ok(());
+        }
+        let _slot = self.acquire()?;
+        let mut child = command
+            .spawn()
+            .with_context(|| format!("Failed to run {label}"))?;
+        let started = Instant::now();
+        let status = loop {
+            if let Some(status) = child
+                .try_wait()
+                .with_context(|| format!("Failed to wait for {label}"))?
+            {
+                break status;
+            }
+            if started.elapsed() >= self.timeout {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(anyhow::anyhow!(
+                    "{label} timed out after {}s",
+                    self.timeout.as_secs_f64()
+                ));
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        };
+        if !status.success() {
+            return Err(anyhow::anyhow!("{label} failed ({status})"));
+        }
+        if let Some(key) = cache_key
+            && let Ok(mut completed) = self.completed.lock()
+        {
+            completed.insert(key.to_string());
+        }
+        Ok(())
+    }
+
+    fn acquire(&self) -> Result<Slot<'_>> {
+        let poisoned = || anyhow::anyhow!("Process pool lock poisoned");
+        let mut free = self.free_slots.lock().map_err(|_| poisoned())?;
+        while *free == 0 {
+            free = self.slot_freed.wait(free).map_err(|_| poisoned())?;
+        }
+        *free -= 1;
+        Ok(Slot(self))
+    }
+}
+
+pub fn content_key(parts: &[&[u8]]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use super::*;
+
+    fn shell(script: &str) -> Command {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(script);
+        command
+    }
+
+    #[test]
+    fn skips_cached_runs() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let log = dir.path().join("runs.txt");
+        let script = format!("echo run >> '{}'", log.display());
+        let pool = ProcessPool::new(2, Duration::from_secs(10));
+        let key = content_key(&[script.as_bytes(), b"page"]);
+        pool.run("hook", shell(&script), Some(&key)).expect("first run");
+        pool.run("hook", shell(&script), Some(&key)).expect("cached run");
+        pool.run("hook", shell(&script), None).expect("uncached run");
+        assert_eq!(std::fs::read_to_string(log).expect("log"), "run\nrun\n");
+    }
+
+    #[test]
+    fn kills_processes_that_time_out() {
+        let pool = ProcessPool::new(1, Duration::from_millis(100));
+        let started = Instant::now();
+        let err = pool.run("hook", shell("sleep 5"), None).expect_err("timeout");
+        assert!(err.to_string().contains("timed out"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
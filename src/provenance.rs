@@ -0,0 +1,151 @@
+use crate::epub::format_utc;
+use crate::i18n::Locale;
+use crate::render::escape_html;
+use anyhow::Result;
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Items `[site] footer_metadata` can list, in the order given there.
+pub const FOOTER_ITEMS: &[&str] = &["generated_at", "rendar_version", "git_commit"];
+
+/// Where and when a site was built, for `{{generated_at}}`,
+/// `{{rendar_version}}`, `{{git_commit}}`, and the built-in footer.
+pub struct Provenance {
+    /// UTC, like `2026-03-01T09:30:00Z`.
+    pub generated_at: String,
+    pub rendar_version: &'static str,
+    /// Short hash of the input's `HEAD`; empty outside a git repository.
+    pub git_commit: String,
+}
+
+impl Default for Provenance {
+    /// No build time or commit, for renders outside a build.
+    fn default() -> Self {
+        Self {
+            generated_at: String::new(),
+            rendar_version: env!("CARGO_PKG_VERSION"),
+            git_commit: String::new(),
+        }
+    }
+}
+
+impl Provenance {
+    /// The build time and the commit checked out in `input`.
+    pub fn collect(input: &Path) -> Self {
+        Self {
+            generated_at: generated_at(),
+            rendar_version: env!("CARGO_PKG_VERSION"),
+            git_commit: git_commit(input).unwrap_or_default(),
+        }
+    }
+}
+
+/// Checks `[site] footer_metadata` against the known items.
+pub fn check_footer_metadata(items: &[String]) -> Result<()> {
+    if let Some(unknown) = items.iter().find(|item| !FOOTER_ITEMS.contains(&item.as_str())) {
+        return Err(anyhow::anyhow!(
+            "Unknown footer_metadata item {unknown}. Known items: {}",
+            FOOTER_ITEMS.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// The `{{site_footer}}` markup showing `items`, or an empty string when
+/// there are none.
+pub fn footer_html(items: &[String], provenance: &Provenance, locale: &Locale) -> String {
+    let parts: Vec<String> = items
+        .iter()
+        .filter_map(|item| match item.as_str() {
+            "generated_at" => Some(format!(
+                "<span class=\"generated-at\">{}</span>",
+                locale.text_with(
+                    "generated_at",
+                    &[(
+                        "time",
+                        &format!("<time datetime=\"{0}\">{0}</time>", escape_html(&provenance.generated_at)),
+                    )]
+                )
+            )),
+            "rendar_version" => Some(format!(
+                "<span class=\"rendar-version\">{}</span>",
+                locale.text_with("built_with", &[("version", provenance.rendar_version)])
+            )),
+            "git_commit" if !provenance.git_commit.is_empty() => Some(format!(
+                "<span class=\"git-commit\">{}</span>",
+                locale.text_with(
+                    "source_commit",
+                    &[("commit", &format!("<code>{}</code>", escape_html(&provenance.git_commit)))]
+                )
+            )),
+            _ => None,
+        })
+        .collect();
+    if parts.is_empty() {
+        return String::new();
+    }
+    format!("<footer class=\"site-footer\">{}</footer>", parts.join(" · "))
+}
+
+/// Now, or `SOURCE_DATE_EPOCH` when set, so reproducible builds produce
+/// identical pages.
+fn generated_at() -> String {
+    let secs = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        });
+    format_utc(secs)
+}
+
+fn git_commit(input: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .current_dir(input)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let commit = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!commit.is_empty()).then_some(commit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Provenance {
+        Provenance {
+            generated_at: "2026-03-01T09:30:00Z".to_string(),
+            rendar_version: "1.2.3",
+            git_commit: "3f9a2c1".to_string(),
+        }
+    }
+
+    #[test]
+    fn builds_footer_from_listed_items() {
+        let items = vec!["git_commit".to_string(), "generated_at".to_string()];
+        assert_eq!(
+            footer_html(&items, &sample(), &Locale::default()),
+            concat!(
+                "<footer class=\"site-footer\"><span class=\"git-commit\">Commit <code>3f9a2c1</code></span> · ",
+                "<span class=\"generated-at\">Generated <time datetime=\"2026-03-01T09:30:00Z\">2026-03-01T09:30:00Z</time></span></footer>"
+            )
+        );
+        let outside_git = Provenance {
+            git_commit: String::new(),
+            ..sample()
+        };
+        assert_eq!(footer_html(&["git_commit".to_string()], &outside_git, &Locale::default()), "");
+        assert_eq!(footer_html(&[], &sample(), &Locale::default()), "");
+    }
+
+    #[test]
+    fn rejects_unknown_footer_items() {
+        assert!(check_footer_metadata(&["commit".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,257 @@
+use crate::diagnostics::Diagnostic;
+use crate::render::escape_html;
+use crate::settings::Settings;
+use crate::site::{page_output_rel, relative_link};
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Netlify-style redirect list in the input root. Netlify and Cloudflare
+/// Pages both read a file with this name and format from the output root.
+pub const REDIRECTS_FILE: &str = "_redirects";
+/// `"/old/path/" = "/new/path/"` pairs in the input root.
+pub const REDIRECTS_TOML: &str = "redirects.toml";
+const DEFAULT_STATUS: u16 = 301;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redirect {
+    pub from: String,
+    pub to: String,
+    pub status: u16,
+}
+
+/// Redirect files are read by rendar rather than copied as assets.
+pub fn is_redirects_file(rel_path: &Path) -> bool {
+    rel_path == Path::new(REDIRECTS_FILE) || rel_path == Path::new(REDIRECTS_TOML)
+}
+
+pub fn load_redirects(input: &Path) -> Result<Vec<Redirect>> {
+    let mut redirects = Vec::new();
+    let path = input.join(REDIRECTS_FILE);
+    if path.is_file() {
+        let raw = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        redirects.extend(parse_redirects_file(&raw, &path)?);
+    }
+    let path = input.join(REDIRECTS_TOML);
+    if path.is_file() {
+        let raw = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let map: BTreeMap<String, String> =
+            toml::from_str(&raw).with_context(|| format!("Failed to parse {}", path.display()))?;
+        redirects.extend(map.into_iter().map(|(from, to)| Redirect {
+            from,
+            to,
+            status: DEFAULT_STATUS,
+        }));
+    }
+    Ok(redirects)
+}
+
+/// `from to [status]` per line, `#` starts a comment.
+fn parse_redirects_file(raw: &str, path: &Path) -> Result<Vec<Redirect>> {
+    let mut redirects = Vec::new();
+    for (idx, line) in raw.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let (from, to, status) = match parts.as_slice() {
+            [from, to] => (from, to, DEFAULT_STATUS),
+            [from, to, status, ..] => {
+                let status = status.trim_end_matches('!').parse().with_context(|| {
+                    format!("{}:{}: invalid redirect status {status}", path.display(), idx + 1)
+                })?;
+                (from, to, status)
+            }
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "{}:{}: expected `<from> <to> [status]`",
+                    path.display(),
+                    idx + 1
+                ));
+            }
+        };
+        redirects.push(Redirect {
+            from: from.to_string(),
+            to: to.to_string(),
+            status,
+        });
+    }
+    Ok(redirects)
+}
+
+/// Writes `_redirects` for the host and an HTML stub for every plain path,
+/// so the redirects also work on hosts that only serve files. Stubs never
+/// replace one of `pages`, the output paths of built pages.
+pub fn write_redirects(
+    input: &Path,
+    output: &Path,
+    redirects: &[Redirect],
+    pages: &HashSet<PathBuf>,
+    settings: &Settings,
+) -> Result<Vec<Diagnostic>> {
+    let source = if input.join(REDIRECTS_FILE).is_file() {
+        input.join(REDIRECTS_FILE)
+    } else {
+        input.join(REDIRECTS_TOML)
+    };
+    let mut diagnostics = Vec::new();
+    let mut host_file = String::new();
+    for redirect in redirects {
+        let to = site_url(&redirect.to, settings);
+        host_file.push_str(&format!("{} {} {}\n", site_url(&redirect.from, settings), to, redirect.status));
+        if is_pattern(&redirect.from) {
+            continue;
+        }
+        let Some(stub) = stub_path(&redirect.from, settings) else {
+            continue;
+        };
+        if !is_external(&redirect.to) && !to.contains('*') && !to.contains(':') {
+            let target = stub_path(&redirect.to, settings).unwrap_or_else(|| PathBuf::from("index.html"));
+            if !pages.contains(&target) && !output.join(&target).exists() {
+                diagnostics.push(Diagnostic::warning(
+                    "redirect-target-missing",
+                    &source,
+                    format!("Redirect target {} is not part of the site", redirect.to),
+                ));
+            }
+        }
+        if pages.contains(&stub) {
+            diagnostics.push(Diagnostic::warning(
+                "redirect-conflict",
+                &source,
+                format!("Redirect from {} is shadowed by a built page", redirect.from),
+            ));
+            continue;
+        }
+        let url = if is_external(&redirect.to) {
+            redirect.to.clone()
+        } else {
+            let from_dir = stub.parent().unwrap_or(Path::new(""));
+            let (path, fragment) = split_fragment(&to);
+            let target = stub_path(path, settings).unwrap_or_else(|| PathBuf::from("index.html"));
+            format!("{}{fragment}", relative_link(from_dir, &target, settings))
+        };
+        let stub_file = output.join(&stub);
+        if let Some(parent) = stub_file.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create output directory {}", parent.display()))?;
+        }
+        std::fs::write(&stub_file, stub_html(&url))
+            .with_context(|| format!("Failed to write redirect {}", stub_file.display()))?;
+    }
+    if !host_file.is_empty() {
+        let path = output.join(REDIRECTS_FILE);
+        std::fs::write(&path, host_file).with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(diagnostics)
+}
+
+pub fn stub_html(url: &str) -> String {
+    let url = escape_html(url);
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Redirecting…</title>\n<link rel=\"canonical\" href=\"{url}\">\n<meta http-equiv=\"refresh\" content=\"0; url={url}\">\n<meta name=\"robots\" content=\"noindex\">\n</head>\n<body>\n<p>This page has moved to <a href=\"{url}\">{url}</a>.</p>\n</body>\n</html>\n"
+    )
+}
+
+fn is_external(url: &str) -> bool {
+    url.contains("://")
+}
+
+/// Splats and `:placeholder` segments only work on the host.
+fn is_pattern(from: &str) -> bool {
+    from.contains('*') || from.split('/').any(|segment| segment.starts_with(':'))
+}
+
+fn split_fragment(url: &str) -> (&str, &str) {
+    match url.find('#') {
+        Some(idx) => url.split_at(idx),
+        None => (url, ""),
+    }
+}
+
+/// The URL a path is served under: root-relative, with source page names
+/// like `guide/setup.md` turned into the built `guide/setup.html`.
+fn site_url(path: &str, settings: &Settings) -> String {
+    if is_external(path) {
+        return path.to_string();
+    }
+    let (path, fragment) = split_fragment(path);
+    let trimmed = path.trim_start_matches('/');
+    let url = if settings.is_markdown(Path::new(trimmed)) {
+        page_output_rel(Path::new(trimmed), settings).to_string_lossy().replace('\\', "/")
+    } else {
+        trimmed.to_string()
+    };
+    format!("/{url}{fragment}")
+}
+
+/// The output file that answers `path`: `dir/` and extensionless paths get
+/// an `index.html`, Markdown paths their built page.
+fn stub_path(path: &str, settings: &Settings) -> Option<PathBuf> {
+    let (path, _) = split_fragment(path);
+    let trimmed = path.trim_start_matches('/');
+    let rel = PathBuf::from(trimmed);
+    if rel.components().any(|component| !matches!(component, std::path::Component::Normal(_))) {
+        return None;
+    }
+    if trimmed.is_empty() || trimmed.ends_with('/') || rel.extension().is_none() {
+        return Some(rel.join("index.html"));
+    }
+    if settings.is_markdown(&rel) {
+        return Some(page_output_rel(&rel, settings));
+    }
+    Some(rel)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_redirect_files() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(
+            dir.path().join(REDIRECTS_FILE),
+            "# moved in 2.0\n/old-guide/ /guide/\n/blog/* https://blog.example.com/:splat 302\n",
+        )
+        .expect("redirects");
+        std::fs::write(dir.path().join(REDIRECTS_TOML), "\"setup.md\" = \"guide/install.md#linux\"\n").expect("toml");
+
+        let redirects = load_redirects(dir.path()).expect("load");
+        assert_eq!(redirects.len(), 3);
+        assert_eq!(redirects[1].status, 302);
+        assert_eq!(redirects[2].to, "guide/install.md#linux");
+
+        std::fs::write(dir.path().join(REDIRECTS_FILE), "/only-one-path\n").expect("bad");
+        let err = load_redirects(dir.path()).expect_err("missing target");
+        assert!(err.to_string().contains("_redirects:1"));
+    }
+
+    #[test]
+    fn writes_stubs_and_host_file() {
+        let input = tempfile::tempdir().expect("input");
+        let output = tempfile::tempdir().expect("output");
+        let pages: HashSet<PathBuf> = ["guide/install.html", "index.html"].into_iter().map(PathBuf::from).collect();
+        let redirects = vec![
+            Redirect { from: "/old/setup.md".to_string(), to: "/guide/install.md#linux".to_string(), status: 301 },
+            Redirect { from: "/docs/*".to_string(), to: "/:splat".to_string(), status: 301 },
+            Redirect { from: "/".to_string(), to: "/guide/install.html".to_string(), status: 302 },
+            Redirect { from: "/gone".to_string(), to: "/missing/".to_string(), status: 301 },
+        ];
+
+        let diagnostics = write_redirects(input.path(), output.path(), &redirects, &pages, &Settings::default()).expect("write");
+        let stub = std::fs::read_to_string(output.path().join("old/setup.html")).expect("stub");
+        assert!(stub.contains(r#"content="0; url=../guide/install.html#linux""#));
+        assert!(output.path().join("gone/index.html").exists());
+        let host = std::fs::read_to_string(output.path().join(REDIRECTS_FILE)).expect("host file");
+        assert_eq!(
+            host,
+            "/old/setup.html /guide/install.html#linux 301\n/docs/* /:splat 301\n/ /guide/install.html 302\n/gone /missing/ 301\n"
+        );
+        let rules: Vec<&str> = diagnostics.iter().map(|diagnostic| diagnostic.rule).collect();
+        assert_eq!(rules, ["redirect-conflict", "redirect-target-missing"]);
+    }
+}
@@ -1,11 +1,40 @@
 use anyhow::{Context, Result};
-use pulldown_cmark::{html, CowStr, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use crate::diagnostics::Diagnostic;
+use crate::i18n::Locale;
+use crate::obsidian;
+use crate::sanitize::sanitize_html;
+use crate::settings::Settings;
+use crate::links::resolve_local;
+use crate::site::{link_target_exists, site_rel_path, source_file, strip_number_prefix};
+use pulldown_cmark::{html, CodeBlockKind, CowStr, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 pub struct RenderedPage {
     pub html: String,
-    pub warnings: Vec<String>,
+    pub diagnostics: Vec<Diagnostic>,
     pub mode: DocMode,
+    pub noindex: bool,
+    pub lightbox: Option<bool>,
+    pub stats: PageStats,
+    /// `<link>` tags for the front matter's `css` list.
+    pub extra_head: Option<String>,
+    /// `<script>` tags for the front matter's `js` list.
+    pub extra_body: Option<String>,
+}
+
+const WORDS_PER_MINUTE: usize = 200;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PageStats {
+    pub words: usize,
+    pub images: usize,
+}
+
+impl PageStats {
+    pub fn reading_minutes(&self) -> usize {
+        self.words.div_ceil(WORDS_PER_MINUTE).max(1)
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -24,6 +53,50 @@ impl FrontMatter {
     fn is_slides(&self) -> bool {
         matches!(self.mode.as_deref(), Some("slides"))
     }
+
+    fn is_noindex(&self) -> bool {
+        self.entries.iter().any(|(key, value)| {
+            key == "noindex" && matches!(value.to_ascii_lowercase().as_str(), "true" | "yes")
+        })
+    }
+
+    fn lightbox(&self) -> Option<bool> {
+        self.flag("lightbox")
+    }
+
+    fn number_headings(&self, settings: &Settings) -> bool {
+        self.flag("number_headings").unwrap_or(settings.number_headings)
+    }
+
+    /// A list value, written `[a.css, b.css]` or as a single item.
+    fn list(&self, name: &str) -> Vec<String> {
+        let Some((_, value)) = self.entries.iter().find(|(key, _)| key == name) else {
+            return Vec::new();
+        };
+        let value = value.trim();
+        let value = value.strip_prefix('[').and_then(|value| value.strip_suffix(']')).unwrap_or(value);
+        value
+            .split(',')
+            .map(|item| item.trim().trim_matches(&['"', '\''][..]).to_string())
+            .filter(|item| !item.is_empty())
+            .collect()
+    }
+
+    fn flag(&self, name: &str) -> Option<bool> {
+        let (_, value) = self.entries.iter().find(|(key, _)| key == name)?;
+        match value.to_ascii_lowercase().as_str() {
+            "true" | "yes" => Some(true),
+            "false" | "no" => Some(false),
+            _ => None,
+        }
+    }
+}
+
+/// The title of a page: front matter `title`, or else its first heading.
+pub fn page_title(markdown: &str) -> Option<String> {
+    front_matter_value(markdown, "title")
+        .filter(|title| !title.trim().is_empty())
+        .or_else(|| first_heading_title(markdown))
 }
 
 pub fn first_heading_title(markdown: &str) -> Option<String> {
@@ -57,41 +130,187 @@ pub fn first_heading_title(markdown: &str) -> Option<String> {
     None
 }
 
+pub fn front_matter_value(markdown: &str, key: &str) -> Option<String> {
+    let (front_matter, _) = parse_front_matter(markdown);
+    front_matter
+        .entries
+        .into_iter()
+        .find(|(entry_key, _)| entry_key == key)
+        .map(|(_, value)| value)
+}
+
+/// All `key: value` lines of the front matter, in order.
+pub fn front_matter_entries(markdown: &str) -> Vec<(String, String)> {
+    parse_front_matter(markdown).0.entries
+}
+
+pub fn strip_front_matter(markdown: &str) -> &str {
+    parse_front_matter(markdown).1
+}
+
 pub fn render_markdown_file(
     path: &Path,
     input_root: &Path,
     index_dirs: &std::collections::HashSet<PathBuf>,
+    settings: &Settings,
 ) -> Result<RenderedPage> {
     let markdown = std::fs::read_to_string(path)
         .with_context(|| format!("Failed to read markdown file {}", path.display()))?;
-    let (front_matter, content) = parse_front_matter(&markdown);
+    Ok(render_markdown_source(&markdown, path, input_root, index_dirs, settings))
+}
+
+pub fn render_markdown_source(
+    markdown: &str,
+    path: &Path,
+    input_root: &Path,
+    index_dirs: &std::collections::HashSet<PathBuf>,
+    settings: &Settings,
+) -> RenderedPage {
+    let (front_matter, content) = parse_front_matter(markdown);
+    let noindex = front_matter.is_noindex();
+    let lightbox = front_matter.lightbox();
+    let stats = page_stats(content);
+    let line_offset = markdown[..markdown.len() - content.len()].matches('\n').count();
+    let mut asset_warnings = Vec::new();
+    let (extra_head, extra_body) =
+        page_assets(&front_matter, markdown, path, input_root, index_dirs, &mut asset_warnings, settings);
     if front_matter.is_slides() {
-        let (html, warnings) = markdown_to_slides_with_rewrites(
+        let (html, mut warnings) = markdown_to_slides_with_rewrites(
             content,
+            line_offset,
             path,
             input_root,
             index_dirs,
             None,
+            settings,
         );
-        Ok(RenderedPage {
+        warnings.splice(0..0, asset_warnings);
+        RenderedPage {
             html,
-            warnings,
+            diagnostics: warnings,
             mode: DocMode::Slides,
-        })
+            noindex,
+            lightbox,
+            stats,
+            extra_head,
+            extra_body,
+        }
     } else {
         let front_matter_table = front_matter_table_html(&front_matter);
-        let (html, warnings) = markdown_to_html_with_rewrites(content, path, input_root, index_dirs);
+        let (html, mut warnings) = markdown_to_html_with_rewrites(
+            content,
+            line_offset,
+            path,
+            input_root,
+            index_dirs,
+            front_matter.number_headings(settings),
+            settings,
+        );
+        warnings.splice(0..0, asset_warnings);
         let html = rewrite_mermaid_blocks(&html);
         let html = if let Some(table_html) = front_matter_table {
             format!("{table_html}{html}")
         } else {
             html
         };
-        Ok(RenderedPage {
+        RenderedPage {
             html,
-            warnings,
+            diagnostics: warnings,
             mode: DocMode::Document,
-        })
+            noindex,
+            lightbox,
+            stats,
+            extra_head,
+            extra_body,
+        }
+    }
+}
+
+/// Stylesheet links and scripts for the front matter's `css` and `js`
+/// lists, so demo pages can bring their own. Paths resolve like links from
+/// the page, and missing files are reported on the key's line.
+fn page_assets(
+    front_matter: &FrontMatter,
+    markdown: &str,
+    path: &Path,
+    input_root: &Path,
+    index_dirs: &std::collections::HashSet<PathBuf>,
+    warnings: &mut Vec<Diagnostic>,
+    settings: &Settings,
+) -> (Option<String>, Option<String>) {
+    let mut tags = |key: &str, tag: fn(&str) -> String| {
+        let items = front_matter.list(key);
+        if items.is_empty() {
+            return None;
+        }
+        let prefix = format!("{key}:");
+        let offset = markdown
+            .split_inclusive('\n')
+            .scan(0, |start, line| {
+                let line_start = *start;
+                *start += line.len();
+                Some((line_start, line))
+            })
+            .find(|(_, line)| line.trim_start().starts_with(&prefix))
+            .map_or(0, |(line_start, _)| line_start);
+        let location = SourceLocation::at(markdown, offset, 0);
+        let html: String = items
+            .into_iter()
+            .map(|item| {
+                let href = rewrite_link_dest(
+                    item.into(),
+                    LinkKind::Asset,
+                    path,
+                    location,
+                    input_root,
+                    index_dirs,
+                    warnings,
+                    settings,
+                );
+                tag(&escape_html(&href))
+            })
+            .collect();
+        Some(html)
+    };
+    let head = tags("css", |href| format!("<link rel=\"stylesheet\" href=\"{href}\">"));
+    let body = tags("js", |src| format!("<script src=\"{src}\"></script>"));
+    (head, body)
+}
+
+fn page_stats(markdown: &str) -> PageStats {
+    let mut images = 0;
+    let mut text = String::new();
+    let mut in_code_block = false;
+    let mut image_depth = 0usize;
+    for event in Parser::new_ext(markdown, markdown_options(false)) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            Event::Start(Tag::Image { .. }) => {
+                images += 1;
+                image_depth += 1;
+            }
+            Event::End(TagEnd::Image) => image_depth = image_depth.saturating_sub(1),
+            Event::Text(value) | Event::Code(value) if !in_code_block && image_depth == 0 => {
+                text.push_str(&value);
+            }
+            Event::SoftBreak
+            | Event::HardBreak
+            | Event::End(
+                TagEnd::Paragraph
+                | TagEnd::Heading(_)
+                | TagEnd::Item
+                | TagEnd::TableCell
+                | TagEnd::FootnoteDefinition
+                | TagEnd::DefinitionListTitle
+                | TagEnd::DefinitionListDefinition,
+            ) => text.push(' '),
+            _ => {}
+        }
+    }
+    PageStats {
+        words: text.split_whitespace().count(),
+        images,
     }
 }
 
@@ -147,7 +366,7 @@ fn parse_front_matter(markdown: &str) -> (FrontMatter, &str) {
     (front_matter, &markdown[end_offset..])
 }
 
-fn escape_html(input: &str) -> String {
+pub fn escape_html(input: &str) -> String {
     let mut escaped = String::with_capacity(input.len());
     for ch in input.chars() {
         match ch {
@@ -210,69 +429,79 @@ fn rewrite_mermaid_blocks(html: &str) -> String {
 
 fn markdown_to_html_with_rewrites(
     markdown: &str,
+    line_offset: usize,
     source_path: &Path,
     input_root: &Path,
     index_dirs: &std::collections::HashSet<PathBuf>,
-) -> (String, Vec<String>) {
+    number_headings: bool,
+    settings: &Settings,
+) -> (String, Vec<Diagnostic>) {
     let options = markdown_options(false);
     let mut warnings = Vec::new();
-    let parser = Parser::new_ext(markdown, options).map(|event| match event {
-        Event::Start(Tag::Link {
-            link_type,
-            dest_url,
-            title,
-            id,
-        }) => Event::Start(Tag::Link {
-            link_type,
-            dest_url: rewrite_link_dest(
-                dest_url,
-                source_path,
-                input_root,
-                index_dirs,
-                &mut warnings,
-            ),
-            title,
-            id,
-        }),
-        _ => event,
-    });
-    let parser = assign_heading_ids(parser);
+    let markdown = obsidian::convert(markdown, source_path, input_root, settings);
+    let markdown = mark_containers(&markdown);
+    let parser = rewrite_links(
+        &markdown,
+        options,
+        line_offset,
+        source_path,
+        input_root,
+        index_dirs,
+        settings.source_lines,
+        &mut warnings,
+        settings,
+    );
+    let mut parser = assign_heading_ids(parser);
+    if settings.strip_title_heading {
+        strip_title_heading(&mut parser);
+    }
+    let parser = if number_headings {
+        number_heading_events(parser)
+    } else {
+        parser
+    };
+    let parser = group_code_tabs(parser);
+    let parser = render_containers(parser);
+    let parser = decorate_code_blocks(parser);
+    let parser = link_footnotes(parser, &settings.locale);
 
     let mut html_output = String::new();
     html::push_html(&mut html_output, parser.into_iter());
+    if settings.sanitize_html {
+        html_output = sanitize_html(&html_output);
+    }
     (html_output, warnings)
 }
 
 fn markdown_to_slides_with_rewrites(
     markdown: &str,
+    line_offset: usize,
     source_path: &Path,
     input_root: &Path,
     index_dirs: &std::collections::HashSet<PathBuf>,
     front_matter_table: Option<&str>,
-) -> (String, Vec<String>) {
+    settings: &Settings,
+) -> (String, Vec<Diagnostic>) {
     let options = markdown_options(false);
     let mut warnings = Vec::new();
-    let parser = Parser::new_ext(markdown, options).map(|event| match event {
-        Event::Start(Tag::Link {
-            link_type,
-            dest_url,
-            title,
-            id,
-        }) => Event::Start(Tag::Link {
-            link_type,
-            dest_url: rewrite_link_dest(
-                dest_url,
-                source_path,
-                input_root,
-                index_dirs,
-                &mut warnings,
-            ),
-            title,
-            id,
-        }),
-        _ => event,
-    });
+    let markdown = obsidian::convert(markdown, source_path, input_root, settings);
+    let markdown = mark_containers(&markdown);
+    let parser = rewrite_links(
+        &markdown,
+        options,
+        line_offset,
+        source_path,
+        input_root,
+        index_dirs,
+        false,
+        &mut warnings,
+        settings,
+    );
     let parser = assign_heading_ids(parser);
+    let parser = group_code_tabs(parser);
+    let parser = render_containers(parser);
+    let parser = decorate_code_blocks(parser);
+    let parser = link_footnotes(parser, &settings.locale);
 
     let mut slides: Vec<Vec<Event>> = Vec::new();
     let mut current: Vec<Event> = Vec::new();
@@ -287,7 +516,7 @@ fn markdown_to_slides_with_rewrites(
             }) => {
                 if !seen_h1 {
                     seen_h1 = true;
-                    current.extend(pending.drain(..));
+                    current.append(&mut pending);
                 } else if !current.is_empty() {
                     slides.push(current);
                     current = Vec::new();
@@ -339,7 +568,10 @@ fn markdown_to_slides_with_rewrites(
     for (idx, events) in slides.into_iter().enumerate() {
         let mut slide_html = String::new();
         html::push_html(&mut slide_html, events.into_iter());
-        let slide_html = rewrite_mermaid_blocks(&slide_html);
+        let mut slide_html = rewrite_mermaid_blocks(&slide_html);
+        if settings.sanitize_html {
+            slide_html = sanitize_html(&slide_html);
+        }
         let slide_index = idx + slide_offset;
         let active_class = if slide_index == 0 { " is-active" } else { "" };
         let hidden_attr = if slide_index == 0 {
@@ -394,6 +626,469 @@ fn assign_heading_ids<'a>(parser: impl IntoIterator<Item = Event<'a>>) -> Vec<Ev
     events
 }
 
+/// Removes the H1 that opens the page, if it does.
+fn strip_title_heading(events: &mut Vec<Event<'_>>) {
+    if matches!(events.first(), Some(Event::Start(Tag::Heading { level: HeadingLevel::H1, .. })))
+        && let Some(end) = heading_end_index(events, 1)
+    {
+        events.drain(..=end);
+    }
+}
+
+/// Prefixes headings with their hierarchical number. A page whose only H1
+/// comes first keeps it as the title and numbers from H2 down. Runs after
+/// `assign_heading_ids`, so anchors don't change when sections move.
+fn number_heading_events<'a>(events: Vec<Event<'a>>) -> Vec<Event<'a>> {
+    let levels: Vec<usize> = events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Start(Tag::Heading { level, .. }) => Some(*level as usize),
+            _ => None,
+        })
+        .collect();
+    let title_h1 = levels.first() == Some(&1) && levels.iter().filter(|level| **level == 1).count() == 1;
+    let top = if title_h1 {
+        levels.iter().copied().filter(|level| *level > 1).min()
+    } else {
+        levels.iter().copied().min()
+    };
+    let Some(top) = top else {
+        return events;
+    };
+    let mut counters = [0usize; 6];
+    let mut output = Vec::with_capacity(events.len() + levels.len());
+    for event in events {
+        let level = match &event {
+            Event::Start(Tag::Heading { level, .. }) if *level as usize >= top => *level as usize,
+            _ => {
+                output.push(event);
+                continue;
+            }
+        };
+        counters[level - 1] += 1;
+        counters[level..].fill(0);
+        let number = heading_number(&counters[top - 1..level]);
+        output.push(event);
+        output.push(Event::InlineHtml(CowStr::from(format!(
+            r#"<span class="heading-number">{number}</span> "#
+        ))));
+    }
+    output
+}
+
+/// `1.` for top-level sections, `1.2.3` below them.
+fn heading_number(counters: &[usize]) -> String {
+    let number = counters.iter().map(usize::to_string).collect::<Vec<_>>().join(".");
+    if counters.len() == 1 {
+        format!("{number}.")
+    } else {
+        number
+    }
+}
+
+struct CodeFence {
+    lang: String,
+    title: Option<String>,
+    tab: Option<String>,
+    highlight: Vec<(usize, usize)>,
+}
+
+/// Parses fence info like `rust title="main.rs" tab="Rust" {3-5,8}`.
+fn parse_code_fence(info: &str) -> CodeFence {
+    let mut fence = CodeFence {
+        lang: String::new(),
+        title: None,
+        tab: None,
+        highlight: Vec::new(),
+    };
+    let mut rest = info.trim();
+    while !rest.is_empty() {
+        if let Some((key, value)) = rest.split_once('=')
+            && !key.is_empty()
+            && key.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+        {
+            let (value, remaining) = match value.strip_prefix(['"', '\'']) {
+                Some(quoted) => {
+                    let quote = value.as_bytes()[0] as char;
+                    let end = quoted.find(quote).unwrap_or(quoted.len());
+                    (&quoted[..end], quoted.get(end + 1..).unwrap_or(""))
+                }
+                None => value.split_at(value.find(char::is_whitespace).unwrap_or(value.len())),
+            };
+            match key {
+                "title" => fence.title = Some(value.to_string()),
+                "tab" => fence.tab = Some(value.to_string()),
+                _ => {}
+            }
+            rest = remaining.trim_start();
+            continue;
+        }
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let (token, remaining) = rest.split_at(end);
+        if let Some(ranges) = token.strip_prefix('{').and_then(|token| token.strip_suffix('}')) {
+            fence.highlight.extend(ranges.split(',').filter_map(parse_line_range));
+        } else if fence.lang.is_empty() {
+            fence.lang = token.to_string();
+        }
+        rest = remaining.trim_start();
+    }
+    fence
+}
+
+fn parse_line_range(range: &str) -> Option<(usize, usize)> {
+    let range = range.trim();
+    match range.split_once('-') {
+        Some((start, end)) => Some((start.trim().parse().ok()?, end.trim().parse().ok()?)),
+        None => range.parse().ok().map(|line| (line, line)),
+    }
+}
+
+/// Renders fenced code blocks with a `title` or `{lines}` in their info string
+/// as a titled block with highlighted lines; other blocks are left as-is.
+fn decorate_code_blocks<'a>(events: impl IntoIterator<Item = Event<'a>>) -> Vec<Event<'a>> {
+    let mut output = Vec::new();
+    let mut open: Option<(CodeFence, String)> = None;
+    for event in events {
+        match (&mut open, event) {
+            (None, Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info)))) => {
+                let fence = parse_code_fence(&info);
+                if fence.title.is_some() || !fence.highlight.is_empty() {
+                    open = Some((fence, String::new()));
+                } else {
+                    output.push(Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))));
+                }
+            }
+            (Some((_, code)), Event::Text(text)) => code.push_str(&text),
+            (Some(_), Event::End(TagEnd::CodeBlock)) => {
+                if let Some((fence, code)) = open.take() {
+                    output.push(Event::Html(CowStr::from(code_block_html(&fence, &code))));
+                }
+            }
+            (_, event) => output.push(event),
+        }
+    }
+    output
+}
+
+/// Gives footnote references ids and ends each footnote with links back to
+/// them, so readers can return to where they were. Footnotes are numbered
+/// in order of first appearance, like pulldown-cmark's own output.
+fn link_footnotes<'a>(events: Vec<Event<'a>>, locale: &Locale) -> Vec<Event<'a>> {
+    let mut references: HashMap<String, usize> = HashMap::new();
+    for event in &events {
+        if let Event::FootnoteReference(name) = event {
+            *references.entry(name.to_string()).or_default() += 1;
+        }
+    }
+    if references.is_empty() {
+        return events;
+    }
+    let mut numbers: HashMap<String, usize> = HashMap::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut open: Option<(String, usize)> = None;
+    let mut output = Vec::with_capacity(events.len());
+    for event in events {
+        match event {
+            Event::FootnoteReference(name) => {
+                let next = numbers.len() + 1;
+                let number = *numbers.entry(name.to_string()).or_insert(next);
+                let nth = seen.entry(name.to_string()).or_default();
+                *nth += 1;
+                let id = escape_html(&name);
+                output.push(Event::InlineHtml(
+                    format!(
+                        "<sup class=\"footnote-reference\" id=\"{}\"><a href=\"#{id}\">{number}</a></sup>",
+                        footnote_reference_id(&id, *nth)
+                    )
+                    .into(),
+                ));
+            }
+            Event::Start(Tag::FootnoteDefinition(name)) => {
+                let next = numbers.len() + 1;
+                let number = *numbers.entry(name.to_string()).or_insert(next);
+                output.push(Event::Html(
+                    format!(
+                        "<div class=\"footnote-definition\" id=\"{}\"><sup class=\"footnote-definition-label\">{number}</sup>",
+                        escape_html(&name)
+                    )
+                    .into(),
+                ));
+                open = Some((name.to_string(), number));
+            }
+            Event::End(TagEnd::FootnoteDefinition) => {
+                let backrefs = open
+                    .take()
+                    .map(|(name, number)| {
+                        let count = references.get(&name).copied().unwrap_or(0);
+                        footnote_backrefs(&escape_html(&name), number, count, locale)
+                    })
+                    .unwrap_or_default();
+                // Inside the last paragraph, so the links follow its text.
+                if let Some(Event::End(TagEnd::Paragraph)) = output.last() {
+                    let end = output.pop();
+                    output.push(Event::InlineHtml(backrefs.into()));
+                    output.extend(end);
+                } else {
+                    output.push(Event::Html(backrefs.into()));
+                }
+                output.push(Event::Html("</div>\n".into()));
+            }
+            event => output.push(event),
+        }
+    }
+    output
+}
+
+/// The id of the `nth` reference to the footnote with id `id`.
+fn footnote_reference_id(id: &str, nth: usize) -> String {
+    if nth == 1 {
+        format!("fnref-{id}")
+    } else {
+        format!("fnref-{id}-{nth}")
+    }
+}
+
+/// `↩` links from footnote `number` back to each of its `count` references.
+fn footnote_backrefs(id: &str, number: usize, count: usize, locale: &Locale) -> String {
+    (1..=count)
+        .map(|nth| {
+            let (mark, reference) = if nth == 1 {
+                ("↩".to_string(), number.to_string())
+            } else {
+                (format!("↩<sup>{nth}</sup>"), format!("{number} ({nth})"))
+            };
+            format!(
+                " <a href=\"#{}\" class=\"footnote-backref\" aria-label=\"{}\">{mark}</a>",
+                footnote_reference_id(id, nth),
+                escape_html(&locale.text_with("footnote_backref", &[("number", &reference)]))
+            )
+        })
+        .collect()
+}
+
+const TABS_OPEN: &str = ":::tabs";
+const CONTAINER_CLOSE: &str = ":::";
+const CONTAINER_MARK: &str = "<!--rendar:";
+/// Container kinds that get their name as a title when they have none.
+const CALLOUTS: &[&str] = &["note", "tip", "info", "important", "warning", "caution", "danger"];
+
+/// Turns `:::` lines outside fenced code into one-line HTML comments. The
+/// parser ends paragraphs and lists at HTML blocks, so markers work without
+/// blank lines around them, and line numbers stay put for diagnostics.
+fn mark_containers(markdown: &str) -> std::borrow::Cow<'_, str> {
+    if !markdown.contains(CONTAINER_CLOSE) {
+        return std::borrow::Cow::Borrowed(markdown);
+    }
+    let mut output = String::with_capacity(markdown.len() + 64);
+    let mut fence: Option<(char, usize)> = None;
+    for line in markdown.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\r', '\n']);
+        let trimmed = content.trim_start();
+        let indent = content.len() - trimmed.len();
+        let fence_char = trimmed.chars().next().filter(|ch| matches!(ch, '`' | '~'));
+        let fence_len = fence_char.map_or(0, |ch| trimmed.chars().take_while(|c| *c == ch).count());
+        match (fence, fence_char) {
+            (None, Some(ch)) if indent < 4 && fence_len >= 3 => fence = Some((ch, fence_len)),
+            (Some((open, len)), Some(ch))
+                if ch == open && fence_len >= len && trimmed[fence_len..].trim().is_empty() =>
+            {
+                fence = None
+            }
+            (None, _) if indent < 4 && trimmed.starts_with(CONTAINER_CLOSE) => {
+                output.push_str(&content[..indent]);
+                output.push_str(CONTAINER_MARK);
+                output.push_str(&trimmed.trim_end().replace("--", "-\u{2010}"));
+                output.push_str("-->");
+                output.push_str(&line[content.len()..]);
+                continue;
+            }
+            _ => {}
+        }
+        output.push_str(line);
+    }
+    std::borrow::Cow::Owned(output)
+}
+
+/// Renders `::: name Title` ... `:::` containers as
+/// `<div class="container name">` with an optional title; `::: details`
+/// becomes a collapsible `<details>`. Containers nest, each `:::` closing
+/// the innermost one, and unclosed ones end with the page.
+fn render_containers<'a>(events: impl IntoIterator<Item = Event<'a>>) -> Vec<Event<'a>> {
+    let events: Vec<Event<'a>> = events.into_iter().collect();
+    let mut output = Vec::with_capacity(events.len());
+    let mut open: Vec<&'static str> = Vec::new();
+    let mut idx = 0;
+    while idx < events.len() {
+        let Some(marker) = container_marker(&events, idx) else {
+            output.push(events[idx].clone());
+            idx += 1;
+            continue;
+        };
+        let spec = marker.trim_start_matches(':').trim();
+        if spec.is_empty() {
+            if let Some(close) = open.pop() {
+                output.push(Event::Html(CowStr::from(close)));
+            }
+        } else {
+            let (name, title) = spec.split_once(char::is_whitespace).unwrap_or((spec, ""));
+            let (open_html, close) = container_html(name, title.trim());
+            output.push(Event::Html(CowStr::from(open_html)));
+            open.push(close);
+        }
+        idx += 3;
+    }
+    while let Some(close) = open.pop() {
+        output.push(Event::Html(CowStr::from(close)));
+    }
+    output
+}
+
+fn container_html(name: &str, title: &str) -> (String, &'static str) {
+    let class: String = name
+        .chars()
+        .filter(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_'))
+        .collect::<String>()
+        .to_ascii_lowercase();
+    if class == "details" {
+        let summary = if title.is_empty() { "Details" } else { title };
+        return (
+            format!(
+                "<details class=\"container details\"><summary>{}</summary>\n",
+                escape_html(summary)
+            ),
+            "</details>\n",
+        );
+    }
+    let title = if title.is_empty() && CALLOUTS.contains(&class.as_str()) {
+        let mut chars = class.chars();
+        chars
+            .next()
+            .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+            .unwrap_or_default()
+    } else {
+        title.to_string()
+    };
+    let mut html = format!("<div class=\"container {class}\">");
+    if !title.is_empty() {
+        html.push_str(&format!("<p class=\"container-title\">{}</p>", escape_html(&title)));
+    }
+    html.push('\n');
+    (html, "</div>\n")
+}
+
+/// Groups the fenced code blocks between `:::tabs` and `:::` paragraphs into
+/// a tabbed widget labelled by each fence's `tab`, `title`, or language.
+fn group_code_tabs<'a>(events: impl IntoIterator<Item = Event<'a>>) -> Vec<Event<'a>> {
+    let events: Vec<Event<'a>> = events.into_iter().collect();
+    let mut output = Vec::with_capacity(events.len());
+    let mut idx = 0;
+    while idx < events.len() {
+        if container_marker(&events, idx).is_some_and(|marker| marker.replace(' ', "") == TABS_OPEN)
+            && let Some((tabs, end)) = collect_tabs(&events, idx + 3)
+        {
+            let mut bar = String::from("<div class=\"code-tabs\"><div class=\"code-tabs-bar\" role=\"tablist\">");
+            for (label, _) in &tabs {
+                bar.push_str(&format!(
+                    "<button type=\"button\" role=\"tab\" data-tab=\"{0}\">{0}</button>",
+                    escape_html(label)
+                ));
+            }
+            bar.push_str("</div>\n");
+            output.push(Event::Html(CowStr::from(bar)));
+            for (label, range) in tabs {
+                output.push(Event::Html(CowStr::from(format!(
+                    "<div class=\"code-tab\" role=\"tabpanel\" data-tab=\"{}\">\n",
+                    escape_html(&label)
+                ))));
+                output.extend(events[range].iter().cloned());
+                output.push(Event::Html(CowStr::from("</div>\n")));
+            }
+            output.push(Event::Html(CowStr::from("</div>\n")));
+            idx = end;
+            continue;
+        }
+        output.push(events[idx].clone());
+        idx += 1;
+    }
+    output
+}
+
+/// Returns the `:::` line of a marker that `mark_containers` left at `idx`.
+fn container_marker<'e>(events: &'e [Event], idx: usize) -> Option<&'e str> {
+    match events.get(idx..idx + 3)? {
+        [Event::Start(Tag::HtmlBlock), Event::Html(html), Event::End(TagEnd::HtmlBlock)] => {
+            html.trim().strip_prefix(CONTAINER_MARK)?.strip_suffix("-->")
+        }
+        _ => None,
+    }
+}
+
+/// A tab label and the event range of its code block.
+type CodeTab = (String, std::ops::Range<usize>);
+
+fn collect_tabs(events: &[Event], mut idx: usize) -> Option<(Vec<CodeTab>, usize)> {
+    let mut tabs = Vec::new();
+    loop {
+        match events.get(idx)? {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => {
+                let fence = parse_code_fence(info);
+                let end = idx + events[idx..]
+                    .iter()
+                    .position(|event| matches!(event, Event::End(TagEnd::CodeBlock)))?;
+                let label = fence
+                    .tab
+                    .or(fence.title)
+                    .filter(|label| !label.is_empty())
+                    .unwrap_or_else(|| {
+                        if fence.lang.is_empty() {
+                            format!("Code {}", tabs.len() + 1)
+                        } else {
+                            fence.lang
+                        }
+                    });
+                tabs.push((label, idx..end + 1));
+                idx = end + 1;
+            }
+            _ if container_marker(events, idx) == Some(CONTAINER_CLOSE) && !tabs.is_empty() => {
+                return Some((tabs, idx + 3));
+            }
+            _ => return None,
+        }
+    }
+}
+
+fn code_block_html(fence: &CodeFence, code: &str) -> String {
+    let mut html = String::new();
+    if let Some(title) = &fence.title {
+        html.push_str(&format!(
+            "<div class=\"code-block\"><div class=\"code-title\">{}</div>",
+            escape_html(title)
+        ));
+    }
+    if fence.lang.is_empty() {
+        html.push_str("<pre><code>");
+    } else {
+        html.push_str(&format!("<pre><code class=\"language-{}\">", escape_html(&fence.lang)));
+    }
+    for (idx, line) in code.split_inclusive('\n').enumerate() {
+        let line_no = idx + 1;
+        if fence
+            .highlight
+            .iter()
+            .any(|(start, end)| (*start..=*end).contains(&line_no))
+        {
+            html.push_str(&format!("<span class=\"highlight-line\">{}</span>", escape_html(line)));
+        } else {
+            html.push_str(&escape_html(line));
+        }
+    }
+    html.push_str("</code></pre>\n");
+    if fence.title.is_some() {
+        html.push_str("</div>\n");
+    }
+    html
+}
+
 fn set_heading_id(event: &mut Event, slug: String) {
     if let Event::Start(Tag::Heading { id, .. }) = event {
         *id = Some(CowStr::from(slug));
@@ -427,7 +1122,7 @@ fn unique_heading_slug(
     text: &str,
     used_ids: &mut std::collections::HashSet<String>,
 ) -> Option<String> {
-    let base = slugify_heading(text);
+    let base = github_slug(text);
     if base.is_empty() {
         return None;
     }
@@ -442,7 +1137,27 @@ fn unique_heading_slug(
     Some(candidate)
 }
 
-fn slugify_heading(text: &str) -> String {
+/// A heading's anchor as GitHub makes it, so links written against a
+/// README on GitHub land on the same section: lowercased, punctuation
+/// dropped, and each space turned into a hyphen without collapsing runs
+/// (`C++ & Rust` becomes `c--rust`). Duplicates get `-1`, `-2`, ... in
+/// [`unique_heading_slug`].
+pub fn github_slug(text: &str) -> String {
+    text.trim()
+        .chars()
+        .filter_map(|ch| match ch {
+            ' ' => Some('-'),
+            '-' | '_' => Some(ch),
+            _ if ch.is_alphanumeric() => Some(ch),
+            _ => None,
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// `text` as a file name: lowercased alphanumeric runs joined by single
+/// hyphens.
+pub fn slugify(text: &str) -> String {
     let mut slug = String::new();
     let mut previous_was_dash = false;
 
@@ -465,12 +1180,256 @@ fn slugify_heading(text: &str) -> String {
     slug
 }
 
+#[allow(clippy::too_many_arguments)]
+fn rewrite_links<'a>(
+    markdown: &'a str,
+    options: Options,
+    line_offset: usize,
+    source_path: &'a Path,
+    input_root: &'a Path,
+    index_dirs: &'a std::collections::HashSet<PathBuf>,
+    source_lines: bool,
+    warnings: &'a mut Vec<Diagnostic>,
+    settings: &'a Settings,
+) -> impl Iterator<Item = Event<'a>> {
+    let newlines: Option<Vec<usize>> =
+        source_lines.then(|| markdown.match_indices('\n').map(|(index, _)| index).collect());
+    Parser::new_ext(markdown, options)
+        .into_offset_iter()
+        .map(move |(event, range)| match newlines.as_ref() {
+            Some(newlines) => {
+                let line = line_offset + newlines.partition_point(|&index| index < range.start) + 1;
+                (with_source_line(event, line), range)
+            }
+            None => (event, range),
+        })
+        .map(move |(event, range)| match event {
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => {
+                let location = SourceLocation::at(markdown, range.start, line_offset);
+                Event::Start(Tag::Link {
+                    link_type,
+                    dest_url: rewrite_link_dest(
+                        dest_url,
+                        LinkKind::Link,
+                        source_path,
+                        location,
+                        input_root,
+                        index_dirs,
+                        warnings,
+                        settings,
+                    ),
+                    title,
+                    id,
+                })
+            }
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => {
+                let location = SourceLocation::at(markdown, range.start, line_offset);
+                Event::Start(Tag::Image {
+                    link_type,
+                    dest_url: rewrite_link_dest(
+                        dest_url,
+                        LinkKind::Image,
+                        source_path,
+                        location,
+                        input_root,
+                        index_dirs,
+                        warnings,
+                        settings,
+                    ),
+                    title,
+                    id,
+                })
+            }
+            Event::Html(html) => {
+                let exact = html_in_source(markdown, &range, &html);
+                Event::Html(rewrite_html_links(html, |dest, kind, at| {
+                    let offset = if exact { range.start + at } else { range.start };
+                    let location = SourceLocation::at(markdown, offset, line_offset);
+                    rewrite_link_dest(dest.into(), kind, source_path, location, input_root, index_dirs, warnings, settings)
+                        .to_string()
+                }))
+            }
+            Event::InlineHtml(html) => {
+                let exact = html_in_source(markdown, &range, &html);
+                Event::InlineHtml(rewrite_html_links(html, |dest, kind, at| {
+                    let offset = if exact { range.start + at } else { range.start };
+                    let location = SourceLocation::at(markdown, offset, line_offset);
+                    rewrite_link_dest(dest.into(), kind, source_path, location, input_root, index_dirs, warnings, settings)
+                        .to_string()
+                }))
+            }
+            _ => event,
+        })
+}
+
+/// `html` with the `href` and `src` attributes of its tags passed through
+/// `rewrite`, which gets the unescaped value, whether it loads an image,
+/// and the value's byte offset in `html`. Comments are left alone.
+fn rewrite_html_links<'a>(
+    html: CowStr<'a>,
+    mut rewrite: impl FnMut(String, LinkKind, usize) -> String,
+) -> CowStr<'a> {
+    let mut output = String::new();
+    let mut copied = 0;
+    let mut pos = 0;
+    while let Some(found) = html[pos..].find('<') {
+        let start = pos + found;
+        if html[start..].starts_with("<!--") {
+            match html[start..].find("-->") {
+                Some(end) => {
+                    pos = start + end + 3;
+                    continue;
+                }
+                None => break,
+            }
+        }
+        let name_len = html[start + 1..]
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(html.len() - start - 1);
+        let tag = html[start + 1..start + 1 + name_len].to_ascii_lowercase();
+        pos = start + 1 + name_len;
+        if tag.is_empty() || !tag.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            continue;
+        }
+        loop {
+            let rest = &html[pos..];
+            let attr = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == '/');
+            pos += rest.len() - attr.len();
+            if attr.is_empty() || attr.starts_with(['>', '<']) {
+                break;
+            }
+            let name_len = attr
+                .find(|c: char| c.is_ascii_whitespace() || matches!(c, '=' | '>' | '/'))
+                .unwrap_or(attr.len())
+                .max(1);
+            let name = attr[..name_len].to_ascii_lowercase();
+            pos += name_len;
+            let Some(value) = html[pos..].trim_start().strip_prefix('=') else {
+                continue;
+            };
+            let value = value.trim_start();
+            pos = html.len() - value.len();
+            let (value_start, value_end, next) = match value.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let end = value[1..].find(quote).map_or(html.len(), |end| pos + 1 + end);
+                    (pos + 1, end, (end + 1).min(html.len()))
+                }
+                _ => {
+                    let len = value.find(|c: char| c.is_ascii_whitespace() || c == '>').unwrap_or(value.len());
+                    (pos, pos + len, pos + len)
+                }
+            };
+            pos = next;
+            let kind = match (tag.as_str(), name.as_str()) {
+                ("img", "src") => LinkKind::Image,
+                (_, "href" | "src") => LinkKind::Link,
+                _ => continue,
+            };
+            let dest = unescape_html(&html[value_start..value_end]);
+            let rewritten = rewrite(dest.clone(), kind, value_start);
+            if rewritten != dest {
+                output.push_str(&html[copied..value_start]);
+                output.push_str(&escape_html(&rewritten));
+                copied = value_end;
+            }
+        }
+    }
+    if copied == 0 {
+        return html;
+    }
+    output.push_str(&html[copied..]);
+    output.into()
+}
+
+/// Whether an HTML event's text is the source at `range` as written, so
+/// offsets into it point into `markdown`. Lines inside block quotes and
+/// list items lose their prefix.
+fn html_in_source(markdown: &str, range: &std::ops::Range<usize>, html: &str) -> bool {
+    markdown.get(range.clone()).is_some_and(|source| source.starts_with(html))
+}
+
+/// Decodes the entities `escape_html` produces, as found in attribute values.
+fn unescape_html(input: &str) -> String {
+    input
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// `event` with a `data-source-line` attribute when it opens a block an
+/// author would click to edit. Other blocks keep their rendering; tables
+/// and code blocks carry state in the HTML writer or later passes.
+fn with_source_line(event: Event<'_>, line: usize) -> Event<'_> {
+    let attr = format!(" data-source-line=\"{line}\"");
+    match event {
+        Event::Start(Tag::Heading {
+            level,
+            id,
+            classes,
+            mut attrs,
+        }) => {
+            attrs.push(("data-source-line".into(), Some(line.to_string().into())));
+            Event::Start(Tag::Heading {
+                level,
+                id,
+                classes,
+                attrs,
+            })
+        }
+        Event::Start(Tag::Paragraph) => Event::Html(format!("<p{attr}>").into()),
+        Event::Start(Tag::Item) => Event::Html(format!("<li{attr}>").into()),
+        Event::Start(Tag::BlockQuote(None)) => Event::Html(format!("<blockquote{attr}>\n").into()),
+        _ => event,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LinkKind {
+    Link,
+    Image,
+    /// A page's own stylesheet or script from front matter.
+    Asset,
+}
+
+#[derive(Clone, Copy)]
+struct SourceLocation {
+    line: usize,
+    column: usize,
+}
+
+impl SourceLocation {
+    fn at(markdown: &str, offset: usize, line_offset: usize) -> Self {
+        let before = &markdown[..offset];
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        Self {
+            line: line_offset + before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn rewrite_link_dest<'a>(
     dest_url: CowStr<'a>,
+    kind: LinkKind,
     source_path: &Path,
+    location: SourceLocation,
     input_root: &Path,
     index_dirs: &std::collections::HashSet<PathBuf>,
-    warnings: &mut Vec<String>,
+    warnings: &mut Vec<Diagnostic>,
+    settings: &Settings,
 ) -> CowStr<'a> {
     let dest = dest_url.to_string();
     let Some((base, suffix)) = split_link(&dest) else {
@@ -485,36 +1444,212 @@ fn rewrite_link_dest<'a>(
         return CowStr::from(dest);
     }
 
-    let normalized_base = normalize_link_path(&base);
-    let (resolved, relative_dir) = resolve_link_path(&normalized_base, source_path, input_root);
-    if is_markdown_path(&normalized_base) {
-        if !resolved.exists() {
-            warnings.push(format!(
-                "Missing link target: {} referenced from {}",
-                normalized_base,
-                source_path.display()
-            ));
+    // Windows authors write `docs\guide\intro.md`; browsers don't treat
+    // backslashes as separators.
+    let backslashes = base.contains('\\');
+    if backslashes {
+        let fixed = base.replace('\\', "/");
+        warnings.push(
+            Diagnostic::warning(
+                "link-backslash",
+                source_path,
+                format!("Link {base} uses backslashes; linking to {fixed}"),
+            )
+            .at(location.line, location.column),
+        );
+    }
+    let mut normalized_base = normalize_link_path(&base.replace('\\', "/"));
+    let case_corrected = case_corrected_link(&normalized_base, source_path, input_root);
+    let rewritten = backslashes || case_corrected.is_some();
+    if let Some(corrected) = case_corrected {
+        warnings.push(
+            Diagnostic::warning(
+                "link-case",
+                source_path,
+                format!("Link target {normalized_base} is spelled {corrected} on disk; case-sensitive hosts would 404"),
+            )
+            .at(location.line, location.column),
+        );
+        normalized_base = corrected;
+    }
+    let (resolved, relative_dir) = resolve_link_path(&normalized_base, source_path, input_root, settings);
+    if is_markdown_path(&normalized_base, settings) {
+        if !link_target_exists(input_root, &resolved, settings) {
+            warnings.push(
+                Diagnostic::warning(
+                    "missing-link",
+                    source_path,
+                    format!("Missing link target: {normalized_base}"),
+                )
+                .at(location.line, location.column),
+            );
+        }
+        if settings.keep_markdown_links {
+            return keep_markdown_link(dest, &normalized_base, &suffix, rewritten);
         }
-        let replacement = replace_markdown_extension(&normalized_base);
-        let mut replacement = if is_readme_path(&normalized_base) {
+        let suffix = match suffix.split_once('#') {
+            Some((query, fragment)) => match target_heading_id(&resolved, fragment) {
+                Some(id) => format!("{query}#{id}"),
+                None => suffix,
+            },
+            None => suffix,
+        };
+        let output_base = strip_folder_number_prefixes(&normalized_base, false, settings);
+        let replacement = replace_markdown_extension(&output_base, settings);
+        let mut replacement = if is_readme_path(&normalized_base, settings) {
             let parent = relative_dir.unwrap_or_else(PathBuf::new);
             if index_dirs.contains(&parent) {
                 replacement
             } else {
-                readme_to_index(&normalized_base)
+                readme_to_index(&output_base)
             }
-        } else if is_index_path(&normalized_base) {
-            replace_markdown_extension(&normalized_base)
+        } else if is_index_path(&normalized_base, settings) {
+            replace_markdown_extension(&output_base, settings)
         } else {
             replacement
         };
+        if settings.pretty_urls {
+            replacement = pretty_link(&replacement, true, is_landing_source(source_path, settings));
+        }
         replacement.push_str(&suffix);
         return CowStr::from(replacement);
     }
 
+    let missing = match kind {
+        LinkKind::Link => None,
+        LinkKind::Image => Some(("missing-image", "Missing image")),
+        LinkKind::Asset => Some(("missing-asset", "Missing page asset")),
+    };
+    if let Some((rule, label)) = missing
+        && !resolved.exists()
+    {
+        warnings.push(
+            Diagnostic::warning(rule, source_path, format!("{label}: {normalized_base}"))
+                .at(location.line, location.column),
+        );
+    }
+    let output_base = strip_folder_number_prefixes(&normalized_base, resolved.is_dir(), settings);
+    if settings.pretty_urls && !is_landing_source(source_path, settings) && !output_base.starts_with('/') {
+        return CowStr::from(format!("{}{suffix}", pretty_link(&output_base, false, false)));
+    }
+    if rewritten || output_base != normalized_base {
+        return CowStr::from(format!("{output_base}{suffix}"));
+    }
     CowStr::from(dest)
 }
 
+/// The id the heading `fragment` refers to has on the Markdown page at
+/// `target`, when that differs from `fragment`: a heading with a custom
+/// `{#id}`, or a fragment written with GitHub's or an older slug of the
+/// heading text. `None` when `fragment` is already an id on the page or
+/// names no heading.
+fn target_heading_id(target: &Path, fragment: &str) -> Option<String> {
+    let markdown = std::fs::read_to_string(target).ok()?;
+    let anchors = heading_anchors(strip_front_matter(&markdown));
+    if anchors.iter().any(|(id, _)| id == fragment) {
+        return None;
+    }
+    let fragment = fragment.to_lowercase();
+    anchors
+        .into_iter()
+        .find(|(_, text)| github_slug(text) == fragment || slugify(text) == fragment)
+        .map(|(id, _)| id)
+}
+
+/// Each heading's id and text, as rendering assigns them.
+fn heading_anchors(markdown: &str) -> Vec<(String, String)> {
+    let events = assign_heading_ids(Parser::new_ext(markdown, markdown_options(false)));
+    events
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, event)| match event {
+            Event::Start(Tag::Heading { id: Some(id), .. }) => {
+                let end_idx = heading_end_index(&events, idx + 1)?;
+                Some((id.to_string(), heading_text(&events[idx + 1..end_idx])))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// A Markdown link left pointing at the `.md` file, with only backslash
+/// and letter case fixes applied.
+fn keep_markdown_link<'a>(dest: String, normalized_base: &str, suffix: &str, rewritten: bool) -> CowStr<'a> {
+    if rewritten {
+        CowStr::from(format!("{normalized_base}{suffix}"))
+    } else {
+        CowStr::from(dest)
+    }
+}
+
+/// Whether the page at `source_path` keeps its own name as output, where
+/// pretty URLs move other pages one folder down, to `name/index.html`.
+fn is_landing_source(source_path: &Path, settings: &Settings) -> bool {
+    let name = source_path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    is_index_path(name, settings) || is_readme_path(name, settings)
+}
+
+/// `link` for a site with pretty URLs. Page links (`page`: already ending
+/// in `.html`) become folder links: `guide.html` to `guide/` and
+/// `index.html` to `./`. Relative links from pages other than landing
+/// pages climb out of the page's own folder.
+fn pretty_link(link: &str, page: bool, from_landing: bool) -> String {
+    let mut link = link.to_string();
+    let (dir, name) = match link.rsplit_once('/') {
+        Some((dir, name)) => (format!("{dir}/"), name),
+        None => (String::new(), link.as_str()),
+    };
+    if page && let Some(stem) = name.strip_suffix(".html") {
+        if stem == "index" {
+            link = if dir.is_empty() { "./".to_string() } else { dir };
+        } else if !stem.eq_ignore_ascii_case("readme") {
+            link = format!("{dir}{stem}/");
+        }
+    }
+    if !from_landing && !link.starts_with('/') {
+        link = format!("../{}", link.trim_start_matches("./"));
+    }
+    link
+}
+
+/// `link` (normalized) with each path segment spelled as on disk, when
+/// that differs only in letter case, as `readme.MD` for `README.md`. `None`
+/// when the link matches exactly or names nothing on disk. Directory
+/// listings are compared rather than trusting `exists`, which ignores case
+/// on macOS and Windows.
+fn case_corrected_link(link: &str, source_path: &Path, input_root: &Path) -> Option<String> {
+    let absolute = link.starts_with('/');
+    let mut dir = if absolute {
+        input_root.to_path_buf()
+    } else {
+        source_path.parent().unwrap_or(input_root).to_path_buf()
+    };
+    let mut corrected = Vec::new();
+    let mut changed = false;
+    for segment in link.split('/').filter(|segment| !segment.is_empty() && *segment != ".") {
+        if segment == ".." {
+            dir.pop();
+            corrected.push(segment.to_string());
+            continue;
+        }
+        let names: Vec<String> = std::fs::read_dir(&dir)
+            .ok()?
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .collect();
+        let name = if names.iter().any(|name| name == segment) {
+            segment.to_string()
+        } else {
+            let lower = segment.to_lowercase();
+            changed = true;
+            names.into_iter().find(|name| name.to_lowercase() == lower)?
+        };
+        dir.push(&name);
+        corrected.push(name);
+    }
+    let corrected = corrected.join("/");
+    changed.then(|| if absolute { format!("/{corrected}") } else { corrected })
+}
+
 fn split_link(dest: &str) -> Option<(String, String)> {
     if dest.is_empty() {
         return None;
@@ -528,7 +1663,22 @@ fn split_link(dest: &str) -> Option<(String, String)> {
     Some((dest.to_string(), String::new()))
 }
 
-fn resolve_link_path(base: &str, source_path: &Path, input_root: &Path) -> (PathBuf, Option<PathBuf>) {
+fn resolve_link_path(
+    base: &str,
+    source_path: &Path,
+    input_root: &Path,
+    settings: &Settings,
+) -> (PathBuf, Option<PathBuf>) {
+    // With several input roots, links resolve in the merged site, so a
+    // mounted page can link to pages in the main input and the other way
+    // round.
+    if !settings.mounted_roots.is_empty()
+        && let Some(source_rel) = site_rel_path(input_root, source_path, settings)
+        && let Some(rel) = resolve_local(base, source_rel.parent().unwrap_or(Path::new("")))
+    {
+        let relative_dir = rel.parent().unwrap_or(Path::new("")).to_path_buf();
+        return (source_file(input_root, &rel, settings), Some(relative_dir));
+    }
     if base.starts_with('/') {
         let rel = PathBuf::from(base.trim_start_matches('/'));
         return (input_root.join(&rel), Some(rel.parent().unwrap_or(Path::new("")).to_path_buf()));
@@ -553,36 +1703,42 @@ fn has_scheme(dest: &str) -> bool {
     dest.starts_with("http://") || dest.starts_with("https://")
 }
 
-fn is_markdown_path(dest: &str) -> bool {
-    matches!(
-        Path::new(dest)
-            .extension()
-            .and_then(|ext| ext.to_str())
-            .map(|ext| ext.to_ascii_lowercase())
-            .as_deref(),
-        Some("md") | Some("markdown")
-    )
+fn is_markdown_path(dest: &str, settings: &Settings) -> bool {
+    settings.is_markdown(Path::new(dest))
 }
 
-fn is_readme_path(dest: &str) -> bool {
+fn is_readme_path(dest: &str, settings: &Settings) -> bool {
     let path = Path::new(dest);
     let stem = path
         .file_stem()
         .and_then(|stem| stem.to_str())
         .map(|s| s.to_ascii_lowercase());
-    matches!(stem.as_deref(), Some("readme")) && is_markdown_path(dest)
+    matches!(stem.as_deref(), Some("readme")) && is_markdown_path(dest, settings)
 }
 
-fn is_index_path(dest: &str) -> bool {
+fn is_index_path(dest: &str, settings: &Settings) -> bool {
     let path = Path::new(dest);
     let stem = path
         .file_stem()
         .and_then(|stem| stem.to_str())
         .map(|s| s.to_ascii_lowercase());
-    matches!(stem.as_deref(), Some("index")) && is_markdown_path(dest)
+    matches!(stem.as_deref(), Some("index")) && is_markdown_path(dest, settings)
+}
+
+/// `dest` with the number prefix dropped from each folder name, and from the
+/// last name too when `dir` is set, the way the site is written out.
+fn strip_folder_number_prefixes(dest: &str, dir: bool, settings: &Settings) -> String {
+    let parts: Vec<&str> = dest.split('/').collect();
+    let last = parts.len() - 1;
+    parts
+        .iter()
+        .enumerate()
+        .map(|(idx, part)| if idx < last || dir { strip_number_prefix(part, settings) } else { part })
+        .collect::<Vec<_>>()
+        .join("/")
 }
 
-fn replace_markdown_extension(dest: &str) -> String {
+fn replace_markdown_extension(dest: &str, settings: &Settings) -> String {
     let path = Path::new(dest);
     let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or(dest);
     let mut base = String::new();
@@ -605,7 +1761,7 @@ fn replace_markdown_extension(dest: &str) -> String {
             }
         }
     }
-    base.push_str(stem);
+    base.push_str(strip_number_prefix(stem, settings));
     base.push_str(".html");
     base
 }
@@ -674,7 +1830,7 @@ fn normalize_link_path(path: &str) -> String {
     }
 }
 
-fn markdown_options(for_title_only: bool) -> Options {
+pub fn markdown_options(for_title_only: bool) -> Options {
     let mut options = Options::empty();
     options.insert(Options::ENABLE_GFM);
     options.insert(Options::ENABLE_SMART_PUNCTUATION);
@@ -685,6 +1841,7 @@ fn markdown_options(for_title_only: bool) -> Options {
         options.insert(Options::ENABLE_FOOTNOTES);
         options.insert(Options::ENABLE_HEADING_ATTRIBUTES);
         options.insert(Options::ENABLE_MATH);
+        options.insert(Options::ENABLE_DEFINITION_LIST);
     }
     options
 }
@@ -703,7 +1860,7 @@ graph TD;
 "#;
         let index_dirs = std::collections::HashSet::new();
         let (html, _warnings) =
-            markdown_to_html_with_rewrites(markdown, Path::new("."), Path::new("."), &index_dirs);
+            markdown_to_html_with_rewrites(markdown, 0, Path::new("."), Path::new("."), &index_dirs, false, &Settings::default());
         let rewritten = rewrite_mermaid_blocks(&html);
         assert!(rewritten.contains(r#"<pre class="mermaid">"#));
         assert!(rewritten.contains("graph TD;"));
@@ -724,12 +1881,36 @@ graph TD;
         let mut index_dirs = std::collections::HashSet::new();
         index_dirs.insert(PathBuf::from("docs"));
         let (html, warnings) =
-            markdown_to_html_with_rewrites(markdown, &source, input_root, &index_dirs);
+            markdown_to_html_with_rewrites(markdown, 0, &source, input_root, &index_dirs, false, &Settings::default());
         assert!(warnings.is_empty());
         assert!(html.contains("guide/intro.html"));
         assert!(html.contains("README.html"));
     }
 
+    #[test]
+    fn rewrites_links_in_raw_html() {
+        let root = tempfile::tempdir().expect("tempdir");
+        std::fs::create_dir_all(root.path().join("guide")).expect("guide dir");
+        std::fs::write(root.path().join("guide/intro.md"), "# Intro").expect("intro");
+        let source = root.path().join("index.md");
+        let markdown = concat!(
+            "<div class=\"card\">\n<a href=\"guide/intro.md?x=1&amp;y=2#setup\"><img src='shot.png' alt=\"\"></a>\n</div>\n\n",
+            "See <a class=more href=guide/missing.md>more</a>.\n\n",
+            "<!-- <a href=\"old.md\"> -->\n"
+        );
+        let rendered = render_markdown_source(markdown, &source, root.path(), &Default::default(), &Settings::default());
+        assert!(rendered.html.contains(r#"<a href="guide/intro.html?x=1&amp;y=2#setup"><img src='shot.png' alt="">"#));
+        assert!(rendered.html.contains(r#"<a class=more href=guide/missing.html>"#));
+        assert!(rendered.html.contains(r#"<!-- <a href="old.md"> -->"#));
+        assert_eq!(
+            rendered.diagnostics.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                format!("{}:2:54: Missing image: shot.png", source.display()),
+                format!("{}:5:24: Missing link target: guide/missing.md", source.display()),
+            ]
+        );
+    }
+
     #[test]
     fn warns_on_missing_markdown_link() {
         let root = tempfile::tempdir().expect("tempdir");
@@ -741,9 +1922,286 @@ graph TD;
         let source = docs_dir.join("index.md");
         let index_dirs = std::collections::HashSet::new();
         let (_html, warnings) =
-            markdown_to_html_with_rewrites(markdown, &source, input_root, &index_dirs);
+            markdown_to_html_with_rewrites(markdown, 0, &source, input_root, &index_dirs, false, &Settings::default());
         assert_eq!(warnings.len(), 1);
-        assert!(warnings[0].contains("missing.md"));
+        assert_eq!(warnings[0].rule, "missing-link");
+        assert!(warnings[0].message.contains("missing.md"));
+    }
+
+    #[test]
+    fn corrects_link_case_with_a_warning() {
+        let root = tempfile::tempdir().expect("tempdir");
+        let input_root = root.path();
+        std::fs::create_dir_all(input_root.join("Docs/img")).expect("create dirs");
+        std::fs::write(input_root.join("Docs/Intro.md"), "# Intro\n").expect("intro");
+        std::fs::write(input_root.join("Docs/img/Logo.png"), "png").expect("logo");
+        std::fs::write(input_root.join("README.md"), "# Home\n").expect("readme");
+
+        let markdown = "[Intro](docs/intro.md#setup) [Home](/readme.MD) ![Logo](Docs/img/logo.PNG) [Same](Docs/Intro.md)";
+        let source = input_root.join("index.md");
+        let (html, warnings) =
+            markdown_to_html_with_rewrites(markdown, 0, &source, input_root, &Default::default(), false, &Settings::default());
+        assert!(html.contains(r#"href="Docs/Intro.html#setup""#));
+        assert!(html.contains(r#"href="/index.html""#));
+        assert!(html.contains(r#"src="Docs/img/Logo.png""#));
+        assert_eq!(warnings.iter().filter(|warning| warning.rule == "link-case").count(), 3);
+        assert!(warnings.iter().all(|warning| warning.rule == "link-case"));
+        assert!(warnings[0].message.contains("docs/intro.md is spelled Docs/Intro.md"));
+    }
+
+    #[test]
+    fn normalizes_backslash_links_with_a_warning() {
+        let root = tempfile::tempdir().expect("tempdir");
+        let input_root = root.path();
+        std::fs::create_dir_all(input_root.join("docs/guide")).expect("create dirs");
+        std::fs::write(input_root.join("docs/guide/intro.md"), "# Intro\n").expect("intro");
+        std::fs::write(input_root.join("docs/guide/shot.png"), "png").expect("shot");
+
+        let markdown = "[Intro](docs\\guide\\intro.md#setup) ![Shot](.\\docs\\guide\\shot.png)";
+        let source = input_root.join("index.md");
+        let (html, warnings) =
+            markdown_to_html_with_rewrites(markdown, 0, &source, input_root, &Default::default(), false, &Settings::default());
+        assert!(html.contains(r#"href="docs/guide/intro.html#setup""#));
+        assert!(html.contains(r#"src="docs/guide/shot.png""#));
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings.iter().all(|warning| warning.rule == "link-backslash"));
+        assert!(warnings[0].message.contains("docs/guide/intro.md"));
+    }
+
+    #[test]
+    fn maps_fragments_to_target_heading_ids() {
+        let root = tempfile::tempdir().expect("tempdir");
+        let input_root = root.path();
+        std::fs::write(
+            input_root.join("guide.md"),
+            "---\ntitle: Guide\n---\n# Guide\n\n## Setting up {#install}\n\n## C++ & Rust\n",
+        )
+        .expect("guide");
+
+        let markdown = "[a](guide.md#setting-up) [b](guide.md#c-rust) [c](guide.md#c--rust) [d](guide.md#elsewhere)";
+        let source = input_root.join("index.md");
+        let (html, warnings) =
+            markdown_to_html_with_rewrites(markdown, 0, &source, input_root, &Default::default(), false, &Settings::default());
+        assert!(warnings.is_empty());
+        assert!(html.contains(r#"href="guide.html#install""#));
+        assert!(html.contains(r#"href="guide.html#c--rust">b"#));
+        assert!(html.contains(r#"href="guide.html#c--rust">c"#));
+        assert!(html.contains(r#"href="guide.html#elsewhere""#));
+    }
+
+    #[test]
+    fn keeps_markdown_links_with_only_fixes_applied() {
+        assert_eq!(
+            keep_markdown_link("guide/intro.md#setup".to_string(), "guide/intro.md", "#setup", false).as_ref(),
+            "guide/intro.md#setup"
+        );
+        assert_eq!(
+            keep_markdown_link("guide\\Intro.md".to_string(), "guide/intro.md", "", true).as_ref(),
+            "guide/intro.md"
+        );
+    }
+
+    #[test]
+    fn turns_page_links_into_folder_links_for_pretty_urls() {
+        assert_eq!(pretty_link("guide/install.html", true, true), "guide/install/");
+        assert_eq!(pretty_link("guide/index.html", true, true), "guide/");
+        assert_eq!(pretty_link("index.html", true, true), "./");
+        assert_eq!(pretty_link("index.html", true, false), "../");
+        assert_eq!(pretty_link("faq.html", true, false), "../faq/");
+        assert_eq!(pretty_link("/guide/faq.html", true, false), "/guide/faq/");
+        assert_eq!(pretty_link("guide/README.html", true, true), "guide/README.html");
+        assert_eq!(pretty_link("img/logo.png", false, false), "../img/logo.png");
+        let settings = Settings::default();
+        assert!(is_landing_source(Path::new("docs/guide/README.md"), &settings));
+        assert!(!is_landing_source(Path::new("docs/guide/install.md"), &settings));
+    }
+
+    #[test]
+    fn reports_missing_link_line_and_column() {
+        let root = tempfile::tempdir().expect("tempdir");
+        let source = root.path().join("index.md");
+        let markdown = "---\ntitle: Home\n---\n# Home\n\nSee – [Missing](missing.md).\n";
+        let rendered = render_markdown_source(markdown, &source, root.path(), &Default::default(), &Settings::default());
+        assert_eq!(
+            rendered.diagnostics.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![format!("{}:6:7: Missing link target: missing.md", source.display())]
+        );
+    }
+
+    #[test]
+    fn links_front_matter_styles_and_scripts() {
+        let root = tempfile::tempdir().expect("tempdir");
+        std::fs::create_dir_all(root.path().join("demos")).expect("demos dir");
+        std::fs::write(root.path().join("demos/demo.css"), "p {}").expect("css");
+        let source = root.path().join("demos/chart.md");
+        let markdown = "---\ncss: demo.css\njs: [\"widget.js\", https://cdn.example.com/chart.js]\n---\n# Chart\n";
+        let rendered = render_markdown_source(markdown, &source, root.path(), &Default::default(), &Settings::default());
+        assert_eq!(rendered.extra_head.as_deref(), Some(r#"<link rel="stylesheet" href="demo.css">"#));
+        assert_eq!(
+            rendered.extra_body.as_deref(),
+            Some(r#"<script src="widget.js"></script><script src="https://cdn.example.com/chart.js"></script>"#)
+        );
+        assert_eq!(
+            rendered.diagnostics.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![format!("{}:3:1: Missing page asset: widget.js", source.display())]
+        );
+    }
+
+    #[test]
+    fn links_footnotes_back_to_their_references() {
+        let markdown = "Alpha[^b] and beta[^a], again[^b].\n\n[^a]: First note.\n[^b]: Second note.\n";
+        let (html, _) = markdown_to_html_with_rewrites(
+            markdown,
+            0,
+            Path::new("."),
+            Path::new("."),
+            &Default::default(),
+            false,
+            &Settings::default(),
+        );
+        assert!(html.contains(concat!(
+            "Alpha<sup class=\"footnote-reference\" id=\"fnref-b\"><a href=\"#b\">1</a></sup> and beta",
+            "<sup class=\"footnote-reference\" id=\"fnref-a\"><a href=\"#a\">2</a></sup>, again",
+            "<sup class=\"footnote-reference\" id=\"fnref-b-2\"><a href=\"#b\">1</a></sup>."
+        )));
+        assert!(html.contains(concat!(
+            "<div class=\"footnote-definition\" id=\"a\"><sup class=\"footnote-definition-label\">2</sup>\n",
+            "<p>First note. <a href=\"#fnref-a\" class=\"footnote-backref\" aria-label=\"Back to reference 2\">↩</a></p>\n</div>"
+        )));
+        assert!(html.contains(concat!(
+            "<p>Second note. <a href=\"#fnref-b\" class=\"footnote-backref\" aria-label=\"Back to reference 1\">↩</a>",
+            " <a href=\"#fnref-b-2\" class=\"footnote-backref\" aria-label=\"Back to reference 1 (2)\">↩<sup>2</sup></a></p>"
+        )));
+    }
+
+    #[test]
+    fn warns_about_missing_images() {
+        let root = tempfile::tempdir().expect("tempdir");
+        std::fs::create_dir_all(root.path().join("guide")).expect("guide dir");
+        std::fs::write(root.path().join("logo.png"), b"png").expect("logo");
+        let source = root.path().join("guide/intro.md");
+        let markdown = "![Logo](../logo.png)\n![Root](/logo.png)\n![Shot](shots/setup.png)\n![Remote](https://example.com/a.png)\n";
+        let rendered = render_markdown_source(markdown, &source, root.path(), &Default::default(), &Settings::default());
+        assert_eq!(
+            rendered.diagnostics.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![format!("{}:3:1: Missing image: shots/setup.png", source.display())]
+        );
+        assert!(rendered.html.contains(r#"<img src="../logo.png" alt="Logo" />"#));
+    }
+
+    #[test]
+    fn renders_code_block_titles_and_highlighted_lines() {
+        let markdown = "```rust title=\"src/main.rs\" {2-3}\nfn main() {\n    let x = 1;\n    println!(\"{x}\");\n}\n```\n\n```sh\nls\n```\n";
+        let (html, _) = markdown_to_html_with_rewrites(
+            markdown,
+            0,
+            Path::new("index.md"),
+            Path::new("."),
+            &Default::default(),
+            false, &Settings::default(),
+        );
+        assert!(html.contains(concat!(
+            r#"<div class="code-block"><div class="code-title">src/main.rs</div>"#,
+            r#"<pre><code class="language-rust">fn main() {"#,
+            "\n",
+            r#"<span class="highlight-line">    let x = 1;"#,
+            "\n",
+            r#"</span><span class="highlight-line">    println!(&quot;{x}&quot;);"#,
+        )));
+        assert!(html.contains(r#"<pre><code class="language-sh">ls"#));
+    }
+
+    #[test]
+    fn marks_blocks_with_source_lines() {
+        let markdown = mark_containers("# Setup\n\nInstall it.\n\n- one\n- two\n\n> Note\n\n:::tabs\n```sh\nls\n```\n:::\n");
+        let index_dirs = Default::default();
+        let mut warnings = Vec::new();
+        let settings = Settings::default();
+        let events = rewrite_links(
+            &markdown,
+            markdown_options(false),
+            2,
+            Path::new("index.md"),
+            Path::new("."),
+            &index_dirs,
+            true,
+            &mut warnings,
+            &settings,
+        );
+        let mut html = String::new();
+        html::push_html(&mut html, group_code_tabs(events).into_iter());
+        assert!(html.contains(r#"<h1 data-source-line="3">Setup</h1>"#));
+        assert!(html.contains(r#"<p data-source-line="5">Install it.</p>"#));
+        assert!(html.contains(r#"<li data-source-line="7">one</li>"#));
+        assert!(html.contains(r#"<li data-source-line="8">two</li>"#));
+        assert!(html.contains(r#"<blockquote data-source-line="10">"#));
+        assert!(html.contains(r#"<div class="code-tabs">"#));
+    }
+
+    #[test]
+    fn renders_definition_lists() {
+        let markdown = "Term\n: The meaning.\n\nOther term\n: First.\n: Second.\n";
+        let (html, _) = markdown_to_html_with_rewrites(
+            markdown,
+            0,
+            Path::new("glossary.md"),
+            Path::new("."),
+            &Default::default(),
+            false,
+            &Settings::default(),
+        );
+        assert!(html.contains("<dl>\n<dt>Term</dt>\n<dd>The meaning.</dd>\n<dt>Other term</dt>\n<dd>First.</dd>\n<dd>Second.</dd>\n</dl>"));
+    }
+
+    #[test]
+    fn renders_custom_containers() {
+        let markdown = "::: warning Mind the gap\nStep *carefully*.\n:::\n\n::: tip\n- one\n:::\n\n\
+                        ::: details Show output\n```\n::: not a marker\n```\n:::\n\n\
+                        :::: columns\n::: column\nLeft\n:::\n::: column\nRight\n:::\n::::\n";
+        let (html, _) = markdown_to_html_with_rewrites(
+            markdown,
+            0,
+            Path::new("index.md"),
+            Path::new("."),
+            &Default::default(),
+            false,
+            &Settings::default(),
+        );
+        assert!(html.contains(concat!(
+            "<div class=\"container warning\"><p class=\"container-title\">Mind the gap</p>\n",
+            "<p>Step <em>carefully</em>.</p>\n</div>\n"
+        )));
+        assert!(html.contains("<div class=\"container tip\"><p class=\"container-title\">Tip</p>\n<ul>\n<li>one</li>\n</ul>\n</div>"));
+        assert!(html.contains("<details class=\"container details\"><summary>Show output</summary>\n<pre><code>::: not a marker\n</code></pre>\n</details>"));
+        assert!(html.contains(concat!(
+            "<div class=\"container columns\">\n<div class=\"container column\">\n<p>Left</p>\n</div>\n",
+            "<div class=\"container column\">\n<p>Right</p>\n</div>\n</div>\n"
+        )));
+        assert!(!html.contains("rendar:"));
+    }
+
+    #[test]
+    fn groups_fences_into_code_tabs() {
+        let markdown = ":::tabs\n```sh tab=\"curl\"\ncurl /api\n```\n```python\nrequests.get(\"/api\")\n```\n:::\n\n```rust\nfn main() {}\n```\n";
+        let (html, _) = markdown_to_html_with_rewrites(
+            markdown,
+            0,
+            Path::new("index.md"),
+            Path::new("."),
+            &Default::default(),
+            false, &Settings::default(),
+        );
+        assert!(html.starts_with(concat!(
+            r#"<div class="code-tabs"><div class="code-tabs-bar" role="tablist">"#,
+            r#"<button type="button" role="tab" data-tab="curl">curl</button>"#,
+            r#"<button type="button" role="tab" data-tab="python">python</button></div>"#,
+            "\n",
+            r#"<div class="code-tab" role="tabpanel" data-tab="curl">"#,
+            "\n",
+            r#"<pre><code class="language-sh">curl /api"#,
+        )));
+        assert!(!html.contains(":::"));
+        assert!(html.ends_with("</div>\n<pre><code class=\"language-rust\">fn main() {}\n</code></pre>\n"));
     }
 
     #[test]
@@ -753,6 +2211,26 @@ graph TD;
         assert_eq!(title.as_deref(), Some("First Title"));
     }
 
+    #[test]
+    fn prefers_front_matter_title() {
+        assert_eq!(page_title("---\ntitle: Install Guide\n---\n# Install\n").as_deref(), Some("Install Guide"));
+        assert_eq!(page_title("---\ntitle: \"\"\n---\n# Install\n").as_deref(), Some("Install"));
+        assert_eq!(page_title("Just text.\n"), None);
+    }
+
+    #[test]
+    fn strips_opening_title_heading() {
+        let strip = |markdown: &str| {
+            let mut events: Vec<Event> = Parser::new_ext(markdown, markdown_options(false)).collect();
+            strip_title_heading(&mut events);
+            let mut html = String::new();
+            html::push_html(&mut html, events.into_iter());
+            html
+        };
+        assert_eq!(strip("# Install *now*\n\nRun it.\n"), "<p>Run it.</p>\n");
+        assert_eq!(strip("Intro.\n\n# Install\n"), "<p>Intro.</p>\n<h1>Install</h1>\n");
+    }
+
     #[test]
     fn ignores_front_matter_in_title() {
         let markdown = "---\nmode: slides\n---\n# Deck Title\n";
@@ -760,6 +2238,43 @@ graph TD;
         assert_eq!(title.as_deref(), Some("Deck Title"));
     }
 
+    #[test]
+    fn numbers_headings_hierarchically() {
+        let render = |markdown: &str| {
+            markdown_to_html_with_rewrites(
+                markdown,
+                0,
+                Path::new("spec.md"),
+                Path::new(""),
+                &Default::default(),
+                true,
+                &Settings::default(),
+            )
+            .0
+        };
+        let html = render("# Spec\n\n## Scope\n\n## Terms\n\n### Words\n\n#### Nouns\n\n## Rules\n");
+        assert!(html.contains("<h1 id=\"spec\">Spec</h1>"));
+        assert!(html.contains(r#"<h2 id="scope"><span class="heading-number">1.</span> Scope</h2>"#));
+        assert!(html.contains(r#"<span class="heading-number">2.1</span> Words"#));
+        assert!(html.contains(r#"<span class="heading-number">2.1.1</span> Nouns"#));
+        assert!(html.contains(r#"<span class="heading-number">3.</span> Rules"#));
+
+        let html = render("# One\n\n## Part\n\n# Two\n");
+        assert!(html.contains(r#"<span class="heading-number">1.1</span> Part"#));
+        assert!(html.contains(r#"<span class="heading-number">2.</span> Two"#));
+    }
+
+    #[test]
+    fn reads_lightbox_toggle_from_front_matter() {
+        let index_dirs = std::collections::HashSet::new();
+        let render = |markdown: &str| {
+            render_markdown_source(markdown, Path::new("page.md"), Path::new(""), &index_dirs, &Settings::default()).lightbox
+        };
+        assert_eq!(render("---\nlightbox: no\n---\n# Page\n"), Some(false));
+        assert_eq!(render("---\nlightbox: true\n---\n# Page\n"), Some(true));
+        assert_eq!(render("# Page\n"), None);
+    }
+
     #[test]
     fn renders_front_matter_table_before_heading() {
         let root = tempfile::tempdir().expect("tempdir");
@@ -769,7 +2284,7 @@ graph TD;
         let index_dirs = std::collections::HashSet::new();
 
         let rendered =
-            render_markdown_file(&path, root.path(), &index_dirs).expect("render markdown");
+            render_markdown_file(&path, root.path(), &index_dirs, &Settings::default()).expect("render markdown");
         assert_eq!(rendered.mode, DocMode::Document);
         let table_index = rendered
             .html
@@ -792,7 +2307,7 @@ graph TD;
         let index_dirs = std::collections::HashSet::new();
 
         let rendered =
-            render_markdown_file(&path, root.path(), &index_dirs).expect("render markdown");
+            render_markdown_file(&path, root.path(), &index_dirs, &Settings::default()).expect("render markdown");
         assert_eq!(rendered.mode, DocMode::Slides);
         assert!(rendered.html.contains(r#"data-slide-count="1""#));
         assert!(!rendered.html.contains("front-matter-table"));
@@ -804,10 +2319,12 @@ graph TD;
         let index_dirs = std::collections::HashSet::new();
         let (html, _warnings) = markdown_to_slides_with_rewrites(
             markdown,
+            0,
             Path::new("."),
             Path::new("."),
             &index_dirs,
             None,
+            &Settings::default(),
         );
         assert!(html.contains(r#"data-slide-count="2""#));
         assert!(html.contains(r#"id="slide-1""#));
@@ -828,7 +2345,7 @@ graph TD;
         let source = input_root.join("docs/index.md");
         let index_dirs = std::collections::HashSet::new();
         let (html, warnings) =
-            markdown_to_html_with_rewrites(markdown, &source, input_root, &index_dirs);
+            markdown_to_html_with_rewrites(markdown, 0, &source, input_root, &index_dirs, false, &Settings::default());
         assert!(warnings.is_empty());
         assert!(html.contains(r#"/guide/intro.html"#));
     }
@@ -847,7 +2364,7 @@ graph TD;
         let source = docs_dir.join("index.md");
         let index_dirs = std::collections::HashSet::new();
         let (html, warnings) =
-            markdown_to_html_with_rewrites(markdown, &source, input_root, &index_dirs);
+            markdown_to_html_with_rewrites(markdown, 0, &source, input_root, &index_dirs, false, &Settings::default());
         assert!(warnings.is_empty());
         assert!(html.contains(r#"../guide/intro.html#part"#));
     }
@@ -864,7 +2381,7 @@ graph TD;
         let source = docs_dir.join("intro.md");
         let index_dirs = std::collections::HashSet::new();
         let (html, warnings) =
-            markdown_to_html_with_rewrites(markdown, &source, input_root, &index_dirs);
+            markdown_to_html_with_rewrites(markdown, 0, &source, input_root, &index_dirs, false, &Settings::default());
         assert!(warnings.is_empty());
         assert!(html.contains(r#"index.html"#));
     }
@@ -883,7 +2400,7 @@ graph TD;
         let mut index_dirs = std::collections::HashSet::new();
         index_dirs.insert(PathBuf::from("docs"));
         let (html, warnings) =
-            markdown_to_html_with_rewrites(markdown, &source, input_root, &index_dirs);
+            markdown_to_html_with_rewrites(markdown, 0, &source, input_root, &index_dirs, false, &Settings::default());
         assert!(warnings.is_empty());
         assert!(html.contains(r#"README.html"#));
     }
@@ -900,7 +2417,7 @@ graph TD;
         let source = docs_dir.join("index.md");
         let index_dirs = std::collections::HashSet::new();
         let (html, warnings) =
-            markdown_to_html_with_rewrites(markdown, &source, input_root, &index_dirs);
+            markdown_to_html_with_rewrites(markdown, 0, &source, input_root, &index_dirs, false, &Settings::default());
         assert!(warnings.is_empty());
         assert!(html.contains("note.html"));
     }
@@ -919,7 +2436,7 @@ graph TD;
         let source = docs_dir.join("index.md");
         let index_dirs = std::collections::HashSet::new();
         let (html, warnings) =
-            markdown_to_html_with_rewrites(markdown, &source, input_root, &index_dirs);
+            markdown_to_html_with_rewrites(markdown, 0, &source, input_root, &index_dirs, false, &Settings::default());
         assert!(warnings.is_empty());
         assert!(html.contains("../guide/intro.html"));
     }
@@ -928,7 +2445,7 @@ graph TD;
     fn ignores_fragment_only_links() {
         let markdown = r#"[Section](#part)"#;
         let (html, warnings) =
-            markdown_to_html_with_rewrites(markdown, Path::new("."), Path::new("."), &std::collections::HashSet::new());
+            markdown_to_html_with_rewrites(markdown, 0, Path::new("."), Path::new("."), &std::collections::HashSet::new(), false, &Settings::default());
         assert!(warnings.is_empty());
         assert!(html.contains(r#"#part"#));
     }
@@ -938,10 +2455,19 @@ graph TD;
         let markdown = "# Hello, World!\n\n## `Code` & More\n";
         let index_dirs = std::collections::HashSet::new();
         let (html, warnings) =
-            markdown_to_html_with_rewrites(markdown, Path::new("."), Path::new("."), &index_dirs);
+            markdown_to_html_with_rewrites(markdown, 0, Path::new("."), Path::new("."), &index_dirs, false, &Settings::default());
         assert!(warnings.is_empty());
         assert!(html.contains(r#"<h1 id="hello-world">Hello, World!</h1>"#));
-        assert!(html.contains(r#"<h2 id="code-more"><code>Code</code> &amp; More</h2>"#));
+        assert!(html.contains(r#"<h2 id="code--more"><code>Code</code> &amp; More</h2>"#));
+    }
+
+    #[test]
+    fn slugs_headings_like_github() {
+        assert_eq!(github_slug("Hello, World!"), "hello-world");
+        assert_eq!(github_slug("C++ & Rust"), "c--rust");
+        assert_eq!(github_slug("snake_case and v1.2"), "snake_case-and-v12");
+        assert_eq!(github_slug("  Ärger mit -- Umlauten  "), "ärger-mit----umlauten");
+        assert_eq!(github_slug("🚀 Launch"), "-launch");
     }
 
     #[test]
@@ -949,7 +2475,7 @@ graph TD;
         let markdown = "# Repeat\n\n## Repeat\n\n### Repeat\n";
         let index_dirs = std::collections::HashSet::new();
         let (html, warnings) =
-            markdown_to_html_with_rewrites(markdown, Path::new("."), Path::new("."), &index_dirs);
+            markdown_to_html_with_rewrites(markdown, 0, Path::new("."), Path::new("."), &index_dirs, false, &Settings::default());
         assert!(warnings.is_empty());
         assert!(html.contains(r#"<h1 id="repeat">Repeat</h1>"#));
         assert!(html.contains(r#"<h2 id="repeat-1">Repeat</h2>"#));
@@ -961,7 +2487,7 @@ graph TD;
         let markdown = "# Repeat {#repeat}\n\n## Repeat\n";
         let index_dirs = std::collections::HashSet::new();
         let (html, warnings) =
-            markdown_to_html_with_rewrites(markdown, Path::new("."), Path::new("."), &index_dirs);
+            markdown_to_html_with_rewrites(markdown, 0, Path::new("."), Path::new("."), &index_dirs, false, &Settings::default());
         assert!(warnings.is_empty());
         assert!(html.contains(r#"<h1 id="repeat">Repeat</h1>"#));
         assert!(html.contains(r#"<h2 id="repeat-1">Repeat</h2>"#));
@@ -972,7 +2498,7 @@ graph TD;
         let markdown = "# Repeat\n\n## Other {#repeat}\n";
         let index_dirs = std::collections::HashSet::new();
         let (html, warnings) =
-            markdown_to_html_with_rewrites(markdown, Path::new("."), Path::new("."), &index_dirs);
+            markdown_to_html_with_rewrites(markdown, 0, Path::new("."), Path::new("."), &index_dirs, false, &Settings::default());
         assert!(warnings.is_empty());
         assert!(html.contains(r#"<h1 id="repeat-1">Repeat</h1>"#));
         assert!(html.contains(r#"<h2 id="repeat">Other</h2>"#));
@@ -984,10 +2510,12 @@ graph TD;
         let index_dirs = std::collections::HashSet::new();
         let (html, warnings) = markdown_to_slides_with_rewrites(
             markdown,
+            0,
             Path::new("."),
             Path::new("."),
             &index_dirs,
             None,
+            &Settings::default(),
         );
         assert!(warnings.is_empty());
         assert!(html.contains(r#"id="slide-1""#));
@@ -1008,7 +2536,7 @@ graph TD;
         let source = docs_dir.join("intro.md");
         let index_dirs = std::collections::HashSet::new();
         let (html, warnings) =
-            markdown_to_html_with_rewrites(markdown, &source, input_root, &index_dirs);
+            markdown_to_html_with_rewrites(markdown, 0, &source, input_root, &index_dirs, false, &Settings::default());
         assert!(warnings.is_empty());
         assert!(html.contains("index.html"));
     }
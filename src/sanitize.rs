@@ -0,0 +1,62 @@
+use ammonia::Builder;
+use std::sync::OnceLock;
+
+/// Rendered Markdown with scripts, iframes, forms, styles, event handlers,
+/// and `javascript:` URLs removed. Everything rendar itself emits, such as
+/// heading ids, containers, code tabs, task list checkboxes, and table
+/// alignment, is kept.
+pub fn sanitize_html(html: &str) -> String {
+    static BUILDER: OnceLock<Builder<'static>> = OnceLock::new();
+    BUILDER.get_or_init(builder).clean(html).to_string()
+}
+
+fn builder() -> Builder<'static> {
+    let mut builder = Builder::default();
+    builder
+        .add_tags(["section", "button", "input"])
+        .add_generic_attributes(["id", "class", "role"])
+        .add_generic_attribute_prefixes(["data-", "aria-"])
+        .add_tag_attributes("details", ["open"])
+        .add_tag_attributes("button", ["type"])
+        .add_tag_attributes("input", ["type", "checked", "disabled"])
+        .add_tag_attributes("th", ["style"])
+        .add_tag_attributes("td", ["style"])
+        .add_tag_attributes("ol", ["start"])
+        .link_rel(None)
+        .attribute_filter(|element, attribute, value| match (element, attribute) {
+            ("th" | "td", "style") => value.starts_with("text-align:").then_some(value.into()),
+            ("input", "type") => (value == "checkbox").then_some(value.into()),
+            ("button", "type") => (value == "button").then_some(value.into()),
+            _ => Some(value.into()),
+        });
+    builder
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_active_content() {
+        let html = concat!(
+            "<p onclick=\"steal()\">Hi<script>alert(1)</script></p>",
+            "<iframe src=\"https://evil.example\"></iframe>",
+            "<a href=\"javascript:alert(1)\">x</a><img src=\"a.png\" onerror=\"steal()\">",
+            "<p style=\"position:fixed\">Styled</p>"
+        );
+        assert_eq!(sanitize_html(html), "<p>Hi</p><a>x</a><img src=\"a.png\"><p>Styled</p>");
+    }
+
+    #[test]
+    fn keeps_rendered_markdown_markup() {
+        let html = concat!(
+            "<h2 id=\"setup\" data-source-line=\"3\">Setup</h2>",
+            "<div class=\"container tip\"><p class=\"container-title\">Tip</p></div>",
+            "<ul><li><input disabled=\"\" type=\"checkbox\" checked=\"\">Done</li></ul>",
+            "<table><thead><tr><th style=\"text-align: right\">N</th></tr></thead></table>",
+            "<button type=\"button\" role=\"tab\" data-tab=\"Rust\">Rust</button>",
+            "<a href=\"guide.html#install\">Install</a>"
+        );
+        assert_eq!(sanitize_html(html), html);
+    }
+}
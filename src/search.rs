@@ -0,0 +1,413 @@
+use crate::links::tag_attr;
+use crate::process_pool::content_key;
+use crate::render::escape_html;
+use crate::settings::Settings;
+use crate::site::relative_link;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+pub const SEARCH_INDEX_FILE: &str = "search-index.js";
+const TINYSEARCH_DIR: &str = "tinysearch";
+
+/// Where the search index lives and how the search UI queries it.
+pub trait SearchBackend: Send + Sync {
+    /// Writes the files the search UI needs into the output directory.
+    fn write(&self, output: &Path, sections: &[SearchSection]) -> Result<()>;
+
+    /// Sends the index to a remote service. Only `build` publishes, so
+    /// preview rebuilds don't touch the service.
+    fn publish(&self, _sections: &[SearchSection]) -> Result<()> {
+        Ok(())
+    }
+
+    /// Attributes for the `rendar-search` meta tag read by the search UI.
+    /// `root` is the relative path from the page to the site root.
+    fn meta_attrs(&self, root: &str) -> String;
+}
+
+/// Default backend: a static index the UI loads with a `<script>` tag and
+/// searches in the browser.
+pub struct JsonIndex;
+
+/// Compiles the index into a WebAssembly module with the `tinysearch` CLI,
+/// which keeps downloads small for large sites.
+pub struct TinySearch {
+    pub command: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ServiceKind {
+    Meilisearch,
+    Typesense,
+}
+
+/// Uploads the index to a hosted Meilisearch or Typesense instance that the
+/// UI queries directly with a search-only key.
+pub struct ExternalService {
+    pub kind: ServiceKind,
+    pub url: String,
+    pub index: String,
+    pub api_key_env: String,
+    pub search_key: String,
+}
+
+pub fn search_enabled(settings: &Settings) -> bool {
+    settings.search.is_some()
+}
+
+pub fn write_search(output: &Path, sections: &[SearchSection], settings: &Settings) -> Result<()> {
+    match &settings.search {
+        Some(backend) => backend.write(output, sections),
+        None => Ok(()),
+    }
+}
+
+pub fn publish_search(sections: &[SearchSection], settings: &Settings) -> Result<()> {
+    match &settings.search {
+        Some(backend) => backend.publish(sections),
+        None => Ok(()),
+    }
+}
+
+/// One searchable section: a heading and the text up to the next heading.
+/// Text before the first heading is indexed under the page title.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct SearchSection {
+    pub url: String,
+    pub page: String,
+    pub heading: String,
+    pub text: String,
+}
+
+pub fn page_sections(page_title: &str, output_rel: &Path, html: &str, settings: &Settings) -> Vec<SearchSection> {
+    let page_url = relative_link(Path::new(""), output_rel, settings);
+    let mut sections = Vec::new();
+    let mut push = |id: Option<&str>, heading: String, body: &str| {
+        let text = html_text(body);
+        if heading.is_empty() && text.is_empty() {
+            return;
+        }
+        sections.push(SearchSection {
+            url: match id {
+                Some(id) => format!("{page_url}#{id}"),
+                None => page_url.clone(),
+            },
+            page: page_title.to_string(),
+            heading: if heading.is_empty() {
+                page_title.to_string()
+            } else {
+                heading
+            },
+            text,
+        });
+    };
+
+    let mut current: (Option<&str>, String) = (None, String::new());
+    let mut rest = html;
+    while let Some((start, level)) = next_heading(rest) {
+        let Some(open_len) = rest[start..].find('>') else {
+            break;
+        };
+        let close = format!("</h{level}>");
+        let content_start = start + open_len + 1;
+        let Some(close_offset) = rest[content_start..].find(&close) else {
+            break;
+        };
+        push(current.0, std::mem::take(&mut current.1), &rest[..start]);
+        let tag = &rest[start..content_start];
+        current = (tag_attr(tag, "id"), html_text(&rest[content_start..content_start + close_offset]));
+        rest = &rest[content_start + close_offset + close.len()..];
+    }
+    push(current.0, current.1, rest);
+    sections
+}
+
+/// `<meta>` tag telling the built-in template how to search from the page
+/// at `page_rel`.
+pub fn search_head(page_rel: &Path, settings: &Settings) -> Option<String> {
+    let backend = settings.search.as_ref()?;
+    let page_dir = page_rel.parent().unwrap_or(Path::new(""));
+    let root = match relative_link(page_dir, Path::new(""), settings) {
+        root if root == "." => String::new(),
+        root => format!("{root}/"),
+    };
+    Some(format!(
+        "<meta name=\"rendar-search\" {} data-root=\"{}\">",
+        backend.meta_attrs(&root),
+        escape_html(&root)
+    ))
+}
+
+impl SearchBackend for JsonIndex {
+    /// Writes the index as a script assigning `window.rendarSearchIndex`, so
+    /// it loads with a `<script>` tag (which also works from `file://`).
+    fn write(&self, output: &Path, sections: &[SearchSection]) -> Result<()> {
+        let json = serde_json::to_string(sections).context("Failed to serialize search index")?;
+        let path = output.join(SEARCH_INDEX_FILE);
+        std::fs::write(&path, format!("window.rendarSearchIndex = {json};\n"))
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    fn meta_attrs(&self, root: &str) -> String {
+        format!(
+            "data-backend=\"json\" content=\"{}\"",
+            escape_html(&format!("{root}{SEARCH_INDEX_FILE}"))
+        )
+    }
+}
+
+#[derive(Serialize)]
+struct TinySearchDocument<'a> {
+    title: String,
+    url: &'a str,
+    body: &'a str,
+}
+
+impl SearchBackend for TinySearch {
+    fn write(&self, output: &Path, sections: &[SearchSection]) -> Result<()> {
+        let documents: Vec<_> = sections
+            .iter()
+            .map(|section| TinySearchDocument {
+                title: section_title(section),
+                url: &section.url,
+                body: &section.text,
+            })
+            .collect();
+        let corpus_dir = tempfile::tempdir().context("Failed to create tinysearch work directory")?;
+        let corpus = corpus_dir.path().join("index.json");
+        let json = serde_json::to_string(&documents).context("Failed to serialize search index")?;
+        std::fs::write(&corpus, json)
+            .with_context(|| format!("Failed to write {}", corpus.display()))?;
+        let status = Command::new(&self.command)
+            .arg("-m")
+            .arg("wasm")
+            .arg("-p")
+            .arg(output.join(TINYSEARCH_DIR))
+            .arg(&corpus)
+            .stdout(Stdio::null())
+            .status()
+            .with_context(|| format!("Failed to run {} (is tinysearch installed?)", self.command))?;
+        if !status.success() {
+            return Err(anyhow::anyhow!("{} failed ({status})", self.command));
+        }
+        Ok(())
+    }
+
+    fn meta_attrs(&self, root: &str) -> String {
+        format!(
+            "data-backend=\"tinysearch\" content=\"{}\"",
+            escape_html(&format!("{root}{TINYSEARCH_DIR}/tinysearch_engine.js"))
+        )
+    }
+}
+
+#[derive(Serialize)]
+struct ServiceDocument<'a> {
+    id: String,
+    #[serde(flatten)]
+    section: &'a SearchSection,
+}
+
+impl ExternalService {
+    fn publish_meilisearch(&self, key: &str, documents: &[ServiceDocument]) -> Result<()> {
+        let base = format!("{}/indexes/{}", self.url.trim_end_matches('/'), self.index);
+        let auth = format!("Bearer {key}");
+        let delete = ureq::delete(&format!("{base}/documents")).set("Authorization", &auth).call();
+        allow_not_found(delete, "Meilisearch delete")?;
+        let body = serde_json::to_string(documents).context("Failed to serialize search index")?;
+        ureq::post(&format!("{base}/documents?primaryKey=id"))
+            .set("Authorization", &auth)
+            .set("Content-Type", "application/json")
+            .send_string(&body)
+            .map_err(|err| service_error("Meilisearch upload", err))?;
+        Ok(())
+    }
+
+    fn publish_typesense(&self, key: &str, documents: &[ServiceDocument]) -> Result<()> {
+        let base = format!("{}/collections", self.url.trim_end_matches('/'));
+        let delete = ureq::delete(&format!("{base}/{}", self.index))
+            .set("X-TYPESENSE-API-KEY", key)
+            .call();
+        allow_not_found(delete, "Typesense delete")?;
+        let schema = serde_json::json!({
+            "name": self.index,
+            "fields": [
+                { "name": "url", "type": "string", "index": false },
+                { "name": "page", "type": "string" },
+                { "name": "heading", "type": "string" },
+                { "name": "text", "type": "string" },
+            ],
+        });
+        ureq::post(&base)
+            .set("X-TYPESENSE-API-KEY", key)
+            .set("Content-Type", "application/json")
+            .send_string(&schema.to_string())
+            .map_err(|err| service_error("Typesense collection create", err))?;
+        let mut lines = String::new();
+        for document in documents {
+            lines.push_str(&serde_json::to_string(document).context("Failed to serialize search index")?);
+            lines.push('\n');
+        }
+        ureq::post(&format!("{base}/{}/documents/import?action=create", self.index))
+            .set("X-TYPESENSE-API-KEY", key)
+            .set("Content-Type", "text/plain")
+            .send_string(&lines)
+            .map_err(|err| service_error("Typesense import", err))?;
+        Ok(())
+    }
+}
+
+impl SearchBackend for ExternalService {
+    fn write(&self, _output: &Path, _sections: &[SearchSection]) -> Result<()> {
+        Ok(())
+    }
+
+    fn publish(&self, sections: &[SearchSection]) -> Result<()> {
+        let key = std::env::var(&self.api_key_env)
+            .with_context(|| format!("Set {} to upload the search index", self.api_key_env))?;
+        let documents: Vec<_> = sections
+            .iter()
+            .map(|section| ServiceDocument {
+                id: content_key(&[section.url.as_bytes()])[..16].to_string(),
+                section,
+            })
+            .collect();
+        match self.kind {
+            ServiceKind::Meilisearch => self.publish_meilisearch(&key, &documents),
+            ServiceKind::Typesense => self.publish_typesense(&key, &documents),
+        }
+    }
+
+    fn meta_attrs(&self, _root: &str) -> String {
+        let backend = match self.kind {
+            ServiceKind::Meilisearch => "meilisearch",
+            ServiceKind::Typesense => "typesense",
+        };
+        format!(
+            "data-backend=\"{backend}\" content=\"{}\" data-index=\"{}\" data-key=\"{}\"",
+            escape_html(self.url.trim_end_matches('/')),
+            escape_html(&self.index),
+            escape_html(&self.search_key)
+        )
+    }
+}
+
+fn section_title(section: &SearchSection) -> String {
+    if section.heading == section.page {
+        section.page.clone()
+    } else {
+        format!("{} – {}", section.heading, section.page)
+    }
+}
+
+fn allow_not_found(result: Result<ureq::Response, ureq::Error>, action: &str) -> Result<()> {
+    match result {
+        Ok(_) | Err(ureq::Error::Status(404, _)) => Ok(()),
+        Err(err) => Err(service_error(action, err)),
+    }
+}
+
+fn service_error(action: &str, err: ureq::Error) -> anyhow::Error {
+    match err {
+        ureq::Error::Status(code, response) => {
+            let detail = response.into_string().unwrap_or_default();
+            anyhow::anyhow!("{action} returned {code}: {detail}")
+        }
+        err => anyhow::anyhow!("{action} failed: {err}"),
+    }
+}
+
+fn next_heading(html: &str) -> Option<(usize, char)> {
+    let mut offset = 0;
+    while let Some(index) = html[offset..].find("<h") {
+        let start = offset + index;
+        let mut chars = html[start + 2..].chars();
+        if let (Some(level @ '1'..='6'), Some(' ' | '>')) = (chars.next(), chars.next()) {
+            return Some((start, level));
+        }
+        offset = start + 2;
+    }
+    None
+}
+
+fn html_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for ch in html.chars() {
+        match ch {
+            '<' => {
+                in_tag = true;
+                text.push(' ');
+            }
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(ch),
+            _ => {}
+        }
+    }
+    let text = text
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_backends_for_the_search_ui() {
+        assert_eq!(
+            JsonIndex.meta_attrs("../"),
+            r#"data-backend="json" content="../search-index.js""#
+        );
+        let service = ExternalService {
+            kind: ServiceKind::Typesense,
+            url: "https://search.example.com/".to_string(),
+            index: "docs".to_string(),
+            api_key_env: "TYPESENSE_API_KEY".to_string(),
+            search_key: "public".to_string(),
+        };
+        assert_eq!(
+            service.meta_attrs("../"),
+            r#"data-backend="typesense" content="https://search.example.com" data-index="docs" data-key="public""#
+        );
+    }
+
+    #[test]
+    fn splits_pages_into_heading_sections() {
+        let html = concat!(
+            "<p>Intro &amp; overview.</p>\n",
+            "<h2 id=\"install\">Install <code>rendar</code></h2>\n<p>Run cargo.</p>\n",
+            "<h3 id=\"from-source\">From source</h3>\n<ul><li>Clone</li><li>Build</li></ul>\n",
+        );
+        let sections = page_sections("Guide", Path::new("docs/guide.html"), html, &Settings::default());
+        assert_eq!(
+            sections,
+            vec![
+                SearchSection {
+                    url: "docs/guide.html".to_string(),
+                    page: "Guide".to_string(),
+                    heading: "Guide".to_string(),
+                    text: "Intro & overview.".to_string(),
+                },
+                SearchSection {
+                    url: "docs/guide.html#install".to_string(),
+                    page: "Guide".to_string(),
+                    heading: "Install rendar".to_string(),
+                    text: "Run cargo.".to_string(),
+                },
+                SearchSection {
+                    url: "docs/guide.html#from-source".to_string(),
+                    page: "Guide".to_string(),
+                    heading: "From source".to_string(),
+                    text: "Clone Build".to_string(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,259 @@
+use anyhow::{Context, Result};
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderValue, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+use tower_http::compression::CompressionLayer;
+use tower_http::services::{ServeDir, ServeFile};
+
+const NOT_FOUND_PAGE: &str = "404.html";
+
+/// Serves a built site from `root` until Ctrl-C or SIGTERM: compressed
+/// when the client accepts gzip or brotli (preferring `.gz`/`.br` files
+/// next to the originals), with cache headers and ETags. Unknown paths get
+/// `404.html` when the site has one, or `index.html` with a 200 status for
+/// `spa`.
+pub async fn serve(root: PathBuf, listener: tokio::net::TcpListener, spa: bool) -> Result<()> {
+    let files = ServeDir::new(&root)
+        .append_index_html_on_directories(true)
+        .precompressed_gzip()
+        .precompressed_br();
+    let not_found = root.join(NOT_FOUND_PAGE);
+    let app = if spa {
+        Router::new().fallback_service(files.fallback(ServeFile::new(root.join("index.html"))))
+    } else if not_found.is_file() {
+        Router::new().fallback_service(files.not_found_service(ServeFile::new(not_found)))
+    } else {
+        Router::new().fallback_service(files)
+    };
+    let app = app
+        .layer(axum::middleware::from_fn_with_state(
+            Arc::new(CachePolicy::production(root)),
+            cache_headers,
+        ))
+        .layer(CompressionLayer::new());
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await
+        .context("Server failed")
+}
+
+/// Resolves on Ctrl-C, or on SIGTERM from a container runtime or service
+/// manager, letting in-flight requests finish.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+    eprintln!("Shutting down");
+}
+
+/// `Cache-Control` for the files of a site directory. Pages are
+/// revalidated on every load, so a rebuild or deploy shows up right away
+/// while unchanged pages are answered with 304.
+pub struct CachePolicy {
+    root: PathBuf,
+    /// For files whose name ends in a content hash, like `app.3f9a2c1b.js`.
+    fingerprinted: &'static str,
+    /// For other files, which keep their name when their content changes.
+    other: &'static str,
+}
+
+impl CachePolicy {
+    /// Fingerprinted assets are cached for a year, other files for an hour.
+    pub fn production(root: PathBuf) -> Self {
+        Self {
+            root,
+            fingerprinted: "public, max-age=31536000, immutable",
+            other: "public, max-age=3600",
+        }
+    }
+
+    /// Every file is revalidated on every use, so edited images and scripts
+    /// show up after a live reload even when their name looks like it ends
+    /// in a hash.
+    pub fn preview(root: PathBuf) -> Self {
+        Self {
+            root,
+            fingerprinted: "no-cache",
+            other: "no-cache",
+        }
+    }
+
+    fn cache_control(&self, path: &Path) -> &'static str {
+        if is_html(path) {
+            "no-cache"
+        } else if is_fingerprinted(path) {
+            self.fingerprinted
+        } else {
+            self.other
+        }
+    }
+}
+
+/// Adds `Cache-Control` and an `ETag` to files served from the site
+/// directory, and answers `If-None-Match` revalidations with 304.
+/// `Last-Modified` comes from `ServeDir`.
+pub async fn cache_headers(State(policy): State<Arc<CachePolicy>>, request: Request, next: Next) -> Response {
+    if !matches!(*request.method(), Method::GET | Method::HEAD) {
+        return next.run(request).await;
+    }
+    let Some(path) = site_file(&policy.root, request.uri().path()) else {
+        return next.run(request).await;
+    };
+    let etag = file_etag(&path);
+    let cache_control = policy.cache_control(&path);
+    if let Some(etag) = etag.as_deref()
+        && request
+            .headers()
+            .get(header::IF_NONE_MATCH)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|given| given.split(',').any(|tag| tag.trim() == etag || tag.trim() == "*"))
+    {
+        let mut response = StatusCode::NOT_MODIFIED.into_response();
+        set_header(&mut response, header::ETAG, etag);
+        set_header(&mut response, header::CACHE_CONTROL, cache_control);
+        return response;
+    }
+    let mut response = next.run(request).await;
+    if response.status() == StatusCode::OK {
+        if let Some(etag) = etag.as_deref() {
+            set_header(&mut response, header::ETAG, etag);
+        }
+        set_header(&mut response, header::CACHE_CONTROL, cache_control);
+    }
+    response
+}
+
+fn set_header(response: &mut Response, name: header::HeaderName, value: &str) {
+    if let Ok(value) = HeaderValue::from_str(value) {
+        response.headers_mut().insert(name, value);
+    }
+}
+
+/// The file a request path names under `root`, with `index.html` for
+/// folders. `None` for paths that escape `root` or don't exist.
+fn site_file(root: &Path, url_path: &str) -> Option<PathBuf> {
+    let decoded = percent_decode(url_path)?;
+    let rel = Path::new(decoded.trim_start_matches('/'));
+    if rel.components().any(|component| !matches!(component, Component::Normal(_))) {
+        return None;
+    }
+    let path = root.join(rel);
+    let path = if path.is_dir() { path.join("index.html") } else { path };
+    path.is_file().then_some(path)
+}
+
+/// A weak validator from the file's size and modification time, which
+/// change whenever a build rewrites the file.
+fn file_etag(path: &Path) -> Option<String> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some(format!("W/\"{:x}-{:x}\"", metadata.len(), modified.as_nanos()))
+}
+
+fn is_html(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("html") || extension.eq_ignore_ascii_case("htm"))
+}
+
+/// Whether the file name ends in a content hash, like `app.3f9a2c1b.js` or
+/// `style-5d41402abc4b.css`: 8 or more hex digits mixing letters and digits,
+/// so dates like `logo-20240101.png` don't count.
+fn is_fingerprinted(path: &Path) -> bool {
+    let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) else {
+        return false;
+    };
+    let hash = stem.rsplit(['.', '-', '_']).next().unwrap_or_default();
+    hash.len() >= 8
+        && hash.chars().all(|c| c.is_ascii_hexdigit())
+        && hash.chars().any(|c| c.is_ascii_digit())
+        && hash.chars().any(|c| c.is_ascii_alphabetic())
+}
+
+fn percent_decode(input: &str) -> Option<String> {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(idx + 1..idx + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            idx += 3;
+        } else {
+            decoded.push(bytes[idx]);
+            idx += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_request_paths_to_site_files() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::create_dir_all(dir.path().join("guide")).expect("guide dir");
+        std::fs::write(dir.path().join("guide/index.html"), "<p>Guide</p>").expect("guide");
+        std::fs::write(dir.path().join("release notes.html"), "<p>Notes</p>").expect("notes");
+
+        assert_eq!(site_file(dir.path(), "/guide/"), Some(dir.path().join("guide/index.html")));
+        assert_eq!(
+            site_file(dir.path(), "/release%20notes.html"),
+            Some(dir.path().join("release notes.html"))
+        );
+        assert_eq!(site_file(dir.path(), "/../etc/passwd"), None);
+        assert_eq!(site_file(dir.path(), "/missing.html"), None);
+    }
+
+    #[test]
+    fn picks_cache_control_by_file_kind() {
+        let production = CachePolicy::production(PathBuf::from("site"));
+        let preview = CachePolicy::preview(PathBuf::from("site"));
+        assert_eq!(production.cache_control(Path::new("site/guide/index.html")), "no-cache");
+        assert_eq!(
+            production.cache_control(Path::new("site/assets/app.3f9a2c1b.js")),
+            "public, max-age=31536000, immutable"
+        );
+        assert_eq!(preview.cache_control(Path::new("site/diagram-a1b2c3d4.png")), "no-cache");
+        assert_eq!(
+            production.cache_control(Path::new("site/style-5d41402abc4b.css")),
+            "public, max-age=31536000, immutable"
+        );
+        assert_eq!(production.cache_control(Path::new("site/logo-20240101.png")), "public, max-age=3600");
+        assert_eq!(preview.cache_control(Path::new("site/logo.png")), "no-cache");
+    }
+
+    #[test]
+    fn etag_changes_with_file_contents() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("app.js");
+        std::fs::write(&path, "one").expect("write");
+        let first = file_etag(&path).expect("etag");
+        assert!(first.starts_with("W/\""));
+        std::fs::write(&path, "three").expect("rewrite");
+        assert_ne!(file_etag(&path).expect("etag"), first);
+    }
+}
@@ -0,0 +1,234 @@
+use crate::config::{self, Config};
+use crate::csv_preview::CsvSettings;
+use crate::freshness::Freshness;
+use crate::i18n::Locale;
+use crate::obsidian::ObsidianSettings;
+use crate::provenance::{check_footer_metadata, Provenance};
+use crate::search::{self, SearchBackend};
+use crate::site::PathFilter;
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::path::{Component, Path, PathBuf};
+
+const DEFAULT_MARKDOWN_EXTENSIONS: &[&str] = &["md", "markdown"];
+
+/// An extra input root whose files appear under `mount` in the site.
+pub struct MountedRoot {
+    pub mount: PathBuf,
+    pub root: PathBuf,
+    /// The root's own `include`/`exclude` patterns and ignore files.
+    pub filter: PathFilter,
+}
+
+/// Config settings that change how pages are found, named, linked, and
+/// rendered. Built from the config when it is loaded and handed to each
+/// stage through `RenderOptions`.
+pub struct Settings {
+    /// Extensions of Markdown pages, lowercase and without the dot.
+    pub markdown_extensions: Vec<String>,
+    /// `[site] base_url`, the address sitemap.xml lists pages under and
+    /// heading permalink buttons copy.
+    pub base_url: Option<String>,
+    /// `[site] strip_number_prefixes`: drop `01-` style prefixes from page
+    /// and folder names in URLs and titles.
+    pub strip_number_prefixes: bool,
+    /// `[site] folder_indexes`: generate a listing `index.html` for folders
+    /// without an index or README.
+    pub folder_indexes: bool,
+    /// `[site] strip_title_numbers`: drop numbers like `10. ` from the start
+    /// of page titles in navigation and listings, after ordering by them.
+    pub strip_title_numbers: bool,
+    /// `[site] strip_title_heading`: leave out a page's opening H1, for
+    /// templates that show `{{title}}` as a heading of their own.
+    pub strip_title_heading: bool,
+    /// `[site] footer_metadata`: the items the built-in footer shows.
+    pub footer_metadata: Vec<String>,
+    /// `[build] pretty_urls`: write pages other than folder landing pages as
+    /// `name/index.html`, and link to folders (`name/`) instead of
+    /// `index.html` files.
+    pub pretty_urls: bool,
+    /// `[build] keep_markdown_links`: leave links to Markdown files pointing
+    /// at the `.md` files. Targets are still checked.
+    pub keep_markdown_links: bool,
+    /// `[markdown] sanitize_html`: strip scripts, iframes, event handlers,
+    /// and other active content from rendered Markdown, for sites built
+    /// from untrusted contributions.
+    pub sanitize_html: bool,
+    /// The roots after the first in `input = [...]`, merged into the site
+    /// under their mount points.
+    pub mounted_roots: Vec<MountedRoot>,
+    /// Source paths of generated pages, so links to them can be checked.
+    pub generated_pages: HashSet<PathBuf>,
+    /// `[freshness]`: show a "Last updated" note on each page, with a stale
+    /// badge once it is older than `stale_after_days`.
+    pub freshness: Option<Freshness>,
+    /// `[site] icon`: source image for the generated favicons and touch icons.
+    pub site_icon: Option<PathBuf>,
+    /// `[site] language` and `[site.strings]`: the UI strings pages are
+    /// rendered with.
+    pub locale: Locale,
+    /// `[search]`: the backend that builds the section-level index and
+    /// answers the search box.
+    pub search: Option<Box<dyn SearchBackend>>,
+    /// `[csv]`: how CSV pages are read, and whether their extra rows go to
+    /// row files.
+    pub csv: CsvSettings,
+    /// `lightbox`: the site-wide lightbox setting, which front matter can
+    /// override.
+    pub lightbox: Option<bool>,
+    /// `[obsidian]`: read wikilinks, embeds, and callouts in every page.
+    pub obsidian: Option<ObsidianSettings>,
+    /// Mark headings, paragraphs, list items, and quotes with the source
+    /// line they start on, for click-to-edit in preview.
+    pub source_lines: bool,
+    /// Number headings hierarchically (`1.`, `1.1`, `1.2.3`) on every
+    /// page, unless front matter `number_headings` says otherwise.
+    pub number_headings: bool,
+    /// When and from which commit the site is built, recorded once the
+    /// input is known.
+    pub provenance: Provenance,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            markdown_extensions: DEFAULT_MARKDOWN_EXTENSIONS.iter().map(ToString::to_string).collect(),
+            base_url: None,
+            strip_number_prefixes: true,
+            folder_indexes: false,
+            strip_title_numbers: false,
+            strip_title_heading: false,
+            footer_metadata: Vec::new(),
+            pretty_urls: false,
+            keep_markdown_links: false,
+            sanitize_html: false,
+            mounted_roots: Vec::new(),
+            generated_pages: HashSet::new(),
+            freshness: None,
+            site_icon: None,
+            locale: Locale::default(),
+            search: None,
+            csv: CsvSettings::default(),
+            lightbox: None,
+            obsidian: None,
+            source_lines: false,
+            number_headings: false,
+            provenance: Provenance::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn from_config(config: Option<&Config>) -> Result<Self> {
+        let mut settings = Self::default();
+        let Some(cfg) = config else {
+            return Ok(settings);
+        };
+        if let Some(extensions) = cfg.markdown_extensions.as_deref() {
+            settings.markdown_extensions = extensions
+                .iter()
+                .map(|ext| ext.trim_start_matches('.').to_ascii_lowercase())
+                .filter(|ext| !ext.is_empty())
+                .collect();
+        }
+        settings.number_headings = cfg.number_headings.unwrap_or(false);
+        if let Some(build) = cfg.build.as_ref() {
+            settings.pretty_urls = build.pretty_urls.unwrap_or(false);
+            settings.keep_markdown_links = build.keep_markdown_links.unwrap_or(false);
+        }
+        if let Some(site) = cfg.site.as_ref() {
+            settings.base_url = site.base_url.clone();
+            settings.site_icon = site.icon.clone();
+            if site.language.is_some() || !site.strings.is_empty() {
+                settings.locale = Locale::new(site.language.as_deref().unwrap_or("en"), &site.strings)?;
+            }
+            if let Some(strip) = site.strip_number_prefixes {
+                settings.strip_number_prefixes = strip;
+            }
+            settings.folder_indexes = site.folder_indexes.unwrap_or(false);
+            settings.strip_title_numbers = site.strip_title_numbers.unwrap_or(false);
+            settings.strip_title_heading = site.strip_title_heading.unwrap_or(false);
+            if let Some(items) = site.footer_metadata.as_deref() {
+                check_footer_metadata(items)?;
+                settings.footer_metadata = items.to_vec();
+            }
+        }
+        for root in cfg.extra_inputs() {
+            let mount = root.mount();
+            if mount.as_os_str().is_empty()
+                || mount.components().any(|component| !matches!(component, Component::Normal(_)))
+            {
+                return Err(anyhow::anyhow!(
+                    "Mount point {} must be a relative path inside the site",
+                    mount.display()
+                ));
+            }
+            settings.mounted_roots.push(MountedRoot {
+                mount,
+                root: root.path().to_path_buf(),
+                filter: PathFilter::default(),
+            });
+        }
+        if let Some(search) = cfg.search.as_ref()
+            && search.enabled.unwrap_or(true)
+        {
+            settings.search = Some(search_backend(search)?);
+        }
+        if !cfg.csv.is_empty() {
+            settings.csv = CsvSettings::new(&cfg.csv)?;
+        }
+        settings.sanitize_html = cfg
+            .markdown
+            .as_ref()
+            .and_then(|markdown| markdown.sanitize_html)
+            .unwrap_or(false);
+        settings.lightbox = cfg.lightbox;
+        if let Some(obsidian) = cfg.obsidian.as_ref()
+            && obsidian.enabled.unwrap_or(true)
+        {
+            settings.obsidian = Some(ObsidianSettings::new(obsidian.attachments.clone()));
+        }
+        settings.freshness = cfg.freshness.as_ref().map(|freshness| Freshness {
+            stale_after_days: freshness.stale_after_days,
+        });
+        Ok(settings)
+    }
+
+    pub fn is_markdown(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(OsStr::to_str)
+            .is_some_and(|ext| self.is_markdown_extension(ext))
+    }
+
+    pub fn is_markdown_extension(&self, ext: &str) -> bool {
+        self.markdown_extensions
+            .iter()
+            .any(|known| known.eq_ignore_ascii_case(ext))
+    }
+}
+
+fn search_backend(search: &config::SearchConfig) -> Result<Box<dyn SearchBackend>> {
+    let kind = match search.backend.unwrap_or_default() {
+        config::SearchBackendKind::Json => return Ok(Box::new(search::JsonIndex)),
+        config::SearchBackendKind::Tinysearch => {
+            return Ok(Box::new(search::TinySearch {
+                command: search.tinysearch.clone().unwrap_or_else(|| "tinysearch".to_string()),
+            }));
+        }
+        config::SearchBackendKind::Meilisearch => search::ServiceKind::Meilisearch,
+        config::SearchBackendKind::Typesense => search::ServiceKind::Typesense,
+    };
+    let required = |value: &Option<String>, name: &str| {
+        value
+            .clone()
+            .with_context(|| format!("[search] {name} is required for the {kind:?} backend"))
+    };
+    Ok(Box::new(search::ExternalService {
+        kind,
+        url: required(&search.url, "url")?,
+        index: required(&search.index, "index")?,
+        api_key_env: required(&search.api_key_env, "api_key_env")?,
+        search_key: required(&search.search_key, "search_key")?,
+    }))
+}
@@ -0,0 +1,138 @@
+use crate::diagnostics::Diagnostic;
+use crate::links::{resolve_local, rewrite_attr};
+use crate::render::{front_matter_value, render_markdown_source, strip_front_matter};
+use crate::settings::Settings;
+use crate::site::{relative_link, site_rel_path};
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+pub const SIDEBAR_FILE: &str = "_sidebar.md";
+
+pub struct Sidebar {
+    dir: PathBuf,
+    html: String,
+    replace: bool,
+}
+
+pub fn is_sidebar(path: &Path) -> bool {
+    path.file_name().and_then(OsStr::to_str) == Some(SIDEBAR_FILE)
+}
+
+pub fn load_sidebar(
+    path: &Path,
+    input_root: &Path,
+    index_dirs: &HashSet<PathBuf>,
+    settings: &Settings,
+) -> Result<(Sidebar, Vec<Diagnostic>)> {
+    let markdown = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read sidebar {}", path.display()))?;
+    let replace = front_matter_value(&markdown, "sidebar")
+        .map(|value| value.eq_ignore_ascii_case("replace"))
+        .unwrap_or(false);
+    let rendered = render_markdown_source(strip_front_matter(&markdown), path, input_root, index_dirs, settings);
+    let dir = site_rel_path(input_root, path, settings)
+        .and_then(|rel| rel.parent().map(Path::to_path_buf))
+        .unwrap_or_default();
+    Ok((
+        Sidebar {
+            dir,
+            html: rendered.html,
+            replace,
+        },
+        rendered.diagnostics,
+    ))
+}
+
+pub fn nearest_sidebar<'a>(
+    sidebars: &'a HashMap<PathBuf, Sidebar>,
+    dir: &Path,
+) -> Option<&'a Sidebar> {
+    let mut current = Some(dir);
+    while let Some(dir) = current {
+        if let Some(sidebar) = sidebars.get(dir) {
+            return Some(sidebar);
+        }
+        current = dir.parent();
+    }
+    None
+}
+
+pub fn apply_sidebar(nav_html: String, sidebar: Option<&Sidebar>, page_dir: &Path, settings: &Settings) -> String {
+    let Some(sidebar) = sidebar else {
+        return nav_html;
+    };
+    let custom = format!(
+        r#"<div class="nav-section nav-custom">{}</div>"#,
+        rebase_links(&sidebar.html, &sidebar.dir, page_dir, settings)
+    );
+    if sidebar.replace {
+        custom
+    } else {
+        nav_html + &custom
+    }
+}
+
+fn rebase_links(html: &str, from_dir: &Path, to_dir: &Path, settings: &Settings) -> String {
+    if from_dir == to_dir {
+        return html.to_string();
+    }
+    let html = rewrite_attr(html, "href", |value| rebase_value(value, from_dir, to_dir, settings));
+    rewrite_attr(&html, "src", |value| rebase_value(value, from_dir, to_dir, settings))
+}
+
+fn rebase_value(value: &str, from_dir: &Path, to_dir: &Path, settings: &Settings) -> Option<String> {
+    if value.starts_with(['#', '/']) || has_scheme(value) {
+        return None;
+    }
+    let split = value.find(['?', '#']).unwrap_or(value.len());
+    let (path, suffix) = value.split_at(split);
+    let target = resolve_local(path, from_dir)?;
+    let mut rebased = relative_link(to_dir, &target, settings);
+    if path.ends_with('/') && !rebased.ends_with('/') {
+        rebased.push('/');
+    }
+    rebased.push_str(suffix);
+    Some(rebased)
+}
+
+fn has_scheme(value: &str) -> bool {
+    match value.find(':') {
+        Some(colon) => !value[..colon].contains('/'),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebases_sidebar_links_for_nested_pages() {
+        let html = r#"<a href="intro.html#setup">Intro</a><a href="https://example.com">Ext</a><img src="../logo.png">"#;
+        let rebased = rebase_links(html, Path::new("guide"), Path::new("guide/advanced"), &Settings::default());
+        assert_eq!(
+            rebased,
+            r#"<a href="../intro.html#setup">Intro</a><a href="https://example.com">Ext</a><img src="../../logo.png">"#
+        );
+    }
+
+    #[test]
+    fn appends_or_replaces_nav() {
+        let sidebar = Sidebar {
+            dir: PathBuf::new(),
+            html: "<p>Custom</p>".to_string(),
+            replace: false,
+        };
+        let appended = apply_sidebar("<nav/>".to_string(), Some(&sidebar), Path::new(""), &Settings::default());
+        assert_eq!(appended, r#"<nav/><div class="nav-section nav-custom"><p>Custom</p></div>"#);
+
+        let sidebar = Sidebar {
+            replace: true,
+            ..sidebar
+        };
+        let replaced = apply_sidebar("<nav/>".to_string(), Some(&sidebar), Path::new(""), &Settings::default());
+        assert_eq!(replaced, r#"<div class="nav-section nav-custom"><p>Custom</p></div>"#);
+    }
+}
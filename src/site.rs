@@ -1,687 +1,3005 @@
-use crate::csv_preview::{csv_extra_head, render_csv_file};
-use crate::render::{first_heading_title, render_markdown_file, DocMode};
+use crate::a11y::{check_a11y, csv_header_warning};
+use crate::children::{children_list, expand_children, has_directive, ChildLink};
+use crate::csv_preview::{csv_extra_head, csv_has_header, is_csv_sidecar, render_csv_file};
+use crate::diagnostics::{print_diagnostic, Diagnostic};
+use crate::duplicates::duplicate_warnings;
+use crate::empty_input::{empty_input_report, placeholder_html};
+use crate::freshness::freshness_html;
+use crate::hooks::{run_hook, PageHook};
+use crate::process_pool::content_key;
+use crate::hygiene::check_hygiene;
+use crate::icons::{generate_icons, icon_links};
+use crate::lightbox::lightbox_head;
+use crate::links::{namespace_anchors, rebase_resources};
+use crate::nav::{breadcrumbs_html, nav_html, NavLink, NavPage, NavSection, NavTree, PageData, PageNav};
+use crate::page_views::page_view_script;
+use crate::plugins::Plugins;
+use crate::render::{
+    escape_html, front_matter_entries, front_matter_value, page_title, render_markdown_source, DocMode,
+    RenderedPage,
+};
+use crate::data_pages::is_generator_file;
+use crate::redirects::{is_redirects_file, load_redirects, write_redirects};
+use crate::search::{page_sections, search_enabled, search_head, write_search, SearchSection};
+use crate::settings::Settings;
+use crate::sitemap::write_sitemap;
+use crate::sidebar::{apply_sidebar, is_sidebar, load_sidebar, nearest_sidebar, Sidebar};
 use crate::slides::{slides_extra_body, slides_extra_head};
-use crate::template::Template;
+use crate::template::{template_json, Template, TemplateData};
+use crate::todos::todo_warnings;
 use anyhow::{Context, Result};
 use globset::GlobSet;
-use std::collections::{HashMap, HashSet};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::cell::RefCell;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::ffi::OsStr;
 use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 use walkdir::WalkDir;
 
+#[derive(Clone)]
 pub struct RenderOptions<'a> {
     pub live_reload: bool,
     pub heartbeat: bool,
     pub template: &'a Template,
-    pub exclude: Option<&'a GlobSet>,
+    pub filter: &'a PathFilter,
     pub csv_max_rows: Option<usize>,
+    pub generated: &'a Generated,
+    pub page_views: Option<&'a str>,
+    pub pre_page_hook: Option<PageHook<'a>>,
+    pub plugins: &'a Plugins,
+    pub settings: &'a Settings,
 }
 
-#[derive(Clone)]
-struct PageEntry {
-    rel_path: PathBuf,
-    output_rel: PathBuf,
-    title: String,
-    is_index: bool,
-    is_readme: bool,
+#[derive(Clone, Default)]
+pub struct PathFilter {
+    pub include: Option<IncludeFilter>,
+    pub exclude: Option<GlobSet>,
+    pub include_hidden: Option<GlobSet>,
+    /// Rules from ignore files, keyed by the folder (relative to the input)
+    /// they apply to, deepest folders first.
+    pub ignore_files: Vec<(PathBuf, Gitignore)>,
 }
 
-struct SiteMap {
-    pages_by_dir: HashMap<PathBuf, Vec<PageEntry>>,
-    pages_by_path: HashMap<PathBuf, PageEntry>,
-    index_dirs: HashSet<PathBuf>,
-    landing_dirs: HashSet<PathBuf>,
+/// Limits the input to paths matching `include` patterns. A matching folder
+/// brings everything inside it, and folders outside the patterns' literal
+/// prefixes are never walked.
+#[derive(Clone)]
+pub struct IncludeFilter {
+    globs: GlobSet,
+    roots: Vec<PathBuf>,
 }
 
-pub fn build_site(input: &Path, output: &Path, options: &RenderOptions<'_>) -> Result<()> {
-    std::fs::create_dir_all(output)
-        .with_context(|| format!("Failed to create output directory {}", output.display()))?;
-
-    let site_map = build_site_map(input, options.exclude);
-
-    let preview_body = preview_extra_body(options.live_reload, options.heartbeat);
-
-    for entry in walk_entries(input, options.exclude) {
-        let path = entry.path();
-        if path == input {
-            continue;
+impl IncludeFilter {
+    pub fn new(patterns: &[String]) -> Result<Option<Self>> {
+        if patterns.is_empty() {
+            return Ok(None);
+        }
+        let mut builder = globset::GlobSetBuilder::new();
+        let mut roots = Vec::new();
+        for pattern in patterns {
+            let pattern = pattern.trim_end_matches('/');
+            builder.add(
+                globset::Glob::new(pattern).with_context(|| format!("Invalid include pattern: {pattern}"))?,
+            );
+            roots.push(
+                pattern
+                    .split('/')
+                    .take_while(|part| !part.contains(['*', '?', '[', '{']))
+                    .collect::<PathBuf>(),
+            );
         }
+        Ok(Some(Self {
+            globs: builder.build()?,
+            roots,
+        }))
+    }
 
-        if is_within(path, output) {
-            continue;
+    fn includes(&self, rel_path: &Path, is_dir: bool) -> bool {
+        if is_dir
+            && self
+                .roots
+                .iter()
+                .any(|root| root.starts_with(rel_path) || rel_path.starts_with(root))
+        {
+            return true;
         }
+        rel_path.ancestors().any(|prefix| !prefix.as_os_str().is_empty() && self.globs.is_match(prefix))
+    }
+}
 
-        let rel_path = path.strip_prefix(input).with_context(|| {
-            format!(
-                "Failed to compute relative path for {}",
-                path.display()
-            )
-        })?;
+/// Folders skipped like hidden ones unless listed in `include_hidden`.
+const SKIPPED_DIRS: &[&str] = &["node_modules"];
+pub const RENDARIGNORE: &str = ".rendarignore";
 
-        if entry.file_type().is_dir() {
-            let out_dir = output.join(rel_path);
-            std::fs::create_dir_all(&out_dir).with_context(|| {
-                format!(
-                    "Failed to create output directory {}",
-                    out_dir.display()
-                )
-            })?;
-            continue;
-        }
+#[derive(Default)]
+pub struct Generated {
+    pub pages: Vec<GeneratedPage>,
+    pub assets: Vec<GeneratedAsset>,
+}
 
-        if is_markdown(path) {
-            let rendered = render_markdown_file(path, input, &site_map.index_dirs)?;
-            let rel_path = rel_path.to_path_buf();
-            let page_entry = match site_map.pages_by_path.get(&rel_path) {
-                Some(entry) => entry,
-                None => continue,
-            };
-            let nav_html = match rendered.mode {
-                DocMode::Slides => String::new(),
-                DocMode::Document => build_nav_html(page_entry, &site_map),
+pub struct GeneratedPage {
+    pub rel_path: PathBuf,
+    pub title: String,
+    pub html: String,
+    pub mode: DocMode,
+}
+
+pub struct GeneratedAsset {
+    pub rel_path: PathBuf,
+    pub source: PathBuf,
+}
+
+impl PathFilter {
+    fn is_hidden(&self, rel_path: &Path) -> bool {
+        let mut prefix = PathBuf::new();
+        for component in rel_path.components() {
+            let Component::Normal(name) = component else {
+                continue;
             };
-            let breadcrumbs_html = build_breadcrumbs_html(page_entry, &site_map);
-            let mut extra_head = None;
-            let mut extra_body = preview_body.clone();
-            if rendered.mode == DocMode::Slides {
-                extra_head = Some(slides_extra_head());
-                extra_body = merge_extra_body(extra_body.as_deref(), Some(slides_extra_body()));
+            prefix.push(name);
+            if (is_dot_name(name) || name.to_str().is_some_and(|name| SKIPPED_DIRS.contains(&name)))
+                && !self
+                    .include_hidden
+                    .as_ref()
+                    .is_some_and(|include| include.is_match(&prefix))
+            {
+                return true;
             }
-            let full_html = options
-                .template
-                .render(
-                    &page_entry.title,
-                    &rendered.html,
-                    &nav_html,
-                    &breadcrumbs_html,
-                    extra_head.as_deref(),
-                    extra_body.as_deref(),
-                );
-            let out_path = output.join(&page_entry.output_rel);
-            write_html(&out_path, &full_html)?;
-            if page_entry.is_readme {
-                if should_write_index(path, input, &site_map.index_dirs) {
-                    let index_path = output
-                        .join(rel_path.parent().unwrap_or(Path::new("")))
-                        .join("index.html");
-                    write_html(&index_path, &full_html)?;
+        }
+        false
+    }
+
+    fn is_excluded(&self, rel_path: &Path) -> bool {
+        self.exclude
+            .as_ref()
+            .is_some_and(|exclude| exclude.is_match(rel_path))
+    }
+
+    /// Applies ignore files like git does: each folder on the way down is
+    /// checked against the nearest rules first, and nothing below an
+    /// ignored folder can be re-included.
+    fn is_ignored(&self, rel_path: &Path, is_dir: bool) -> bool {
+        let mut prefix = PathBuf::new();
+        let mut components = rel_path.components().peekable();
+        while let Some(component) = components.next() {
+            prefix.push(component);
+            let prefix_is_dir = is_dir || components.peek().is_some();
+            for (dir, rules) in &self.ignore_files {
+                let Ok(local) = prefix.strip_prefix(dir) else {
+                    continue;
+                };
+                if local.as_os_str().is_empty() {
+                    continue;
+                }
+                match rules.matched(local, prefix_is_dir) {
+                    Match::Ignore(_) => return true,
+                    Match::Whitelist(_) => break,
+                    Match::None => {}
                 }
             }
-            for warning in rendered.warnings {
-                eprintln!("Warning: {warning}");
-            }
-        } else if is_csv(path) {
-            let rendered = render_csv_file(path, options.csv_max_rows)?;
-            let rel_path = rel_path.to_path_buf();
-            let page_entry = match site_map.pages_by_path.get(&rel_path) {
-                Some(entry) => entry,
-                None => continue,
-            };
-            let nav_html = build_nav_html(page_entry, &site_map);
-            let breadcrumbs_html = build_breadcrumbs_html(page_entry, &site_map);
-            let full_html = options.template.render(
-                &page_entry.title,
-                &rendered,
-                &nav_html,
-                &breadcrumbs_html,
-                Some(csv_extra_head()),
-                preview_body.as_deref(),
-            );
-            let out_path = output.join(&page_entry.output_rel);
-            write_html(&out_path, &full_html)?;
-        } else {
-            let out_path = output.join(rel_path);
-            if let Some(parent) = out_path.parent() {
-                std::fs::create_dir_all(parent).with_context(|| {
-                    format!(
-                        "Failed to create output directory {}",
-                        parent.display()
-                    )
-                })?;
-            }
-            std::fs::copy(path, &out_path).with_context(|| {
-                format!(
-                    "Failed to copy asset from {} to {}",
-                    path.display(),
-                    out_path.display()
-                )
-            })?;
         }
+        false
     }
-
-    Ok(())
 }
 
-pub fn check_site(input: &Path, excludes: Option<&GlobSet>) -> Result<usize> {
-    let site_map = build_site_map(input, excludes);
-    let mut warnings = 0usize;
-
-    for entry in walk_entries(input, excludes) {
-        let path = entry.path();
-        if path == input {
+/// Reads every `.rendarignore` (and `.gitignore` when `gitignore` is set)
+/// in folders the filter doesn't already skip.
+pub fn load_ignore_files(input: &Path, filter: &PathFilter, gitignore: bool) -> Result<Vec<(PathBuf, Gitignore)>> {
+    let names: &[&str] = if gitignore { &[".gitignore", RENDARIGNORE] } else { &[RENDARIGNORE] };
+    let mut rules = Vec::new();
+    for entry in walk_entries(input, filter) {
+        if !entry.file_type().is_dir() {
             continue;
         }
-
-        if is_markdown(path) {
-            let rendered = render_markdown_file(path, input, &site_map.index_dirs)?;
-            for warning in rendered.warnings {
-                eprintln!("Warning: {warning}");
-                warnings += 1;
+        let mut builder = GitignoreBuilder::new(entry.path());
+        let mut found = false;
+        for name in names {
+            let path = entry.path().join(name);
+            if path.is_file() {
+                if let Some(err) = builder.add(&path) {
+                    return Err(err).with_context(|| format!("Failed to read {}", path.display()));
+                }
+                found = true;
             }
         }
+        if found {
+            let rel_dir = entry.path().strip_prefix(input).unwrap_or(Path::new("")).to_path_buf();
+            let gitignore = builder
+                .build()
+                .with_context(|| format!("Invalid ignore rules in {}", entry.path().display()))?;
+            rules.push((rel_dir, gitignore));
+        }
     }
+    rules.sort_by_key(|(dir, _)| std::cmp::Reverse(dir.components().count()));
+    Ok(rules)
+}
 
-    Ok(warnings)
+impl Generated {
+    pub fn extend(&mut self, other: Generated) {
+        self.pages.extend(other.pages);
+        self.assets.extend(other.assets);
+    }
 }
 
-fn is_markdown(path: &Path) -> bool {
-    matches!(
-        path.extension()
-            .and_then(OsStr::to_str)
-            .map(|ext| ext.to_ascii_lowercase())
-            .as_deref(),
-        Some("md") | Some("markdown")
-    )
+#[derive(Clone)]
+struct PageEntry {
+    rel_path: PathBuf,
+    output_rel: PathBuf,
+    title: String,
+    is_index: bool,
+    is_readme: bool,
+    /// `hidden: true` in front matter: built and linkable, but left out of
+    /// navigation, search, and page lists.
+    hidden: bool,
+    /// The number stripped from a title like `10. Deployment`, which
+    /// orders the page like a `10-` file name prefix would.
+    title_order: Option<u64>,
 }
 
-fn is_csv(path: &Path) -> bool {
-    matches!(
-        path.extension()
-            .and_then(OsStr::to_str)
-            .map(|ext| ext.to_ascii_lowercase())
-            .as_deref(),
-        Some("csv")
-    )
+struct SiteMap {
+    pages_by_dir: HashMap<PathBuf, Vec<PageEntry>>,
+    pages_by_path: HashMap<PathBuf, PageEntry>,
+    index_dirs: HashSet<PathBuf>,
+    landing_dirs: HashSet<PathBuf>,
 }
 
-pub fn is_excluded_path(path: &Path, input: &Path, excludes: Option<&GlobSet>) -> bool {
-    let Some(excludes) = excludes else {
-        return false;
-    };
-    if path == input {
-        return false;
+#[derive(Debug, Default)]
+pub struct BuildSummary {
+    pub pages: usize,
+    pub assets: usize,
+    pub diagnostics: Vec<Diagnostic>,
+    pub page_timings: Vec<(PathBuf, Duration)>,
+    pub search_sections: Vec<SearchSection>,
+    /// Input assets copied to the output, relative to the input.
+    pub copied_assets: Vec<PathBuf>,
+}
+
+impl BuildSummary {
+    pub fn warnings(&self) -> usize {
+        self.diagnostics.len()
     }
-    let rel_path = match path.strip_prefix(input) {
-        Ok(rel) => rel,
-        Err(_) => return false,
-    };
-    if rel_path.as_os_str().is_empty() {
-        return false;
+
+    pub fn slowest_pages(&self, count: usize) -> Vec<&(PathBuf, Duration)> {
+        let mut timings: Vec<_> = self.page_timings.iter().collect();
+        timings.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        timings.truncate(count);
+        timings
     }
-    excludes.is_match(rel_path)
 }
 
-pub fn is_ignored_path(path: &Path, input: &Path, excludes: Option<&GlobSet>) -> bool {
-    is_in_dot_directory(path, input) || is_excluded_path(path, input, excludes)
+/// Page titles, copied assets, and search sections from the last preview
+/// build, used to work out which pages a change affects and which outputs
+/// went stale.
+#[derive(Debug, Default)]
+pub struct SiteSnapshot {
+    titles: HashMap<PathBuf, String>,
+    hidden: HashSet<PathBuf>,
+    neighbours: HashMap<PathBuf, (Option<PathBuf>, Option<PathBuf>)>,
+    assets: HashSet<PathBuf>,
+    search_sections: Vec<SearchSection>,
 }
 
-fn is_in_dot_directory(path: &Path, input: &Path) -> bool {
-    if path == input {
-        return false;
-    }
-    let rel_path = match path.strip_prefix(input) {
-        Ok(rel) => rel,
-        Err(_) => return false,
-    };
-    if rel_path.as_os_str().is_empty() {
-        return false;
+impl SiteSnapshot {
+    fn matches_pages(&self, site_map: &SiteMap) -> bool {
+        self.titles.len() == site_map.pages_by_path.len()
+            && site_map
+                .pages_by_path
+                .keys()
+                .all(|rel_path| self.titles.contains_key(rel_path))
     }
+}
 
-    let include_last = path.is_dir();
-    let mut components = rel_path.components().peekable();
-    while let Some(component) = components.next() {
-        let is_last = components.peek().is_none();
-        if is_last && !include_last {
-            break;
-        }
-        if let Component::Normal(name) = component
-            && is_dot_dir_name(name)
-        {
-            return true;
-        }
-    }
-    false
+pub fn build_site(
+    input: &Path,
+    output: &Path,
+    options: &RenderOptions<'_>,
+) -> Result<BuildSummary> {
+    SitePipeline::new(input, options)?.build(output)
 }
 
-fn is_dot_dir_name(name: &OsStr) -> bool {
-    name.to_str()
-        .map(|name| name.starts_with('.') && name != "." && name != "..")
-        .unwrap_or(false)
+pub fn check_site(
+    input: &Path,
+    options: &RenderOptions<'_>,
+    hygiene: bool,
+    todos: bool,
+    a11y: bool,
+    duplicates: Option<f64>,
+) -> Result<Vec<Diagnostic>> {
+    SitePipeline::new(input, options)?
+        .with_hygiene(hygiene)
+        .with_todos(todos)
+        .with_a11y(a11y)
+        .with_printing(false)
+        .with_duplicates(duplicates)
+        .check()
 }
 
-fn is_readme(path: &Path) -> bool {
-    path.file_stem()
-        .and_then(OsStr::to_str)
-        .map(|stem| stem.eq_ignore_ascii_case("readme"))
-        .unwrap_or(false)
-        && is_markdown(path)
+/// Shared analysis for `build` and `check`: both walk the same sources and
+/// render every page the same way; `build` adds the write stage.
+pub struct SitePipeline<'a> {
+    input: &'a Path,
+    options: &'a RenderOptions<'a>,
+    site_map: SiteMap,
+    generated: Vec<&'a GeneratedPage>,
+    /// Listing pages for folders without an index or README.
+    folder_indexes: Vec<PathBuf>,
+    sidebars: HashMap<PathBuf, Sidebar>,
+    page_data: HashMap<PathBuf, PageData>,
+    site_json: String,
+    /// The previous and next visible page of each page in navigation
+    /// order, by source path.
+    neighbours: HashMap<PathBuf, (Option<PathBuf>, Option<PathBuf>)>,
+    hygiene: bool,
+    todos: bool,
+    a11y: bool,
+    duplicates: Option<f64>,
+    print: bool,
+    diagnostics: RefCell<Vec<Diagnostic>>,
 }
 
-fn is_index(path: &Path) -> bool {
-    path.file_stem()
-        .and_then(OsStr::to_str)
-        .map(|stem| stem.eq_ignore_ascii_case("index"))
-        .unwrap_or(false)
-        && is_markdown(path)
+enum Source<'p> {
+    Dir(PathBuf),
+    Page { path: PathBuf, entry: &'p PageEntry },
+    Asset { path: PathBuf, rel_path: PathBuf },
 }
 
-pub fn output_rel_path(
-    path: &Path,
-    input_root: &Path,
-    index_dirs: &HashSet<PathBuf>,
-) -> Option<PathBuf> {
-    if is_markdown(path) {
-        let rel = path.strip_prefix(input_root).ok()?;
-        if is_readme(path) && should_write_index(path, input_root, index_dirs) {
-            Some(rel.parent().unwrap_or(Path::new("")).join("index.html"))
+enum PageBody {
+    Markdown(RenderedPage),
+    Csv(String),
+}
+
+impl<'a> SitePipeline<'a> {
+    pub fn new(input: &'a Path, options: &'a RenderOptions<'a>) -> Result<Self> {
+        crate::obsidian::index_vault(input, options.filter, options.settings);
+        let mut site_map = build_site_map(input, options.filter, options.settings);
+        let generated = add_generated_pages(&mut site_map, &options.generated.pages, options.settings);
+        let folder_indexes = if options.settings.folder_indexes {
+            add_folder_indexes(input, &mut site_map, options.settings)
         } else {
-            Some(rel.with_extension("html"))
-        }
-    } else if is_csv(path) {
-        let rel = path.strip_prefix(input_root).ok()?;
-        Some(rel.with_extension("html"))
-    } else {
-        None
+            Vec::new()
+        };
+        let mut diagnostics = output_collisions(&site_map, options.settings);
+        diagnostics.iter().for_each(print_diagnostic);
+        let sidebars = load_sidebars(input, options.filter, &site_map.index_dirs, &mut diagnostics, options.settings)?;
+        let (page_data, site_json) = if options.template.uses_data() {
+            template_site_data(input, &site_map, options.settings)
+        } else {
+            (HashMap::new(), String::new())
+        };
+        let neighbours = page_neighbours(&site_map);
+        Ok(Self {
+            input,
+            options,
+            site_map,
+            generated,
+            folder_indexes,
+            sidebars,
+            page_data,
+            site_json,
+            neighbours,
+            hygiene: false,
+            todos: false,
+            a11y: false,
+            duplicates: None,
+            print: true,
+            diagnostics: RefCell::new(diagnostics),
+        })
     }
-}
 
-fn write_html(path: &Path, content: &str) -> Result<()> {
-    if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent)
-            .with_context(|| format!("Failed to create output directory {}", parent.display()))?;
+    pub fn with_hygiene(mut self, hygiene: bool) -> Self {
+        self.hygiene = hygiene;
+        self
     }
-    std::fs::write(path, content)
-        .with_context(|| format!("Failed to write output file {}", path.display()))
-}
 
-fn build_site_map(input: &Path, excludes: Option<&GlobSet>) -> SiteMap {
-    let mut pages_by_dir: HashMap<PathBuf, Vec<PageEntry>> = HashMap::new();
-    let mut pages_by_path: HashMap<PathBuf, PageEntry> = HashMap::new();
-    let mut index_dirs = HashSet::new();
-    let mut landing_dirs = HashSet::new();
+    pub fn with_todos(mut self, todos: bool) -> Self {
+        self.todos = todos;
+        self
+    }
 
-    for entry in walk_entries(input, excludes) {
-        if entry.file_type().is_file() && (is_markdown(entry.path()) || is_csv(entry.path())) {
-            let path = entry.path();
-            let rel_path = match path.strip_prefix(input) {
-                Ok(rel) => rel.to_path_buf(),
-                Err(_) => continue,
-            };
-            let rel_dir = rel_path.parent().unwrap_or(Path::new("")).to_path_buf();
-            let is_index = is_index(path);
-            let is_readme = is_readme(path);
-            let title = title_from_path(path);
-            let output_rel = rel_path.with_extension("html");
-            let page = PageEntry {
-                rel_path: rel_path.clone(),
-                output_rel,
-                title,
-                is_index,
-                is_readme,
-            };
-            pages_by_dir
-                .entry(rel_dir.clone())
-                .or_default()
-                .push(page.clone());
-            pages_by_path.insert(rel_path, page);
-            if is_index {
-                index_dirs.insert(rel_dir.clone());
-            }
-            if is_index || is_readme {
-                landing_dirs.insert(rel_dir);
-            }
-        }
+    pub fn with_a11y(mut self, a11y: bool) -> Self {
+        self.a11y = a11y;
+        self
     }
 
-    for pages in pages_by_dir.values_mut() {
-        pages.sort_by(|a, b| a.title.cmp(&b.title));
+    /// Compare pages with each other and report pairs at least this similar.
+    pub fn with_duplicates(mut self, threshold: Option<f64>) -> Self {
+        self.duplicates = threshold;
+        self
     }
 
-    SiteMap {
-        pages_by_dir,
-        pages_by_path,
-        index_dirs,
-        landing_dirs,
+    /// Print diagnostics as they are found (the default), or leave that to
+    /// the caller.
+    pub fn with_printing(mut self, print: bool) -> Self {
+        self.print = print;
+        self
     }
-}
 
-fn walk_entries<'a>(
-    input: &'a Path,
-    excludes: Option<&'a GlobSet>,
-) -> impl Iterator<Item = walkdir::DirEntry> + 'a {
-    WalkDir::new(input)
-        .into_iter()
-        .filter_entry(move |entry| {
-            let path = entry.path();
-            if path == input {
-                return true;
-            }
-            if entry.file_type().is_dir() && is_dot_dir_name(entry.file_name()) {
-                return false;
-            }
-            !is_excluded_path(path, input, excludes)
-        })
-        .filter_map(Result::ok)
-}
+    /// Everything reported so far, in the order it was found.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.diagnostics.borrow().clone()
+    }
 
-pub fn collect_index_dirs(input: &Path, excludes: Option<&GlobSet>) -> HashSet<PathBuf> {
-    let mut dirs = HashSet::new();
-    for entry in walk_entries(input, excludes) {
-        if entry.file_type().is_file() && is_index(entry.path()) {
-            if let Ok(rel) = entry.path().parent().unwrap_or(input).strip_prefix(input) {
-                dirs.insert(rel.to_path_buf());
-            } else {
-                dirs.insert(PathBuf::new());
+    pub fn check(&self) -> Result<Vec<Diagnostic>> {
+        let mut markdown_pages = Vec::new();
+        for source in self.sources(None)? {
+            if let Source::Page { path, entry } = source {
+                self.analyze(&path, entry, None)?;
+                if self.duplicates.is_some() && self.options.settings.is_markdown(&path) {
+                    let source = std::fs::read_to_string(&path)
+                        .with_context(|| format!("Failed to read markdown file {}", path.display()))?;
+                    markdown_pages.push((path, source));
+                }
             }
         }
+        if let Some(threshold) = self.duplicates {
+            self.report(&duplicate_warnings(&markdown_pages, threshold));
+        }
+        load_redirects(self.input)?;
+        if let Some(report) = empty_input_report(self.input, self.options.filter, self.options.settings) {
+            self.report(&[report]);
+        }
+        Ok(self.diagnostics())
     }
-    dirs
-}
 
-fn should_write_index(path: &Path, input: &Path, index_dirs: &HashSet<PathBuf>) -> bool {
-    let parent = path.parent().unwrap_or(input);
-    let rel = parent.strip_prefix(input).unwrap_or(parent);
-    !index_dirs.contains(rel)
-}
+    pub fn build(&self, output: &Path) -> Result<BuildSummary> {
+        std::fs::create_dir_all(output)
+            .with_context(|| format!("Failed to create output directory {}", output.display()))?;
+
+        let input = self.input;
+        let options = self.options;
+        let site_map = &self.site_map;
+        let mut summary = BuildSummary {
+            assets: generate_icons(output, options.settings)?,
+            ..BuildSummary::default()
+        };
+        let preview_body = preview_extra_body(options.live_reload, options.heartbeat);
+        let mut sitemap_pages = Vec::new();
+
+        let sources = self.sources(Some(output))?;
+        let pages: Vec<(&Path, &PageEntry)> = sources
+            .iter()
+            .filter_map(|source| match source {
+                Source::Page { path, entry } => Some((path.as_path(), *entry)),
+                _ => None,
+            })
+            .collect();
+        run_pre_page_hooks(options, input, output, &pages)?;
+        for source in sources {
+            let (path, page_entry) = match source {
+                Source::Dir(rel_path) => {
+                    let out_dir = output.join(output_dir_rel(&rel_path, options.settings));
+                    std::fs::create_dir_all(&out_dir).with_context(|| {
+                        format!("Failed to create output directory {}", out_dir.display())
+                    })?;
+                    continue;
+                }
+                Source::Asset { path, rel_path } => {
+                    copy_asset(&path, &output.join(asset_output_rel(&rel_path, options.settings)))?;
+                    summary.assets += 1;
+                    summary.copied_assets.push(rel_path);
+                    continue;
+                }
+                Source::Page { path, entry } => (path, entry),
+            };
 
-fn build_nav_html(current: &PageEntry, site_map: &SiteMap) -> String {
-    let current_dir = current.rel_path.parent().unwrap_or(Path::new(""));
-    let from_dir = current.output_rel.parent().unwrap_or(Path::new(""));
-    let mut nav = String::new();
+            let started = Instant::now();
+            let (full_html, noindex) = self.render_page(
+                output,
+                &path,
+                page_entry,
+                preview_body.as_deref(),
+                &mut summary.search_sections,
+            )?;
+            self.write_page(output, page_entry, &full_html)?;
+            if !noindex && !page_entry.hidden {
+                sitemap_pages.push(page_entry.output_rel.clone());
+            }
+            summary.pages += 1;
+            summary
+                .page_timings
+                .push((page_entry.rel_path.clone(), started.elapsed()));
+        }
 
-    let pages = site_map.pages_by_dir.get(current_dir);
-    let mut page_items = Vec::new();
-    if let Some(pages) = pages {
-        for page in pages {
-            if page.rel_path == current.rel_path {
+        for page in &self.generated {
+            let Some(page_entry) = site_map.pages_by_path.get(&page.rel_path) else {
                 continue;
+            };
+            let nav_html = match page.mode {
+                DocMode::Slides => String::new(),
+                DocMode::Document => page_nav_html(page_entry, site_map, &self.sidebars, options.settings),
+            };
+            let breadcrumbs_html = build_breadcrumbs_html(page_entry, site_map, options.settings);
+            if search_enabled(options.settings) && page.mode == DocMode::Document {
+                summary
+                    .search_sections
+                    .extend(page_sections(&page_entry.title, &page_entry.output_rel, &page.html, options.settings));
+            }
+            let mut extra_head = self.page_extra_head(None, page_entry);
+            let mut extra_body = page_extra_body(preview_body.as_deref(), options, page_entry);
+            if page.mode == DocMode::Slides {
+                extra_head = merge_extra_body(extra_head.as_deref(), Some(&slides_extra_head()));
+                extra_body = merge_extra_body(extra_body.as_deref(), Some(slides_extra_body()));
+            }
+            let full_html = options.template.render(
+                &page_entry.title,
+                &page.html,
+                &nav_html,
+                &breadcrumbs_html,
+                extra_head.as_deref(),
+                extra_body.as_deref(),
+                None,
+                &self.template_data(page_entry),
+                options.settings,
+            );
+            write_html(&output.join(&page_entry.output_rel), &full_html)?;
+            sitemap_pages.push(page_entry.output_rel.clone());
+            let rel_dir = page.rel_path.parent().unwrap_or(Path::new(""));
+            if page_entry.is_readme && !site_map.index_dirs.contains(rel_dir) {
+                write_html(&output.join(dir_index_rel(rel_dir, options.settings)), &full_html)?;
             }
-            let href = relative_link(from_dir, &page.output_rel);
-            let label = html_escape(&page.title);
-            page_items.push(format!(r#"<li><a href="{}">{}</a></li>"#, href, label));
+            summary.pages += 1;
         }
-    }
 
-    let mut folder_items = Vec::new();
-    for dir in &site_map.landing_dirs {
-        if dir == current_dir {
-            continue;
+        for rel_path in &self.folder_indexes {
+            self.write_folder_index(output, rel_path, preview_body.as_deref())?;
+            summary.pages += 1;
         }
-        if dir.parent().unwrap_or(Path::new("")) == current_dir {
-            let folder_label = landing_title(dir, site_map).unwrap_or_else(|| display_dir_name(dir));
-            let target = dir.join("index.html");
-            let href = relative_link(from_dir, &target);
-            folder_items.push(format!(
-                r#"<li><a href="{}">{}</a></li>"#,
-                href,
-                html_escape(&folder_label)
-            ));
+
+        for asset in &options.generated.assets {
+            if input.join(&asset.rel_path).exists() {
+                continue;
+            }
+            copy_asset(&asset.source, &output.join(asset_output_rel(&asset.rel_path, options.settings)))?;
+            summary.assets += 1;
         }
-    }
-    folder_items.sort();
 
-    if !page_items.is_empty() {
-        nav.push_str(r#"<div class="nav-section">"#);
-        nav.push_str(r#"<div class="nav-title">Pages</div>"#);
-        nav.push_str(r#"<ul class="nav-list nav-pages">"#);
-        for item in page_items {
-            nav.push_str(&item);
+        if let Some(base_url) = options.settings.base_url.as_deref() {
+            sitemap_pages.sort();
+            write_sitemap(output, base_url, &sitemap_pages)?;
         }
-        nav.push_str("</ul></div>");
-    }
 
-    if !folder_items.is_empty() {
-        nav.push_str(r#"<div class="nav-section">"#);
-        nav.push_str(r#"<div class="nav-title">Folders</div>"#);
-        nav.push_str(r#"<ul class="nav-list nav-folders">"#);
-        for item in folder_items {
-            nav.push_str(&item);
+        let redirects = load_redirects(input)?;
+        if !redirects.is_empty() {
+            self.report(&write_redirects(input, output, &redirects, &self.output_pages(), options.settings)?);
         }
-        nav.push_str("</ul></div>");
-    }
 
-    nav
-}
+        if let Some(report) = empty_input_report(input, options.filter, options.settings) {
+            if options.live_reload && !output.join("index.html").exists() {
+                let html = options.template.render(
+                    "No pages",
+                    &placeholder_html(&report.to_string()),
+                    "",
+                    "",
+                    None,
+                    preview_body.as_deref(),
+                    None,
+                    &TemplateData::default(),
+                    options.settings,
+                );
+                write_html(&output.join("index.html"), &html)?;
+            }
+            self.report(&[report]);
+        }
 
-fn build_breadcrumbs_html(current: &PageEntry, site_map: &SiteMap) -> String {
-    let mut crumbs = Vec::new();
-    let current_dir = current.rel_path.parent().unwrap_or(Path::new(""));
-    let from_dir = current.output_rel.parent().unwrap_or(Path::new(""));
-    let is_landing = current.is_index || current.is_readme;
+        write_search(output, &summary.search_sections, options.settings)?;
+        summary.diagnostics = self.diagnostics();
+        Ok(summary)
+    }
 
-    let ancestors = ancestor_dirs(current_dir);
-    for dir in ancestors {
-        if dir == current_dir && is_landing {
-            continue;
+    /// Re-renders only the pages affected by `changed` (paths relative to
+    /// the input), copies changed assets, and deletes the outputs of removed
+    /// ones. Returns `None` when the change needs a full build: pages were
+    /// added, removed, or renamed, or a changed path is not a known page,
+    /// sidebar, or asset file. Templates using `{{site}}` embed every page,
+    /// so they always get a full build.
+    pub fn rebuild_changed(
+        &self,
+        output: &Path,
+        changed: &[PathBuf],
+        snapshot: &mut SiteSnapshot,
+    ) -> Result<Option<usize>> {
+        if !snapshot.matches_pages(&self.site_map) || self.options.template.uses_data() {
+            return Ok(None);
         }
-        if site_map.landing_dirs.contains(&dir) {
-            let label = if dir.as_os_str().is_empty() {
-                "Home".to_string()
+        let settings = self.options.settings;
+        let mut affected = BTreeSet::new();
+        let mut assets = Vec::new();
+        let mut removed = Vec::new();
+        for rel_path in changed {
+            let path = source_file(self.input, rel_path, settings);
+            let generated = self.options.generated.assets.iter().any(|asset| &asset.rel_path == rel_path);
+            if !path.exists() && snapshot.assets.contains(rel_path) && !generated {
+                removed.push(rel_path);
+                continue;
+            }
+            if !path.is_file() || is_redirects_file(rel_path) || is_generator_file(&path) || is_csv_sidecar(&path) {
+                return Ok(None);
+            }
+            if is_sidebar(&path) {
+                let dir = rel_path.parent().unwrap_or(Path::new(""));
+                affected.extend(self.pages_where(|page| page.rel_path.starts_with(dir)));
+            } else if let Some(page) = self.site_map.pages_by_path.get(rel_path) {
+                if snapshot.hidden.contains(rel_path) != page.hidden {
+                    return Ok(None);
+                }
+                affected.insert(page.rel_path.clone());
+                if snapshot.titles.get(rel_path) != Some(&page.title) {
+                    affected.extend(self.title_dependents(page));
+                }
+                affected.extend(self.listing_dependents(page));
+            } else if settings.is_markdown(&path) || is_csv(&path) {
+                return Ok(None);
             } else {
-                landing_title(&dir, site_map).unwrap_or_else(|| display_dir_name(&dir))
-            };
-            let target = dir.join("index.html");
-            let href = relative_link(from_dir, &target);
-            crumbs.push(format!(
-                r#"<a href="{}">{}</a>"#,
-                href,
-                html_escape(&label)
-            ));
+                assets.push((path, rel_path));
+            }
+        }
+        // A retitled page can move in the nav order, which changes the
+        // prev/next links of the pages around its old and new place.
+        affected.extend(
+            self.neighbours
+                .iter()
+                .filter(|(rel_path, pair)| snapshot.neighbours.get(*rel_path) != Some(pair))
+                .map(|(rel_path, _)| rel_path.clone()),
+        );
+        // Generated folder indexes have no source; the listings pass below
+        // rewrites them.
+        let (relisted, affected): (BTreeSet<PathBuf>, BTreeSet<PathBuf>) =
+            affected.into_iter().partition(|rel_path| self.folder_indexes.contains(rel_path));
+
+        let preview_body = preview_extra_body(self.options.live_reload, self.options.heartbeat);
+        let paths: Vec<PathBuf> = affected
+            .iter()
+            .map(|rel_path| source_file(self.input, rel_path, settings))
+            .collect();
+        let pages: Vec<(&Path, &PageEntry)> = paths
+            .iter()
+            .zip(&affected)
+            .map(|(path, rel_path)| (path.as_path(), &self.site_map.pages_by_path[rel_path]))
+            .collect();
+        run_pre_page_hooks(self.options, self.input, output, &pages)?;
+        let mut rendered = Vec::new();
+        let mut sections = Vec::new();
+        for (path, page) in pages {
+            let (html, _) = self.render_page(output, path, page, preview_body.as_deref(), &mut sections)?;
+            rendered.push((page, html));
+        }
+        for (page, html) in &rendered {
+            self.write_page(output, page, html)?;
+        }
+        // Folder listings show the titles and descriptions of their pages.
+        let listings: BTreeSet<&PathBuf> = self
+            .folder_indexes
+            .iter()
+            .filter(|index| {
+                let dir = index.parent().unwrap_or(Path::new(""));
+                relisted.contains(*index)
+                    || affected.iter().any(|rel_path| {
+                        let page_dir = rel_path.parent().unwrap_or(Path::new(""));
+                        let page = &self.site_map.pages_by_path[rel_path];
+                        page_dir == dir || ((page.is_index || page.is_readme) && page_dir.parent() == Some(dir))
+                    })
+            })
+            .collect();
+        for rel_path in &listings {
+            self.write_folder_index(output, rel_path, preview_body.as_deref())?;
+        }
+        for (path, rel_path) in assets {
+            copy_asset(&path, &output.join(asset_output_rel(rel_path, settings)))?;
+            snapshot.assets.insert(rel_path.clone());
+        }
+        for rel_path in removed {
+            let out_path = output.join(asset_output_rel(rel_path, settings));
+            if out_path.is_file() {
+                std::fs::remove_file(&out_path)
+                    .with_context(|| format!("Failed to remove stale output {}", out_path.display()))?;
+            }
+            snapshot.assets.remove(rel_path);
+        }
+        if search_enabled(settings) {
+            let urls: HashSet<String> = rendered
+                .iter()
+                .map(|(page, _)| relative_link(Path::new(""), &page.output_rel, settings))
+                .collect();
+            snapshot
+                .search_sections
+                .retain(|section| !urls.contains(section.url.split('#').next().unwrap_or_default()));
+            snapshot.search_sections.extend(sections);
+            write_search(output, &snapshot.search_sections, settings)?;
         }
+        snapshot.titles = self.page_titles();
+        snapshot.hidden = self.hidden_pages();
+        snapshot.neighbours = self.neighbours.clone();
+        Ok(Some(rendered.len() + listings.len()))
     }
 
-    crumbs.push(format!(r#"<span>{}</span>"#, html_escape(&current.title)));
+    /// State a later [`SitePipeline::rebuild_changed`] compares against.
+    pub fn snapshot(&self, summary: BuildSummary) -> SiteSnapshot {
+        SiteSnapshot {
+            titles: self.page_titles(),
+            hidden: self.hidden_pages(),
+            neighbours: self.neighbours.clone(),
+            assets: summary.copied_assets.into_iter().collect(),
+            search_sections: summary.search_sections,
+        }
+    }
+
+    fn page_titles(&self) -> HashMap<PathBuf, String> {
+        self.site_map
+            .pages_by_path
+            .iter()
+            .map(|(rel_path, page)| (rel_path.clone(), page.title.clone()))
+            .collect()
+    }
+
+    /// Output paths of every built page, including the `index.html` a
+    /// README is copied to.
+    fn output_pages(&self) -> HashSet<PathBuf> {
+        let mut pages: HashSet<PathBuf> = self
+            .site_map
+            .pages_by_path
+            .values()
+            .map(|page| page.output_rel.clone())
+            .collect();
+        pages.extend(self.site_map.landing_dirs.iter().map(|dir| dir.join("index.html")));
+        pages
+    }
 
-    let mut html = String::new();
-    for (idx, crumb) in crumbs.iter().enumerate() {
-        if idx > 0 {
-            html.push_str(r#"<span class="sep">/</span>"#);
+    fn hidden_pages(&self) -> HashSet<PathBuf> {
+        self.site_map
+            .pages_by_path
+            .values()
+            .filter(|page| page.hidden)
+            .map(|page| page.rel_path.clone())
+            .collect()
+    }
+
+    /// Pages whose nav, breadcrumbs, or prev/next links show the title of
+    /// `page`: the pages listing it in their folder's nav, its neighbours
+    /// in nav order (which may sit in another folder) and, for a folder
+    /// landing page, the parent folder's pages (which list the folder
+    /// under its title) and every page below the folder (whose breadcrumbs
+    /// show it).
+    fn title_dependents(&self, page: &PageEntry) -> Vec<PathBuf> {
+        let dir = page.rel_path.parent().unwrap_or(Path::new(""));
+        let mut dependents = if page.is_index || page.is_readme {
+            let parent = dir.parent().unwrap_or(Path::new(""));
+            self.pages_where(|other| {
+                other.rel_path.starts_with(dir) || other.rel_path.parent() == Some(parent)
+            })
+        } else {
+            self.pages_where(|other| other.rel_path.parent() == Some(dir))
+        };
+        if let Some((prev, next)) = self.neighbours.get(&page.rel_path) {
+            dependents.extend(prev.iter().chain(next).cloned());
         }
-        html.push_str(crumb);
+        dependents
     }
-    html
-}
 
-fn ancestor_dirs(dir: &Path) -> Vec<PathBuf> {
-    let mut ancestors = Vec::new();
-    let mut current = PathBuf::new();
-    ancestors.push(PathBuf::new());
-    for component in dir.components() {
-        current.push(component.as_os_str());
-        ancestors.push(current.clone());
+    /// Landing pages whose `{{children}}` listing shows `page`: the ones
+    /// in its folder and, for a folder landing page, the parent folder's,
+    /// if they use the directive. Descriptions can change without the
+    /// title changing.
+    fn listing_dependents(&self, page: &PageEntry) -> Vec<PathBuf> {
+        let dir = page.rel_path.parent().unwrap_or(Path::new(""));
+        let listing_dir = if page.is_index || page.is_readme {
+            match dir.parent() {
+                Some(parent) => parent,
+                None => return Vec::new(),
+            }
+        } else {
+            dir
+        };
+        self.pages_where(|other| {
+            (other.is_index || other.is_readme)
+                && other.rel_path.parent() == Some(listing_dir)
+                && std::fs::read_to_string(self.input.join(&other.rel_path))
+                    .is_ok_and(|source| has_directive(&source))
+        })
     }
-    ancestors
-}
 
-fn relative_link(from_dir: &Path, target: &Path) -> String {
-    let from_parts = path_parts(from_dir);
-    let to_parts = path_parts(target);
-    let mut common = 0usize;
-    while common < from_parts.len()
-        && common < to_parts.len()
-        && from_parts[common] == to_parts[common]
-    {
-        common += 1;
+    /// Subfolders with a landing page, then the other pages of the folder of
+    /// the landing page `current`, with their `description` front matter.
+    fn child_links(&self, current: &PageEntry) -> Vec<ChildLink> {
+        let site_map = &self.site_map;
+        let settings = self.options.settings;
+        let dir = current.rel_path.parent().unwrap_or(Path::new(""));
+        let from_dir = current.output_rel.parent().unwrap_or(Path::new(""));
+        let mut folders: Vec<&PathBuf> = site_map
+            .landing_dirs
+            .iter()
+            .filter(|sub| {
+                sub.as_path() != dir
+                    && sub.parent() == Some(dir)
+                    && !landing_is_hidden(sub, site_map)
+            })
+            .collect();
+        folders.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+        let mut links: Vec<ChildLink> = folders
+            .into_iter()
+            .map(|sub| ChildLink {
+                title: landing_title(sub, site_map).unwrap_or_else(|| display_dir_name(sub, settings)),
+                url: relative_link(from_dir, &sub.join("index.html"), settings),
+                description: landing_page(sub, site_map).and_then(|page| self.description(page)),
+                folder: true,
+            })
+            .collect();
+        links.extend(
+            site_map
+                .pages_by_dir
+                .get(dir)
+                .into_iter()
+                .flatten()
+                .filter(|page| page.rel_path != current.rel_path && !page.hidden)
+                .map(|page| ChildLink {
+                    title: page.title.clone(),
+                    url: relative_link(from_dir, &page.output_rel, settings),
+                    description: self.description(page),
+                    folder: false,
+                }),
+        );
+        links
     }
 
-    let mut parts = Vec::new();
-    for _ in common..from_parts.len() {
-        parts.push("..".to_string());
+    fn description(&self, page: &PageEntry) -> Option<String> {
+        let settings = self.options.settings;
+        if !settings.is_markdown(&page.rel_path) {
+            return None;
+        }
+        let source = std::fs::read_to_string(source_file(self.input, &page.rel_path, settings)).ok()?;
+        front_matter_value(&source, "description").filter(|description| !description.is_empty())
     }
-    for part in &to_parts[common..] {
-        parts.push(part.clone());
+
+    /// Source pages (not generated ones) matching `keep`.
+    fn pages_where(&self, keep: impl Fn(&PageEntry) -> bool) -> Vec<PathBuf> {
+        self.site_map
+            .pages_by_path
+            .values()
+            .filter(|page| keep(page) && source_file(self.input, &page.rel_path, self.options.settings).is_file())
+            .map(|page| page.rel_path.clone())
+            .collect()
     }
 
-    if parts.is_empty() {
-        ".".to_string()
-    } else {
-        parts.join("/")
+    /// Renders a source page into the template. Also returns whether the
+    /// page asked not to be indexed, to keep it out of sitemap.xml.
+    fn render_page(
+        &self,
+        output: &Path,
+        path: &Path,
+        page_entry: &PageEntry,
+        preview_body: Option<&str>,
+        search_sections: &mut Vec<SearchSection>,
+    ) -> Result<(String, bool)> {
+        let options = self.options;
+        let site_map = &self.site_map;
+        let mut extra_body = page_extra_body(preview_body, options, page_entry);
+        let mut noindex = false;
+        let full_html = match self.analyze(path, page_entry, Some(output))? {
+            PageBody::Markdown(rendered) => {
+                noindex = rendered.noindex;
+                let nav_html = match rendered.mode {
+                    DocMode::Slides => String::new(),
+                    DocMode::Document => page_nav_html(page_entry, site_map, &self.sidebars, options.settings),
+                };
+                let mut extra_head = self.page_extra_head(rendered.noindex.then_some(NOINDEX_META), page_entry);
+                let mut content = rendered.html;
+                if search_enabled(options.settings) && rendered.mode == DocMode::Document && !rendered.noindex && !page_entry.hidden {
+                    search_sections.extend(page_sections(&page_entry.title, &page_entry.output_rel, &content, options.settings));
+                }
+                if (page_entry.is_index || page_entry.is_readme) && has_directive(&content) {
+                    content = expand_children(&content, &self.child_links(page_entry));
+                }
+                if rendered.mode == DocMode::Slides {
+                    extra_head = merge_extra_body(extra_head.as_deref(), Some(&slides_extra_head()));
+                    extra_body = merge_extra_body(extra_body.as_deref(), Some(slides_extra_body()));
+                } else {
+                    extra_head = merge_extra_body(extra_head.as_deref(), lightbox_head(rendered.lightbox, options.settings));
+                    if let Some(note) = freshness_html(path, options.settings) {
+                        content.push_str(&note);
+                    }
+                }
+                extra_head = merge_extra_body(extra_head.as_deref(), rendered.extra_head.as_deref());
+                extra_body = merge_extra_body(extra_body.as_deref(), rendered.extra_body.as_deref());
+                options.template.render(
+                    &page_entry.title,
+                    &content,
+                    &nav_html,
+                    &build_breadcrumbs_html(page_entry, site_map, options.settings),
+                    extra_head.as_deref(),
+                    extra_body.as_deref(),
+                    Some(&rendered.stats),
+                    &self.template_data(page_entry),
+                    options.settings,
+                )
+            }
+            PageBody::Csv(html) => options.template.render(
+                &page_entry.title,
+                &html,
+                &page_nav_html(page_entry, site_map, &self.sidebars, options.settings),
+                &build_breadcrumbs_html(page_entry, site_map, options.settings),
+                self.page_extra_head(Some(csv_extra_head()), page_entry).as_deref(),
+                extra_body.as_deref(),
+                None,
+                &self.template_data(page_entry),
+                options.settings,
+            ),
+        };
+        Ok((full_html, noindex))
+    }
+
+    /// `page_extra_head` plus `<link rel="prev">` and `<link rel="next">`
+    /// for the theme's keyboard shortcuts.
+    fn page_extra_head(&self, base: Option<&str>, page: &PageEntry) -> Option<String> {
+        let head = page_extra_head(base, self.options, page);
+        let Some((prev, next)) = self.neighbours.get(&page.rel_path) else {
+            return head;
+        };
+        let from_dir = page.output_rel.parent().unwrap_or(Path::new(""));
+        let links: String = [("prev", prev), ("next", next)]
+            .into_iter()
+            .filter_map(|(rel, target)| {
+                let target = self.site_map.pages_by_path.get(target.as_ref()?)?;
+                Some(format!(
+                    "<link rel=\"{rel}\" href=\"{}\" title=\"{}\">",
+                    escape_html(&relative_link(from_dir, &target.output_rel, self.options.settings)),
+                    escape_html(&target.title)
+                ))
+            })
+            .collect();
+        merge_extra_body(head.as_deref(), (!links.is_empty()).then_some(links.as_str()))
+    }
+
+    fn template_data(&self, page: &PageEntry) -> TemplateData<'_> {
+        #[derive(Serialize)]
+        struct PageContext<'p> {
+            #[serde(flatten)]
+            page: &'p PageData,
+            root: String,
+        }
+        let page = self.page_data.get(&page.rel_path).map(|data| {
+            let depth = page.output_rel.components().count().saturating_sub(1);
+            template_json(&PageContext {
+                page: data,
+                root: "../".repeat(depth),
+            })
+        });
+        TemplateData {
+            site: &self.site_json,
+            page: page.unwrap_or_default(),
+        }
+    }
+
+    /// Writes the generated listing page at `rel_path`: the folder name as
+    /// its title, then its subfolders and pages.
+    fn write_folder_index(&self, output: &Path, rel_path: &Path, preview_body: Option<&str>) -> Result<()> {
+        let page_entry = &self.site_map.pages_by_path[rel_path];
+        let content = format!(
+            "<h1>{}</h1>\n{}",
+            escape_html(&page_entry.title),
+            children_list(&self.child_links(page_entry), false)
+        );
+        let full_html = self.options.template.render(
+            &page_entry.title,
+            &content,
+            &page_nav_html(page_entry, &self.site_map, &self.sidebars, self.options.settings),
+            &build_breadcrumbs_html(page_entry, &self.site_map, self.options.settings),
+            self.page_extra_head(None, page_entry).as_deref(),
+            page_extra_body(preview_body, self.options, page_entry).as_deref(),
+            None,
+            &self.template_data(page_entry),
+            self.options.settings,
+        );
+        write_html(&output.join(&page_entry.output_rel), &full_html)
+    }
+
+    fn write_page(&self, output: &Path, page_entry: &PageEntry, full_html: &str) -> Result<()> {
+        write_html(&output.join(&page_entry.output_rel), full_html)?;
+        if page_entry.is_readme && should_write_index(&page_entry.rel_path, &self.site_map.index_dirs) {
+            let index_path = output.join(dir_index_rel(
+                page_entry.rel_path.parent().unwrap_or(Path::new("")),
+                self.options.settings,
+            ));
+            write_html(&index_path, full_html)?;
+        }
+        Ok(())
+    }
+
+    fn sources(&self, output: Option<&Path>) -> Result<Vec<Source<'_>>> {
+        let mut sources = Vec::new();
+        for (mount, root, filter) in input_roots(self.input, self.options.filter, self.options.settings) {
+            for entry in walk_entries(root, filter) {
+                let path = entry.path();
+                if path == root || output.is_some_and(|output| is_within(path, output)) {
+                    continue;
+                }
+                let rel_path = mount.join(path.strip_prefix(root).with_context(|| {
+                    format!("Failed to compute relative path for {}", path.display())
+                })?);
+                if entry.file_type().is_dir() {
+                    sources.push(Source::Dir(rel_path));
+                } else if is_sidebar(path) || is_redirects_file(&rel_path) || is_generator_file(path) || is_csv_sidecar(path) {
+                    continue;
+                } else if !mount.as_os_str().is_empty() && self.input.join(&rel_path).exists() {
+                    // Shadowed by the file at the same path in the main input.
+                    continue;
+                } else if self.options.settings.is_markdown(path) || is_csv(path) {
+                    if let Some(entry) = self.site_map.pages_by_path.get(&rel_path) {
+                        sources.push(Source::Page {
+                            path: path.to_path_buf(),
+                            entry,
+                        });
+                    }
+                } else {
+                    sources.push(Source::Asset {
+                        path: path.to_path_buf(),
+                        rel_path,
+                    });
+                }
+            }
+        }
+        Ok(sources)
+    }
+
+    /// Renders and checks a page. CSV pages write the rows they don't embed
+    /// under `output`, when given.
+    fn analyze(&self, path: &Path, page: &PageEntry, output: Option<&Path>) -> Result<PageBody> {
+        if !self.options.settings.is_markdown(path) {
+            if self.a11y
+                && let Some(warning) = csv_header_warning(path, csv_has_header(path, &page.rel_path, self.options.settings)?)
+            {
+                self.report(&[warning]);
+            }
+            let row_files = output.map(|output| output.join(&page.output_rel));
+            return Ok(PageBody::Csv(render_csv_file(
+                path,
+                &page.rel_path,
+                self.options.csv_max_rows,
+                row_files.as_deref(),
+                self.options.settings,
+            )?));
+        }
+        let rendered = render_page_markdown(
+            path,
+            &page.rel_path,
+            self.input,
+            &self.site_map.index_dirs,
+            self.options,
+        )?;
+        self.report(&rendered.diagnostics);
+        if self.hygiene || self.todos || self.a11y {
+            let source = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read markdown file {}", path.display()))?;
+            if self.hygiene {
+                self.report(&check_hygiene(path, &source));
+            }
+            if self.a11y {
+                self.report(&check_a11y(path, &source));
+            }
+            if self.todos {
+                self.report(&todo_warnings(path, &source));
+            }
+        }
+        Ok(PageBody::Markdown(rendered))
+    }
+
+    fn report(&self, diagnostics: &[Diagnostic]) {
+        if self.print {
+            diagnostics.iter().for_each(print_diagnostic);
+        }
+        self.diagnostics.borrow_mut().extend_from_slice(diagnostics);
     }
 }
 
-fn path_parts(path: &Path) -> Vec<String> {
-    path.components()
-        .filter_map(|component| match component {
-            std::path::Component::Normal(os) => Some(os.to_string_lossy().to_string()),
-            std::path::Component::ParentDir => Some("..".to_string()),
-            std::path::Component::CurDir => None,
-            _ => None,
+fn copy_asset(source: &Path, out_path: &Path) -> Result<()> {
+    if let Some(parent) = out_path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create output directory {}", parent.display()))?;
+    }
+    std::fs::copy(source, out_path).with_context(|| {
+        format!(
+            "Failed to copy asset from {} to {}",
+            source.display(),
+            out_path.display()
+        )
+    })?;
+    Ok(())
+}
+
+pub struct SitePage {
+    pub output_rel: PathBuf,
+}
+
+pub fn pages_in_nav_order(input: &Path, options: &RenderOptions<'_>) -> Vec<SitePage> {
+    let site_map = export_site_map(input, options);
+    ordered_entries(&site_map)
+        .into_iter()
+        .map(|page| SitePage {
+            output_rel: page.output_rel.clone(),
         })
         .collect()
 }
 
-fn title_from_markdown(path: &Path) -> String {
-    if let Ok(contents) = std::fs::read_to_string(path) {
-        if let Some(title) = first_heading_title(&contents) {
-            return title;
+pub fn page_paths(input: &Path, options: &RenderOptions<'_>) -> Vec<PathBuf> {
+    let mut site_map = build_site_map(input, options.filter, options.settings);
+    add_generated_pages(&mut site_map, &options.generated.pages, options.settings);
+    ordered_entries(&site_map)
+        .into_iter()
+        .filter(|page| !page.hidden)
+        .map(|page| page.output_rel.clone())
+        .collect()
+}
+
+/// The source file behind each output file the build copies or renders,
+/// keyed by output path. Generated pages have no source file.
+pub fn output_sources(input: &Path, options: &RenderOptions<'_>) -> Result<HashMap<PathBuf, PathBuf>> {
+    let pipeline = SitePipeline::new(input, options)?;
+    let mut sources = HashMap::new();
+    for asset in &options.generated.assets {
+        sources.insert(asset.rel_path.clone(), asset.source.clone());
+    }
+    for source in pipeline.sources(None)? {
+        match source {
+            Source::Dir(_) => {}
+            Source::Asset { path, rel_path } => {
+                sources.insert(rel_path, path);
+            }
+            Source::Page { path, entry } => {
+                if entry.is_readme && should_write_index(&entry.rel_path, &pipeline.site_map.index_dirs) {
+                    let dir = entry.rel_path.parent().unwrap_or(Path::new(""));
+                    sources.insert(dir.join("index.html"), path.clone());
+                }
+                sources.insert(entry.output_rel.clone(), path);
+            }
         }
     }
-    display_title(path)
+    Ok(sources)
 }
 
-fn title_from_path(path: &Path) -> String {
-    if is_markdown(path) {
-        title_from_markdown(path)
-    } else {
-        display_title(path)
+pub struct ExportedPage {
+    pub rel_path: PathBuf,
+    pub output_rel: PathBuf,
+    pub title: String,
+    pub html: String,
+}
+
+pub fn render_pages_in_nav_order(
+    input: &Path,
+    options: &RenderOptions<'_>,
+) -> Result<Vec<ExportedPage>> {
+    let site_map = export_site_map(input, options);
+    let mut pages = Vec::new();
+    for page in ordered_entries(&site_map) {
+        let path = source_file(input, &page.rel_path, options.settings);
+        let generated = options.generated.pages.iter().find(|generated| generated.rel_path == page.rel_path);
+        let html = match generated {
+            Some(generated) if !path.is_file() => generated.html.clone(),
+            _ if options.settings.is_markdown(&path) => {
+                render_page_markdown(&path, &page.rel_path, input, &site_map.index_dirs, options)?.html
+            }
+            _ => render_csv_file(&path, &page.rel_path, options.csv_max_rows, None, options.settings)?,
+        };
+        pages.push(ExportedPage {
+            rel_path: page.rel_path.clone(),
+            output_rel: page.output_rel.clone(),
+            title: page.title.clone(),
+            html,
+        });
     }
+    Ok(pages)
 }
 
-fn display_title(path: &Path) -> String {
-    let stem = path
-        .file_stem()
-        .and_then(OsStr::to_str)
-        .unwrap_or("Document");
-    stem.replace(['-', '_'], " ")
+pub fn build_combined_html(
+    input: &Path,
+    title: &str,
+    options: &RenderOptions<'_>,
+) -> Result<String> {
+    let pages = render_pages_in_nav_order(input, options)?;
+    let mut namespaces = HashMap::new();
+    for (idx, page) in pages.iter().enumerate() {
+        let namespace = format!("page-{}", idx + 1);
+        if is_index(&page.rel_path, options.settings) || is_readme(&page.rel_path, options.settings) {
+            let dir = page.output_rel.parent().unwrap_or(Path::new(""));
+            namespaces
+                .entry(dir.join("index.html"))
+                .or_insert_with(|| namespace.clone());
+        }
+        namespaces.insert(page.output_rel.clone(), namespace);
+    }
+    let mut content = String::new();
+    for page in &pages {
+        let namespace = &namespaces[&page.output_rel];
+        let page_dir = page.output_rel.parent().unwrap_or(Path::new(""));
+        content.push_str(&format!(
+            r#"<section class="export-page" id="{}" data-source="{}">"#,
+            namespace,
+            html_escape(&page.rel_path.to_string_lossy())
+        ));
+        let html = rebase_resources(&page.html, page_dir);
+        content.push_str(&namespace_anchors(&html, namespace, page_dir, &namespaces));
+        content.push_str("</section>");
+    }
+    Ok(options.template.render(title, &content, "", "", None, None, None, &TemplateData::default(), options.settings))
 }
 
-fn display_dir_name(path: &Path) -> String {
-    path.file_name()
-        .and_then(OsStr::to_str)
-        .unwrap_or("Folder")
-        .replace(['-', '_'], " ")
+pub fn default_site_title(input: &Path, filter: &PathFilter, settings: &Settings) -> String {
+    let site_map = build_site_map(input, filter, settings);
+    landing_title(Path::new(""), &site_map).unwrap_or_else(|| {
+        let root = input.canonicalize().unwrap_or_else(|_| input.to_path_buf());
+        display_dir_name(&root, settings)
+    })
 }
 
-fn landing_title(dir: &Path, site_map: &SiteMap) -> Option<String> {
-    let pages = site_map.pages_by_dir.get(dir)?;
-    if let Some(index) = pages.iter().find(|page| page.is_index) {
-        return Some(index.title.clone());
+/// Source and generated pages, as exports and nav.json list them.
+fn export_site_map(input: &Path, options: &RenderOptions<'_>) -> SiteMap {
+    let mut site_map = build_site_map(input, options.filter, options.settings);
+    add_generated_pages(&mut site_map, &options.generated.pages, options.settings);
+    if options.settings.folder_indexes {
+        add_folder_indexes(input, &mut site_map, options.settings);
     }
-    if let Some(readme) = pages.iter().find(|page| page.is_readme) {
-        return Some(readme.title.clone());
+    site_map
+}
+
+pub fn nav_tree(input: &Path, title: &str, options: &RenderOptions<'_>) -> NavTree {
+    let site_map = export_site_map(input, options);
+    let children = dir_children(&site_map);
+    NavTree {
+        title: title.to_string(),
+        root: nav_section(Path::new(""), title, &site_map, &children, options.settings),
     }
-    None
 }
 
-fn html_escape(input: &str) -> String {
-    input
-        .replace('&', "&amp;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-        .replace('"', "&quot;")
-        .replace('\'', "&#39;")
+fn nav_section(
+    dir: &Path,
+    site_title: &str,
+    site_map: &SiteMap,
+    children: &HashMap<PathBuf, Vec<PathBuf>>,
+    settings: &Settings,
+) -> NavSection {
+    let mut pages: Vec<&PageEntry> = site_map
+        .pages_by_dir
+        .get(dir)
+        .map(|pages| pages.iter().filter(|page| !page.hidden).collect())
+        .unwrap_or_default();
+    pages.sort_by_key(|page| (!page.is_index, !page.is_readme));
+    let url = (site_map.landing_dirs.contains(dir) && !landing_is_hidden(dir, site_map))
+        .then(|| relative_link(Path::new(""), &dir_index_rel(dir, settings), settings));
+    let sections = children
+        .get(dir)
+        .map(|subdirs| {
+            subdirs
+                .iter()
+                .map(|subdir| nav_section(subdir, site_title, site_map, children, settings))
+                .collect()
+        })
+        .unwrap_or_default();
+    let title = landing_title(dir, site_map).unwrap_or_else(|| {
+        if dir.as_os_str().is_empty() {
+            site_title.to_string()
+        } else {
+            display_dir_name(dir, settings)
+        }
+    });
+    NavSection {
+        title,
+        path: path_parts(dir).join("/"),
+        url,
+        pages: pages
+            .iter()
+            .map(|page| NavPage {
+                title: page.title.clone(),
+                url: relative_link(Path::new(""), &page.output_rel, settings),
+                source: relative_path(Path::new(""), &page.rel_path),
+                landing: page.is_index || page.is_readme,
+            })
+            .collect(),
+        sections,
+    }
 }
 
-fn is_within(path: &Path, root: &Path) -> bool {
-    let path = match path.canonicalize() {
-        Ok(p) => p,
-        Err(_) => return false,
-    };
-    let root = match root.canonicalize() {
-        Ok(p) => p,
-        Err(_) => return false,
-    };
-    path.starts_with(root)
+/// Every page with its front matter for templates using `{{page}}`, and
+/// the JSON for `{{site}}`, which lists the pages in navigation order
+/// without hidden ones.
+fn template_site_data(
+    input: &Path,
+    site_map: &SiteMap,
+    settings: &Settings,
+) -> (HashMap<PathBuf, PageData>, String) {
+    let mut page_data = HashMap::new();
+    let mut pages = Vec::new();
+    for page in ordered_entries(site_map) {
+        let front_matter: BTreeMap<String, String> = if settings.is_markdown(&page.rel_path) {
+            std::fs::read_to_string(source_file(input, &page.rel_path, settings))
+                .map(|source| front_matter_entries(&source).into_iter().collect())
+                .unwrap_or_default()
+        } else {
+            BTreeMap::new()
+        };
+        let data = PageData {
+            title: page.title.clone(),
+            url: relative_link(Path::new(""), &page.output_rel, settings),
+            source: relative_path(Path::new(""), &page.rel_path),
+            section: path_parts(page.rel_path.parent().unwrap_or(Path::new(""))).join("/"),
+            landing: page.is_index || page.is_readme,
+            front_matter,
+        };
+        if !page.hidden {
+            pages.push(data.clone());
+        }
+        page_data.insert(page.rel_path.clone(), data);
+    }
+    #[derive(Serialize)]
+    struct SiteData {
+        pages: Vec<PageData>,
+    }
+    (page_data, template_json(&SiteData { pages }))
 }
 
-fn live_reload_script() -> &'static str {
-    r#"<script>
-(function () {
-  const endpoint = "/__rendar_version";
-  let last = null;
-  async function poll() {
-    try {
-      const res = await fetch(endpoint, { cache: "no-store" });
-      const text = await res.text();
-      if (last === null) {
-        last = text;
-      } else if (last !== text) {
-        location.reload();
-        return;
-      }
-    } catch (_) {}
-    setTimeout(poll, 1000);
-  }
-  poll();
-})();
-</script>
-"#
+/// The pages before and after each visible page in navigation order.
+/// Hidden pages get none.
+fn page_neighbours(site_map: &SiteMap) -> HashMap<PathBuf, (Option<PathBuf>, Option<PathBuf>)> {
+    let pages: Vec<&PathBuf> = ordered_entries(site_map)
+        .into_iter()
+        .filter(|page| !page.hidden)
+        .map(|page| &page.rel_path)
+        .collect();
+    pages
+        .iter()
+        .enumerate()
+        .map(|(idx, page)| {
+            let prev = idx.checked_sub(1).map(|prev| pages[prev].clone());
+            let next = pages.get(idx + 1).map(|next| (*next).clone());
+            ((*page).clone(), (prev, next))
+        })
+        .collect()
 }
 
-fn preview_extra_body(live_reload: bool, heartbeat: bool) -> Option<String> {
-    let mut body = String::new();
-    if live_reload {
-        body.push_str(live_reload_script());
+fn ordered_entries(site_map: &SiteMap) -> Vec<&PageEntry> {
+    let children = dir_children(site_map);
+    let mut ordered = Vec::new();
+    push_dir_entries(Path::new(""), site_map, &children, &mut ordered);
+    ordered
+}
+
+fn dir_children(site_map: &SiteMap) -> HashMap<PathBuf, Vec<PathBuf>> {
+    let mut children: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+    for dir in site_map.pages_by_dir.keys() {
+        let ancestors = ancestor_dirs(dir);
+        for pair in ancestors.windows(2) {
+            let subdirs = children.entry(pair[0].clone()).or_default();
+            if !subdirs.contains(&pair[1]) {
+                subdirs.push(pair[1].clone());
+            }
+        }
     }
-    if heartbeat {
-        body.push_str(heartbeat_script());
+    for subdirs in children.values_mut() {
+        subdirs.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
     }
-    if body.is_empty() {
-        None
-    } else {
-        Some(body)
+    children
+}
+
+fn push_dir_entries<'a>(
+    dir: &Path,
+    site_map: &'a SiteMap,
+    children: &HashMap<PathBuf, Vec<PathBuf>>,
+    ordered: &mut Vec<&'a PageEntry>,
+) {
+    if let Some(pages) = site_map.pages_by_dir.get(dir) {
+        let mut pages: Vec<&PageEntry> = pages.iter().collect();
+        pages.sort_by_key(|page| (!page.is_index, !page.is_readme));
+        ordered.extend(pages);
+    }
+    if let Some(subdirs) = children.get(dir) {
+        for subdir in subdirs {
+            push_dir_entries(subdir, site_map, children, ordered);
+        }
     }
 }
 
-fn merge_extra_body(base: Option<&str>, extra: Option<&str>) -> Option<String> {
-    match (base, extra) {
-        (None, None) => None,
-        (Some(value), None) | (None, Some(value)) => Some(value.to_string()),
-        (Some(base), Some(extra)) => {
-            let mut merged = String::with_capacity(base.len() + extra.len());
-            merged.push_str(base);
-            merged.push_str(extra);
-            Some(merged)
+/// Whether `path`, a link target below `input`, is a file or a page
+/// generated at that path.
+pub fn link_target_exists(input: &Path, path: &Path, settings: &Settings) -> bool {
+    if path.exists() {
+        return true;
+    }
+    let Ok(rel) = path.strip_prefix(input) else {
+        return false;
+    };
+    let mut normalized = PathBuf::new();
+    for component in rel.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::Normal(name) => normalized.push(name),
+            _ => {}
         }
     }
+    settings.generated_pages.contains(&normalized)
+}
+
+fn number_prefix(name: &str) -> Option<(u64, &str)> {
+    let rest = name.trim_start_matches(|ch: char| ch.is_ascii_digit());
+    let digits = &name[..name.len() - rest.len()];
+    let stripped = rest.trim_start_matches(['-', '_', '.', ' ']);
+    if digits.is_empty() || stripped.len() == rest.len() || stripped.is_empty() {
+        return None;
+    }
+    Some((digits.parse().ok()?, stripped))
 }
 
-fn heartbeat_script() -> &'static str {
-    r#"<script>
-(function () {
-  const endpoint = "/__rendar_heartbeat";
-  function ping() {
-    fetch(endpoint, { method: "POST", cache: "no-store" }).catch(() => {});
-  }
-  ping();
-  setInterval(ping, 5000);
-  window.addEventListener("beforeunload", () => {
-    if (navigator.sendBeacon) {
-      navigator.sendBeacon(endpoint, "bye");
+/// The number and the rest of a title like `10. Deployment` or
+/// `02-Setup`. Section numbers (`1.2 Scope`) and titles that merely start
+/// with a number (`2024 Roadmap`) don't count.
+fn title_number_prefix(title: &str) -> Option<(u64, &str)> {
+    let rest = title.trim_start_matches(|ch: char| ch.is_ascii_digit());
+    let digits = &title[..title.len() - rest.len()];
+    let rest = rest.strip_prefix(['.', ')', ':', '-', '_'])?.trim_start();
+    if digits.is_empty() || rest.is_empty() || rest.starts_with(|ch: char| ch.is_ascii_digit()) {
+        return None;
+    }
+    Some((digits.parse().ok()?, rest))
+}
+
+pub fn strip_number_prefix<'n>(name: &'n str, settings: &Settings) -> &'n str {
+    match number_prefix(name) {
+        Some((_, rest)) if settings.strip_number_prefixes => rest,
+        _ => name,
+    }
+}
+
+fn is_csv(path: &Path) -> bool {
+    matches!(
+        path.extension()
+            .and_then(OsStr::to_str)
+            .map(|ext| ext.to_ascii_lowercase())
+            .as_deref(),
+        Some("csv")
+    )
+}
+
+pub fn is_ignored_path(path: &Path, input: &Path, filter: &PathFilter) -> bool {
+    let rel_path = match path.strip_prefix(input) {
+        Ok(rel) => rel,
+        Err(_) => return false,
+    };
+    if rel_path.as_os_str().is_empty() {
+        return false;
+    }
+    filter.is_hidden(rel_path)
+        || filter.is_excluded(rel_path)
+        || filter
+            .include
+            .as_ref()
+            .is_some_and(|include| !include.includes(rel_path, path.is_dir()))
+        || (!filter.ignore_files.is_empty() && filter.is_ignored(rel_path, path.is_dir()))
+}
+
+fn is_dot_name(name: &OsStr) -> bool {
+    name.to_str()
+        .map(|name| name.starts_with('.') && name != "." && name != "..")
+        .unwrap_or(false)
+}
+
+fn is_readme(path: &Path, settings: &Settings) -> bool {
+    path.file_stem()
+        .and_then(OsStr::to_str)
+        .map(|stem| stem.eq_ignore_ascii_case("readme"))
+        .unwrap_or(false)
+        && settings.is_markdown(path)
+}
+
+fn is_index(path: &Path, settings: &Settings) -> bool {
+    path.file_stem()
+        .and_then(OsStr::to_str)
+        .map(|stem| stem.eq_ignore_ascii_case("index"))
+        .unwrap_or(false)
+        && settings.is_markdown(path)
+}
+
+pub fn output_rel_path(
+    path: &Path,
+    input_root: &Path,
+    index_dirs: &HashSet<PathBuf>,
+    settings: &Settings,
+) -> Option<PathBuf> {
+    if settings.is_markdown(path) {
+        let rel = path.strip_prefix(input_root).ok()?;
+        if is_readme(path, settings) && should_write_index(rel, index_dirs) {
+            Some(dir_index_rel(rel.parent().unwrap_or(Path::new("")), settings))
+        } else {
+            Some(page_output_rel(rel, settings))
+        }
+    } else if is_csv(path) {
+        let rel = path.strip_prefix(input_root).ok()?;
+        Some(page_output_rel(rel, settings))
+    } else {
+        None
+    }
+}
+
+pub fn page_output_rel(rel_path: &Path, settings: &Settings) -> PathBuf {
+    let stem = strip_number_prefix(rel_path.file_stem().and_then(OsStr::to_str).unwrap_or_default(), settings);
+    let dir = output_dir_rel(rel_path.parent().unwrap_or(Path::new("")), settings);
+    if settings.pretty_urls && !is_index(rel_path, settings) && !is_readme(rel_path, settings) {
+        return dir.join(stem).join("index.html");
+    }
+    dir.join(format!("{stem}.html"))
+}
+
+fn asset_output_rel(rel_path: &Path, settings: &Settings) -> PathBuf {
+    output_dir_rel(rel_path.parent().unwrap_or(Path::new("")), settings)
+        .join(rel_path.file_name().unwrap_or_default())
+}
+
+fn dir_index_rel(rel_dir: &Path, settings: &Settings) -> PathBuf {
+    output_dir_rel(rel_dir, settings).join("index.html")
+}
+
+/// Where the folder `rel_dir` is written: its path with the number prefix
+/// dropped from every folder name.
+fn output_dir_rel(rel_dir: &Path, settings: &Settings) -> PathBuf {
+    rel_dir
+        .components()
+        .map(|component| match component.as_os_str().to_str() {
+            Some(name) => OsStr::new(strip_number_prefix(name, settings)),
+            None => component.as_os_str(),
+        })
+        .collect()
+}
+
+fn compare_pages(a: &PageEntry, b: &PageEntry) -> std::cmp::Ordering {
+    let order = |page: &PageEntry| {
+        page.rel_path
+            .file_stem()
+            .and_then(OsStr::to_str)
+            .and_then(number_prefix)
+            .map(|(number, _)| number)
+            .or(page.title_order)
+    };
+    let (a_order, b_order) = (order(a), order(b));
+    (a_order.is_none(), a_order)
+        .cmp(&(b_order.is_none(), b_order))
+        .then_with(|| natural_cmp(&a.title, &b.title))
+}
+
+/// Compares names so that runs of digits order by their value, `2. Setup`
+/// before `10. Deployment`. Letters compare ignoring case, with the exact
+/// spelling breaking ties.
+pub fn natural_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+
+    let (mut a_rest, mut b_rest) = (a, b);
+    loop {
+        let (Some(a_char), Some(b_char)) = (a_rest.chars().next(), b_rest.chars().next()) else {
+            return a_rest.is_empty().cmp(&b_rest.is_empty()).reverse().then_with(|| a.cmp(b));
+        };
+        let ordering = if a_char.is_ascii_digit() && b_char.is_ascii_digit() {
+            let a_end = a_rest.find(|ch: char| !ch.is_ascii_digit()).unwrap_or(a_rest.len());
+            let b_end = b_rest.find(|ch: char| !ch.is_ascii_digit()).unwrap_or(b_rest.len());
+            let a_number = a_rest[..a_end].trim_start_matches('0');
+            let b_number = b_rest[..b_end].trim_start_matches('0');
+            a_rest = &a_rest[a_end..];
+            b_rest = &b_rest[b_end..];
+            a_number.len().cmp(&b_number.len()).then_with(|| a_number.cmp(b_number))
+        } else {
+            a_rest = &a_rest[a_char.len_utf8()..];
+            b_rest = &b_rest[b_char.len_utf8()..];
+            a_char.to_lowercase().cmp(b_char.to_lowercase())
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+fn write_html(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create output directory {}", parent.display()))?;
+    }
+    std::fs::write(path, content)
+        .with_context(|| format!("Failed to write output file {}", path.display()))
+}
+
+fn build_site_map(input: &Path, filter: &PathFilter, settings: &Settings) -> SiteMap {
+    let mut pages_by_dir: HashMap<PathBuf, Vec<PageEntry>> = HashMap::new();
+    let mut pages_by_path: HashMap<PathBuf, PageEntry> = HashMap::new();
+    let mut index_dirs = HashSet::new();
+    let mut landing_dirs = HashSet::new();
+
+    for (mount, root, filter) in input_roots(input, filter, settings) {
+        for entry in walk_entries(root, filter) {
+            if is_sidebar(entry.path()) || is_generator_file(entry.path()) {
+                continue;
+            }
+            if !entry.file_type().is_file() || !(settings.is_markdown(entry.path()) || is_csv(entry.path())) {
+                continue;
+            }
+            let path = entry.path();
+            let rel_path = match path.strip_prefix(root) {
+                Ok(rel) => mount.join(rel),
+                Err(_) => continue,
+            };
+            // Pages in the main input win over mounted ones at the same path.
+            if pages_by_path.contains_key(&rel_path) {
+                continue;
+            }
+            let rel_dir = rel_path.parent().unwrap_or(Path::new("")).to_path_buf();
+            let is_index = is_index(path, settings);
+            let is_readme = is_readme(path, settings);
+            let (title, hidden) = title_and_visibility(path, settings);
+            let (title, title_order) = match title_number_prefix(&title).filter(|_| settings.strip_title_numbers) {
+                Some((number, rest)) => (rest.to_string(), Some(number)),
+                None => (title, None),
+            };
+            let output_rel = page_output_rel(&rel_path, settings);
+            let page = PageEntry {
+                rel_path: rel_path.clone(),
+                output_rel,
+                title,
+                is_index,
+                is_readme,
+                hidden,
+                title_order,
+            };
+            pages_by_dir
+                .entry(rel_dir.clone())
+                .or_default()
+                .push(page.clone());
+            pages_by_path.insert(rel_path, page);
+            if is_index {
+                index_dirs.insert(rel_dir.clone());
+            }
+            if is_index || is_readme {
+                landing_dirs.insert(rel_dir);
+            }
+        }
+    }
+
+    for pages in pages_by_dir.values_mut() {
+        pages.sort_by(compare_pages);
+    }
+
+    SiteMap {
+        pages_by_dir,
+        pages_by_path,
+        index_dirs,
+        landing_dirs,
+    }
+}
+
+fn add_generated_pages<'a>(
+    site_map: &mut SiteMap,
+    generated: &'a [GeneratedPage],
+    settings: &Settings,
+) -> Vec<&'a GeneratedPage> {
+    let mut added = Vec::new();
+    for page in generated {
+        if site_map.pages_by_path.contains_key(&page.rel_path) {
+            eprintln!(
+                "Warning: generated page {} is shadowed by a source file",
+                page.rel_path.display()
+            );
+            continue;
+        }
+        let rel_dir = page.rel_path.parent().unwrap_or(Path::new("")).to_path_buf();
+        let is_index = is_index(&page.rel_path, settings);
+        let is_readme = is_readme(&page.rel_path, settings);
+        let entry = PageEntry {
+            rel_path: page.rel_path.clone(),
+            output_rel: page_output_rel(&page.rel_path, settings),
+            title: page.title.clone(),
+            is_index,
+            is_readme,
+            hidden: false,
+            title_order: None,
+        };
+        let pages = site_map.pages_by_dir.entry(rel_dir.clone()).or_default();
+        pages.push(entry.clone());
+        pages.sort_by(compare_pages);
+        site_map.pages_by_path.insert(page.rel_path.clone(), entry);
+        if is_index {
+            site_map.index_dirs.insert(rel_dir.clone());
+        }
+        if is_index || is_readme {
+            site_map.landing_dirs.insert(rel_dir);
+        }
+        added.push(page);
+    }
+    added
+}
+
+/// Warnings for pages written to the same output file: `01-intro.md` next
+/// to `intro.md`, or `02-guide/setup.md` next to `guide/setup.md`, once
+/// number prefixes are dropped, or, with pretty URLs, `guide.md` next to
+/// a `guide/` folder with a landing page.
+fn output_collisions(site_map: &SiteMap, settings: &Settings) -> Vec<Diagnostic> {
+    let mut by_output: BTreeMap<&Path, Vec<&Path>> = BTreeMap::new();
+    for page in site_map.pages_by_path.values() {
+        by_output.entry(&page.output_rel).or_default().push(&page.rel_path);
+    }
+    by_output
+        .into_iter()
+        .filter(|(_, pages)| pages.len() > 1)
+        .map(|(output_rel, mut pages)| {
+            pages.sort();
+            let prefixed = pages.iter().any(|page| {
+                page.components().any(|component| {
+                    component
+                        .as_os_str()
+                        .to_str()
+                        .is_some_and(|name| strip_number_prefix(name, settings) != name)
+                })
+            });
+            let hint = if prefixed {
+                " (number prefixes are dropped from output file and folder names; rename a page or set `strip_number_prefixes = false` under `[site]`)"
+            } else {
+                ""
+            };
+            Diagnostic::warning(
+                "output-collision",
+                pages[0],
+                format!(
+                    "{} are all written to {}; only one of them will be served{hint}",
+                    pages.iter().map(|page| page.display().to_string()).collect::<Vec<_>>().join(", "),
+                    output_rel.display()
+                ),
+            )
+        })
+        .collect()
+}
+
+/// Adds an `index` page to every folder on the way to a page that has no
+/// landing page, titled after the folder (or the input directory, for the
+/// root). Returns their paths; they have no source file.
+fn add_folder_indexes(input: &Path, site_map: &mut SiteMap, settings: &Settings) -> Vec<PathBuf> {
+    let dirs: BTreeSet<PathBuf> = site_map
+        .pages_by_dir
+        .keys()
+        .flat_map(|dir| ancestor_dirs(dir))
+        .filter(|dir| !site_map.landing_dirs.contains(dir))
+        .collect();
+    let mut added = Vec::new();
+    for dir in dirs {
+        let rel_path = dir.join("index.md");
+        let title = if dir.as_os_str().is_empty() {
+            display_dir_name(&input.canonicalize().unwrap_or_else(|_| input.to_path_buf()), settings)
+        } else {
+            display_dir_name(&dir, settings)
+        };
+        let entry = PageEntry {
+            rel_path: rel_path.clone(),
+            output_rel: page_output_rel(&rel_path, settings),
+            title,
+            is_index: true,
+            is_readme: false,
+            hidden: false,
+            title_order: None,
+        };
+        let pages = site_map.pages_by_dir.entry(dir.clone()).or_default();
+        pages.push(entry.clone());
+        pages.sort_by(compare_pages);
+        site_map.pages_by_path.insert(rel_path.clone(), entry);
+        site_map.index_dirs.insert(dir.clone());
+        site_map.landing_dirs.insert(dir);
+        added.push(rel_path);
+    }
+    added
+}
+
+pub fn walk_entries<'a>(
+    input: &'a Path,
+    filter: &'a PathFilter,
+) -> impl Iterator<Item = walkdir::DirEntry> + 'a {
+    WalkDir::new(input)
+        .into_iter()
+        .filter_entry(move |entry| {
+            let path = entry.path();
+            if path == input {
+                return true;
+            }
+            !is_ignored_path(path, input, filter)
+        })
+        .filter_map(Result::ok)
+}
+
+/// Each input root with its mount point and filter: the main input at the
+/// top of the site, then the extra roots of `input = [...]`.
+fn input_roots<'a>(
+    input: &'a Path,
+    filter: &'a PathFilter,
+    settings: &'a Settings,
+) -> impl Iterator<Item = (&'a Path, &'a Path, &'a PathFilter)> {
+    std::iter::once((Path::new(""), input, filter)).chain(
+        settings
+            .mounted_roots
+            .iter()
+            .map(|mounted| (mounted.mount.as_path(), mounted.root.as_path(), &mounted.filter)),
+    )
+}
+
+/// The file behind `rel_path` in the site: the one in the main input when
+/// it exists, otherwise the one in the root mounted there.
+pub fn source_file(input: &Path, rel_path: &Path, settings: &Settings) -> PathBuf {
+    let path = input.join(rel_path);
+    if path.exists() {
+        return path;
+    }
+    settings
+        .mounted_roots
+        .iter()
+        .find_map(|mounted| rel_path.strip_prefix(&mounted.mount).ok().map(|rest| mounted.root.join(rest)))
+        .unwrap_or(path)
+}
+
+/// Where `path`, a file in the main input or in a mounted root, sits in
+/// the site.
+pub fn site_rel_path(input: &Path, path: &Path, settings: &Settings) -> Option<PathBuf> {
+    if let Ok(rel) = path.strip_prefix(input) {
+        return Some(rel.to_path_buf());
+    }
+    settings
+        .mounted_roots
+        .iter()
+        .find_map(|mounted| path.strip_prefix(&mounted.root).ok().map(|rest| mounted.mount.join(rest)))
+}
+
+pub fn collect_index_dirs(input: &Path, filter: &PathFilter, settings: &Settings) -> HashSet<PathBuf> {
+    let mut dirs = HashSet::new();
+    for entry in walk_entries(input, filter) {
+        if entry.file_type().is_file() && is_index(entry.path(), settings) {
+            if let Ok(rel) = entry.path().parent().unwrap_or(input).strip_prefix(input) {
+                dirs.insert(rel.to_path_buf());
+            } else {
+                dirs.insert(PathBuf::new());
+            }
+        }
+    }
+    dirs
+}
+
+fn should_write_index(rel_path: &Path, index_dirs: &HashSet<PathBuf>) -> bool {
+    !index_dirs.contains(rel_path.parent().unwrap_or(Path::new("")))
+}
+
+fn render_page_markdown(
+    path: &Path,
+    rel_path: &Path,
+    input: &Path,
+    index_dirs: &HashSet<PathBuf>,
+    options: &RenderOptions<'_>,
+) -> Result<RenderedPage> {
+    let markdown = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read markdown file {}", path.display()))?;
+    let markdown = options.plugins.transform_markdown(rel_path, markdown)?;
+    let mut rendered = render_markdown_source(&markdown, path, input, index_dirs, options.settings);
+    rendered.html = options.plugins.transform_html(rel_path, rendered.html)?;
+    Ok(rendered)
+}
+
+/// Runs the pre-page hook for `pages` before any of them is rendered, on as
+/// many threads as the hook's process pool has workers.
+fn run_pre_page_hooks(
+    options: &RenderOptions<'_>,
+    input: &Path,
+    output: &Path,
+    pages: &[(&Path, &PageEntry)],
+) -> Result<()> {
+    let Some(hook) = options.pre_page_hook else {
+        return Ok(());
+    };
+    let next = AtomicUsize::new(0);
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..hook.pool.workers().min(pages.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    while let Some((path, page)) = pages.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        run_pre_page_hook(hook, input, output, path, page)?;
+                    }
+                    Ok(())
+                })
+            })
+            .collect();
+        workers.into_iter().try_for_each(|worker| {
+            worker
+                .join()
+                .unwrap_or_else(|_| Err(anyhow::anyhow!("pre_page hook worker panicked")))
+        })
+    })
+}
+
+/// Skipped while the pool remembers a successful run for the same command,
+/// page, and source, so preview rebuilds only rerun it for edited pages.
+fn run_pre_page_hook(hook: PageHook<'_>, input: &Path, output: &Path, path: &Path, page: &PageEntry) -> Result<()> {
+    let source = std::fs::read(path)
+        .with_context(|| format!("Failed to read page {}", path.display()))?;
+    let page_output = output.join(&page.output_rel);
+    let cache_key = content_key(&[
+        hook.command.as_bytes(),
+        page.output_rel.as_os_str().as_encoded_bytes(),
+        &source,
+    ]);
+    run_hook(
+        hook.pool,
+        "pre_page",
+        hook.command,
+        &[
+            ("RENDAR_INPUT", input),
+            ("RENDAR_OUTPUT", output),
+            ("RENDAR_PAGE", path),
+            ("RENDAR_PAGE_OUTPUT", &page_output),
+        ],
+        Some(&cache_key),
+    )
+}
+
+fn load_sidebars(
+    input: &Path,
+    filter: &PathFilter,
+    index_dirs: &HashSet<PathBuf>,
+    diagnostics: &mut Vec<Diagnostic>,
+    settings: &Settings,
+) -> Result<HashMap<PathBuf, Sidebar>> {
+    let mut sidebars = HashMap::new();
+    for (mount, root, filter) in input_roots(input, filter, settings) {
+        for entry in walk_entries(root, filter) {
+            if !entry.file_type().is_file() || !is_sidebar(entry.path()) {
+                continue;
+            }
+            let rel_dir = mount.join(
+                entry
+                    .path()
+                    .parent()
+                    .and_then(|parent| parent.strip_prefix(root).ok())
+                    .unwrap_or(Path::new("")),
+            );
+            // A sidebar in the main input wins over a mounted one.
+            if sidebars.contains_key(&rel_dir) {
+                continue;
+            }
+            let (sidebar, sidebar_diagnostics) = load_sidebar(entry.path(), input, index_dirs, settings)?;
+            sidebar_diagnostics.iter().for_each(print_diagnostic);
+            diagnostics.extend(sidebar_diagnostics);
+            sidebars.insert(rel_dir, sidebar);
+        }
+    }
+    Ok(sidebars)
+}
+
+fn page_nav_html(
+    page: &PageEntry,
+    site_map: &SiteMap,
+    sidebars: &HashMap<PathBuf, Sidebar>,
+    settings: &Settings,
+) -> String {
+    let page_dir = page.rel_path.parent().unwrap_or(Path::new(""));
+    let from_dir = page.output_rel.parent().unwrap_or(Path::new(""));
+    apply_sidebar(
+        build_nav_html(page, site_map, settings),
+        nearest_sidebar(sidebars, page_dir),
+        from_dir,
+        settings,
+    )
+}
+
+fn build_nav_html(current: &PageEntry, site_map: &SiteMap, settings: &Settings) -> String {
+    nav_html(&build_page_nav(current, site_map, settings), &settings.locale)
+}
+
+fn build_breadcrumbs_html(current: &PageEntry, site_map: &SiteMap, settings: &Settings) -> String {
+    breadcrumbs_html(&build_page_nav(current, site_map, settings))
+}
+
+/// Navigation model of the page at `rel_path` (relative to `input`), or
+/// `None` when no such page is built.
+#[cfg(test)]
+pub fn page_nav(input: &Path, filter: &PathFilter, rel_path: &Path, settings: &Settings) -> Option<PageNav> {
+    let site_map = build_site_map(input, filter, settings);
+    let page = site_map.pages_by_path.get(rel_path)?;
+    Some(build_page_nav(page, &site_map, settings))
+}
+
+fn build_page_nav(current: &PageEntry, site_map: &SiteMap, settings: &Settings) -> PageNav {
+    let current_dir = current.rel_path.parent().unwrap_or(Path::new(""));
+    let from_dir = current.output_rel.parent().unwrap_or(Path::new(""));
+
+    let pages = site_map
+        .pages_by_dir
+        .get(current_dir)
+        .into_iter()
+        .flatten()
+        .filter(|page| page.rel_path != current.rel_path && !page.hidden)
+        .map(|page| NavLink {
+            title: page.title.clone(),
+            url: relative_link(from_dir, &page.output_rel, settings),
+        })
+        .collect();
+
+    let mut folders: Vec<NavLink> = site_map
+        .landing_dirs
+        .iter()
+        .filter(|dir| {
+            *dir != current_dir
+                && dir.parent().unwrap_or(Path::new("")) == current_dir
+                && !landing_is_hidden(dir, site_map)
+        })
+        .map(|dir| NavLink {
+            title: landing_title(dir, site_map).unwrap_or_else(|| display_dir_name(dir, settings)),
+            url: relative_link(from_dir, &dir_index_rel(dir, settings), settings),
+        })
+        .collect();
+    folders.sort_by(|a, b| natural_cmp(&a.url, &b.url).then_with(|| natural_cmp(&a.title, &b.title)));
+
+    let is_landing = current.is_index || current.is_readme;
+    let breadcrumbs = ancestor_dirs(current_dir)
+        .into_iter()
+        .filter(|dir| !(dir == current_dir && is_landing) && site_map.landing_dirs.contains(dir))
+        .map(|dir| NavLink {
+            title: if dir.as_os_str().is_empty() {
+                settings.locale.text("home").to_string()
+            } else {
+                landing_title(&dir, site_map).unwrap_or_else(|| display_dir_name(&dir, settings))
+            },
+            url: relative_link(from_dir, &dir_index_rel(&dir, settings), settings),
+        })
+        .collect();
+
+    PageNav {
+        title: current.title.clone(),
+        pages,
+        folders,
+        breadcrumbs,
+    }
+}
+
+fn ancestor_dirs(dir: &Path) -> Vec<PathBuf> {
+    let mut ancestors = Vec::new();
+    let mut current = PathBuf::new();
+    ancestors.push(PathBuf::new());
+    for component in dir.components() {
+        current.push(component.as_os_str());
+        ancestors.push(current.clone());
+    }
+    ancestors
+}
+
+/// The link from a page in `from_dir` to `target`, both relative to the
+/// output root. With pretty URLs, links to `index.html` become folder
+/// links.
+pub fn relative_link(from_dir: &Path, target: &Path, settings: &Settings) -> String {
+    let link = relative_path(from_dir, target);
+    if !settings.pretty_urls {
+        return link;
+    }
+    match link.strip_suffix("index.html") {
+        Some("") => "./".to_string(),
+        Some(dir) if dir.ends_with('/') => dir.to_string(),
+        _ => link,
+    }
+}
+
+/// The path from `from_dir` to `target`, both relative to the output root.
+pub fn relative_path(from_dir: &Path, target: &Path) -> String {
+    let from_parts = path_parts(from_dir);
+    let to_parts = path_parts(target);
+    let mut common = 0usize;
+    while common < from_parts.len()
+        && common < to_parts.len()
+        && from_parts[common] == to_parts[common]
+    {
+        common += 1;
+    }
+
+    let mut parts = Vec::new();
+    for _ in common..from_parts.len() {
+        parts.push("..".to_string());
+    }
+    for part in &to_parts[common..] {
+        parts.push(part.clone());
+    }
+
+    if parts.is_empty() {
+        ".".to_string()
+    } else {
+        parts.join("/")
+    }
+}
+
+fn path_parts(path: &Path) -> Vec<String> {
+    path.components()
+        .filter_map(|component| match component {
+            std::path::Component::Normal(os) => Some(os.to_string_lossy().to_string()),
+            std::path::Component::ParentDir => Some("..".to_string()),
+            std::path::Component::CurDir => None,
+            _ => None,
+        })
+        .collect()
+}
+
+/// The nav title of a page and whether its front matter hides it.
+fn title_and_visibility(path: &Path, settings: &Settings) -> (String, bool) {
+    if settings.is_markdown(path)
+        && let Ok(contents) = std::fs::read_to_string(path)
+    {
+        let title = page_title(&contents).unwrap_or_else(|| display_title(path, settings));
+        let hidden = front_matter_value(&contents, "hidden")
+            .is_some_and(|value| matches!(value.to_ascii_lowercase().as_str(), "true" | "yes"));
+        return (title, hidden);
+    }
+    (display_title(path, settings), false)
+}
+
+fn display_title(path: &Path, settings: &Settings) -> String {
+    let stem = path
+        .file_stem()
+        .and_then(OsStr::to_str)
+        .unwrap_or("Document");
+    strip_number_prefix(stem, settings).replace(['-', '_'], " ")
+}
+
+fn display_dir_name(path: &Path, settings: &Settings) -> String {
+    let name = path.file_name().and_then(OsStr::to_str).unwrap_or("Folder");
+    strip_number_prefix(name, settings).replace(['-', '_'], " ")
+}
+
+fn landing_title(dir: &Path, site_map: &SiteMap) -> Option<String> {
+    landing_page(dir, site_map).map(|page| page.title.clone())
+}
+
+/// The `index` page of `dir`, or its README when it has none.
+fn landing_page<'m>(dir: &Path, site_map: &'m SiteMap) -> Option<&'m PageEntry> {
+    let pages = site_map.pages_by_dir.get(dir)?;
+    pages
+        .iter()
+        .find(|page| page.is_index)
+        .or_else(|| pages.iter().find(|page| page.is_readme))
+}
+
+/// A folder whose landing page is hidden is left out of its parent's
+/// folder listing.
+fn landing_is_hidden(dir: &Path, site_map: &SiteMap) -> bool {
+    landing_page(dir, site_map).is_some_and(|landing| landing.hidden)
+}
+
+fn html_escape(input: &str) -> String {
+    input
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+pub fn is_within(path: &Path, root: &Path) -> bool {
+    let path = match path.canonicalize() {
+        Ok(p) => p,
+        Err(_) => return false,
+    };
+    let root = match root.canonicalize() {
+        Ok(p) => p,
+        Err(_) => return false,
+    };
+    path.starts_with(root)
+}
+
+fn live_reload_script() -> &'static str {
+    r#"<script>
+(function () {
+  const endpoint = "/__rendar_version";
+  let last = null;
+  async function showBuildError() {
+    try {
+      const res = await fetch("/__rendar_error", { cache: "no-store" });
+      const message = await res.text();
+      if (!message) {
+        return;
+      }
+      const overlay = document.createElement("div");
+      overlay.className = "rendar-error-overlay";
+      overlay.setAttribute("role", "alert");
+      overlay.style.cssText = "position:fixed;inset:0;z-index:10000;background:rgba(20,0,0,0.85);color:#fff;padding:32px;overflow:auto;font-family:monospace;";
+      const title = document.createElement("h2");
+      title.textContent = "Rebuild failed – showing the last good version";
+      title.style.cssText = "margin-top:0;color:#ff8a80;";
+      const details = document.createElement("pre");
+      details.textContent = message;
+      details.style.cssText = "white-space:pre-wrap;background:none;border:none;color:inherit;";
+      const close = document.createElement("button");
+      close.type = "button";
+      close.textContent = "Dismiss";
+      close.addEventListener("click", () => overlay.remove());
+      overlay.append(title, details, close);
+      document.body.appendChild(overlay);
+    } catch (_) {}
+  }
+  showBuildError();
+  async function showStatus() {
+    try {
+      const res = await fetch("/__rendar_status", { cache: "no-store" });
+      const status = await res.json();
+      const meta = document.querySelector('meta[name="rendar-source"]');
+      const source = meta ? meta.content : null;
+      const diagnostics = status.diagnostics;
+      const here = diagnostics.filter((diagnostic) => diagnostic.path === source);
+      const bar = document.createElement("details");
+      bar.className = "rendar-status";
+      bar.style.cssText = "position:fixed;right:16px;bottom:16px;z-index:9999;max-width:min(640px,90vw);max-height:50vh;overflow:auto;background:" + (diagnostics.length ? '#fff8e1' : '#f5f5f5') + ";color:#3e2723;border:1px solid " + (diagnostics.length ? '#ffb300' : '#ccc') + ";border-radius:6px;padding:4px 10px;font:12px/1.4 monospace;opacity:0.85;box-shadow:0 2px 8px rgba(0,0,0,0.15);";
+      const summary = document.createElement("summary");
+      const time = new Date(status.built_at).toLocaleTimeString();
+      const seconds = (status.duration_ms / 1000).toFixed(2);
+      const plural = (count) => `${count} warning${count === 1 ? "" : "s"}`;
+      summary.textContent = `Built ${time} in ${seconds}s · ${plural(diagnostics.length)}` + (here.length ? ` (${here.length} on this page)` : "");
+      summary.style.cursor = "pointer";
+      const list = document.createElement("ul");
+      list.style.cssText = "margin:8px 0 0;padding-left:16px;";
+      for (const diagnostic of here) {
+        const item = document.createElement("li");
+        const span = diagnostic.span ? `:${diagnostic.span.line}:${diagnostic.span.column}` : "";
+        item.textContent = `${diagnostic.path}${span}: ${diagnostic.message} [${diagnostic.rule}]`;
+        list.appendChild(item);
+      }
+      const others = diagnostics.length - here.length;
+      if (others || !diagnostics.length) {
+        const item = document.createElement("li");
+        item.textContent = diagnostics.length ? `${plural(others)} on other pages; see the terminal` : "No warnings";
+        item.style.opacity = "0.7";
+        list.appendChild(item);
+      }
+      bar.append(summary, list);
+      document.body.appendChild(bar);
+    } catch (_) {}
+  }
+  showStatus();
+  document.addEventListener("click", (event) => {
+    const meta = document.querySelector('meta[name="rendar-source"]');
+    if (!event.altKey || !meta) {
+      return;
+    }
+    event.preventDefault();
+    const block = event.target.closest("[data-source-line]");
+    const line = block ? block.dataset.sourceLine : "1";
+    const query = new URLSearchParams({ path: meta.content, line });
+    fetch(`/__rendar_edit?${query}`, { method: "POST" }).catch(() => {});
+  });
+  async function poll() {
+    try {
+      const res = await fetch(endpoint, { cache: "no-store" });
+      const text = await res.text();
+      if (last === null) {
+        last = text;
+      } else if (last !== text) {
+        location.reload();
+        return;
+      }
+    } catch (_) {}
+    setTimeout(poll, 1000);
+  }
+  poll();
+})();
+</script>
+"#
+}
+
+fn preview_extra_body(live_reload: bool, heartbeat: bool) -> Option<String> {
+    let mut body = String::new();
+    if live_reload {
+        body.push_str(live_reload_script());
+    }
+    if heartbeat {
+        body.push_str(heartbeat_script());
+    }
+    if body.is_empty() {
+        None
+    } else {
+        Some(body)
+    }
+}
+
+fn page_extra_body(
+    base: Option<&str>,
+    options: &RenderOptions<'_>,
+    page: &PageEntry,
+) -> Option<String> {
+    let script = options
+        .page_views
+        .map(|endpoint| page_view_script(endpoint, &page.output_rel));
+    merge_extra_body(base, script.as_deref())
+}
+
+fn page_extra_head(base: Option<&str>, options: &RenderOptions<'_>, page: &PageEntry) -> Option<String> {
+    let settings = options.settings;
+    // Lets the preview status bar pick out this page's warnings.
+    let source = options.live_reload.then(|| {
+        format!(
+            "<meta name=\"rendar-source\" content=\"{}\">",
+            escape_html(&relative_path(Path::new(""), &page.rel_path))
+        )
+    });
+    let head = merge_extra_body(base, source.as_deref());
+    let head = merge_extra_body(head.as_deref(), icon_links(&page.output_rel, settings).as_deref());
+    let head = merge_extra_body(head.as_deref(), search_head(&page.output_rel, settings).as_deref());
+    let permalink = settings.base_url.as_deref().map(|base_url| {
+        format!(
+            "<meta name=\"rendar-permalink\" content=\"{}\">",
+            escape_html(&page_permalink(base_url, &page.output_rel, settings))
+        )
+    });
+    let head = merge_extra_body(head.as_deref(), permalink.as_deref());
+    merge_extra_body(head.as_deref(), settings.locale.strings_script().as_deref())
+}
+
+/// Absolute URL of a page under `[site] base_url`, used by the heading
+/// permalink buttons so copied links point at the published site.
+fn page_permalink(base_url: &str, output_rel: &Path, settings: &Settings) -> String {
+    format!(
+        "{}/{}",
+        base_url.trim_end_matches('/'),
+        relative_link(Path::new(""), output_rel, settings).trim_start_matches("./")
+    )
+}
+
+const NOINDEX_META: &str = r#"<meta name="robots" content="noindex">"#;
+
+fn merge_extra_body(base: Option<&str>, extra: Option<&str>) -> Option<String> {
+    match (base, extra) {
+        (None, None) => None,
+        (Some(value), None) | (None, Some(value)) => Some(value.to_string()),
+        (Some(base), Some(extra)) => {
+            let mut merged = String::with_capacity(base.len() + extra.len());
+            merged.push_str(base);
+            merged.push_str(extra);
+            Some(merged)
+        }
+    }
+}
+
+fn heartbeat_script() -> &'static str {
+    r#"<script>
+(function () {
+  const endpoint = "/__rendar_heartbeat";
+  function ping() {
+    fetch(endpoint, { method: "POST", cache: "no-store" }).catch(() => {});
+  }
+  ping();
+  setInterval(ping, 5000);
+  window.addEventListener("beforeunload", () => {
+    if (navigator.sendBeacon) {
+      navigator.sendBeacon(endpoint, "bye");
+    }
+  });
+})();
+</script>
+"#
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::MountedRoot;
+    use tempfile::tempdir;
+
+    #[test]
+    fn builds_html_and_copies_assets() {
+        let input_dir = tempdir().expect("input tempdir");
+        let output_dir = tempdir().expect("output tempdir");
+
+        let docs_dir = input_dir.path().join("docs");
+        std::fs::create_dir_all(&docs_dir).expect("create docs dir");
+        std::fs::write(docs_dir.join("index.md"), "# Hello").expect("write markdown");
+
+        let assets_dir = input_dir.path().join("assets");
+        std::fs::create_dir_all(&assets_dir).expect("create assets dir");
+        std::fs::write(assets_dir.join("logo.txt"), "logo").expect("write asset");
+
+        let template = Template::built_in();
+        build_site(
+            input_dir.path(),
+            output_dir.path(),
+            &RenderOptions {
+                live_reload: false,
+                heartbeat: false,
+                template: &template,
+                filter: &PathFilter::default(),
+                csv_max_rows: None,
+                generated: &Generated::default(),
+                page_views: None,
+                pre_page_hook: None,
+                plugins: &Plugins::default(),
+                settings: &Settings::default(),
+            },
+        )
+        .expect("build site");
+
+        let html_path = output_dir.path().join("docs/index.html");
+        let html = std::fs::read_to_string(html_path).expect("read html");
+        assert!(html.contains("<!doctype html>"));
+        assert!(html.contains("Hello"));
+        assert!(html.contains("class=\"sidebar\""));
+        assert!(html.contains("class=\"breadcrumbs\""));
+
+        let asset_path = output_dir.path().join("assets/logo.txt");
+        let asset = std::fs::read_to_string(asset_path).expect("read asset");
+        assert_eq!(asset, "logo");
+    }
+
+    #[test]
+    fn excludes_matching_paths() {
+        let input_dir = tempdir().expect("input tempdir");
+        let output_dir = tempdir().expect("output tempdir");
+
+        std::fs::write(input_dir.path().join("AGENTS.md"), "# Agents").expect("agents");
+        let docs_dir = input_dir.path().join("docs");
+        std::fs::create_dir_all(&docs_dir).expect("docs dir");
+        std::fs::write(docs_dir.join("index.md"), "# Docs").expect("docs");
+
+        let private_dir = input_dir.path().join("private");
+        std::fs::create_dir_all(&private_dir).expect("private dir");
+        std::fs::write(private_dir.join("secret.md"), "# Secret").expect("secret");
+        std::fs::write(private_dir.join("asset.txt"), "nope").expect("asset");
+
+        let mut builder = globset::GlobSetBuilder::new();
+        builder
+            .add(globset::Glob::new("**/AGENTS.md").expect("glob"))
+            .add(globset::Glob::new("private/**").expect("glob"));
+        let excludes = builder.build().expect("globset");
+
+        let template = Template::built_in();
+        build_site(
+            input_dir.path(),
+            output_dir.path(),
+            &RenderOptions {
+                live_reload: false,
+                heartbeat: false,
+                template: &template,
+                filter: &PathFilter {
+                    include: None,
+                    exclude: Some(excludes),
+                    include_hidden: None,
+                    ignore_files: Vec::new(),
+                },
+                csv_max_rows: None,
+                generated: &Generated::default(),
+                page_views: None,
+                pre_page_hook: None,
+                plugins: &Plugins::default(),
+                settings: &Settings::default(),
+            },
+        )
+        .expect("build site");
+
+        assert!(!output_dir.path().join("AGENTS.html").exists());
+        assert!(!output_dir.path().join("private/secret.html").exists());
+        assert!(!output_dir.path().join("private/asset.txt").exists());
+        assert!(output_dir.path().join("docs/index.html").exists());
+    }
+
+    #[test]
+    fn ignores_dot_directories() {
+        let input_dir = tempdir().expect("input tempdir");
+        let output_dir = tempdir().expect("output tempdir");
+
+        let docs_dir = input_dir.path().join("docs");
+        std::fs::create_dir_all(&docs_dir).expect("docs dir");
+        std::fs::write(docs_dir.join("index.md"), "# Docs").expect("docs");
+
+        let hidden_dir = input_dir.path().join(".obsidian");
+        std::fs::create_dir_all(&hidden_dir).expect("hidden dir");
+        std::fs::write(hidden_dir.join("note.md"), "# Hidden").expect("hidden markdown");
+        std::fs::write(hidden_dir.join("cache.json"), "{}").expect("hidden asset");
+
+        std::fs::write(input_dir.path().join(".hidden.md"), "# Hidden").expect("dotfile");
+        std::fs::write(docs_dir.join(".DS_Store"), "").expect("ds store");
+        let well_known = input_dir.path().join(".well-known");
+        std::fs::create_dir_all(&well_known).expect("well-known dir");
+        std::fs::write(well_known.join("security.txt"), "Contact: x").expect("security.txt");
+
+        let mut include_hidden = globset::GlobSetBuilder::new();
+        include_hidden.add(globset::Glob::new(".well-known").expect("glob"));
+
+        let template = Template::built_in();
+        build_site(
+            input_dir.path(),
+            output_dir.path(),
+            &RenderOptions {
+                live_reload: false,
+                heartbeat: false,
+                template: &template,
+                filter: &PathFilter {
+                    include: None,
+                    exclude: None,
+                    include_hidden: Some(include_hidden.build().expect("globset")),
+                    ignore_files: Vec::new(),
+                },
+                csv_max_rows: None,
+                generated: &Generated::default(),
+                page_views: None,
+                pre_page_hook: None,
+                plugins: &Plugins::default(),
+                settings: &Settings::default(),
+            },
+        )
+        .expect("build site");
+
+        assert!(output_dir.path().join("docs/index.html").exists());
+        assert!(!output_dir.path().join(".hidden.html").exists());
+        assert!(!output_dir.path().join("docs/.DS_Store").exists());
+        assert!(output_dir.path().join(".well-known/security.txt").exists());
+        assert!(!output_dir.path().join(".obsidian/note.html").exists());
+        assert!(!output_dir.path().join(".obsidian/cache.json").exists());
+    }
+
+    #[test]
+    fn includes_hidden_folders_only_when_listed() {
+        let input = tempdir().expect("input tempdir");
+        for file in [".github/notes.md", ".github/workflows/ci.yml", "node_modules/pkg/README.md", "docs/.env", "docs/guide.md"] {
+            let path = input.path().join(file);
+            std::fs::create_dir_all(path.parent().expect("parent")).expect("dir");
+            std::fs::write(&path, "x").expect("file");
+        }
+        let files = |filter: &PathFilter| -> Vec<String> {
+            let mut files: Vec<String> = walk_entries(input.path(), filter)
+                .filter(|entry| entry.file_type().is_file())
+                .map(|entry| entry.path().strip_prefix(input.path()).expect("rel").to_string_lossy().replace('\\', "/"))
+                .collect();
+            files.sort();
+            files
+        };
+        assert_eq!(files(&PathFilter::default()), ["docs/guide.md"]);
+
+        let mut include_hidden = globset::GlobSetBuilder::new();
+        include_hidden.add(globset::Glob::new(".github").expect("glob"));
+        include_hidden.add(globset::Glob::new("node_modules").expect("glob"));
+        let filter = PathFilter {
+            include_hidden: Some(include_hidden.build().expect("globset")),
+            ..PathFilter::default()
+        };
+        assert_eq!(
+            files(&filter),
+            [".github/notes.md", ".github/workflows/ci.yml", "docs/guide.md", "node_modules/pkg/README.md"]
+        );
+    }
+
+    #[test]
+    fn honors_ignore_files_and_skips_node_modules() {
+        let input = tempdir().expect("input tempdir");
+        std::fs::create_dir_all(input.path().join("node_modules/pkg")).expect("node_modules");
+        std::fs::write(input.path().join("node_modules/pkg/README.md"), "# Pkg").expect("pkg");
+        std::fs::create_dir_all(input.path().join("guide/drafts")).expect("drafts");
+        std::fs::write(input.path().join(".gitignore"), "build/\n*.log\n").expect("gitignore");
+        std::fs::write(input.path().join(".rendarignore"), "guide/drafts/\n").expect("rendarignore");
+        std::fs::write(input.path().join("guide/.rendarignore"), "*.tmp.md\n!keep.tmp.md\n").expect("nested");
+        std::fs::write(input.path().join("guide/drafts/wip.md"), "# WIP").expect("wip");
+        std::fs::write(input.path().join("guide/scratch.tmp.md"), "# Scratch").expect("scratch");
+        std::fs::write(input.path().join("guide/keep.tmp.md"), "# Keep").expect("keep");
+        std::fs::write(input.path().join("debug.log"), "log").expect("log");
+
+        let filter_with = |gitignore: bool| PathFilter {
+            ignore_files: load_ignore_files(input.path(), &PathFilter::default(), gitignore).expect("ignore files"),
+            ..PathFilter::default()
+        };
+        let filter = filter_with(false);
+        let ignored = |filter: &PathFilter, rel: &str| is_ignored_path(&input.path().join(rel), input.path(), filter);
+        assert!(ignored(&filter, "node_modules/pkg/README.md"));
+        assert!(ignored(&filter, "guide/drafts"));
+        assert!(ignored(&filter, "guide/drafts/wip.md"));
+        assert!(ignored(&filter, "guide/scratch.tmp.md"));
+        assert!(!ignored(&filter, "guide/keep.tmp.md"));
+        assert!(!ignored(&filter, "debug.log"));
+        assert!(ignored(&filter_with(true), "debug.log"));
+    }
+
+    #[test]
+    fn include_and_exclude_prune_the_walk() {
+        let input = tempdir().expect("input tempdir");
+        for file in ["README.md", "guide/intro.md", "guide/deep/more.md", "vendor/lib/README.md", "notes/todo.md"] {
+            let path = input.path().join(file);
+            std::fs::create_dir_all(path.parent().expect("parent")).expect("dir");
+            std::fs::write(&path, "# Page").expect("page");
+        }
+        let mut excludes = globset::GlobSetBuilder::new();
+        excludes.add(globset::Glob::new("vendor").expect("glob"));
+        excludes.add(globset::Glob::new("vendor/**").expect("glob"));
+        let filter = PathFilter {
+            include: IncludeFilter::new(&["guide/**".to_string(), "vendor/**".to_string()]).expect("include"),
+            exclude: Some(excludes.build().expect("globs")),
+            ..PathFilter::default()
+        };
+        let walked: Vec<PathBuf> = walk_entries(input.path(), &filter)
+            .filter_map(|entry| entry.path().strip_prefix(input.path()).ok().map(Path::to_path_buf))
+            .collect();
+        assert!(walked.contains(&PathBuf::from("guide/deep/more.md")));
+        assert!(!walked.iter().any(|path| path.starts_with("vendor")));
+        assert!(!walked.iter().any(|path| path.starts_with("notes")));
+        assert!(!walked.contains(&PathBuf::from("README.md")));
+    }
+
+    #[test]
+    fn builds_nav_and_breadcrumbs() {
+        let input_dir = tempdir().expect("input tempdir");
+
+        std::fs::write(input_dir.path().join("README.md"), "# Root").expect("root readme");
+        let docs_dir = input_dir.path().join("docs");
+        std::fs::create_dir_all(&docs_dir).expect("docs dir");
+        std::fs::write(docs_dir.join("README.md"), "# Docs").expect("docs readme");
+        std::fs::write(docs_dir.join("intro.md"), "# Intro").expect("intro");
+
+        let guide_dir = docs_dir.join("guide");
+        std::fs::create_dir_all(&guide_dir).expect("guide dir");
+        std::fs::write(guide_dir.join("index.md"), "# Guide").expect("guide index");
+        std::fs::write(guide_dir.join("extra.md"), "# Extra").expect("extra page");
+
+        let sub_dir = guide_dir.join("sub");
+        std::fs::create_dir_all(&sub_dir).expect("sub dir");
+        std::fs::write(sub_dir.join("README.md"), "# Subsection").expect("sub readme");
+
+        let site_map = build_site_map(input_dir.path(), &PathFilter::default(), &Settings::default());
+        let current = site_map
+            .pages_by_path
+            .get(&PathBuf::from("docs/guide/extra.md"))
+            .expect("current page");
+
+        let nav = build_nav_html(current, &site_map, &Settings::default());
+        assert!(nav.contains("Pages"));
+        assert!(nav.contains("Guide"));
+        assert!(!nav.contains("intro.html"));
+        assert!(nav.contains("Subsection"));
+
+        let breadcrumbs = build_breadcrumbs_html(current, &site_map, &Settings::default());
+        assert!(breadcrumbs.contains("Home"));
+        assert!(breadcrumbs.contains("Docs"));
+        assert!(breadcrumbs.contains("Guide"));
+        assert!(breadcrumbs.contains("Extra"));
+    }
+
+    #[test]
+    fn excludes_current_folder_from_nav_folders() {
+        let input_dir = tempdir().expect("input tempdir");
+        let docs_dir = input_dir.path().join("docs");
+        std::fs::create_dir_all(&docs_dir).expect("docs dir");
+
+        let guide_dir = docs_dir.join("guide");
+        std::fs::create_dir_all(&guide_dir).expect("guide dir");
+        std::fs::write(guide_dir.join("index.md"), "# Guide").expect("guide index");
+
+        let current_path = guide_dir.join("page.md");
+        std::fs::write(&current_path, "# Page").expect("page");
+
+        let sub_dir = guide_dir.join("sub");
+        std::fs::create_dir_all(&sub_dir).expect("sub dir");
+        std::fs::write(sub_dir.join("README.md"), "# Subsection").expect("sub readme");
+        let site_map = build_site_map(input_dir.path(), &PathFilter::default(), &Settings::default());
+        let current = site_map
+            .pages_by_path
+            .get(&PathBuf::from("docs/guide/page.md"))
+            .expect("current page");
+
+        let nav = build_nav_html(current, &site_map, &Settings::default());
+        assert!(nav.contains("Subsection"));
+        assert_eq!(nav.matches("Guide</a>").count(), 1);
+    }
+
+    #[test]
+    fn sorts_pages_and_folders_by_title() {
+        let input_dir = tempdir().expect("input tempdir");
+        let docs_dir = input_dir.path().join("docs");
+        std::fs::create_dir_all(&docs_dir).expect("docs dir");
+        std::fs::write(docs_dir.join("README.md"), "# Docs").expect("docs readme");
+
+        std::fs::write(docs_dir.join("zeta.md"), "# Zeta").expect("zeta");
+        std::fs::write(docs_dir.join("alpha.md"), "# Alpha").expect("alpha");
+
+        let alpha_dir = docs_dir.join("alpha-folder");
+        std::fs::create_dir_all(&alpha_dir).expect("alpha dir");
+        std::fs::write(alpha_dir.join("README.md"), "# Alpha Folder").expect("alpha readme");
+
+        let zeta_dir = docs_dir.join("zeta-folder");
+        std::fs::create_dir_all(&zeta_dir).expect("zeta dir");
+        std::fs::write(zeta_dir.join("README.md"), "# Zeta Folder").expect("zeta readme");
+
+        let site_map = build_site_map(input_dir.path(), &PathFilter::default(), &Settings::default());
+        let current = site_map
+            .pages_by_path
+            .get(&PathBuf::from("docs/README.md"))
+            .expect("current page");
+
+        let nav = build_nav_html(current, &site_map, &Settings::default());
+        let alpha_index = nav.find("Alpha</a>").expect("alpha page");
+        let zeta_index = nav.find("Zeta</a>").expect("zeta page");
+        assert!(alpha_index < zeta_index);
+
+        let alpha_folder = nav.find("Alpha Folder</a>").expect("alpha folder");
+        let zeta_folder = nav.find("Zeta Folder</a>").expect("zeta folder");
+        assert!(alpha_folder < zeta_folder);
+    }
+
+    #[test]
+    fn orders_pages_for_export_by_nav() {
+        let input_dir = tempdir().expect("input tempdir");
+        std::fs::write(input_dir.path().join("README.md"), "# Root").expect("root readme");
+        std::fs::write(input_dir.path().join("zeta.md"), "# Zeta").expect("zeta");
+        std::fs::write(input_dir.path().join("alpha.md"), "# Alpha").expect("alpha");
+        let guide_dir = input_dir.path().join("guide");
+        std::fs::create_dir_all(&guide_dir).expect("guide dir");
+        std::fs::write(guide_dir.join("setup.md"), "# Setup").expect("setup");
+        std::fs::write(guide_dir.join("index.md"), "# Guide").expect("guide index");
+
+        let template = Template::built_in();
+        let pages = pages_in_nav_order(
+            input_dir.path(),
+            &RenderOptions {
+                live_reload: false,
+                heartbeat: false,
+                template: &template,
+                filter: &PathFilter::default(),
+                csv_max_rows: None,
+                generated: &Generated::default(),
+                page_views: None,
+                pre_page_hook: None,
+                plugins: &Plugins::default(),
+                settings: &Settings::default(),
+            },
+        );
+        let outputs: Vec<PathBuf> = pages.into_iter().map(|page| page.output_rel).collect();
+        assert_eq!(
+            outputs,
+            vec![
+                PathBuf::from("README.html"),
+                PathBuf::from("alpha.html"),
+                PathBuf::from("zeta.html"),
+                PathBuf::from("guide/index.html"),
+                PathBuf::from("guide/setup.html"),
+            ]
+        );
+    }
+
+    #[test]
+    fn builds_combined_html_in_nav_order() {
+        let input_dir = tempdir().expect("input tempdir");
+        std::fs::write(input_dir.path().join("index.md"), "# Home").expect("index");
+        std::fs::write(input_dir.path().join("next.md"), "# Next").expect("next");
+        std::fs::create_dir_all(input_dir.path().join("guide/img")).expect("guide dir");
+        std::fs::write(input_dir.path().join("guide/img/shot.png"), b"png").expect("image");
+        std::fs::write(input_dir.path().join("guide/setup.md"), "# Setup\n\n![Shot](img/shot.png)\n").expect("setup");
+
+        let template = Template::built_in();
+        let html = build_combined_html(
+            input_dir.path(),
+            "Site",
+            &RenderOptions {
+                live_reload: false,
+                heartbeat: false,
+                template: &template,
+                filter: &PathFilter::default(),
+                csv_max_rows: None,
+                generated: &Generated::default(),
+                page_views: None,
+                pre_page_hook: None,
+                plugins: &Plugins::default(),
+                settings: &Settings::default(),
+            },
+        )
+        .expect("combined html");
+
+        assert!(html.contains("<title>Site</title>"));
+        assert_eq!(html.matches(r#"class="export-page""#).count(), 3);
+        assert!(html.contains(r#"<img src="guide/img/shot.png" alt="Shot" />"#));
+        let home = html.find("Home</h1>").expect("home");
+        let next = html.find("Next</h1>").expect("next");
+        assert!(home < next);
+    }
+
+    #[test]
+    fn builds_nav_tree() {
+        let input_dir = tempdir().expect("input tempdir");
+        std::fs::create_dir_all(input_dir.path().join("guide/advanced")).expect("dirs");
+        std::fs::write(input_dir.path().join("index.md"), "# Home").expect("home");
+        std::fs::write(input_dir.path().join("guide/README.md"), "# Guide").expect("guide");
+        std::fs::write(input_dir.path().join("guide/intro.md"), "# Intro").expect("intro");
+        std::fs::write(input_dir.path().join("guide/advanced/tips.md"), "# Tips").expect("tips");
+
+        let template = Template::built_in();
+        let nav = nav_tree(
+            input_dir.path(),
+            "Handbook",
+            &RenderOptions {
+                live_reload: false,
+                heartbeat: false,
+                template: &template,
+                filter: &PathFilter::default(),
+                csv_max_rows: None,
+                generated: &Generated::default(),
+                page_views: None,
+                pre_page_hook: None,
+                plugins: &Plugins::default(),
+                settings: &Settings::default(),
+            },
+        );
+        let nav = serde_json::to_value(&nav).expect("nav json");
+
+        assert_eq!(nav["title"], "Handbook");
+        assert_eq!(nav["root"]["title"], "Home");
+        assert_eq!(nav["root"]["url"], "index.html");
+        let guide = &nav["root"]["sections"][0];
+        assert_eq!(guide["path"], "guide");
+        assert_eq!(guide["url"], "guide/index.html");
+        assert_eq!(guide["pages"][0]["title"], "Guide");
+        assert_eq!(guide["pages"][0]["landing"], true);
+        assert_eq!(guide["pages"][1]["url"], "guide/intro.html");
+        let advanced = &guide["sections"][0];
+        assert_eq!(advanced["title"], "advanced");
+        assert!(advanced["url"].is_null());
+        assert_eq!(advanced["pages"][0]["source"], "guide/advanced/tips.md");
+    }
+
+    #[test]
+    fn renders_generated_pages_with_nav() {
+        let input_dir = tempdir().expect("input tempdir");
+        let output_dir = tempdir().expect("output tempdir");
+        std::fs::write(input_dir.path().join("README.md"), "# Home").expect("readme");
+
+        let generated = Generated {
+            pages: vec![GeneratedPage {
+                rel_path: PathBuf::from("api/index.md"),
+                title: "demo".to_string(),
+                html: "<h1>API</h1>".to_string(),
+                mode: DocMode::Document,
+            }],
+            assets: Vec::new(),
+        };
+        let template = Template::built_in();
+        build_site(
+            input_dir.path(),
+            output_dir.path(),
+            &RenderOptions {
+                live_reload: false,
+                heartbeat: false,
+                template: &template,
+                filter: &PathFilter::default(),
+                csv_max_rows: None,
+                generated: &generated,
+                page_views: None,
+                pre_page_hook: None,
+                plugins: &Plugins::default(),
+                settings: &Settings::default(),
+            },
+        )
+        .expect("build site");
+
+        let api = std::fs::read_to_string(output_dir.path().join("api/index.html")).expect("api");
+        assert!(api.contains("<h1>API</h1>"));
+        assert!(api.contains("Home</a>"));
+        let home = std::fs::read_to_string(output_dir.path().join("index.html")).expect("home");
+        assert!(home.contains(r#"href="api/index.html""#));
     }
-  });
-})();
-</script>
-"#
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::tempdir;
+    #[test]
+    fn links_pages_to_neighbours_in_navigation_order() {
+        let input_dir = tempdir().expect("input tempdir");
+        std::fs::create_dir_all(input_dir.path().join("guide")).expect("guide dir");
+        std::fs::write(input_dir.path().join("README.md"), "# Home").expect("readme");
+        std::fs::write(input_dir.path().join("guide/1-setup.md"), "# Setup").expect("setup");
+        std::fs::write(input_dir.path().join("guide/2-draft.md"), "---\nhidden: true\n---\n# Draft").expect("draft");
+        std::fs::write(input_dir.path().join("guide/3-usage.md"), "# Usage").expect("usage");
+        let site_map = build_site_map(input_dir.path(), &PathFilter::default(), &Settings::default());
+        let neighbours = page_neighbours(&site_map);
+        let path = |path: &str| Some(PathBuf::from(path));
+        assert_eq!(neighbours[Path::new("README.md")], (None, path("guide/1-setup.md")));
+        assert_eq!(
+            neighbours[Path::new("guide/1-setup.md")],
+            (path("README.md"), path("guide/3-usage.md"))
+        );
+        assert_eq!(neighbours[Path::new("guide/3-usage.md")], (path("guide/1-setup.md"), None));
+        assert!(!neighbours.contains_key(Path::new("guide/2-draft.md")));
+    }
 
     #[test]
-    fn builds_html_and_copies_assets() {
+    fn exposes_site_and_page_data_to_templates() {
         let input_dir = tempdir().expect("input tempdir");
         let output_dir = tempdir().expect("output tempdir");
+        std::fs::create_dir_all(input_dir.path().join("guide")).expect("guide dir");
+        std::fs::write(input_dir.path().join("README.md"), "# Home").expect("readme");
+        std::fs::write(
+            input_dir.path().join("guide/setup.md"),
+            "---\nauthor: Ada </script>\n---\n# Setup",
+        )
+        .expect("setup");
+        std::fs::write(input_dir.path().join("guide/draft.md"), "---\nhidden: true\n---\n# Draft").expect("draft");
+        let template_path = input_dir.path().join("template.html");
+        std::fs::write(
+            &template_path,
+            "<html><title>{{title}}</title>{{nav}}{{breadcrumbs}}{{content}}\
+             <script type=\"application/json\" id=\"site\">{{site}}</script>\
+             <script type=\"application/json\" id=\"page\">{{page}}</script></html>",
+        )
+        .expect("template");
+        let template = Template::from_path(&template_path, crate::config::PlaceholderPolicy::Error, &BTreeMap::new())
+            .expect("template");
+        let options = RenderOptions {
+            live_reload: false,
+            heartbeat: false,
+            template: &template,
+            filter: &PathFilter::default(),
+            csv_max_rows: None,
+            generated: &Generated::default(),
+            page_views: None,
+            pre_page_hook: None,
+            plugins: &Plugins::default(),
+            settings: &Settings::default(),
+        };
+        build_site(input_dir.path(), output_dir.path(), &options).expect("build site");
+
+        let html = std::fs::read_to_string(output_dir.path().join("guide/draft.html")).expect("draft");
+        let json = |id: &str| -> serde_json::Value {
+            let start = html.find(&format!("id=\"{id}\">")).expect("script") + id.len() + 6;
+            let end = start + html[start..].find("</script>").expect("end");
+            serde_json::from_str(&html[start..end]).expect("json")
+        };
+        let site = json("site");
+        let titles: Vec<&str> = site["pages"]
+            .as_array()
+            .expect("pages")
+            .iter()
+            .map(|page| page["title"].as_str().expect("title"))
+            .collect();
+        assert_eq!(titles, ["Home", "Setup"]);
+        assert_eq!(site["pages"][1]["url"], "guide/setup.html");
+        assert_eq!(site["pages"][1]["section"], "guide");
+        assert_eq!(site["pages"][1]["front_matter"]["author"], "Ada </script>");
+        let page = json("page");
+        assert_eq!(page["title"], "Draft");
+        assert_eq!(page["root"], "../");
+        assert_eq!(page["front_matter"]["hidden"], "true");
+    }
 
-        let docs_dir = input_dir.path().join("docs");
-        std::fs::create_dir_all(&docs_dir).expect("create docs dir");
-        std::fs::write(docs_dir.join("index.md"), "# Hello").expect("write markdown");
+    #[test]
+    fn exports_generated_pages_in_nav_order() {
+        let input_dir = tempdir().expect("input tempdir");
+        std::fs::write(input_dir.path().join("README.md"), "# Home").expect("readme");
+        let generated = Generated {
+            pages: vec![GeneratedPage {
+                rel_path: PathBuf::from("api/index.md"),
+                title: "demo".to_string(),
+                html: "<h1>API</h1><a href=\"parts/index.html\">parts</a>".to_string(),
+                mode: DocMode::Document,
+            }],
+            assets: Vec::new(),
+        };
+        let template = Template::built_in();
+        let options = RenderOptions {
+            live_reload: false,
+            heartbeat: false,
+            template: &template,
+            filter: &PathFilter::default(),
+            csv_max_rows: None,
+            generated: &generated,
+            page_views: None,
+            pre_page_hook: None,
+            plugins: &Plugins::default(),
+            settings: &Settings::default(),
+        };
+
+        let outputs: Vec<PathBuf> = pages_in_nav_order(input_dir.path(), &options)
+            .into_iter()
+            .map(|page| page.output_rel)
+            .collect();
+        assert_eq!(outputs, vec![PathBuf::from("README.html"), PathBuf::from("api/index.html")]);
+        let html = build_combined_html(input_dir.path(), "Site", &options).expect("combined html");
+        assert_eq!(html.matches(r#"class="export-page""#).count(), 2);
+        assert!(html.contains(r#"<h1>API</h1><a href="api/parts/index.html">parts</a>"#));
+    }
 
-        let assets_dir = input_dir.path().join("assets");
-        std::fs::create_dir_all(&assets_dir).expect("create assets dir");
-        std::fs::write(assets_dir.join("logo.txt"), "logo").expect("write asset");
+    #[test]
+    fn renders_configured_markdown_extensions() {
+        let config: crate::config::Config =
+            toml::from_str(r#"markdown_extensions = ["md", "markdown", ".MDX"]"#).expect("config");
+        let settings = Settings::from_config(Some(&config)).expect("settings");
+        let input_dir = tempdir().expect("input tempdir");
+        let output_dir = tempdir().expect("output tempdir");
+        std::fs::write(input_dir.path().join("README.mdx"), "# Home\n\n[Usage](usage.mdx)").expect("readme");
+        std::fs::write(input_dir.path().join("usage.mdx"), "# Usage").expect("usage");
 
         let template = Template::built_in();
         build_site(
@@ -691,44 +3009,84 @@ mod tests {
                 live_reload: false,
                 heartbeat: false,
                 template: &template,
-                exclude: None,
+                filter: &PathFilter::default(),
                 csv_max_rows: None,
+                generated: &Generated::default(),
+                page_views: None,
+                pre_page_hook: None,
+                plugins: &Plugins::default(),
+                settings: &settings,
             },
         )
         .expect("build site");
 
-        let html_path = output_dir.path().join("docs/index.html");
-        let html = std::fs::read_to_string(html_path).expect("read html");
-        assert!(html.contains("<!doctype html>"));
-        assert!(html.contains("Hello"));
-        assert!(html.contains("class=\"sidebar\""));
-        assert!(html.contains("class=\"breadcrumbs\""));
+        let home = std::fs::read_to_string(output_dir.path().join("index.html")).expect("home");
+        assert!(home.contains(r#"<a href="usage.html">Usage</a>"#));
+        assert!(output_dir.path().join("usage.html").exists());
+        assert!(!output_dir.path().join("usage.mdx").exists());
+    }
 
-        let asset_path = output_dir.path().join("assets/logo.txt");
-        let asset = std::fs::read_to_string(asset_path).expect("read asset");
-        assert_eq!(asset, "logo");
+    #[test]
+    fn hides_pages_from_navigation() {
+        let input = tempdir().expect("input tempdir");
+        std::fs::write(input.path().join("README.md"), "# Home").expect("home");
+        std::fs::write(input.path().join("guide.md"), "# Guide").expect("guide");
+        std::fs::write(input.path().join("scratch.md"), "---\nhidden: true\n---\n# Scratch").expect("scratch");
+        std::fs::create_dir_all(input.path().join("lab")).expect("lab");
+        std::fs::write(input.path().join("lab/index.md"), "---\nhidden: yes\n---\n# Lab").expect("lab");
+        std::fs::write(input.path().join("lab/run.md"), "# Run").expect("run");
+
+        let settings = Settings {
+            base_url: Some("https://docs.example.com".to_string()),
+            ..Settings::default()
+        };
+        let nav = page_nav(input.path(), &PathFilter::default(), Path::new("guide.md"), &settings).expect("guide nav");
+        assert_eq!(nav.pages.iter().map(|link| link.title.as_str()).collect::<Vec<_>>(), ["Home"]);
+        assert!(nav.folders.is_empty());
+
+        let scratch = page_nav(input.path(), &PathFilter::default(), Path::new("scratch.md"), &settings).expect("scratch nav");
+        assert_eq!(scratch.pages.len(), 2);
+        let run = page_nav(input.path(), &PathFilter::default(), Path::new("lab/run.md"), &settings).expect("run nav");
+        assert_eq!(run.breadcrumbs.last().map(|link| link.title.as_str()), Some("Lab"));
+
+        let output = tempdir().expect("output tempdir");
+        let template = Template::built_in();
+        build_site(
+            input.path(),
+            output.path(),
+            &RenderOptions {
+                live_reload: false,
+                heartbeat: false,
+                template: &template,
+                filter: &PathFilter::default(),
+                csv_max_rows: None,
+                generated: &Generated::default(),
+                page_views: None,
+                pre_page_hook: None,
+                plugins: &Plugins::default(),
+                settings: &settings,
+            },
+        )
+        .expect("build site");
+        assert!(output.path().join("scratch.html").exists());
+        let sitemap = std::fs::read_to_string(output.path().join("sitemap.xml")).expect("sitemap");
+        assert!(sitemap.contains("<loc>https://docs.example.com/guide.html</loc>"));
+        assert!(sitemap.contains("<loc>https://docs.example.com/lab/run.html</loc>"));
+        assert!(!sitemap.contains("scratch.html"));
+        assert!(!sitemap.contains("lab/index.html"));
     }
 
     #[test]
-    fn excludes_matching_paths() {
+    fn marks_noindex_pages_for_robots() {
         let input_dir = tempdir().expect("input tempdir");
         let output_dir = tempdir().expect("output tempdir");
+        std::fs::write(input_dir.path().join("index.md"), "# Home").expect("home");
+        std::fs::write(input_dir.path().join("legal.md"), "---\nnoindex: true\n---\n# Legal").expect("legal");
 
-        std::fs::write(input_dir.path().join("AGENTS.md"), "# Agents").expect("agents");
-        let docs_dir = input_dir.path().join("docs");
-        std::fs::create_dir_all(&docs_dir).expect("docs dir");
-        std::fs::write(docs_dir.join("index.md"), "# Docs").expect("docs");
-
-        let private_dir = input_dir.path().join("private");
-        std::fs::create_dir_all(&private_dir).expect("private dir");
-        std::fs::write(private_dir.join("secret.md"), "# Secret").expect("secret");
-        std::fs::write(private_dir.join("asset.txt"), "nope").expect("asset");
-
-        let mut builder = globset::GlobSetBuilder::new();
-        builder
-            .add(globset::Glob::new("**/AGENTS.md").expect("glob"))
-            .add(globset::Glob::new("private/**").expect("glob"));
-        let excludes = builder.build().expect("globset");
+        let settings = Settings {
+            base_url: Some("https://docs.example.com/".to_string()),
+            ..Settings::default()
+        };
 
         let template = Template::built_in();
         build_site(
@@ -738,150 +3096,659 @@ mod tests {
                 live_reload: false,
                 heartbeat: false,
                 template: &template,
-                exclude: Some(&excludes),
+                filter: &PathFilter::default(),
                 csv_max_rows: None,
+                generated: &Generated::default(),
+                page_views: None,
+                pre_page_hook: None,
+                plugins: &Plugins::default(),
+                settings: &settings,
             },
         )
         .expect("build site");
 
-        assert!(!output_dir.path().join("AGENTS.html").exists());
-        assert!(!output_dir.path().join("private/secret.html").exists());
-        assert!(!output_dir.path().join("private/asset.txt").exists());
-        assert!(output_dir.path().join("docs/index.html").exists());
+        let legal = std::fs::read_to_string(output_dir.path().join("legal.html")).expect("legal");
+        assert!(legal.contains(NOINDEX_META));
+        let home = std::fs::read_to_string(output_dir.path().join("index.html")).expect("home");
+        assert!(!home.contains(NOINDEX_META));
+        assert!(home.contains(r#"href="legal.html""#));
+        let sitemap = std::fs::read_to_string(output_dir.path().join("sitemap.xml")).expect("sitemap");
+        assert!(sitemap.contains("<loc>https://docs.example.com/index.html</loc>"));
+        assert!(!sitemap.contains("legal.html"));
     }
 
     #[test]
-    fn ignores_dot_directories() {
+    fn orders_by_number_prefix_and_strips_it_from_urls() {
         let input_dir = tempdir().expect("input tempdir");
         let output_dir = tempdir().expect("output tempdir");
+        std::fs::create_dir_all(input_dir.path().join("02-guide/03-shots")).expect("guide dir");
+        std::fs::write(input_dir.path().join("index.md"), "# Home\n\n[Setup](02-guide/02-setup.md)").expect("home");
+        std::fs::write(input_dir.path().join("02-guide/README.md"), "# Guide").expect("guide");
+        std::fs::write(input_dir.path().join("02-guide/01-install.md"), "Install steps").expect("install");
+        std::fs::write(input_dir.path().join("02-guide/02-setup.md"), "# Setup\n\n![Screen](03-shots/screen.png)").expect("setup");
+        std::fs::write(input_dir.path().join("02-guide/03-shots/screen.png"), b"png").expect("screen");
+        std::fs::write(input_dir.path().join("02-guide/10-alpha.md"), "# Alpha").expect("alpha");
 
-        let docs_dir = input_dir.path().join("docs");
-        std::fs::create_dir_all(&docs_dir).expect("docs dir");
-        std::fs::write(docs_dir.join("index.md"), "# Docs").expect("docs");
+        let template = Template::built_in();
+        build_site(
+            input_dir.path(),
+            output_dir.path(),
+            &RenderOptions {
+                live_reload: false,
+                heartbeat: false,
+                template: &template,
+                filter: &PathFilter::default(),
+                csv_max_rows: None,
+                generated: &Generated::default(),
+                page_views: None,
+                pre_page_hook: None,
+                plugins: &Plugins::default(),
+                settings: &Settings::default(),
+            },
+        )
+        .expect("build site");
 
-        let hidden_dir = input_dir.path().join(".obsidian");
-        std::fs::create_dir_all(&hidden_dir).expect("hidden dir");
-        std::fs::write(hidden_dir.join("note.md"), "# Hidden").expect("hidden markdown");
-        std::fs::write(hidden_dir.join("cache.json"), "{}").expect("hidden asset");
+        let home = std::fs::read_to_string(output_dir.path().join("index.html")).expect("home");
+        assert!(home.contains(r#"<a href="guide/setup.html">Setup</a>"#));
+        assert!(home.contains(r#"<a href="guide/index.html">Guide</a>"#));
+        assert!(!output_dir.path().join("02-guide").exists());
+        assert!(output_dir.path().join("guide/shots/screen.png").exists());
+        let setup = std::fs::read_to_string(output_dir.path().join("guide/setup.html")).expect("setup");
+        assert!(setup.contains(r#"src="shots/screen.png""#));
+        let alpha = std::fs::read_to_string(output_dir.path().join("guide/alpha.html")).expect("alpha");
+        let install = alpha.find(r#"<a href="install.html">install</a>"#).expect("install link");
+        let setup = alpha.find(r#"<a href="setup.html">Setup</a>"#).expect("setup link");
+        assert!(install < setup);
+    }
 
-        std::fs::write(input_dir.path().join(".visible.md"), "# Visible").expect("dotfile");
+    #[test]
+    fn warns_when_stripped_number_prefixes_collide() {
+        let input_dir = tempdir().expect("input tempdir");
+        let output_dir = tempdir().expect("output tempdir");
+        std::fs::write(input_dir.path().join("01-intro.md"), "# Intro").expect("numbered intro");
+        std::fs::write(input_dir.path().join("intro.md"), "# Other intro").expect("intro");
+        std::fs::write(input_dir.path().join("02-setup.md"), "# Setup").expect("setup");
+        std::fs::create_dir_all(input_dir.path().join("03-guide")).expect("numbered guide dir");
+        std::fs::create_dir_all(input_dir.path().join("guide")).expect("guide dir");
+        std::fs::write(input_dir.path().join("03-guide/usage.md"), "# Usage").expect("numbered usage");
+        std::fs::write(input_dir.path().join("guide/usage.md"), "# Other usage").expect("usage");
 
         let template = Template::built_in();
-        build_site(
+        let summary = build_site(
             input_dir.path(),
             output_dir.path(),
             &RenderOptions {
                 live_reload: false,
                 heartbeat: false,
                 template: &template,
-                exclude: None,
+                filter: &PathFilter::default(),
                 csv_max_rows: None,
+                generated: &Generated::default(),
+                page_views: None,
+                pre_page_hook: None,
+                plugins: &Plugins::default(),
+                settings: &Settings::default(),
             },
         )
         .expect("build site");
 
-        assert!(output_dir.path().join("docs/index.html").exists());
-        assert!(output_dir.path().join(".visible.html").exists());
-        assert!(!output_dir.path().join(".obsidian/note.html").exists());
-        assert!(!output_dir.path().join(".obsidian/cache.json").exists());
+        let collisions: Vec<&Diagnostic> = summary
+            .diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.rule == "output-collision")
+            .collect();
+        assert_eq!(collisions.len(), 2);
+        assert!(collisions[0].message.starts_with("03-guide/usage.md, guide/usage.md are all written to guide/usage.html"));
+        assert!(collisions[1].message.starts_with("01-intro.md, intro.md are all written to intro.html"));
+        assert!(collisions.iter().all(|collision| collision.message.contains("strip_number_prefixes")));
     }
 
     #[test]
-    fn builds_nav_and_breadcrumbs() {
+    fn compares_names_naturally() {
+        use std::cmp::Ordering;
+
+        assert_eq!(natural_cmp("2. Setup", "10. Deployment"), Ordering::Less);
+        assert_eq!(natural_cmp("chapter-10", "Chapter-9"), Ordering::Greater);
+        assert_eq!(natural_cmp("v1.10", "v1.9"), Ordering::Greater);
+        assert_eq!(natural_cmp("item", "item 2"), Ordering::Less);
+        assert_eq!(natural_cmp("02", "2"), "02".cmp("2"));
+        assert_eq!(natural_cmp("Zebra", "apple"), Ordering::Greater);
+
+        assert_eq!(title_number_prefix("10. Deployment"), Some((10, "Deployment")));
+        assert_eq!(title_number_prefix("02-Setup"), Some((2, "Setup")));
+        assert_eq!(title_number_prefix("3) Usage"), Some((3, "Usage")));
+        assert_eq!(title_number_prefix("1.2 Scope"), None);
+        assert_eq!(title_number_prefix("2024 Roadmap"), None);
+    }
+
+    #[test]
+    fn orders_numbered_titles_by_value() {
         let input_dir = tempdir().expect("input tempdir");
+        let output_dir = tempdir().expect("output tempdir");
+        std::fs::write(input_dir.path().join("deployment.md"), "# 10. Deployment").expect("deployment");
+        std::fs::write(input_dir.path().join("setup.md"), "# 2. Setup").expect("setup");
+        std::fs::write(input_dir.path().join("intro.md"), "# 1. Intro").expect("intro");
 
-        std::fs::write(input_dir.path().join("README.md"), "# Root").expect("root readme");
-        let docs_dir = input_dir.path().join("docs");
-        std::fs::create_dir_all(&docs_dir).expect("docs dir");
-        std::fs::write(docs_dir.join("README.md"), "# Docs").expect("docs readme");
-        std::fs::write(docs_dir.join("intro.md"), "# Intro").expect("intro");
+        let template = Template::built_in();
+        build_site(
+            input_dir.path(),
+            output_dir.path(),
+            &RenderOptions {
+                live_reload: false,
+                heartbeat: false,
+                template: &template,
+                filter: &PathFilter::default(),
+                csv_max_rows: None,
+                generated: &Generated::default(),
+                page_views: None,
+                pre_page_hook: None,
+                plugins: &Plugins::default(),
+                settings: &Settings::default(),
+            },
+        )
+        .expect("build site");
 
-        let guide_dir = docs_dir.join("guide");
-        std::fs::create_dir_all(&guide_dir).expect("guide dir");
-        std::fs::write(guide_dir.join("index.md"), "# Guide").expect("guide index");
-        std::fs::write(guide_dir.join("extra.md"), "# Extra").expect("extra page");
+        let intro = std::fs::read_to_string(output_dir.path().join("intro.html")).expect("intro");
+        let setup = intro.find(">2. Setup</a>").expect("setup link");
+        let deployment = intro.find(">10. Deployment</a>").expect("deployment link");
+        assert!(setup < deployment);
+    }
 
-        let sub_dir = guide_dir.join("sub");
-        std::fs::create_dir_all(&sub_dir).expect("sub dir");
-        std::fs::write(sub_dir.join("README.md"), "# Subsection").expect("sub readme");
+    #[test]
+    fn generates_index_pages_for_folders_without_one() {
+        let input = tempfile::tempdir().expect("input");
+        let output = tempfile::tempdir().expect("output");
+        std::fs::create_dir_all(input.path().join("guides/setup")).expect("dirs");
+        std::fs::create_dir_all(input.path().join("notes")).expect("notes dir");
+        std::fs::write(input.path().join("guides/setup/linux.md"), "# Linux\n").expect("linux");
+        std::fs::write(input.path().join("guides/faq.md"), "---\ndescription: Answers\n---\n# FAQ\n").expect("faq");
+        std::fs::write(input.path().join("notes/README.md"), "# Notes\n").expect("notes");
+        let template = Template::built_in();
+        let settings = Settings {
+            folder_indexes: true,
+            ..Settings::default()
+        };
+        let options = RenderOptions {
+            live_reload: false,
+            heartbeat: false,
+            template: &template,
+            filter: &PathFilter::default(),
+            csv_max_rows: None,
+            generated: &Generated::default(),
+            page_views: None,
+            pre_page_hook: None,
+            plugins: &Plugins::default(),
+            settings: &settings,
+        };
+        let pipeline = SitePipeline::new(input.path(), &options).expect("pipeline");
+        let mut snapshot = pipeline.snapshot(pipeline.build(output.path()).expect("build"));
+        let read = |path: &str| std::fs::read_to_string(output.path().join(path)).expect(path);
+
+        let guides = read("guides/index.html");
+        assert!(guides.contains("<h1>guides</h1>"));
+        assert!(guides.contains(concat!(
+            "<li class=\"child-folder\"><a href=\"setup/index.html\">setup</a></li>\n",
+            "<li class=\"child-page\"><a href=\"faq.html\">FAQ</a><p>Answers</p></li>"
+        )));
+        assert!(read("guides/setup/index.html").contains("<a href=\"linux.html\">Linux</a>"));
+        let root = read("index.html");
+        assert!(root.contains("<a href=\"guides/index.html\">guides</a>"));
+        assert!(root.contains("<a href=\"notes/index.html\">Notes</a>"));
+        assert!(read("notes/index.html").contains("<h1 id=\"notes\">Notes</h1>"));
+
+        std::fs::write(input.path().join("guides/faq.md"), "# Questions\n").expect("faq edit");
+        let rebuilt = SitePipeline::new(input.path(), &options)
+            .expect("pipeline")
+            .rebuild_changed(output.path(), &[PathBuf::from("guides/faq.md")], &mut snapshot)
+            .expect("rebuild");
+        assert_eq!(rebuilt, Some(3));
+        assert!(read("guides/index.html").contains("<a href=\"faq.html\">Questions</a></li>"));
+    }
 
-        let site_map = build_site_map(input_dir.path(), None);
-        let current = site_map
-            .pages_by_path
-            .get(&PathBuf::from("docs/guide/extra.md"))
-            .expect("current page");
+    #[test]
+    fn expands_children_listing_on_landing_pages() {
+        let input = tempfile::tempdir().expect("input");
+        let output = tempfile::tempdir().expect("output");
+        std::fs::write(input.path().join("index.md"), "# Home\n\n{{children}}\n").expect("home");
+        std::fs::write(input.path().join("faq.md"), "---\ndescription: Common questions\n---\n# FAQ\n").expect("faq");
+        std::fs::write(input.path().join("draft.md"), "---\nhidden: true\n---\n# Draft\n").expect("draft");
+        std::fs::create_dir_all(input.path().join("guide")).expect("guide dir");
+        std::fs::write(input.path().join("guide/README.md"), "---\ndescription: Start here\n---\n# Guide\n")
+            .expect("guide");
+        let template = Template::built_in();
+        let options = RenderOptions {
+            live_reload: false,
+            heartbeat: false,
+            template: &template,
+            filter: &PathFilter::default(),
+            csv_max_rows: None,
+            generated: &Generated::default(),
+            page_views: None,
+            pre_page_hook: None,
+            plugins: &Plugins::default(),
+            settings: &Settings::default(),
+        };
+        let pipeline = SitePipeline::new(input.path(), &options).expect("pipeline");
+        let mut snapshot = pipeline.snapshot(pipeline.build(output.path()).expect("build"));
+        let home = || std::fs::read_to_string(output.path().join("index.html")).expect("home html");
+        assert!(home().contains(concat!(
+            "<ul class=\"child-pages\">\n",
+            "<li class=\"child-folder\"><a href=\"guide/index.html\">Guide</a><p>Start here</p></li>\n",
+            "<li class=\"child-page\"><a href=\"faq.html\">FAQ</a><p>Common questions</p></li>\n</ul>"
+        )));
+        assert!(!home().contains("Draft"));
+
+        std::fs::write(input.path().join("faq.md"), "---\ndescription: Answers\n---\n# FAQ\n").expect("faq edit");
+        SitePipeline::new(input.path(), &options)
+            .expect("pipeline")
+            .rebuild_changed(output.path(), &[PathBuf::from("faq.md")], &mut snapshot)
+            .expect("rebuild");
+        assert!(home().contains("<p>Answers</p>"));
+    }
 
-        let nav = build_nav_html(current, &site_map);
-        assert!(nav.contains("Pages"));
-        assert!(nav.contains("Guide"));
-        assert!(!nav.contains("intro.html"));
-        assert!(nav.contains("Subsection"));
+    #[test]
+    fn preview_rebuild_removes_deleted_and_renamed_assets() {
+        let input = tempfile::tempdir().expect("input");
+        let output = tempfile::tempdir().expect("output");
+        std::fs::write(input.path().join("index.md"), "# Home\n").expect("home");
+        std::fs::write(input.path().join("logo.png"), "png").expect("logo");
+        std::fs::write(input.path().join("notes.txt"), "notes").expect("notes");
+        let template = Template::built_in();
+        let options = RenderOptions {
+            live_reload: true,
+            heartbeat: false,
+            template: &template,
+            filter: &PathFilter::default(),
+            csv_max_rows: None,
+            generated: &Generated::default(),
+            page_views: None,
+            pre_page_hook: None,
+            plugins: &Plugins::default(),
+            settings: &Settings::default(),
+        };
+        let pipeline = SitePipeline::new(input.path(), &options).expect("pipeline");
+        let mut snapshot = pipeline.snapshot(pipeline.build(output.path()).expect("build"));
+        let rebuild = |changed: &[&str], snapshot: &mut SiteSnapshot| {
+            let changed: Vec<PathBuf> = changed.iter().map(PathBuf::from).collect();
+            SitePipeline::new(input.path(), &options)
+                .expect("pipeline")
+                .rebuild_changed(output.path(), &changed, snapshot)
+                .expect("rebuild")
+        };
+
+        std::fs::remove_file(input.path().join("notes.txt")).expect("delete");
+        assert_eq!(rebuild(&["notes.txt"], &mut snapshot), Some(0));
+        assert!(!output.path().join("notes.txt").exists());
+
+        std::fs::rename(input.path().join("logo.png"), input.path().join("brand.png")).expect("rename");
+        assert_eq!(rebuild(&["logo.png", "brand.png"], &mut snapshot), Some(0));
+        assert!(!output.path().join("logo.png").exists());
+        assert!(output.path().join("brand.png").exists());
+
+        std::fs::remove_file(input.path().join("brand.png")).expect("delete renamed");
+        assert_eq!(rebuild(&["brand.png"], &mut snapshot), Some(0));
+        assert!(!output.path().join("brand.png").exists());
+    }
 
-        let breadcrumbs = build_breadcrumbs_html(current, &site_map);
-        assert!(breadcrumbs.contains("Home"));
-        assert!(breadcrumbs.contains("Docs"));
-        assert!(breadcrumbs.contains("Guide"));
-        assert!(breadcrumbs.contains("Extra"));
+    #[test]
+    fn builds_page_permalinks_from_base_url() {
+        assert_eq!(
+            page_permalink("https://docs.example.com/", Path::new("guide/install.html"), &Settings::default()),
+            "https://docs.example.com/guide/install.html"
+        );
+        assert_eq!(
+            page_permalink("https://example.com/docs", Path::new("index.html"), &Settings::default()),
+            "https://example.com/docs/index.html"
+        );
     }
 
     #[test]
-    fn excludes_current_folder_from_nav_folders() {
+    fn summarizes_build() {
         let input_dir = tempdir().expect("input tempdir");
-        let docs_dir = input_dir.path().join("docs");
-        std::fs::create_dir_all(&docs_dir).expect("docs dir");
+        let output_dir = tempdir().expect("output tempdir");
+        std::fs::write(input_dir.path().join("index.md"), "# Home\n\n[Gone](gone.md)").expect("home");
+        std::fs::write(input_dir.path().join("data.csv"), "a,b\n1,2\n").expect("csv");
+        std::fs::write(input_dir.path().join("logo.png"), "png").expect("logo");
 
-        let guide_dir = docs_dir.join("guide");
-        std::fs::create_dir_all(&guide_dir).expect("guide dir");
-        std::fs::write(guide_dir.join("index.md"), "# Guide").expect("guide index");
+        let template = Template::built_in();
+        let summary = build_site(
+            input_dir.path(),
+            output_dir.path(),
+            &RenderOptions {
+                live_reload: false,
+                heartbeat: false,
+                template: &template,
+                filter: &PathFilter::default(),
+                csv_max_rows: None,
+                generated: &Generated::default(),
+                page_views: None,
+                pre_page_hook: None,
+                plugins: &Plugins::default(),
+                settings: &Settings::default(),
+            },
+        )
+        .expect("build site");
 
-        let current_path = guide_dir.join("page.md");
-        std::fs::write(&current_path, "# Page").expect("page");
+        assert_eq!((summary.pages, summary.assets, summary.warnings()), (2, 1, 1));
+        let slowest = summary.slowest_pages(1);
+        assert_eq!(slowest.len(), 1);
+        assert!(summary.page_timings.iter().any(|(page, _)| page == Path::new("data.csv")));
+    }
 
-        let sub_dir = guide_dir.join("sub");
-        std::fs::create_dir_all(&sub_dir).expect("sub dir");
-        std::fs::write(sub_dir.join("README.md"), "# Subsection").expect("sub readme");
-        let site_map = build_site_map(input_dir.path(), None);
-        let current = site_map
-            .pages_by_path
-            .get(&PathBuf::from("docs/guide/page.md"))
-            .expect("current page");
+    #[test]
+    fn check_and_build_report_the_same_warnings() {
+        let input_dir = tempdir().expect("input tempdir");
+        let output_dir = tempdir().expect("output tempdir");
+        std::fs::create_dir_all(input_dir.path().join("guide")).expect("guide dir");
+        std::fs::write(input_dir.path().join("index.md"), "# Home\n\n[Gone](gone.md)").expect("home");
+        std::fs::write(input_dir.path().join("guide/_sidebar.md"), "[Missing](missing.md)").expect("sidebar");
+        std::fs::write(input_dir.path().join("guide/intro.md"), "# Intro").expect("intro");
 
-        let nav = build_nav_html(current, &site_map);
-        assert!(nav.contains("Subsection"));
-        assert_eq!(nav.matches("Guide</a>").count(), 1);
+        let template = Template::built_in();
+        let options = RenderOptions {
+            live_reload: false,
+            heartbeat: false,
+            template: &template,
+            filter: &PathFilter::default(),
+            csv_max_rows: None,
+            generated: &Generated::default(),
+            page_views: None,
+            pre_page_hook: None,
+            plugins: &Plugins::default(),
+            settings: &Settings::default(),
+        };
+        let checked = check_site(input_dir.path(), &options, false, false, false, None).expect("check site");
+        let summary = build_site(input_dir.path(), output_dir.path(), &options).expect("build site");
+        assert_eq!(checked.len(), 2);
+        assert_eq!(summary.diagnostics, checked);
     }
 
+    #[cfg(not(windows))]
     #[test]
-    fn sorts_pages_and_folders_by_title() {
+    fn rebuilds_rerun_pre_page_hooks_only_for_edited_pages() {
         let input_dir = tempdir().expect("input tempdir");
-        let docs_dir = input_dir.path().join("docs");
-        std::fs::create_dir_all(&docs_dir).expect("docs dir");
-        std::fs::write(docs_dir.join("README.md"), "# Docs").expect("docs readme");
+        let output_dir = tempdir().expect("output tempdir");
+        let log = output_dir.path().join("hooks.log");
+        std::fs::write(input_dir.path().join("index.md"), "# Home").expect("home");
+        std::fs::write(input_dir.path().join("intro.md"), "# Intro").expect("intro");
 
-        std::fs::write(docs_dir.join("zeta.md"), "# Zeta").expect("zeta");
-        std::fs::write(docs_dir.join("alpha.md"), "# Alpha").expect("alpha");
+        let template = Template::built_in();
+        let pool = crate::process_pool::ProcessPool::new(2, std::time::Duration::from_secs(10));
+        let command = format!("echo \"$RENDAR_PAGE\" >> '{}'", log.display());
+        let options = RenderOptions {
+            live_reload: false,
+            heartbeat: false,
+            template: &template,
+            filter: &PathFilter::default(),
+            csv_max_rows: None,
+            generated: &Generated::default(),
+            page_views: None,
+            pre_page_hook: Some(PageHook { command: &command, pool: &pool }),
+            plugins: &Plugins::default(),
+            settings: &Settings::default(),
+        };
+        let runs = || std::fs::read_to_string(&log).expect("log").lines().count();
+
+        build_site(input_dir.path(), output_dir.path(), &options).expect("build site");
+        build_site(input_dir.path(), output_dir.path(), &options).expect("rebuild site");
+        assert_eq!(runs(), 2);
+
+        std::fs::write(input_dir.path().join("intro.md"), "# Welcome").expect("edit");
+        build_site(input_dir.path(), output_dir.path(), &options).expect("rebuild site");
+        assert_eq!(runs(), 3);
+    }
 
-        let alpha_dir = docs_dir.join("alpha-folder");
-        std::fs::create_dir_all(&alpha_dir).expect("alpha dir");
-        std::fs::write(alpha_dir.join("README.md"), "# Alpha Folder").expect("alpha readme");
+    #[test]
+    fn rebuilds_only_pages_affected_by_a_change() {
+        let input = tempfile::tempdir().expect("input");
+        let output = tempfile::tempdir().expect("output");
+        std::fs::create_dir_all(input.path().join("guide")).expect("guide dir");
+        std::fs::write(input.path().join("index.md"), "# Home\n").expect("home");
+        std::fs::write(input.path().join("guide/index.md"), "# Guide\n").expect("guide");
+        std::fs::write(input.path().join("guide/install.md"), "# Install\n").expect("install");
+        std::fs::write(input.path().join("guide/usage.md"), "# Usage\n").expect("usage");
+        let template = Template::built_in();
+        let options = RenderOptions {
+            live_reload: true,
+            heartbeat: false,
+            template: &template,
+            filter: &PathFilter::default(),
+            csv_max_rows: None,
+            generated: &Generated::default(),
+            page_views: None,
+            pre_page_hook: None,
+            plugins: &Plugins::default(),
+            settings: &Settings::default(),
+        };
+        let pipeline = SitePipeline::new(input.path(), &options).expect("pipeline");
+        let mut snapshot = pipeline.snapshot(pipeline.build(output.path()).expect("build"));
+        let rebuild = |changed: &[&str], snapshot: &mut SiteSnapshot| {
+            let changed: Vec<PathBuf> = changed.iter().map(PathBuf::from).collect();
+            SitePipeline::new(input.path(), &options)
+                .expect("pipeline")
+                .rebuild_changed(output.path(), &changed, snapshot)
+                .expect("rebuild")
+        };
+
+        std::fs::write(input.path().join("guide/install.md"), "# Install\n\nRun it.\n").expect("edit");
+        assert_eq!(rebuild(&["guide/install.md"], &mut snapshot), Some(1));
+        let install = std::fs::read_to_string(output.path().join("guide/install.html")).expect("install html");
+        assert!(install.contains("Run it."));
+
+        std::fs::write(input.path().join("guide/install.md"), "# Setup\n").expect("retitle");
+        assert_eq!(rebuild(&["guide/install.md"], &mut snapshot), Some(3));
+        let usage = std::fs::read_to_string(output.path().join("guide/usage.html")).expect("usage html");
+        assert!(usage.contains(">Setup</a>"));
+
+        std::fs::write(input.path().join("guide/faq.md"), "# FAQ\n").expect("new page");
+        assert_eq!(rebuild(&["guide/faq.md"], &mut snapshot), None);
+    }
 
-        let zeta_dir = docs_dir.join("zeta-folder");
-        std::fs::create_dir_all(&zeta_dir).expect("zeta dir");
-        std::fs::write(zeta_dir.join("README.md"), "# Zeta Folder").expect("zeta readme");
+    #[test]
+    fn rebuilds_neighbours_in_other_folders_after_a_retitle() {
+        let input = tempfile::tempdir().expect("input");
+        let output = tempfile::tempdir().expect("output");
+        std::fs::create_dir_all(input.path().join("guide")).expect("guide dir");
+        std::fs::create_dir_all(input.path().join("reference")).expect("reference dir");
+        std::fs::write(input.path().join("index.md"), "# Home\n").expect("home");
+        std::fs::write(input.path().join("guide/index.md"), "# Guide\n").expect("guide");
+        std::fs::write(input.path().join("guide/usage.md"), "# Usage\n").expect("usage");
+        std::fs::write(input.path().join("reference/index.md"), "# Reference\n").expect("reference");
+        let template = Template::built_in();
+        let options = RenderOptions {
+            live_reload: true,
+            heartbeat: false,
+            template: &template,
+            filter: &PathFilter::default(),
+            csv_max_rows: None,
+            generated: &Generated::default(),
+            page_views: None,
+            pre_page_hook: None,
+            plugins: &Plugins::default(),
+            settings: &Settings::default(),
+        };
+        let pipeline = SitePipeline::new(input.path(), &options).expect("pipeline");
+        let mut snapshot = pipeline.snapshot(pipeline.build(output.path()).expect("build"));
+
+        std::fs::write(input.path().join("guide/usage.md"), "# Running\n").expect("retitle");
+        let changed = [PathBuf::from("guide/usage.md")];
+        let rebuilt = SitePipeline::new(input.path(), &options)
+            .expect("pipeline")
+            .rebuild_changed(output.path(), &changed, &mut snapshot)
+            .expect("rebuild");
+        assert_eq!(rebuilt, Some(3));
+        let reference = std::fs::read_to_string(output.path().join("reference/index.html")).expect("reference html");
+        assert!(reference.contains(r#"<link rel="prev" href="../guide/usage.html" title="Running">"#));
+    }
 
-        let site_map = build_site_map(input_dir.path(), None);
-        let current = site_map
-            .pages_by_path
-            .get(&PathBuf::from("docs/README.md"))
-            .expect("current page");
+    #[test]
+    fn merges_mounted_roots_into_the_site() {
+        let input = tempfile::tempdir().expect("input");
+        let shared = tempfile::tempdir().expect("shared");
+        let output = tempfile::tempdir().expect("output");
+        std::fs::create_dir_all(input.path().join("shared")).expect("shadow dir");
+        std::fs::write(input.path().join("index.md"), "# Home\n\n[Start](shared/start.md)\n").expect("home");
+        std::fs::write(input.path().join("shared/setup.md"), "# Local Setup\n").expect("shadowing page");
+        std::fs::write(shared.path().join("start.md"), "# Start\n\n[Setup](setup.md) ![Logo](logo.png)\n")
+            .expect("start");
+        std::fs::write(shared.path().join("setup.md"), "# Shared Setup\n").expect("shadowed page");
+        std::fs::write(shared.path().join("logo.png"), "png").expect("logo");
+        let template = Template::built_in();
+        let mut settings = Settings::default();
+        settings.mounted_roots.push(MountedRoot {
+            mount: PathBuf::from("shared"),
+            root: shared.path().to_path_buf(),
+            filter: PathFilter::default(),
+        });
+        let options = RenderOptions {
+            live_reload: true,
+            heartbeat: false,
+            template: &template,
+            filter: &PathFilter::default(),
+            csv_max_rows: None,
+            generated: &Generated::default(),
+            page_views: None,
+            pre_page_hook: None,
+            plugins: &Plugins::default(),
+            settings: &settings,
+        };
+        let pipeline = SitePipeline::new(input.path(), &options).expect("pipeline");
+        let summary = pipeline.build(output.path()).expect("build");
+        assert!(summary.diagnostics.is_empty());
+        let mut snapshot = pipeline.snapshot(summary);
+        let read = |path: &str| std::fs::read_to_string(output.path().join(path)).expect(path);
+
+        let home = read("index.html");
+        assert!(home.contains("<a href=\"shared/start.html\">Start</a>"));
+        let start = read("shared/start.html");
+        assert!(start.contains("<a href=\"setup.html\">Setup</a>"));
+        assert!(start.contains(">Local Setup</a>"));
+        assert!(!start.contains("Shared Setup"));
+        assert!(read("shared/setup.html").contains("Local Setup"));
+        assert_eq!(read("shared/logo.png"), "png");
+
+        std::fs::write(shared.path().join("start.md"), "# Start\n\nUpdated.\n").expect("edit");
+        let rebuilt = SitePipeline::new(input.path(), &options)
+            .expect("pipeline")
+            .rebuild_changed(output.path(), &[PathBuf::from("shared/start.md")], &mut snapshot)
+            .expect("rebuild");
+        assert_eq!(rebuilt, Some(1));
+        assert!(read("shared/start.html").contains("Updated."));
+    }
 
-        let nav = build_nav_html(current, &site_map);
-        let alpha_index = nav.find("Alpha</a>").expect("alpha page");
-        let zeta_index = nav.find("Zeta</a>").expect("zeta page");
-        assert!(alpha_index < zeta_index);
+    #[test]
+    fn resolves_mounted_links_and_sidebars_in_the_merged_site() {
+        let dir = tempfile::tempdir().expect("dir");
+        let output = tempfile::tempdir().expect("output");
+        let docs = dir.path().join("docs");
+        let guides = dir.path().join("guides");
+        std::fs::create_dir_all(&docs).expect("docs");
+        std::fs::create_dir_all(&guides).expect("guides");
+        std::fs::write(docs.join("README.md"), "# Home\n\n[Intro](guides/intro.md)\n").expect("home");
+        std::fs::write(guides.join("intro.md"), "# Intro\n\n[home](../README.md) [next](next.md)\n").expect("intro");
+        std::fs::write(guides.join("next.md"), "# Next\n").expect("next");
+        std::fs::write(guides.join("_sidebar.md"), "- [Back home](../README.md)\n").expect("sidebar");
+        let template = Template::built_in();
+        let mut settings = Settings::default();
+        settings.mounted_roots.push(MountedRoot {
+            mount: PathBuf::from("guides"),
+            root: guides.clone(),
+            filter: PathFilter::default(),
+        });
+        let summary = build_site(
+            &docs,
+            output.path(),
+            &RenderOptions {
+                live_reload: false,
+                heartbeat: false,
+                template: &template,
+                filter: &PathFilter::default(),
+                csv_max_rows: None,
+                generated: &Generated::default(),
+                page_views: None,
+                pre_page_hook: None,
+                plugins: &Plugins::default(),
+                settings: &settings,
+            },
+        )
+        .expect("build site");
+        assert!(summary.diagnostics.is_empty(), "{:?}", summary.diagnostics);
+
+        let intro = std::fs::read_to_string(output.path().join("guides/intro.html")).expect("intro");
+        assert!(intro.contains(r#"<a href="../index.html">home</a>"#));
+        assert!(intro.contains(r#"<a href="next.html">next</a>"#));
+        assert!(intro.contains("nav-custom"));
+        assert!(intro.contains(r#"<a href="../index.html">Back home</a>"#));
+        let next = std::fs::read_to_string(output.path().join("guides/next.html")).expect("next");
+        assert!(next.contains("Back home"));
+        let home = std::fs::read_to_string(output.path().join("index.html")).expect("home");
+        assert!(home.contains(r#"<a href="guides/intro.html">Intro</a>"#));
+        assert!(!home.contains("Back home"));
+    }
 
-        let alpha_folder = nav.find("Alpha Folder</a>").expect("alpha folder");
-        let zeta_folder = nav.find("Zeta Folder</a>").expect("zeta folder");
-        assert!(alpha_folder < zeta_folder);
+    #[test]
+    fn retitled_folder_page_rebuilds_pages_across_the_folder_boundary() {
+        let input = tempfile::tempdir().expect("input");
+        let output = tempfile::tempdir().expect("output");
+        std::fs::create_dir_all(input.path().join("guide/advanced")).expect("guide dirs");
+        std::fs::write(input.path().join("index.md"), "# Home\n").expect("home");
+        std::fs::write(input.path().join("faq.md"), "# FAQ\n").expect("faq");
+        std::fs::write(input.path().join("guide/index.md"), "# Guide\n").expect("guide");
+        std::fs::write(input.path().join("guide/install.md"), "# Install\n").expect("install");
+        std::fs::write(input.path().join("guide/advanced/index.md"), "# Advanced\n").expect("advanced");
+        std::fs::write(input.path().join("guide/advanced/tuning.md"), "# Tuning\n").expect("tuning");
+        let template = Template::built_in();
+        let options = RenderOptions {
+            live_reload: true,
+            heartbeat: false,
+            template: &template,
+            filter: &PathFilter::default(),
+            csv_max_rows: None,
+            generated: &Generated::default(),
+            page_views: None,
+            pre_page_hook: None,
+            plugins: &Plugins::default(),
+            settings: &Settings::default(),
+        };
+        let pipeline = SitePipeline::new(input.path(), &options).expect("pipeline");
+        let mut snapshot = pipeline.snapshot(pipeline.build(output.path()).expect("build"));
+
+        std::fs::write(input.path().join("guide/index.md"), "# Handbook\n").expect("retitle");
+        let rebuilt = SitePipeline::new(input.path(), &options)
+            .expect("pipeline")
+            .rebuild_changed(output.path(), &[PathBuf::from("guide/index.md")], &mut snapshot)
+            .expect("rebuild");
+        assert_eq!(rebuilt, Some(6));
+        let read = |rel: &str| std::fs::read_to_string(output.path().join(rel)).expect("page html");
+        // The root folder's nav lists the folder, and the pages below it
+        // show it in their breadcrumbs.
+        assert!(read("faq.html").contains(">Handbook</a>"));
+        assert!(read("guide/advanced/tuning.html").contains(">Handbook</a>"));
+        assert!(!read("guide/advanced/tuning.html").contains(">Guide</a>"));
+    }
+
+    #[test]
+    fn previews_a_placeholder_for_empty_input() {
+        let input_dir = tempdir().expect("input tempdir");
+        let output_dir = tempdir().expect("output tempdir");
+        std::fs::write(input_dir.path().join("notes.txt"), "not markdown").expect("notes");
+        let template = Template::built_in();
+        let options = RenderOptions {
+            live_reload: true,
+            heartbeat: false,
+            template: &template,
+            filter: &PathFilter::default(),
+            csv_max_rows: None,
+            generated: &Generated::default(),
+            page_views: None,
+            pre_page_hook: None,
+            plugins: &Plugins::default(),
+            settings: &Settings::default(),
+        };
+        let summary = build_site(input_dir.path(), output_dir.path(), &options).expect("build site");
+        assert_eq!(summary.diagnostics[0].rule, "empty-input");
+        let index = std::fs::read_to_string(output_dir.path().join("index.html")).expect("placeholder");
+        assert!(index.contains("No pages to preview yet"));
+        assert!(index.contains("Scanned 1 file in 1 folder"));
     }
 }
@@ -0,0 +1,47 @@
+use crate::render::escape_html;
+use anyhow::{Context, Result};
+use std::path::{Component, Path, PathBuf};
+
+pub const SITEMAP_FILE: &str = "sitemap.xml";
+
+pub fn write_sitemap(out: &Path, base_url: &str, pages: &[PathBuf]) -> Result<PathBuf> {
+    let base_url = base_url.trim_end_matches('/');
+    let mut xml = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    ));
+    for page in pages {
+        let url = format!("{base_url}/{}", url_path(page));
+        xml.push_str(&format!("  <url><loc>{}</loc></url>\n", escape_html(&url)));
+    }
+    xml.push_str("</urlset>\n");
+    let path = out.join(SITEMAP_FILE);
+    std::fs::write(&path, xml).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+fn url_path(path: &Path) -> String {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_pages_under_the_base_url() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let pages = vec![PathBuf::from("index.html"), PathBuf::from("guide/q&a.html")];
+        let path = write_sitemap(dir.path(), "https://docs.example.com/", &pages).expect("sitemap");
+        let xml = std::fs::read_to_string(path).expect("read");
+        assert!(xml.contains("<loc>https://docs.example.com/index.html</loc>"));
+        assert!(xml.contains("<loc>https://docs.example.com/guide/q&amp;a.html</loc>"));
+        assert!(xml.ends_with("</urlset>\n"));
+    }
+}
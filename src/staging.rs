@@ -0,0 +1,143 @@
+use crate::site::is_within;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Runs `build` against a sibling staging directory and swaps it into place
+/// only when it succeeds, so a failed or interrupted build never leaves a
+/// half-written `output`. With `seed`, staging starts as a copy of the
+/// current output, keeping files the build doesn't write itself.
+pub fn build_staged<T>(
+    input: &Path,
+    output: &Path,
+    seed: bool,
+    build: impl FnOnce(&Path) -> Result<T>,
+) -> Result<T> {
+    let (staging, previous) = staging_paths(output)?;
+    let canonical_input = input.canonicalize().unwrap_or_else(|_| input.to_path_buf());
+    if output.exists() && is_within(&canonical_input, &output.canonicalize()?) {
+        // Swapping would move the input too; build in place instead.
+        return build(output);
+    }
+    remove_if_exists(&staging)?;
+    if seed && output.is_dir() {
+        copy_dir(output, &staging)?;
+    } else {
+        std::fs::create_dir_all(&staging)
+            .with_context(|| format!("Failed to create staging directory {}", staging.display()))?;
+    }
+    let value = match build(&staging) {
+        Ok(value) => value,
+        Err(err) => {
+            let _ = std::fs::remove_dir_all(&staging);
+            return Err(err);
+        }
+    };
+    swap_into_place(&staging, output, &previous)?;
+    Ok(value)
+}
+
+/// `.<name>.staging` and `.<name>.previous` next to `output`, on the same
+/// filesystem so the swap is a rename.
+fn staging_paths(output: &Path) -> Result<(PathBuf, PathBuf)> {
+    let absolute = std::path::absolute(output)
+        .with_context(|| format!("Failed to resolve output directory {}", output.display()))?;
+    let absolute = absolute.canonicalize().unwrap_or(absolute);
+    let (Some(parent), Some(name)) = (absolute.parent(), absolute.file_name()) else {
+        return Err(anyhow::anyhow!(
+            "Output directory {} has no parent to stage the build in",
+            output.display()
+        ));
+    };
+    let name = name.to_string_lossy();
+    Ok((
+        parent.join(format!(".{name}.staging")),
+        parent.join(format!(".{name}.previous")),
+    ))
+}
+
+fn swap_into_place(staging: &Path, output: &Path, previous: &Path) -> Result<()> {
+    remove_if_exists(previous)?;
+    let had_output = output.exists();
+    if had_output {
+        std::fs::rename(output, previous)
+            .with_context(|| format!("Failed to move {} aside", output.display()))?;
+    }
+    if let Err(err) = std::fs::rename(staging, output) {
+        if had_output {
+            let _ = std::fs::rename(previous, output);
+        }
+        return Err(err).with_context(|| format!("Failed to move the new build into {}", output.display()));
+    }
+    remove_if_exists(previous)
+}
+
+fn remove_if_exists(path: &Path) -> Result<()> {
+    if path.exists() {
+        std::fs::remove_dir_all(path).with_context(|| format!("Failed to remove {}", path.display()))?;
+    }
+    Ok(())
+}
+
+pub fn copy_dir(source: &Path, target: &Path) -> Result<()> {
+    for entry in WalkDir::new(source) {
+        let entry = entry.with_context(|| format!("Failed to walk {}", source.display()))?;
+        let rel = entry.path().strip_prefix(source).unwrap_or(Path::new(""));
+        let dest = target.join(rel);
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&dest)
+                .with_context(|| format!("Failed to create {}", dest.display()))?;
+        } else {
+            std::fs::copy(entry.path(), &dest)
+                .with_context(|| format!("Failed to copy {}", entry.path().display()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_build_leaves_output_untouched() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let input = dir.path().join("docs");
+        let output = dir.path().join("site");
+        std::fs::create_dir_all(&input).expect("input");
+        std::fs::create_dir_all(&output).expect("output");
+        std::fs::write(output.join("index.html"), "old").expect("old page");
+
+        let result = build_staged(&input, &output, true, |staging| {
+            std::fs::write(staging.join("index.html"), "half")?;
+            Err::<(), _>(anyhow::anyhow!("render failed"))
+        });
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(output.join("index.html")).expect("page"), "old");
+        assert!(!dir.path().join(".site.staging").exists());
+    }
+
+    #[test]
+    fn swaps_in_seeded_or_fresh_builds() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let input = dir.path().join("docs");
+        let output = dir.path().join("site");
+        std::fs::create_dir_all(&input).expect("input");
+        std::fs::create_dir_all(&output).expect("output");
+        std::fs::write(output.join("stale.html"), "stale").expect("stale page");
+
+        build_staged(&input, &output, true, |staging| {
+            std::fs::write(staging.join("index.html"), "new").context("write")
+        })
+        .expect("seeded build");
+        assert!(output.join("stale.html").exists());
+        assert!(output.join("index.html").exists());
+
+        build_staged(&input, &output, false, |staging| {
+            std::fs::write(staging.join("index.html"), "new").context("write")
+        })
+        .expect("fresh build");
+        assert!(!output.join("stale.html").exists());
+        assert!(!dir.path().join(".site.previous").exists());
+    }
+}
@@ -0,0 +1,137 @@
+use crate::render::render_markdown_file;
+use crate::sidebar::is_sidebar;
+use crate::settings::Settings;
+use crate::site::{collect_index_dirs, walk_entries, PathFilter};
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::ops::AddAssign;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DirStats {
+    pub pages: usize,
+    pub words: usize,
+    pub images: usize,
+    pub broken_links: usize,
+}
+
+impl AddAssign for DirStats {
+    fn add_assign(&mut self, other: Self) {
+        self.pages += other.pages;
+        self.words += other.words;
+        self.images += other.images;
+        self.broken_links += other.broken_links;
+    }
+}
+
+pub fn collect_stats(input: &Path, filter: &PathFilter, settings: &Settings) -> Result<BTreeMap<PathBuf, DirStats>> {
+    let index_dirs = collect_index_dirs(input, filter, settings);
+    let mut dirs: BTreeMap<PathBuf, DirStats> = BTreeMap::new();
+    for entry in walk_entries(input, filter) {
+        let path = entry.path();
+        if !entry.file_type().is_file() || !settings.is_markdown(path) || is_sidebar(path) {
+            continue;
+        }
+        let rendered = render_markdown_file(path, input, &index_dirs, settings)?;
+        let rel_dir = path
+            .parent()
+            .and_then(|parent| parent.strip_prefix(input).ok())
+            .unwrap_or(Path::new(""))
+            .to_path_buf();
+        *dirs.entry(rel_dir).or_default() += DirStats {
+            pages: 1,
+            words: rendered.stats.words,
+            images: rendered.stats.images,
+            broken_links: rendered.diagnostics.len(),
+        };
+    }
+    Ok(dirs)
+}
+
+pub fn format_stats(dirs: &BTreeMap<PathBuf, DirStats>) -> String {
+    let mut total = DirStats::default();
+    let mut rows = vec![[
+        "Directory".to_string(),
+        "Pages".to_string(),
+        "Words".to_string(),
+        "Images".to_string(),
+        "Broken links".to_string(),
+    ]];
+    let mut push_row = |name: String, stats: &DirStats| {
+        rows.push([
+            name,
+            stats.pages.to_string(),
+            stats.words.to_string(),
+            stats.images.to_string(),
+            stats.broken_links.to_string(),
+        ]);
+    };
+    for (dir, stats) in dirs {
+        let name = if dir.as_os_str().is_empty() {
+            ".".to_string()
+        } else {
+            dir.to_string_lossy().replace('\\', "/")
+        };
+        push_row(name, stats);
+        total += *stats;
+    }
+    push_row("Total".to_string(), &total);
+
+    let mut widths = [0usize; 5];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let mut output = String::new();
+    for row in &rows {
+        let mut line = format!("{:<width$}", row[0], width = widths[0]);
+        for (cell, width) in row.iter().zip(widths).skip(1) {
+            line.push_str(&format!("  {cell:>width$}"));
+        }
+        output.push_str(line.trim_end());
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn totals_pages_words_images_and_broken_links_per_directory() {
+        let input_dir = tempdir().expect("input tempdir");
+        std::fs::create_dir_all(input_dir.path().join("guide")).expect("guide dir");
+        std::fs::write(input_dir.path().join("index.md"), "# Home\n\nWelcome to the docs.").expect("home");
+        std::fs::write(
+            input_dir.path().join("guide/intro.md"),
+            "# Intro\n\n![Diagram](diagram.png)\n\nSee [setup](setup.md).\n\n```sh\nnot counted\n```",
+        )
+        .expect("intro");
+        std::fs::write(input_dir.path().join("guide/usage.md"), "# Usage").expect("usage");
+        std::fs::write(input_dir.path().join("guide/diagram.png"), b"png").expect("diagram");
+
+        let dirs = collect_stats(input_dir.path(), &PathFilter::default(), &Settings::default()).expect("stats");
+        assert_eq!(
+            dirs[Path::new("")],
+            DirStats { pages: 1, words: 5, images: 0, broken_links: 0 }
+        );
+        assert_eq!(
+            dirs[Path::new("guide")],
+            DirStats { pages: 2, words: 4, images: 1, broken_links: 1 }
+        );
+
+        let table = format_stats(&dirs);
+        assert_eq!(
+            table,
+            concat!(
+                "Directory  Pages  Words  Images  Broken links\n",
+                ".              1      5       0             0\n",
+                "guide          2      4       1             1\n",
+                "Total          3      9       1             1\n",
+            )
+        );
+    }
+}
@@ -1,9 +1,46 @@
+use crate::config::PlaceholderPolicy;
+use crate::diagnostics::{print_diagnostic, Diagnostic};
+use crate::provenance::footer_html;
+use crate::render::PageStats;
+use crate::settings::Settings;
 use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
 use std::path::Path;
 
+const PLACEHOLDERS: &[&str] = &[
+    "{{title}}",
+    "{{content}}",
+    "{{nav}}",
+    "{{breadcrumbs}}",
+    "{{style}}",
+    "{{extra_head}}",
+    "{{extra_body}}",
+    "{{word_count}}",
+    "{{reading_time}}",
+    "{{lang}}",
+    "{{versions}}",
+    "{{site}}",
+    "{{page}}",
+    "{{generated_at}}",
+    "{{rendar_version}}",
+    "{{git_commit}}",
+    "{{site_footer}}",
+];
+
+/// JSON for the `{{site}}` and `{{page}}` placeholders.
+#[derive(Default)]
+pub struct TemplateData<'a> {
+    pub site: &'a str,
+    pub page: String,
+}
+
 pub struct Template {
     raw: String,
     style: String,
+    /// `{{key}}` tokens from `[template.vars]` and their values.
+    vars: Vec<(String, String)>,
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl Template {
@@ -11,19 +48,63 @@ impl Template {
         Self {
             raw: include_str!("../assets/theme/template.html").to_string(),
             style: include_str!("../assets/theme/style.css").to_string(),
+            vars: Vec::new(),
+            diagnostics: Vec::new(),
         }
     }
 
-    pub fn from_path(path: &Path) -> Result<Self> {
+    pub fn from_path(path: &Path, unknown: PlaceholderPolicy, vars: &BTreeMap<String, String>) -> Result<Self> {
         let raw = std::fs::read_to_string(path)
             .with_context(|| format!("Failed to read template {}", path.display()))?;
-        warn_missing_placeholders(&raw, path);
+        let mut tokens = Vec::new();
+        for (key, value) in vars {
+            let token = format!("{{{{{key}}}}}");
+            if PLACEHOLDERS.contains(&token.as_str()) {
+                return Err(anyhow::anyhow!(
+                    "Template variable `{key}` clashes with the built-in {token} placeholder"
+                ));
+            }
+            tokens.push((token, value.clone()));
+        }
+        let mut diagnostics: Vec<Diagnostic> = missing_placeholder_warning(&raw, path).into_iter().collect();
+        let unknown_found: Vec<String> = unknown_placeholders(&raw)
+            .into_iter()
+            .filter(|token| !tokens.iter().any(|(var, _)| var == token))
+            .collect();
+        if !unknown_found.is_empty() {
+            let message = format!(
+                "template uses unknown placeholders: {}. Supported placeholders: {}",
+                unknown_found.join(", "),
+                PLACEHOLDERS.join(", ")
+            );
+            match unknown {
+                PlaceholderPolicy::Error => return Err(anyhow::anyhow!("{}: {message}", path.display())),
+                PlaceholderPolicy::Warn => {
+                    diagnostics.push(Diagnostic::warning("unknown-placeholder", path, message));
+                }
+            }
+        }
+        diagnostics.iter().for_each(print_diagnostic);
         Ok(Self {
             raw,
             style: String::new(),
+            vars: tokens,
+            diagnostics,
         })
     }
 
+    /// Whether the template reads `{{site}}` or `{{page}}`, which are only
+    /// worth computing when used.
+    pub fn uses_data(&self) -> bool {
+        self.raw.contains("{{site}}") || self.raw.contains("{{page}}")
+    }
+
+    /// Warnings found while loading the template.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn render(
         &self,
         title: &str,
@@ -32,22 +113,47 @@ impl Template {
         breadcrumbs: &str,
         extra_head: Option<&str>,
         extra_body: Option<&str>,
+        stats: Option<&PageStats>,
+        data: &TemplateData<'_>,
+        settings: &Settings,
     ) -> String {
-        render_template(
-            &self.raw,
-            &[
-                ("{{title}}", title),
-                ("{{content}}", content),
-                ("{{nav}}", nav),
-                ("{{breadcrumbs}}", breadcrumbs),
-                ("{{style}}", &self.style),
-                ("{{extra_head}}", extra_head.unwrap_or("")),
-                ("{{extra_body}}", extra_body.unwrap_or("")),
-            ],
-        )
+        let word_count = stats.map(|stats| stats.words.to_string()).unwrap_or_default();
+        let reading_time = stats
+            .map(|stats| stats.reading_minutes().to_string())
+            .unwrap_or_default();
+        let provenance = &settings.provenance;
+        let site_footer = footer_html(&settings.footer_metadata, provenance, &settings.locale);
+        let mut replacements = vec![
+            ("{{title}}", title),
+            ("{{content}}", content),
+            ("{{nav}}", nav),
+            ("{{breadcrumbs}}", breadcrumbs),
+            ("{{style}}", &self.style),
+            ("{{extra_head}}", extra_head.unwrap_or("")),
+            ("{{extra_body}}", extra_body.unwrap_or("")),
+            ("{{word_count}}", &word_count),
+            ("{{reading_time}}", &reading_time),
+            ("{{lang}}", settings.locale.language()),
+            ("{{versions}}", crate::versions::SLOT),
+            ("{{site}}", data.site),
+            ("{{page}}", &data.page),
+            ("{{generated_at}}", &provenance.generated_at),
+            ("{{rendar_version}}", provenance.rendar_version),
+            ("{{git_commit}}", &provenance.git_commit),
+            ("{{site_footer}}", &site_footer),
+        ];
+        replacements.extend(self.vars.iter().map(|(token, value)| (token.as_str(), value.as_str())));
+        render_template(&self.raw, &replacements)
     }
 }
 
+/// JSON that is safe to place in a `<script>` element.
+pub fn template_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value)
+        .unwrap_or_else(|_| "null".to_string())
+        .replace('<', "\\u003c")
+}
+
 fn render_template(template: &str, replacements: &[(&str, &str)]) -> String {
     let mut html = String::with_capacity(template.len());
     let mut rest = template;
@@ -76,15 +182,44 @@ fn next_placeholder<'a>(
         .min_by_key(|(index, _, _)| *index)
 }
 
-fn warn_missing_placeholders(template: &str, path: &Path) {
+fn missing_placeholder_warning(template: &str, path: &Path) -> Option<Diagnostic> {
     let missing = missing_placeholders(template);
-    if !missing.is_empty() {
-        eprintln!(
-            "Warning: template {} is missing placeholders: {}",
-            path.display(),
-            missing.join(", ")
-        );
+    (!missing.is_empty()).then(|| {
+        Diagnostic::warning(
+            "missing-placeholder",
+            path,
+            format!("template is missing placeholders: {}", missing.join(", ")),
+        )
+    })
+}
+
+/// Lists `{{name}}` tokens that are not supported placeholders, in order of
+/// first appearance.
+fn unknown_placeholders(template: &str) -> Vec<String> {
+    let mut unknown: Vec<String> = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(len) = after.find("}}") else {
+            break;
+        };
+        let name = &after[..len];
+        let is_token = !name.trim().is_empty()
+            && name
+                .trim()
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '_' | '-' | '.'));
+        if is_token {
+            let token = format!("{{{{{name}}}}}");
+            if !PLACEHOLDERS.contains(&token.as_str()) && !unknown.contains(&token) {
+                unknown.push(token);
+            }
+            rest = &after[len + 2..];
+        } else {
+            rest = after;
+        }
     }
+    unknown
 }
 
 fn missing_placeholders(template: &str) -> Vec<&'static str> {
@@ -105,7 +240,10 @@ fn missing_placeholders(template: &str) -> Vec<&'static str> {
 
 #[cfg(test)]
 mod tests {
-    use super::{missing_placeholders, Template};
+    use super::{missing_placeholders, unknown_placeholders, Template, TemplateData};
+    use crate::config::PlaceholderPolicy;
+    use crate::settings::Settings;
+    use std::collections::BTreeMap;
 
     #[test]
     fn detects_missing_placeholders() {
@@ -116,11 +254,40 @@ mod tests {
         assert!(missing.contains(&"{{breadcrumbs}}"));
     }
 
+    #[test]
+    fn detects_unknown_placeholders() {
+        let template = "<title>{{title}}</title>{{contnet}}{{ nav }}{{contnet}}<script>if (a) {{}}</script>";
+        assert_eq!(unknown_placeholders(template), vec!["{{contnet}}", "{{ nav }}"]);
+        assert!(unknown_placeholders(&Template::built_in().raw).is_empty());
+    }
+
+    #[test]
+    fn replaces_config_vars() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("template.html");
+        std::fs::write(&path, "<title>{{title}} | {{site_name}}</title>{{nav}}{{breadcrumbs}}{{content}}<footer>{{footer}}</footer>")
+            .expect("template");
+        let mut vars = BTreeMap::new();
+        vars.insert("site_name".to_string(), "Handbook".to_string());
+        vars.insert("footer".to_string(), "&copy; 2026 ACME".to_string());
+        let template = Template::from_path(&path, PlaceholderPolicy::Error, &vars).expect("template");
+        let html = template.render("Setup", "<p>{{footer}}</p>", "", "", None, None, None, &TemplateData::default(), &Settings::default());
+        assert!(html.starts_with("<title>Setup | Handbook</title>"));
+        assert!(html.ends_with("<p>{{footer}}</p><footer>&copy; 2026 ACME</footer>"));
+
+        assert!(Template::from_path(&path, PlaceholderPolicy::Error, &BTreeMap::new()).is_err());
+        vars.insert("title".to_string(), "Other".to_string());
+        let err = Template::from_path(&path, PlaceholderPolicy::Error, &vars).err().expect("clash");
+        assert!(err.to_string().contains("`title` clashes"));
+    }
+
     #[test]
     fn does_not_replace_placeholders_inside_rendered_values() {
         let template = Template {
             raw: "<html>{{content}}<style>{{style}}</style></html>".to_string(),
             style: "body {}".to_string(),
+            vars: Vec::new(),
+            diagnostics: Vec::new(),
         };
 
         let html = template.render(
@@ -130,6 +297,9 @@ mod tests {
             "<span>Home</span>",
             None,
             None,
+            None,
+            &TemplateData::default(),
+            &Settings::default(),
         );
 
         assert!(html.contains("<style>body {}</style>"));
@@ -0,0 +1,200 @@
+use crate::links::{resolve_local, tag_attr};
+use crate::process_pool::content_key;
+use crate::site::relative_path;
+use anyhow::{Context, Result};
+use image::imageops::FilterType;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+pub const DEFAULT_MIN_BYTES: u64 = 1024 * 1024;
+pub const DEFAULT_WIDTH: u32 = 1200;
+pub const THUMBNAIL_DIR: &str = "_thumbnails";
+pub const DEFAULT_CACHE_DIR: &str = ".rendar-cache/thumbnails";
+
+pub struct ThumbnailSettings {
+    pub min_bytes: u64,
+    pub width: u32,
+    pub cache_dir: PathBuf,
+}
+
+/// Replaces large local PNG/JPEG images in every page of `output` with
+/// resized thumbnails linking to the full image. Thumbnails are cached by
+/// source content in `cache_dir`, so unchanged images are not resized again.
+pub fn generate_thumbnails(output: &Path, settings: &ThumbnailSettings) -> Result<usize> {
+    let mut generated = 0;
+    for entry in WalkDir::new(output).into_iter().filter_map(Result::ok) {
+        let path = entry.path();
+        if !entry.file_type().is_file() || path.extension().and_then(OsStr::to_str) != Some("html") {
+            continue;
+        }
+        let html = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let page_dir = path
+            .parent()
+            .and_then(|parent| parent.strip_prefix(output).ok())
+            .unwrap_or(Path::new(""));
+        let mut failed = None;
+        let rewritten = rewrite_images(&html, |src| {
+            match thumbnail_for(src, page_dir, output, settings) {
+                Ok(Some((thumbnail, fresh))) => {
+                    generated += usize::from(fresh);
+                    Some(relative_path(page_dir, &thumbnail))
+                }
+                Ok(None) => None,
+                Err(err) => {
+                    failed.get_or_insert(err);
+                    None
+                }
+            }
+        });
+        if let Some(err) = failed {
+            return Err(err);
+        }
+        if rewritten != html {
+            std::fs::write(path, rewritten)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+    }
+    Ok(generated)
+}
+
+/// Returns the thumbnail path relative to `output`, and whether it had to be
+/// created rather than copied from the cache.
+fn thumbnail_for(
+    src: &str,
+    page_dir: &Path,
+    output: &Path,
+    settings: &ThumbnailSettings,
+) -> Result<Option<(PathBuf, bool)>> {
+    if src.starts_with("//") || src.contains(['?', '#']) {
+        return Ok(None);
+    }
+    let Some(rel) = resolve_local(src, page_dir) else {
+        return Ok(None);
+    };
+    let source = output.join(&rel);
+    let Some(ext) = rel.extension().and_then(OsStr::to_str).map(str::to_ascii_lowercase) else {
+        return Ok(None);
+    };
+    if !matches!(ext.as_str(), "png" | "jpg" | "jpeg")
+        || std::fs::metadata(&source).map_or(true, |meta| meta.len() < settings.min_bytes)
+    {
+        return Ok(None);
+    }
+    let bytes = std::fs::read(&source)
+        .with_context(|| format!("Failed to read {}", source.display()))?;
+    let key = content_key(&[&bytes, &settings.width.to_le_bytes()]);
+    let stem = rel.file_stem().and_then(OsStr::to_str).unwrap_or("image");
+    let name = format!("{stem}-{}.{ext}", &key[..16]);
+    let thumbnail = Path::new(THUMBNAIL_DIR).join(&name);
+    let target = output.join(&thumbnail);
+    if target.exists() {
+        return Ok(Some((thumbnail, false)));
+    }
+    std::fs::create_dir_all(output.join(THUMBNAIL_DIR))
+        .with_context(|| format!("Failed to create {}", output.join(THUMBNAIL_DIR).display()))?;
+    let cached = settings.cache_dir.join(&name);
+    if cached.exists() {
+        std::fs::copy(&cached, &target)
+            .with_context(|| format!("Failed to copy {}", cached.display()))?;
+        return Ok(Some((thumbnail, false)));
+    }
+    let image = image::load_from_memory(&bytes)
+        .with_context(|| format!("Failed to decode {}", source.display()))?;
+    if image.width() <= settings.width {
+        return Ok(None);
+    }
+    image
+        .resize(settings.width, u32::MAX, FilterType::Lanczos3)
+        .save(&target)
+        .with_context(|| format!("Failed to write {}", target.display()))?;
+    std::fs::create_dir_all(&settings.cache_dir)
+        .with_context(|| format!("Failed to create {}", settings.cache_dir.display()))?;
+    std::fs::copy(&target, &cached)
+        .with_context(|| format!("Failed to cache {}", cached.display()))?;
+    Ok(Some((thumbnail, true)))
+}
+
+/// Swaps `<img src>` for the thumbnail returned by `thumbnail` and wraps the
+/// image in a link to the original, unless it is already inside a link.
+fn rewrite_images(html: &str, mut thumbnail: impl FnMut(&str) -> Option<String>) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find("<img ") {
+        let Some(len) = rest[start..].find('>') else {
+            break;
+        };
+        let end = start + len + 1;
+        output.push_str(&rest[..start]);
+        let tag = &rest[start..end];
+        let in_link = output.rfind("<a ").is_some_and(|open| output.rfind("</a>").is_none_or(|close| close < open));
+        match tag_attr(tag, "src").and_then(|src| Some((src, thumbnail(src)?))) {
+            Some((src, thumb)) => {
+                let img = tag.replacen(&format!(" src=\"{src}\""), &format!(" src=\"{thumb}\""), 1);
+                if in_link {
+                    output.push_str(&img);
+                } else {
+                    output.push_str(&format!("<a href=\"{src}\" class=\"thumbnail\">{img}</a>"));
+                }
+            }
+            None => output.push_str(tag),
+        }
+        rest = &rest[end..];
+    }
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_thumbnails_in_links_to_the_original() {
+        let html = concat!(
+            r#"<p><img src="photo.jpg" alt="Photo" /></p>"#,
+            r#"<p><a href="https://example.com"><img src="linked.jpg" alt="" /></a></p>"#,
+            r#"<p><img src="icon.png" alt="" /></p>"#,
+        );
+        let rewritten = rewrite_images(html, |src| {
+            (src != "icon.png").then(|| format!("_thumbnails/{src}"))
+        });
+        assert_eq!(
+            rewritten,
+            concat!(
+                r#"<p><a href="photo.jpg" class="thumbnail"><img src="_thumbnails/photo.jpg" alt="Photo" /></a></p>"#,
+                r#"<p><a href="https://example.com"><img src="_thumbnails/linked.jpg" alt="" /></a></p>"#,
+                r#"<p><img src="icon.png" alt="" /></p>"#,
+            )
+        );
+    }
+
+    #[test]
+    fn resizes_large_images_and_reuses_the_cache() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let output = dir.path().join("dist");
+        std::fs::create_dir_all(output.join("guide")).expect("guide dir");
+        image::RgbImage::from_pixel(400, 200, image::Rgb([10, 20, 30]))
+            .save(output.join("guide/photo.png"))
+            .expect("photo");
+        std::fs::write(output.join("guide/page.html"), r#"<img src="photo.png" alt="" />"#).expect("page");
+        let settings = ThumbnailSettings {
+            min_bytes: 0,
+            width: 100,
+            cache_dir: dir.path().join("cache"),
+        };
+
+        assert_eq!(generate_thumbnails(&output, &settings).expect("thumbnails"), 1);
+        let html = std::fs::read_to_string(output.join("guide/page.html")).expect("page");
+        let thumb = html.split("src=\"").nth(1).and_then(|rest| rest.split('"').next()).expect("src");
+        assert!(thumb.starts_with("../_thumbnails/photo-"));
+        let resized = image::open(output.join("guide").join(thumb)).expect("thumbnail");
+        assert_eq!((resized.width(), resized.height()), (100, 50));
+
+        std::fs::remove_dir_all(output.join(THUMBNAIL_DIR)).expect("clear output");
+        std::fs::write(output.join("guide/page.html"), r#"<img src="photo.png" alt="" />"#).expect("page");
+        assert_eq!(generate_thumbnails(&output, &settings).expect("cached"), 0);
+        assert!(output.join("guide").join(thumb).exists());
+    }
+}
@@ -0,0 +1,151 @@
+use crate::diagnostics::Diagnostic;
+use crate::hygiene::{closes_fence, fence_marker};
+use crate::render::{escape_html, DocMode};
+use crate::sidebar::is_sidebar;
+use crate::settings::Settings;
+use crate::site::{page_output_rel, relative_link, walk_entries, GeneratedPage, PathFilter};
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+const MARKERS: &[&str] = &["TODO:", "FIXME:"];
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Todo {
+    pub line: usize,
+    pub column: usize,
+    pub marker: &'static str,
+    pub text: String,
+}
+
+/// Finds `TODO:`/`FIXME:` markers and `<!-- todo ... -->` comments outside
+/// fenced code blocks.
+pub fn find_todos(source: &str) -> Vec<Todo> {
+    let mut todos = Vec::new();
+    let mut fence: Option<(char, usize)> = None;
+    for (idx, line) in source.lines().enumerate() {
+        match fence {
+            Some((marker, len)) => {
+                if closes_fence(line, marker, len) {
+                    fence = None;
+                }
+                continue;
+            }
+            None => {
+                if let Some(open) = fence_marker(line) {
+                    fence = Some(open);
+                    continue;
+                }
+            }
+        }
+        let mut comment_end = 0;
+        if let Some(start) = line.find("<!--")
+            && let Some(body) = line[start + 4..].trim_start().get(..4)
+            && body.eq_ignore_ascii_case("todo")
+        {
+            let rest = line[start + 4..].trim_start()[4..].trim_start_matches(':');
+            let end = rest.find("-->").unwrap_or(rest.len());
+            comment_end = line.len() - rest.len() + end;
+            todos.push(Todo {
+                line: idx + 1,
+                column: line[..start].chars().count() + 1,
+                marker: "TODO",
+                text: rest[..end].trim().to_string(),
+            });
+        }
+        for marker in MARKERS {
+            if let Some(start) = line[comment_end..].find(marker) {
+                let start = comment_end + start;
+                let rest = &line[start + marker.len()..];
+                let text = rest.split("-->").next().unwrap_or(rest);
+                todos.push(Todo {
+                    line: idx + 1,
+                    column: line[..start].chars().count() + 1,
+                    marker: marker.trim_end_matches(':'),
+                    text: text.trim().to_string(),
+                });
+            }
+        }
+    }
+    todos
+}
+
+pub fn todo_warnings(path: &Path, source: &str) -> Vec<Diagnostic> {
+    find_todos(source)
+        .into_iter()
+        .map(|todo| {
+            Diagnostic::warning("todo", path, format!("{}: {}", todo.marker, todo.text))
+                .at(todo.line, todo.column)
+        })
+        .collect()
+}
+
+/// Builds the `todos.html` report page listing every marker in the input.
+pub fn todo_report_page(input: &Path, filter: &PathFilter, settings: &Settings) -> Result<GeneratedPage> {
+    let rel_path = PathBuf::from("todos.md");
+    let mut items = String::new();
+    let mut count = 0;
+    for entry in walk_entries(input, filter) {
+        let path = entry.path();
+        if !entry.file_type().is_file() || !settings.is_markdown(path) || is_sidebar(path) {
+            continue;
+        }
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read markdown file {}", path.display()))?;
+        let Ok(page_rel) = path.strip_prefix(input) else {
+            continue;
+        };
+        let href = relative_link(Path::new(""), &page_output_rel(page_rel, settings), settings);
+        let source_name = page_rel.to_string_lossy().replace('\\', "/");
+        for todo in find_todos(&source) {
+            count += 1;
+            items.push_str(&format!(
+                "<li><a href=\"{}\">{}:{}</a> <strong>{}</strong> {}</li>\n",
+                escape_html(&href),
+                escape_html(&source_name),
+                todo.line,
+                todo.marker,
+                escape_html(&todo.text)
+            ));
+        }
+    }
+    let html = if count == 0 {
+        "<h1>TODOs</h1>\n<p>No TODO or FIXME markers found.</p>\n".to_string()
+    } else {
+        format!("<h1>TODOs</h1>\n<p>{count} open marker(s).</p>\n<ul class=\"todos\">\n{items}</ul>\n")
+    };
+    Ok(GeneratedPage {
+        rel_path,
+        title: "TODOs".to_string(),
+        html,
+        mode: DocMode::Document,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_markers_and_todo_comments_outside_code() {
+        let source = "# Setup\n\nTODO: add screenshots\n<!-- todo: explain proxies -->\n```sh\n# TODO: not a doc marker\n```\nSee FIXME: broken example\n";
+        assert_eq!(
+            todo_warnings(Path::new("setup.md"), source).iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                "setup.md:3:1: TODO: add screenshots",
+                "setup.md:4:1: TODO: explain proxies",
+                "setup.md:8:5: FIXME: broken example",
+            ]
+        );
+    }
+
+    #[test]
+    fn lists_markers_with_links_on_report_page() {
+        let input = tempfile::tempdir().expect("tempdir");
+        std::fs::create_dir_all(input.path().join("guide")).expect("guide dir");
+        std::fs::write(input.path().join("guide/intro.md"), "# Intro\n\nTODO: write <this>\n").expect("intro");
+        let page = todo_report_page(input.path(), &PathFilter::default(), &Settings::default()).expect("report");
+        assert!(page.html.contains(
+            r#"<li><a href="guide/intro.html">guide/intro.md:3</a> <strong>TODO</strong> write &lt;this&gt;</li>"#
+        ));
+    }
+}
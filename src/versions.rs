@@ -0,0 +1,217 @@
+use crate::config::VersionConfig;
+use crate::redirects::stub_html;
+use crate::render::escape_html;
+use crate::settings::Settings;
+use crate::site::relative_link;
+use anyhow::{Context, Result};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tempfile::TempDir;
+use walkdir::WalkDir;
+
+/// Output folder that always holds a copy of the latest version.
+pub const LATEST: &str = "latest";
+
+/// What `{{versions}}` expands to. Versioned builds put the dropdown in
+/// it; elsewhere it stays empty and hidden.
+pub const SLOT: &str = r#"<div class="version-switcher" hidden></div>"#;
+
+/// A version ready to build: its name and the folder its sources are in.
+pub struct VersionInput {
+    pub name: String,
+    pub input: PathBuf,
+    pub latest: bool,
+    /// Holds a git checkout until the build is done.
+    _checkout: Option<TempDir>,
+}
+
+/// Resolves each `[[versions]]` entry to a source folder, exporting git
+/// revisions into temporary folders. `input` is relative to the working
+/// directory and defaults to the site input.
+pub fn prepare_versions(versions: &[VersionConfig], default_input: &Path) -> Result<Vec<VersionInput>> {
+    let latest = versions
+        .iter()
+        .position(|version| version.latest.unwrap_or(false))
+        .unwrap_or(0);
+    let mut prepared = Vec::new();
+    for (idx, version) in versions.iter().enumerate() {
+        let name = version.name.trim();
+        if name.is_empty() || name == LATEST || name.contains(['/', '\\']) || name.starts_with('.') {
+            return Err(anyhow::anyhow!(
+                "Invalid version name {:?}: it must be a plain folder name other than `{LATEST}`",
+                version.name
+            ));
+        }
+        if prepared.iter().any(|other: &VersionInput| other.name == name) {
+            return Err(anyhow::anyhow!("Version {name} is listed twice"));
+        }
+        let input = version.input.clone().unwrap_or_else(|| default_input.to_path_buf());
+        let (input, checkout) = match version.git.as_deref() {
+            Some(rev) => {
+                let checkout = tempfile::tempdir().context("Failed to create a folder for the git checkout")?;
+                export_git_tree(rev, checkout.path())
+                    .with_context(|| format!("Failed to check out {rev} for version {name}"))?;
+                let rel = input.strip_prefix(std::env::current_dir()?).unwrap_or(&input).to_path_buf();
+                (checkout.path().join(rel), Some(checkout))
+            }
+            None => (input, None),
+        };
+        if !input.is_dir() {
+            return Err(anyhow::anyhow!(
+                "Input folder {} for version {name} does not exist",
+                input.display()
+            ));
+        }
+        prepared.push(VersionInput {
+            name: name.to_string(),
+            input,
+            latest: idx == latest,
+            _checkout: checkout,
+        });
+    }
+    Ok(prepared)
+}
+
+/// Extracts the tree of `rev` below the current directory into `dest`.
+fn export_git_tree(rev: &str, dest: &Path) -> Result<()> {
+    let mut git = Command::new("git")
+        .args(["archive", "--format=tar", rev])
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("Failed to run git")?;
+    let stdout = git.stdout.take().context("Failed to read git archive output")?;
+    let tar = Command::new("tar")
+        .arg("-x")
+        .arg("-C")
+        .arg(dest)
+        .stdin(stdout)
+        .status()
+        .context("Failed to run tar")?;
+    let git = git.wait().context("Failed to wait for git")?;
+    if !git.success() {
+        return Err(anyhow::anyhow!("git archive {rev} exited with {git}"));
+    }
+    if !tar.success() {
+        return Err(anyhow::anyhow!("tar exited with {tar}"));
+    }
+    Ok(())
+}
+
+/// Fills the `{{versions}}` slot of every page built under `output/<dir>`
+/// with a version dropdown, returning how many pages have one. Each entry
+/// opens the same page in that version, or its home page when the page
+/// doesn't exist there.
+pub fn add_version_selector(output: &Path, dir: &str, versions: &[VersionInput], settings: &Settings) -> Result<usize> {
+    let root = output.join(dir);
+    let mut pages = 0;
+    for entry in WalkDir::new(&root).into_iter().filter_map(Result::ok) {
+        let path = entry.path();
+        if !entry.file_type().is_file() || path.extension().and_then(OsStr::to_str) != Some("html") {
+            continue;
+        }
+        let page = path.strip_prefix(&root).unwrap_or(path);
+        let html = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        if !html.contains(SLOT) {
+            continue;
+        }
+        let selector = format!(
+            r#"<div class="version-switcher">{}</div>"#,
+            selector_html(output, dir, page, versions, settings)
+        );
+        let html = html.replacen(SLOT, &selector, 1);
+        std::fs::write(path, html).with_context(|| format!("Failed to write {}", path.display()))?;
+        pages += 1;
+    }
+    Ok(pages)
+}
+
+fn selector_html(output: &Path, dir: &str, page: &Path, versions: &[VersionInput], settings: &Settings) -> String {
+    let from_dir = Path::new(dir).join(page.parent().unwrap_or(Path::new("")));
+    let mut html =
+        r#"<select class="version-select" aria-label="Version" onchange="location.href=this.value">"#.to_string();
+    for version in versions {
+        let target_dir = if version.latest && dir == LATEST { LATEST } else { version.name.as_str() };
+        let same_page = Path::new(target_dir).join(page);
+        let target = if output.join(&same_page).is_file() {
+            same_page
+        } else {
+            Path::new(target_dir).join("index.html")
+        };
+        let selected = if target_dir == dir { " selected" } else { "" };
+        let label = if version.latest {
+            format!("{} (latest)", version.name)
+        } else {
+            version.name.clone()
+        };
+        html.push_str(&format!(
+            r#"<option value="{}"{selected}>{}</option>"#,
+            escape_html(&relative_link(&from_dir, &target, settings)),
+            escape_html(&label)
+        ));
+    }
+    html.push_str("</select>");
+    html
+}
+
+/// Sends visitors of the output root to the latest version.
+pub fn write_root_redirect(output: &Path) -> Result<()> {
+    let path = output.join("index.html");
+    std::fs::write(&path, stub_html(&format!("{LATEST}/index.html")))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(name: &str, latest: bool) -> VersionInput {
+        VersionInput {
+            name: name.to_string(),
+            input: PathBuf::new(),
+            latest,
+            _checkout: None,
+        }
+    }
+
+    #[test]
+    fn links_each_page_to_the_same_page_in_other_versions() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let output = dir.path();
+        for page in ["1.0/index.html", "1.0/guide/setup.html", "2.0/index.html", "latest/index.html", "latest/guide/setup.html"] {
+            let path = output.join(page);
+            std::fs::create_dir_all(path.parent().expect("parent")).expect("dir");
+            std::fs::write(&path, format!("<html><body>{SLOT}<p>Page</p></body></html>")).expect("page");
+        }
+        std::fs::write(output.join("1.0/plain.html"), "<html><body><p>Custom</p></body></html>").expect("plain");
+        let versions = [version("1.0", true), version("2.0", false)];
+        assert_eq!(add_version_selector(output, "1.0", &versions, &Settings::default()).expect("selector"), 2);
+        add_version_selector(output, LATEST, &versions, &Settings::default()).expect("latest selector");
+
+        let setup = std::fs::read_to_string(output.join("1.0/guide/setup.html")).expect("setup");
+        assert!(setup.contains(r#"<option value="setup.html" selected>1.0 (latest)</option>"#));
+        assert!(setup.contains(r#"<option value="../../2.0/index.html">2.0</option>"#));
+        assert!(setup.contains(r#"<body><div class="version-switcher"><select class="version-select""#));
+        assert!(setup.ends_with("</select></div><p>Page</p></body></html>"));
+        let plain = std::fs::read_to_string(output.join("1.0/plain.html")).expect("plain");
+        assert_eq!(plain, "<html><body><p>Custom</p></body></html>");
+        let latest = std::fs::read_to_string(output.join("latest/guide/setup.html")).expect("latest");
+        assert!(latest.contains(r#"<option value="setup.html" selected>1.0 (latest)</option>"#));
+    }
+
+    #[test]
+    fn rejects_bad_version_names() {
+        let config = |name: &str| VersionConfig {
+            name: name.to_string(),
+            input: None,
+            git: None,
+            latest: None,
+        };
+        let dir = tempfile::tempdir().expect("tempdir");
+        assert!(prepare_versions(&[config("latest")], dir.path()).is_err());
+        assert!(prepare_versions(&[config("1.0"), config("1.0")], dir.path()).is_err());
+        let prepared = prepare_versions(&[config("1.0"), config("2.0")], dir.path()).expect("versions");
+        assert!(prepared[0].latest);
+    }
+}